@@ -0,0 +1,65 @@
+//! Render benchmark over synthetic diffs: a 50k-line lockfile and many
+//! small source files. Run with `cargo bench`.
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use diffy_tui::config::Config;
+use diffy_tui::model::Diff;
+use diffy_tui::parse::parse_diff;
+use diffy_tui::tui::{self, BenchStep};
+use std::fmt::Write;
+
+/// One new file of `lines` lockfile-like lines
+fn lockfile_diff(lines: usize) -> Diff {
+    let mut input =
+        String::from("diff --git a/Cargo.lock b/Cargo.lock\n--- /dev/null\n+++ b/Cargo.lock\n");
+    writeln!(input, "@@ -0,0 +1,{} @@", lines).unwrap();
+    for i in 0..lines {
+        match i % 4 {
+            0 => writeln!(input, "+[[package]]").unwrap(),
+            1 => writeln!(input, "+name = \"crate-{}\"", i / 4).unwrap(),
+            2 => writeln!(input, "+version = \"0.{}.{}\"", i % 17, i % 5).unwrap(),
+            _ => writeln!(input, "+").unwrap(),
+        }
+    }
+    parse_diff(&input).unwrap()
+}
+
+/// `files` Rust files with `hunks` small hunks each
+fn source_diff(files: usize, hunks: usize) -> Diff {
+    let mut input = String::new();
+    for f in 0..files {
+        writeln!(input, "diff --git a/src/m{f}.rs b/src/m{f}.rs").unwrap();
+        writeln!(input, "--- a/src/m{f}.rs\n+++ b/src/m{f}.rs").unwrap();
+        for h in 0..hunks {
+            let start = h * 20 + 1;
+            writeln!(input, "@@ -{start},4 +{start},4 @@").unwrap();
+            writeln!(input, " fn helper_{h}() {{").unwrap();
+            writeln!(input, "-    let value = compute({h});").unwrap();
+            writeln!(input, "+    let value = compute_checked({h})?;").unwrap();
+            writeln!(input, "     value + 1\n }}").unwrap();
+        }
+    }
+    parse_diff(&input).unwrap()
+}
+
+fn render(c: &mut Criterion) {
+    let config = Config::default();
+    let cases = [
+        ("lockfile_50k", lockfile_diff(50_000)),
+        ("source_200x50", source_diff(200, 50)),
+    ];
+    for (name, diff) in cases {
+        for (step_name, step) in [("scroll", BenchStep::Scroll), ("review", BenchStep::Review)] {
+            c.bench_function(&format!("{}/{}", name, step_name), |b| {
+                b.iter(|| tui::bench_frames(diff.clone(), &config, (160, 50), step, 20).unwrap())
+            });
+        }
+    }
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = render
+}
+criterion_main!(benches);
@@ -1,6 +1,12 @@
 //! CLI 인자 파싱
 
+use crate::config::{self, Config};
+use crate::model::Diff;
+use crate::parse::ParseOptions;
+use crate::script;
+use anyhow::{Context, Result};
 use clap::Parser;
+use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
 #[command(
@@ -30,7 +36,43 @@ pub struct Cli {
     #[arg(long)]
     pub json: bool,
 
-    /// Filter changes to specific path (optional, e.g., diffy -- src/main.rs)
+    /// Accept all hunks in files matching GLOB without opening the TUI (repeatable)
+    #[arg(long, value_name = "GLOB")]
+    pub accept: Vec<String>,
+
+    /// Reject all hunks in files matching GLOB without opening the TUI (repeatable, wins over --accept)
+    #[arg(long, value_name = "GLOB")]
+    pub reject: Vec<String>,
+
+    /// Replay decisions from a previous --json output without opening the TUI
+    #[arg(long, value_name = "FILE")]
+    pub decisions: Option<PathBuf>,
+
+    /// Restore decisions, comments and viewed marks from a previous --json output
+    /// (or :write FILE) and continue the review in the TUI
+    #[arg(long, value_name = "FILE", conflicts_with = "decisions")]
+    pub resume: Option<PathBuf>,
+
+    /// Collapse hunks that only change whitespace
+    #[arg(short = 'w', long)]
+    pub ignore_whitespace: bool,
+
+    /// Collapse hunks that only add or remove blank lines
+    #[arg(long)]
+    pub ignore_blank_lines: bool,
+
+    /// Fail on malformed input (bad hunk headers, line counts that do not match the header)
+    /// instead of reviewing what could be parsed
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Strip N leading directories from file paths, like `patch -pN`
+    /// (default: strip git's a/ b/ or the two top directories of `diff -r`)
+    #[arg(short = 'p', long = "strip", value_name = "N")]
+    pub strip: Option<usize>,
+
+    /// Filter changes to specific path (optional, e.g., diffy -- src/main.rs),
+    /// or `-` to read the diff from stdin
     pub path: Option<String>,
 }
 
@@ -49,3 +91,53 @@ pub struct DiffRange {
     #[arg(long = "ref", value_name = "REF")]
     pub git_ref: Option<String>,
 }
+
+impl Cli {
+    /// 설정 파일을 읽고 CLI 플래그로 덮어쓴다
+    pub fn load_config(&self) -> Config {
+        let mut config = config::load();
+        config.defaults.ignore_whitespace |= self.ignore_whitespace;
+        config.defaults.ignore_blank_lines |= self.ignore_blank_lines;
+        config
+    }
+
+    /// diff 파싱 옵션
+    pub fn parse_options(&self) -> ParseOptions {
+        ParseOptions {
+            strict: self.strict,
+            strip: self.strip,
+        }
+    }
+
+    /// TTY 없이 결정만 적용하는 스크립트 모드인지
+    pub fn is_scripted(&self) -> bool {
+        !self.accept.is_empty() || !self.reject.is_empty() || self.decisions.is_some()
+    }
+
+    /// `--decisions`/`--resume` 파일의 결정을 diff에 적용한다 (없으면 그대로)
+    pub fn apply_saved_decisions(&self, diff: &mut Diff) -> Result<()> {
+        let Some(path) = self.decisions.as_ref().or(self.resume.as_ref()) else {
+            return Ok(());
+        };
+        let file = std::fs::File::open(path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        let report = script::apply_decisions(diff, std::io::BufReader::new(file))?;
+        if report.unmatched > 0 {
+            eprintln!(
+                "[diffy] warning: {} decisions did not match any hunk.",
+                report.unmatched
+            );
+        }
+        Ok(())
+    }
+
+    /// `diffy -`: diff를 stdin에서 읽는다
+    pub fn reads_stdin(&self) -> bool {
+        self.path.as_deref() == Some("-")
+    }
+
+    /// `--staged`/`--head`/`--ref` 중 하나로 git diff 범위를 지정했는지
+    pub fn has_diff_range(&self) -> bool {
+        self.diff_range.staged || self.diff_range.head || self.diff_range.git_ref.is_some()
+    }
+}
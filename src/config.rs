@@ -1,13 +1,30 @@
 //! 설정 파일 지원 (~/.config/diffy/config.toml)
 
+use crate::rules::Rule;
 use serde::Deserialize;
 use std::path::PathBuf;
 
+/// 저장소별 설정 파일 이름 (저장소 루트 기준)
+pub const REPO_CONFIG_FILE: &str = ".diffy.toml";
+
 #[derive(Debug, Clone, Default, Deserialize)]
 #[serde(default)]
 pub struct Config {
     #[serde(default)]
     pub defaults: Defaults,
+    #[serde(default)]
+    pub hook: HookConfig,
+    /// 자동 accept/reject 정책 규칙 (`[[rules]]`)
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+}
+
+/// `--hook-mode` 동작 설정
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct HookConfig {
+    /// accepted 헌크의 non-blocking 코멘트(follow-up)도 exit code 2로 처리할지 여부
+    pub follow_ups_block: bool,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -17,6 +34,34 @@ pub struct Defaults {
     pub mouse: bool,
     pub view: ViewMode,
     pub file_tree: bool,
+    /// 긴 라인을 줄바꿈할지 여부 (false면 잘라내고 가로 스크롤)
+    pub wrap: bool,
+    /// 탭 문자를 펼칠 칸 수
+    pub tab_width: usize,
+    /// 공백 문자(탭, 줄 끝 공백, CR)를 보이게 표시
+    pub show_whitespace: bool,
+    /// 이동된 코드를 다른 색으로 표시하고 원래 위치를 주석으로 표시
+    pub color_moved: bool,
+    /// 리뷰가 끝난(accept/reject) 헌크를 헤더 한 줄로 접기
+    pub fold_reviewed: bool,
+    /// 다음 미결정 헌크로 이동(Tab)할 때 "viewed" 표시한 파일은 건너뛰기
+    pub skip_viewed: bool,
+    /// 공백만 바뀐 헌크를 접어서 표시 (`-w`)
+    pub ignore_whitespace: bool,
+    /// 빈 라인만 추가/삭제된 헌크를 접어서 표시 (`--ignore-blank-lines`)
+    pub ignore_blank_lines: bool,
+    /// 되돌리기(undo) 기록을 몇 단계까지 남길지 (0이면 끔)
+    pub undo_limit: usize,
+    /// 파일 트리와 diff 창 배치 (auto면 좁은 터미널에서 위아래로 배치)
+    pub layout: LayoutMode,
+    /// 좌우 배치에서 파일 트리 너비 (칸, 테두리 포함)
+    pub tree_width: u16,
+    /// 위아래 배치에서 파일 트리 높이 (줄, 테두리 포함)
+    pub tree_height: u16,
+    /// 현재 헌크의 코멘트와 정보를 보여주는 하단 패널 표시
+    pub panel: bool,
+    /// 하단 패널 높이 (줄, 테두리 포함)
+    pub panel_height: u16,
 }
 
 #[derive(Debug, Clone, Deserialize, PartialEq)]
@@ -26,6 +71,15 @@ pub enum ViewMode {
     SideBySide,
 }
 
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum LayoutMode {
+    #[default]
+    Auto,
+    Horizontal,
+    Vertical,
+}
+
 impl Default for Defaults {
     fn default() -> Self {
         Self {
@@ -33,27 +87,67 @@ impl Default for Defaults {
             mouse: false,
             view: ViewMode::Unified,
             file_tree: true,
+            wrap: false,
+            tab_width: 4,
+            show_whitespace: false,
+            color_moved: true,
+            fold_reviewed: true,
+            skip_viewed: true,
+            ignore_whitespace: false,
+            ignore_blank_lines: false,
+            undo_limit: 100,
+            layout: LayoutMode::Auto,
+            tree_width: 60,
+            tree_height: 12,
+            panel: false,
+            panel_height: 8,
         }
     }
 }
 
-/// Load config from ~/.config/diffy/config.toml (or XDG_CONFIG_HOME)
+/// Load config from ~/.config/diffy/config.toml (or XDG_CONFIG_HOME),
+/// plus `[[rules]]` from the repository's `.diffy.toml` as committed in HEAD
 pub fn load() -> Config {
     let path = config_path();
-    match std::fs::read_to_string(&path) {
-        Ok(contents) => match toml::from_str(&contents) {
-            Ok(config) => config,
-            Err(e) => {
-                eprintln!(
-                    "[diffy] warning: invalid config file {}: {}",
-                    path.display(),
-                    e
-                );
-                Config::default()
-            }
-        },
-        Err(_) => Config::default(), // file doesn't exist, use defaults silently
+    let mut config = std::fs::read_to_string(&path)
+        .ok() // file doesn't exist, use defaults silently
+        .and_then(|contents| parse_config(&contents, &path.display().to_string()))
+        .unwrap_or_default();
+    if let Some(repo) = read_repo_config() {
+        merge_repo_rules(&mut config, repo);
     }
+    config
+}
+
+/// 저장소 설정은 작업 트리가 아니라 HEAD에서 읽는다.
+/// 리뷰 대상 변경이 `.diffy.toml`을 고쳐 자기 헌크를 자동 accept하지 못하게 하기 위함이며,
+/// 작업 트리의 수정본이 HEAD와 다르면 무시된다는 경고를 출력한다.
+fn read_repo_config() -> Option<Config> {
+    let root = crate::git::repo_root().ok()?;
+    let committed = crate::git::show_file("HEAD", REPO_CONFIG_FILE);
+    let working = std::fs::read_to_string(root.join(REPO_CONFIG_FILE)).ok();
+    if working.is_some() && working != committed {
+        eprintln!(
+            "[diffy] warning: {} has uncommitted changes; using the version from HEAD",
+            REPO_CONFIG_FILE
+        );
+    }
+    parse_config(&committed?, &format!("HEAD:{}", REPO_CONFIG_FILE))
+}
+
+fn parse_config(contents: &str, source: &str) -> Option<Config> {
+    match toml::from_str(contents) {
+        Ok(config) => Some(config),
+        Err(e) => {
+            eprintln!("[diffy] warning: invalid config file {}: {}", source, e);
+            None
+        }
+    }
+}
+
+/// 사용자 규칙이 저장소 규칙보다 먼저 평가된다 (먼저 일치한 규칙이 적용됨)
+fn merge_repo_rules(config: &mut Config, repo: Config) {
+    config.rules.extend(repo.rules);
 }
 
 fn config_path() -> PathBuf {
@@ -80,6 +174,29 @@ mod tests {
         assert!(!config.defaults.mouse);
         assert_eq!(config.defaults.view, ViewMode::Unified);
         assert!(config.defaults.file_tree);
+        assert!(!config.defaults.wrap);
+        assert_eq!(config.defaults.tab_width, 4);
+        assert!(!config.defaults.show_whitespace);
+        assert!(config.defaults.color_moved);
+        assert!(config.defaults.fold_reviewed);
+        assert!(config.defaults.skip_viewed);
+        assert!(!config.defaults.ignore_whitespace);
+        assert_eq!(config.defaults.undo_limit, 100);
+        assert_eq!(config.defaults.layout, LayoutMode::Auto);
+        assert_eq!(config.defaults.tree_width, 60);
+        assert!(!config.defaults.panel);
+        assert!(!config.hook.follow_ups_block);
+    }
+
+    #[test]
+    fn test_parse_hook_section() {
+        let toml_str = r#"
+[hook]
+follow_ups_block = true
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert!(config.hook.follow_ups_block);
+        assert!(!config.defaults.highlight); // default
     }
 
     #[test]
@@ -90,12 +207,26 @@ highlight = true
 mouse = true
 view = "side-by-side"
 file_tree = false
+wrap = true
+tab_width = 8
+ignore_whitespace = true
+layout = "vertical"
+tree_width = 40
+panel = true
 "#;
         let config: Config = toml::from_str(toml_str).unwrap();
         assert!(config.defaults.highlight);
         assert!(config.defaults.mouse);
         assert_eq!(config.defaults.view, ViewMode::SideBySide);
         assert!(!config.defaults.file_tree);
+        assert!(config.defaults.wrap);
+        assert_eq!(config.defaults.tab_width, 8);
+        assert!(config.defaults.ignore_whitespace);
+        assert!(!config.defaults.ignore_blank_lines);
+        assert_eq!(config.defaults.layout, LayoutMode::Vertical);
+        assert_eq!(config.defaults.tree_width, 40);
+        assert!(config.defaults.panel);
+        assert_eq!(config.defaults.panel_height, 8);
     }
 
     #[test]
@@ -117,6 +248,66 @@ highlight = true
         assert!(!config.defaults.mouse);
     }
 
+    #[test]
+    fn test_parse_rules_section() {
+        let toml_str = r#"
+[[rules]]
+name = "lockfiles"
+paths = ["*.lock"]
+action = "accept"
+
+[[rules]]
+added = 'unwrap\(\)'
+kind = "modified"
+action = "reject"
+comment = "avoid unwrap"
+severity = "question"
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.rules.len(), 2);
+        assert_eq!(config.rules[0].name.as_deref(), Some("lockfiles"));
+        assert_eq!(config.rules[0].paths, vec!["*.lock"]);
+        assert_eq!(config.rules[1].added.as_deref(), Some("unwrap\\(\\)"));
+        assert_eq!(
+            config.rules[1].kind,
+            Some(crate::model::ChangeKind::Modified)
+        );
+        assert_eq!(
+            config.rules[1].severity,
+            Some(crate::model::Severity::Question)
+        );
+    }
+
+    #[test]
+    fn test_merge_user_rules_first() {
+        let mut config: Config = toml::from_str("[[rules]]\nname = \"user\"").unwrap();
+        let repo: Config = toml::from_str("[[rules]]\nname = \"repo\"").unwrap();
+        merge_repo_rules(&mut config, repo);
+        let names: Vec<_> = config
+            .rules
+            .iter()
+            .filter_map(|r| r.name.as_deref())
+            .collect();
+        assert_eq!(names, vec!["user", "repo"]);
+    }
+
+    #[test]
+    fn test_user_rule_overrides_repo_accept() {
+        use crate::model::ReviewStatus;
+        let mut config: Config =
+            toml::from_str("[[rules]]\npaths = [\"*.rs\"]\naction = \"reject\"").unwrap();
+        let repo: Config =
+            toml::from_str("[[rules]]\npaths = [\"*\"]\naction = \"accept\"").unwrap();
+        merge_repo_rules(&mut config, repo);
+
+        let mut diff = crate::parse::parse_diff(
+            "diff --git a/src/lib.rs b/src/lib.rs\n--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1,1 +1,1 @@\n-a\n+b\n",
+        )
+        .unwrap();
+        crate::rules::apply_rules(&mut diff, &config.rules).unwrap();
+        assert_eq!(diff.files[0].hunks[0].status, ReviewStatus::Rejected);
+    }
+
     #[test]
     fn test_parse_invalid_toml() {
         let result: Result<Config, _> = toml::from_str("not valid [[[ toml");
@@ -70,6 +70,20 @@ pub fn git_diff(mode: &DiffMode, path: Option<&str>) -> Result<String> {
     Ok(String::from_utf8(output.stdout)?)
 }
 
+/// `rev`의 커밋에 저장된 파일 내용 (경로는 저장소 루트 기준, 없으면 None)
+pub fn show_file(rev: &str, path: &str) -> Option<String> {
+    let output = Command::new("git")
+        .arg("show")
+        .arg(format!("{rev}:{path}"))
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()
+}
+
 /// Read file content from working tree
 pub fn read_working_file(path: &str) -> Result<Vec<String>> {
     let root = repo_root()?;
@@ -95,6 +109,13 @@ mod tests {
         assert!(root.join("Cargo.toml").exists());
     }
 
+    #[test]
+    fn test_show_file() {
+        let manifest = show_file("HEAD", "Cargo.toml").unwrap();
+        assert!(manifest.contains("[package]"));
+        assert!(show_file("HEAD", "no/such/file").is_none());
+    }
+
     #[test]
     fn test_git_diff_unstaged() {
         // unstaged diff는 에러 없이 실행돼야 함
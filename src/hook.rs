@@ -1,6 +1,7 @@
 //! Claude Code hook mode: stderr 피드백
 
-use crate::model::{Diff, DiffLine, ReviewStatus};
+use crate::config::HookConfig;
+use crate::model::{Comment, Diff, DiffLine, FileDiff, Hunk, LineAnchor, ReviewStatus, Severity};
 use anyhow::Result;
 use std::io::Write;
 
@@ -40,74 +41,167 @@ fn format_diff_lines(lines: &[DiffLine]) -> String {
     result
 }
 
-/// 리뷰 결과를 stderr로 출력한다.
-/// 모든 헌크가 accepted이면 true, rejected가 있으면 false를 반환한다.
-pub fn write_feedback(diff: &Diff, writer: &mut impl Write) -> Result<bool> {
-    let total: usize = diff.files.iter().map(|f| f.hunks.len()).sum();
+/// 코멘트를 피드백 라인으로 출력
+fn write_comment(writer: &mut impl Write, comment: &Comment) -> Result<()> {
+    // 라인 앵커가 있으면 new 라인 번호 우선, 삭제된 라인이면 old 라인 번호
+    let location = match comment.anchor {
+        Some(LineAnchor {
+            new_line: Some(n), ..
+        }) => format!(" on line {}", n),
+        Some(LineAnchor {
+            old_line: Some(n), ..
+        }) => format!(" on old line {}", n),
+        _ => String::new(),
+    };
+    writeln!(
+        writer,
+        "  [{}] comment{}: {}",
+        comment.severity.as_str(),
+        location,
+        comment.text
+    )?;
+    if !comment.tags.is_empty() {
+        writeln!(writer, "  tags: {}", comment.tags.join(", "))?;
+    }
+    Ok(())
+}
 
-    let rejected: Vec<_> = diff
-        .files
-        .iter()
-        .flat_map(|f| f.hunks.iter().map(move |h| (f, h)))
-        .filter(|(_, h)| h.status == ReviewStatus::Rejected)
-        .collect();
+/// 헌크 하나를 피드백 항목(위치, 코멘트, diff 블록)으로 출력
+fn write_hunk_entry(writer: &mut impl Write, file: &FileDiff, hunk: &Hunk) -> Result<()> {
+    let status = match hunk.status {
+        ReviewStatus::Rejected => "rejected",
+        ReviewStatus::Accepted => "accepted",
+        ReviewStatus::Pending => "pending",
+    };
+    write!(
+        writer,
+        "- {} (lines {}-{}): {}",
+        file.new_path,
+        hunk.new_start,
+        hunk.new_start + hunk.new_count.saturating_sub(1),
+        status,
+    )?;
+    match &hunk.rule {
+        Some(rule) => writeln!(writer, " (rule: {})", rule)?,
+        None => writeln!(writer)?,
+    }
 
-    if rejected.is_empty() {
-        writeln!(writer, "[diffy] all {} hunks accepted.", total)?;
-        return Ok(true);
+    for comment in &hunk.comments {
+        write_comment(writer, comment)?;
     }
 
-    let max_size = feedback_max_size();
-    let mut buffer = Vec::new();
-    let mut truncated = false;
+    // Add diff code block
+    let diff_content = format_diff_lines(&hunk.lines);
+    if !diff_content.is_empty() {
+        writeln!(writer, "  ```diff")?;
+        write!(writer, "{}", diff_content)?;
+        writeln!(writer, "  ```")?;
+    }
 
-    writeln!(&mut buffer, "[diffy review result]")?;
-    writeln!(
-        &mut buffer,
-        "rejected {} of {} hunks.\n",
-        rejected.len(),
-        total
-    )?;
+    writeln!(writer)?;
+    Ok(())
+}
 
-    for (file, hunk) in &rejected {
+/// 헌크 항목들을 최대 크기 안에서 buffer에 추가한다. 잘렸으면 true를 반환한다.
+fn write_hunk_entries(
+    buffer: &mut Vec<u8>,
+    entries: &[(&FileDiff, &Hunk)],
+    max_size: usize,
+) -> Result<bool> {
+    for (file, hunk) in entries {
         let mut hunk_buffer = Vec::new();
+        write_hunk_entry(&mut hunk_buffer, file, hunk)?;
 
-        writeln!(
-            &mut hunk_buffer,
-            "- {} (lines {}-{}): rejected",
-            file.new_path,
-            hunk.new_start,
-            hunk.new_start + hunk.new_count.saturating_sub(1),
-        )?;
-
-        if let Some(comment) = &hunk.comment {
-            writeln!(&mut hunk_buffer, "  comment: {}", comment)?;
+        // Check if adding this hunk would exceed max size
+        if buffer.len() + hunk_buffer.len() > max_size {
+            return Ok(true);
         }
 
-        // Add diff code block
-        let diff_content = format_diff_lines(&hunk.lines);
-        if !diff_content.is_empty() {
-            writeln!(&mut hunk_buffer, "  ```diff")?;
-            write!(&mut hunk_buffer, "{}", diff_content)?;
-            writeln!(&mut hunk_buffer, "  ```")?;
-        }
+        buffer.extend_from_slice(&hunk_buffer);
+    }
+    Ok(false)
+}
 
-        writeln!(&mut hunk_buffer)?;
+/// 리뷰 결과를 stderr로 출력한다.
+/// 에이전트를 멈춰야 할 피드백(rejected 헌크, blocking 코멘트, 설정에 따라 follow-up)이
+/// 없으면 true, 있으면 false를 반환한다.
+pub fn write_feedback(diff: &Diff, writer: &mut impl Write, config: &HookConfig) -> Result<bool> {
+    let total: usize = diff.files.iter().map(|f| f.hunks.len()).sum();
 
-        // Check if adding this hunk would exceed max size
-        if buffer.len() + hunk_buffer.len() > max_size {
-            truncated = true;
-            break;
+    let hunks = || {
+        diff.files
+            .iter()
+            .flat_map(|f| f.hunks.iter().map(move |h| (f, h)))
+    };
+    let blocking: Vec<_> = hunks().filter(|(_, h)| h.is_blocking()).collect();
+    let follow_ups: Vec<_> = hunks().filter(|(_, h)| h.is_follow_up()).collect();
+
+    let max_size = feedback_max_size();
+    let mut buffer = Vec::new();
+
+    if blocking.is_empty() && (follow_ups.is_empty() || !config.follow_ups_block) {
+        writeln!(&mut buffer, "[diffy] all {} hunks accepted.", total)?;
+        if let Some(summary) = &diff.summary {
+            writeln!(&mut buffer, "[diffy] summary: {}", summary)?;
+        }
+        if !follow_ups.is_empty() {
+            writeln!(
+                &mut buffer,
+                "[diffy] {} accepted hunks have non-blocking follow-ups:",
+                follow_ups.len()
+            )?;
+            writeln!(&mut buffer)?;
+            if write_hunk_entries(&mut buffer, &follow_ups, max_size)? {
+                writeln!(&mut buffer, "... (output truncated)")?;
+            }
         }
+        writer.write_all(&buffer)?;
+        return Ok(true);
+    }
 
-        buffer.extend_from_slice(&hunk_buffer);
+    let rejected = blocking
+        .iter()
+        .filter(|(_, h)| h.status == ReviewStatus::Rejected)
+        .count();
+    let blocking_comments = diff.comment_count(Severity::Blocking);
+
+    writeln!(&mut buffer, "[diffy review result]")?;
+    if let Some(summary) = &diff.summary {
+        writeln!(&mut buffer, "summary: {}", summary)?;
+    }
+    writeln!(&mut buffer, "rejected {} of {} hunks.", rejected, total)?;
+    if blocking_comments > 0 {
+        writeln!(&mut buffer, "{} blocking comments.", blocking_comments)?;
+    }
+    if !follow_ups.is_empty() {
+        writeln!(
+            &mut buffer,
+            "{} accepted hunks with follow-ups.",
+            follow_ups.len()
+        )?;
+    }
+    writeln!(&mut buffer)?;
+
+    let mut truncated = write_hunk_entries(&mut buffer, &blocking, max_size)?;
+    if !truncated && !follow_ups.is_empty() {
+        writeln!(&mut buffer, "follow-ups (accepted, non-blocking):")?;
+        truncated = write_hunk_entries(&mut buffer, &follow_ups, max_size)?;
     }
 
     if truncated {
         writeln!(&mut buffer, "... (output truncated)")?;
     }
 
-    writeln!(&mut buffer, "please fix the rejected hunks and try again.")?;
+    if rejected > 0 {
+        writeln!(&mut buffer, "please fix the rejected hunks and try again.")?;
+    } else if !blocking.is_empty() {
+        writeln!(
+            &mut buffer,
+            "please address the blocking comments and try again."
+        )?;
+    } else {
+        writeln!(&mut buffer, "please address the follow-ups and try again.")?;
+    }
 
     writer.write_all(&buffer)?;
     Ok(false)
@@ -127,7 +221,10 @@ mod tests {
             new_count,
             lines: vec![DiffLine::Added("test".to_string())],
             status,
-            comment: None,
+            comments: Vec::new(),
+            rule: None,
+            edited: false,
+            original_lines: None,
         }
     }
 
@@ -139,23 +236,23 @@ mod tests {
             raw_new_path: format!("b/{}", path),
             hunks,
             is_binary: false,
+            viewed: false,
+            deferred: None,
         }
     }
 
     #[test]
     fn test_all_accepted() {
-        let diff = Diff {
-            files: vec![make_file(
-                "src/main.rs",
-                vec![
-                    make_hunk(1, 3, ReviewStatus::Accepted),
-                    make_hunk(10, 5, ReviewStatus::Accepted),
-                ],
-            )],
-        };
+        let diff = Diff::new(vec![make_file(
+            "src/main.rs",
+            vec![
+                make_hunk(1, 3, ReviewStatus::Accepted),
+                make_hunk(10, 5, ReviewStatus::Accepted),
+            ],
+        )]);
 
         let mut output = Vec::new();
-        let result = write_feedback(&diff, &mut output).unwrap();
+        let result = write_feedback(&diff, &mut output, &HookConfig::default()).unwrap();
         assert!(result);
 
         let text = String::from_utf8(output).unwrap();
@@ -164,18 +261,16 @@ mod tests {
 
     #[test]
     fn test_some_rejected() {
-        let diff = Diff {
-            files: vec![make_file(
-                "src/main.rs",
-                vec![
-                    make_hunk(1, 3, ReviewStatus::Accepted),
-                    make_hunk(10, 5, ReviewStatus::Rejected),
-                ],
-            )],
-        };
+        let diff = Diff::new(vec![make_file(
+            "src/main.rs",
+            vec![
+                make_hunk(1, 3, ReviewStatus::Accepted),
+                make_hunk(10, 5, ReviewStatus::Rejected),
+            ],
+        )]);
 
         let mut output = Vec::new();
-        let result = write_feedback(&diff, &mut output).unwrap();
+        let result = write_feedback(&diff, &mut output, &HookConfig::default()).unwrap();
         assert!(!result);
 
         let text = String::from_utf8(output).unwrap();
@@ -185,18 +280,16 @@ mod tests {
 
     #[test]
     fn test_all_rejected() {
-        let diff = Diff {
-            files: vec![make_file(
-                "src/lib.rs",
-                vec![
-                    make_hunk(1, 2, ReviewStatus::Rejected),
-                    make_hunk(5, 3, ReviewStatus::Rejected),
-                ],
-            )],
-        };
+        let diff = Diff::new(vec![make_file(
+            "src/lib.rs",
+            vec![
+                make_hunk(1, 2, ReviewStatus::Rejected),
+                make_hunk(5, 3, ReviewStatus::Rejected),
+            ],
+        )]);
 
         let mut output = Vec::new();
-        let result = write_feedback(&diff, &mut output).unwrap();
+        let result = write_feedback(&diff, &mut output, &HookConfig::default()).unwrap();
         assert!(!result);
 
         let text = String::from_utf8(output).unwrap();
@@ -214,12 +307,10 @@ mod tests {
             DiffLine::Context("}".to_string()),
         ];
 
-        let diff = Diff {
-            files: vec![make_file("src/main.rs", vec![hunk])],
-        };
+        let diff = Diff::new(vec![make_file("src/main.rs", vec![hunk])]);
 
         let mut output = Vec::new();
-        let result = write_feedback(&diff, &mut output).unwrap();
+        let result = write_feedback(&diff, &mut output, &HookConfig::default()).unwrap();
         assert!(!result);
 
         let text = String::from_utf8(output).unwrap();
@@ -234,27 +325,173 @@ mod tests {
     #[test]
     fn test_feedback_with_comment_and_diff() {
         let mut hunk = make_hunk(12, 3, ReviewStatus::Rejected);
-        hunk.comment = Some("this breaks error handling".to_string());
+        hunk.comments.push(Comment::from_input(
+            "this breaks error handling #errors",
+            Severity::Blocking,
+        ));
         hunk.lines = vec![
             DiffLine::Removed("old line".to_string()),
             DiffLine::Added("new line".to_string()),
         ];
 
-        let diff = Diff {
-            files: vec![make_file("src/lib.rs", vec![hunk])],
-        };
+        let diff = Diff::new(vec![make_file("src/lib.rs", vec![hunk])]);
 
         let mut output = Vec::new();
-        let result = write_feedback(&diff, &mut output).unwrap();
+        let result = write_feedback(&diff, &mut output, &HookConfig::default()).unwrap();
         assert!(!result);
 
         let text = String::from_utf8(output).unwrap();
-        assert!(text.contains("comment: this breaks error handling"));
+        assert!(text.contains("[blocking] comment: this breaks error handling"));
+        assert!(text.contains("tags: errors"));
         assert!(text.contains("```diff"));
         assert!(text.contains("-old line"));
         assert!(text.contains("+new line"));
     }
 
+    #[test]
+    fn test_blocking_comment_on_accepted_hunk() {
+        let mut hunk = make_hunk(3, 2, ReviewStatus::Accepted);
+        hunk.comments
+            .push(Comment::from_input("must not ship", Severity::Blocking));
+
+        let diff = Diff::new(vec![make_file("src/lib.rs", vec![hunk])]);
+
+        let mut output = Vec::new();
+        let result = write_feedback(&diff, &mut output, &HookConfig::default()).unwrap();
+        assert!(!result);
+
+        let text = String::from_utf8(output).unwrap();
+        assert!(text.contains("rejected 0 of 1 hunks"));
+        assert!(text.contains("1 blocking comments"));
+        assert!(text.contains("- src/lib.rs (lines 3-4): accepted"));
+        assert!(text.contains("please address the blocking comments"));
+    }
+
+    #[test]
+    fn test_nit_comment_does_not_block() {
+        let mut hunk = make_hunk(3, 2, ReviewStatus::Accepted);
+        hunk.comments
+            .push(Comment::from_input("style nit", Severity::Nit));
+
+        let mut diff = Diff::new(vec![make_file("src/lib.rs", vec![hunk])]);
+        diff.summary = Some("looks good overall".to_string());
+
+        let mut output = Vec::new();
+        let result = write_feedback(&diff, &mut output, &HookConfig::default()).unwrap();
+        assert!(result);
+
+        let text = String::from_utf8(output).unwrap();
+        assert!(text.contains("all 1 hunks accepted"));
+        assert!(text.contains("summary: looks good overall"));
+        assert!(text.contains("1 accepted hunks have non-blocking follow-ups"));
+        assert!(text.contains("- src/lib.rs (lines 3-4): accepted"));
+        assert!(text.contains("[nit] comment: style nit"));
+    }
+
+    #[test]
+    fn test_follow_ups_block_when_configured() {
+        let mut hunk = make_hunk(3, 2, ReviewStatus::Accepted);
+        hunk.comments
+            .push(Comment::from_input("rename later", Severity::Nit));
+        let diff = Diff::new(vec![make_file("src/lib.rs", vec![hunk])]);
+        let config = HookConfig {
+            follow_ups_block: true,
+        };
+
+        let mut output = Vec::new();
+        let result = write_feedback(&diff, &mut output, &config).unwrap();
+        assert!(!result);
+
+        let text = String::from_utf8(output).unwrap();
+        assert!(text.contains("[diffy review result]"));
+        assert!(text.contains("1 accepted hunks with follow-ups"));
+        assert!(text.contains("[nit] comment: rename later"));
+        assert!(text.contains("please address the follow-ups"));
+    }
+
+    #[test]
+    fn test_praise_does_not_block() {
+        let mut hunk = make_hunk(3, 2, ReviewStatus::Accepted);
+        hunk.comments
+            .push(Comment::from_input("nice cleanup", Severity::Praise));
+        let diff = Diff::new(vec![make_file("src/lib.rs", vec![hunk])]);
+        let config = HookConfig {
+            follow_ups_block: true,
+        };
+
+        let mut output = Vec::new();
+        let result = write_feedback(&diff, &mut output, &config).unwrap();
+        assert!(result);
+
+        let text = String::from_utf8(output).unwrap();
+        assert!(text.contains("all 1 hunks accepted"));
+        assert!(!text.contains("follow-ups"));
+    }
+
+    #[test]
+    fn test_follow_ups_listed_after_blocking() {
+        let mut accepted = make_hunk(3, 2, ReviewStatus::Accepted);
+        accepted
+            .comments
+            .push(Comment::from_input("rename later", Severity::Nit));
+        let rejected = make_hunk(20, 2, ReviewStatus::Rejected);
+        let diff = Diff::new(vec![make_file("src/lib.rs", vec![accepted, rejected])]);
+
+        let mut output = Vec::new();
+        let result = write_feedback(&diff, &mut output, &HookConfig::default()).unwrap();
+        assert!(!result);
+
+        let text = String::from_utf8(output).unwrap();
+        let rejected_at = text.find("(lines 20-21): rejected").unwrap();
+        let follow_up_at = text.find("follow-ups (accepted, non-blocking):").unwrap();
+        assert!(rejected_at < follow_up_at);
+        assert!(text.contains("rename later"));
+        assert!(text.contains("please fix the rejected hunks"));
+    }
+
+    #[test]
+    fn test_feedback_shows_rule() {
+        let mut hunk = make_hunk(5, 1, ReviewStatus::Rejected);
+        hunk.rule = Some("no-unwrap".to_string());
+        let diff = Diff::new(vec![make_file("src/lib.rs", vec![hunk])]);
+
+        let mut output = Vec::new();
+        write_feedback(&diff, &mut output, &HookConfig::default()).unwrap();
+
+        let text = String::from_utf8(output).unwrap();
+        assert!(text.contains("- src/lib.rs (lines 5-5): rejected (rule: no-unwrap)"));
+    }
+
+    #[test]
+    fn test_feedback_line_anchored_comments() {
+        let mut hunk = make_hunk(12, 3, ReviewStatus::Rejected);
+        hunk.old_start = 10;
+        hunk.lines = vec![
+            DiffLine::Context("ctx".to_string()),
+            DiffLine::Removed("old line".to_string()),
+            DiffLine::Added("new line".to_string()),
+        ];
+        let on_removed = hunk.anchor_at(1);
+        let on_added = hunk.anchor_at(2);
+        hunk.comments.push(
+            Comment::from_input("why drop this?", Severity::Question).with_anchor(on_removed),
+        );
+        hunk.comments
+            .push(Comment::from_input("wrong name", Severity::Blocking).with_anchor(on_added));
+        hunk.comments
+            .push(Comment::from_input("split this hunk", Severity::Nit));
+
+        let diff = Diff::new(vec![make_file("src/lib.rs", vec![hunk])]);
+
+        let mut output = Vec::new();
+        write_feedback(&diff, &mut output, &HookConfig::default()).unwrap();
+
+        let text = String::from_utf8(output).unwrap();
+        assert!(text.contains("[question] comment on old line 11: why drop this?"));
+        assert!(text.contains("[blocking] comment on line 13: wrong name"));
+        assert!(text.contains("[nit] comment: split this hunk"));
+    }
+
     #[test]
     fn test_feedback_truncation() {
         // Create many rejected hunks with long lines to exceed 10KB
@@ -278,12 +515,10 @@ mod tests {
             hunks.push(hunk);
         }
 
-        let diff = Diff {
-            files: vec![make_file("src/large.rs", hunks)],
-        };
+        let diff = Diff::new(vec![make_file("src/large.rs", hunks)]);
 
         let mut output = Vec::new();
-        let result = write_feedback(&diff, &mut output).unwrap();
+        let result = write_feedback(&diff, &mut output, &HookConfig::default()).unwrap();
         assert!(!result);
 
         let text = String::from_utf8(output).unwrap();
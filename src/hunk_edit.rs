@@ -0,0 +1,496 @@
+//! 헌크 수동 분할/편집 (`git add -p`의 `s`/`e`)
+//!
+//! 파서는 context 경계에서 이미 헌크를 나누므로, 수동 분할은 변경 라인 묶음
+//! 한가운데를 자른다. 잘린 조각은 context 없이 맞닿아 있어 따로 적용할 수 없다.
+//! 그래서 출력할 때 맞닿은 조각들을 다시 한 헌크로 합치고, 적용하지 않는 조각은
+//! context로 바꾼다 (`join_pieces`).
+//!
+//! 고친 헌크는 원래 라인을 기억해 두었다가, `--apply`에서 작업 트리를 고친 내용대로
+//! 바꾸는 데 쓴다 (`revert_pieces`).
+
+use crate::model::{Comment, DiffLine, Hunk, ReviewStatus};
+use anyhow::{Result, bail};
+use std::borrow::Cow;
+
+/// 적용하지 않는 조각을 어느 쪽 내용으로 채울지
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Base {
+    /// 원래 파일에 적용하는 patch (출력): 삭제 라인은 context, 추가 라인은 버림
+    Old,
+    /// 작업 트리에 적용하는 역방향 patch: 추가 라인은 context, 삭제 라인은 버림
+    New,
+}
+
+fn is_change(line: &DiffLine) -> bool {
+    matches!(line, DiffLine::Added(_) | DiffLine::Removed(_))
+}
+
+/// context와 삭제 라인 (원래 파일 쪽 내용)
+fn old_side(lines: &[DiffLine]) -> Vec<&str> {
+    lines
+        .iter()
+        .filter_map(|l| match l {
+            DiffLine::Context(s) | DiffLine::Removed(s) => Some(s.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// `at` 라인 앞에서 헌크를 둘로 나눈다. 양쪽 모두 변경 라인이 있어야 한다.
+/// 라인 코멘트는 그 라인이 들어간 조각으로, 헌크 코멘트는 앞 조각으로 간다.
+/// 이미 고친 헌크는 나누지 않는다 (원래 라인을 조각별로 나눌 수 없음).
+pub fn split_at(hunk: &Hunk, at: usize) -> Option<(Hunk, Hunk)> {
+    if hunk.original_lines.is_some() {
+        return None;
+    }
+    // "\ No newline" 표시는 앞 라인과 떨어지지 않게
+    let at = if matches!(hunk.lines.get(at), Some(DiffLine::NoNewline)) {
+        at + 1
+    } else {
+        at
+    };
+    let (head, tail) = hunk.lines.split_at(at.min(hunk.lines.len()));
+    if !head.iter().any(is_change) || !tail.iter().any(is_change) {
+        return None;
+    }
+
+    let piece = |lines: &[DiffLine], old_start, new_start| {
+        let mut piece = Hunk {
+            lines: lines.to_vec(),
+            old_start,
+            new_start,
+            comments: Vec::new(),
+            ..hunk.clone()
+        };
+        piece.recount();
+        piece
+    };
+    let mut first = piece(head, hunk.old_start, hunk.new_start);
+    let mut second = piece(
+        tail,
+        first.old_start + first.old_count,
+        first.new_start + first.new_count,
+    );
+    for comment in &hunk.comments {
+        match comment.line_index() {
+            Some(li) if li >= at => {
+                let anchor = second.anchor_at(li - at);
+                second.comments.push(comment.clone().with_anchor(anchor));
+            }
+            _ => first.comments.push(comment.clone()),
+        }
+    }
+    Some((first, second))
+}
+
+/// 에디터에 보여줄 헌크 텍스트
+pub fn edit_template(hunk: &Hunk) -> String {
+    let mut text = String::from(
+        "# Edit the hunk below, then save and quit.\n\
+         # To drop a '-' line, turn its '-' into ' ' (context).\n\
+         # To drop a '+' line, delete it. You may add '+' lines.\n\
+         # Context and '-' lines must stay as they are.\n\
+         # Lines starting with '#' are ignored; the header is recomputed.\n",
+    );
+    text.push_str(&hunk.header);
+    text.push('\n');
+    for line in &hunk.lines {
+        match line {
+            DiffLine::Context(s) => text.push_str(&format!(" {}\n", s)),
+            DiffLine::Added(s) => text.push_str(&format!("+{}\n", s)),
+            DiffLine::Removed(s) => text.push_str(&format!("-{}\n", s)),
+            DiffLine::NoNewline => text.push_str("\\ No newline at end of file\n"),
+        }
+    }
+    text
+}
+
+/// 편집한 텍스트를 검사해 새 헌크를 만든다.
+/// 원래 파일 쪽(context, 삭제 라인)은 그대로여야 하고 변경이 하나는 남아야 한다.
+/// 라인 번호가 바뀌므로 라인 코멘트는 헌크 코멘트가 된다.
+pub fn apply_edit(hunk: &Hunk, text: &str) -> Result<Hunk> {
+    let mut lines = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        if line.starts_with('#') || line.starts_with("@@") {
+            continue;
+        }
+        let body = line.get(1..).unwrap_or("").to_string();
+        lines.push(match line.chars().next() {
+            // 에디터가 빈 context 라인의 공백을 지운 경우
+            None => DiffLine::Context(String::new()),
+            Some(' ') => DiffLine::Context(body),
+            Some('+') => DiffLine::Added(body),
+            Some('-') => DiffLine::Removed(body),
+            Some('\\') => DiffLine::NoNewline,
+            Some(_) => bail!("line {}: expected ' ', '+', '-' or '#'", n + 1),
+        });
+    }
+    if old_side(&lines) != old_side(&hunk.lines) {
+        bail!("context and '-' lines must stay as in the original hunk");
+    }
+    if !lines.iter().any(is_change) {
+        bail!("the edited hunk has no changes");
+    }
+
+    let mut edited = Hunk {
+        lines,
+        comments: hunk
+            .comments
+            .iter()
+            .map(|c| Comment {
+                anchor: None,
+                ..c.clone()
+            })
+            .collect(),
+        edited: true,
+        original_lines: Some(
+            hunk.original_lines
+                .clone()
+                .unwrap_or_else(|| hunk.lines.clone()),
+        ),
+        ..hunk.clone()
+    };
+    edited.recount();
+    Ok(edited)
+}
+
+/// 변경 라인으로 끝나는 헌크 바로 뒤에 변경 라인으로 시작하는 헌크가 맞닿아 있는지
+/// (수동 분할된 조각). 파서가 만든 헌크는 경계에 항상 context가 있다.
+fn touches(a: &Hunk, b: &Hunk) -> bool {
+    let a_end = a
+        .lines
+        .iter()
+        .rev()
+        .find(|l| !matches!(l, DiffLine::NoNewline));
+    let seam_has_change = a_end.is_some_and(is_change) || b.lines.first().is_some_and(is_change);
+    seam_has_change
+        && b.old_start == a.old_start + a.old_count
+        && b.new_start == a.new_start + a.new_count
+}
+
+/// 조각을 `base` 쪽 내용으로 바꾼다
+fn neutralize(lines: &[DiffLine], base: Base, out: &mut Vec<DiffLine>) {
+    let mut dropped = false;
+    for line in lines {
+        match (line, base) {
+            (DiffLine::Removed(s), Base::Old) | (DiffLine::Added(s), Base::New) => {
+                out.push(DiffLine::Context(s.clone()));
+                dropped = false;
+            }
+            (DiffLine::Added(_), Base::Old) | (DiffLine::Removed(_), Base::New) => {
+                dropped = true;
+            }
+            // 버린 라인에 붙은 "\ No newline"도 버린다
+            (DiffLine::NoNewline, _) if dropped => {}
+            _ => {
+                out.push(line.clone());
+                dropped = false;
+            }
+        }
+    }
+}
+
+/// 맞닿은 조각끼리 묶은 헌크 묶음들 (대부분 헌크 하나짜리)
+fn runs(hunks: &[Hunk]) -> impl Iterator<Item = &[Hunk]> {
+    let mut i = 0;
+    std::iter::from_fn(move || {
+        if i >= hunks.len() {
+            return None;
+        }
+        let mut end = i + 1;
+        while end < hunks.len() && touches(&hunks[end - 1], &hunks[end]) {
+            end += 1;
+        }
+        let run = &hunks[i..end];
+        i = end;
+        Some(run)
+    })
+}
+
+fn join_run<'a>(
+    run: &'a [Hunk],
+    keep: &impl Fn(&Hunk) -> bool,
+    base: Base,
+) -> Option<Cow<'a, Hunk>> {
+    if !run.iter().any(keep) {
+        return None;
+    }
+    if run.len() == 1 {
+        return Some(Cow::Borrowed(&run[0]));
+    }
+    let mut lines = Vec::new();
+    for piece in run {
+        if keep(piece) {
+            lines.extend(piece.lines.iter().cloned());
+        } else {
+            neutralize(&piece.lines, base, &mut lines);
+        }
+    }
+    let mut joined = Hunk {
+        lines,
+        comments: Vec::new(),
+        ..run[0].clone()
+    };
+    joined.recount();
+    Some(Cow::Owned(joined))
+}
+
+/// 한 파일의 헌크 중 `keep`인 것만 patch로 낼 헌크 목록.
+/// 수동 분할로 맞닿은 조각들은 하나로 합치고, `keep`이 아닌 조각은 `base` 쪽
+/// 내용(context)으로 바꾼다. 맞닿은 조각이 없으면 `keep`인 헌크를 그대로 돌려준다.
+pub fn join_pieces(hunks: &[Hunk], keep: impl Fn(&Hunk) -> bool, base: Base) -> Vec<Cow<'_, Hunk>> {
+    runs(hunks)
+        .filter_map(|run| join_run(run, &keep, base))
+        .collect()
+}
+
+/// `--apply`에서 작업 트리에 거꾸로 적용할 헌크 목록 (정방향 헌크, 뒤집는 건 호출하는 쪽).
+/// 보통은 rejected 헌크를 `join_pieces`로 합친 것이다. 고친 헌크가 낀 묶음은 작업
+/// 트리 내용을 결과 내용으로 바꾸는 헌크 하나가 된다: accepted는 고친 내용,
+/// rejected는 원래 파일 내용, pending은 그대로.
+pub fn revert_pieces(hunks: &[Hunk]) -> Vec<Cow<'_, Hunk>> {
+    let rejected = |h: &Hunk| h.status == ReviewStatus::Rejected;
+    runs(hunks)
+        .filter_map(|run| {
+            if run.iter().any(|h| h.original_lines.is_some()) {
+                rewrite_worktree(run).map(Cow::Owned)
+            } else {
+                join_run(run, &rejected, Base::New)
+            }
+        })
+        .collect()
+}
+
+/// 작업 트리 내용(`+` 쪽)을 결과 내용(`-` 쪽)으로 바꾸는 헌크.
+/// 앞뒤 공통 라인은 context로 남긴다.
+fn rewrite_worktree(run: &[Hunk]) -> Option<Hunk> {
+    let mut worktree = Vec::new();
+    let mut result = Vec::new();
+    for piece in run {
+        let current = piece.original_lines.as_ref().unwrap_or(&piece.lines);
+        neutralize(current, Base::New, &mut worktree);
+        match piece.status {
+            ReviewStatus::Accepted => neutralize(&piece.lines, Base::New, &mut result),
+            ReviewStatus::Rejected => neutralize(&piece.lines, Base::Old, &mut result),
+            ReviewStatus::Pending => neutralize(current, Base::New, &mut result),
+        }
+    }
+    if worktree == result {
+        return None;
+    }
+
+    let is_marker =
+        |lines: &[DiffLine], i: usize| matches!(lines.get(i), Some(DiffLine::NoNewline));
+    let common =
+        |a: &[DiffLine], b: &[DiffLine]| a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let mut prefix = common(&worktree, &result);
+    // "\ No newline"이 앞 라인과 떨어지지 않게
+    if prefix > 0 && (is_marker(&worktree, prefix) || is_marker(&result, prefix)) {
+        prefix -= 1;
+    }
+    let max_suffix = worktree.len().min(result.len()) - prefix;
+    let mut suffix = worktree
+        .iter()
+        .rev()
+        .zip(result.iter().rev())
+        .take(max_suffix)
+        .take_while(|(x, y)| x == y)
+        .count();
+    if suffix > 0 && is_marker(&worktree, worktree.len() - suffix) {
+        suffix -= 1;
+    }
+
+    let as_change = |lines: &[DiffLine], change: fn(String) -> DiffLine| {
+        lines
+            .iter()
+            .map(|l| match l {
+                DiffLine::Context(s) => change(s.clone()),
+                other => other.clone(),
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut lines = worktree[..prefix].to_vec();
+    lines.extend(as_change(
+        &result[prefix..result.len() - suffix],
+        DiffLine::Removed,
+    ));
+    lines.extend(as_change(
+        &worktree[prefix..worktree.len() - suffix],
+        DiffLine::Added,
+    ));
+    lines.extend_from_slice(&worktree[worktree.len() - suffix..]);
+
+    let mut hunk = Hunk {
+        lines,
+        comments: Vec::new(),
+        original_lines: None,
+        ..run[0].clone()
+    };
+    hunk.recount();
+    Some(hunk)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ReviewStatus, Severity};
+    use crate::parse::parse_diff;
+    use indoc::indoc;
+
+    fn sample() -> Hunk {
+        let diff = parse_diff(indoc! {"
+            diff --git a/a.rs b/a.rs
+            --- a/a.rs
+            +++ b/a.rs
+            @@ -1,4 +1,4 @@ fn main() {
+             start
+            -one
+            -two
+            +ONE
+            +TWO
+             end
+        "})
+        .unwrap();
+        diff.files[0].hunks[0].clone()
+    }
+
+    fn new_side(lines: &[DiffLine]) -> Vec<&str> {
+        lines
+            .iter()
+            .filter_map(|l| match l {
+                DiffLine::Context(s) | DiffLine::Added(s) => Some(s.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn text(hunk: &Hunk) -> String {
+        edit_template(hunk)
+            .lines()
+            .filter(|l| !l.starts_with('#'))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_split_at_cursor() {
+        let mut hunk = sample();
+        let anchor = hunk.anchor_at(4);
+        hunk.comments
+            .push(Comment::from_input("on TWO", Severity::Nit).with_anchor(anchor));
+        hunk.comments
+            .push(Comment::from_input("overall", Severity::Nit));
+
+        // Nothing to split off before the first change
+        assert!(split_at(&hunk, 1).is_none());
+        let (first, second) = split_at(&hunk, 3).unwrap();
+        assert_eq!(
+            text(&first),
+            "@@ -1,3 +1,1 @@ fn main() {\n start\n-one\n-two"
+        );
+        assert_eq!(
+            text(&second),
+            "@@ -4,1 +2,3 @@ fn main() {\n+ONE\n+TWO\n end"
+        );
+        assert_eq!(first.comments[0].text, "overall");
+        assert_eq!(second.comments[0].line_index(), Some(1));
+        assert_eq!(second.comments[0].anchor.unwrap().new_line, Some(3));
+    }
+
+    #[test]
+    fn test_join_pieces_neutralizes_skipped_pieces() {
+        let (mut first, mut second) = split_at(&sample(), 3).unwrap();
+        first.status = ReviewStatus::Accepted;
+        second.status = ReviewStatus::Rejected;
+        let hunks = vec![first, second];
+
+        // Output: only the removal, the rejected additions dropped
+        let out = join_pieces(&hunks, |h| h.status == ReviewStatus::Accepted, Base::Old);
+        assert_eq!(out.len(), 1);
+        assert_eq!(
+            text(&out[0]),
+            "@@ -1,4 +1,2 @@ fn main() {\n start\n-one\n-two\n end"
+        );
+
+        // Reverse patch: the accepted removal stays (nothing to restore there)
+        let rev = join_pieces(&hunks, |h| h.status == ReviewStatus::Rejected, Base::New);
+        assert_eq!(
+            text(&rev[0]),
+            "@@ -1,2 +1,4 @@ fn main() {\n start\n+ONE\n+TWO\n end"
+        );
+
+        // Both accepted: the original hunk again
+        let out = join_pieces(&hunks, |_| true, Base::Old);
+        assert_eq!(text(&out[0]), text(&sample()));
+    }
+
+    #[test]
+    fn test_context_separated_hunks_are_not_joined() {
+        let mut a = sample();
+        a.status = ReviewStatus::Accepted;
+        let mut b = sample();
+        b.old_start = 5;
+        b.new_start = 5;
+        let hunks = vec![a, b];
+        let out = join_pieces(&hunks, |h| h.status == ReviewStatus::Accepted, Base::Old);
+        assert_eq!(out.len(), 1);
+        assert!(matches!(out[0], Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_apply_edit() {
+        let hunk = sample();
+        // Keep "one", replace "two" with a different line
+        let edited = apply_edit(
+            &hunk,
+            "# comment\n@@ -1,4 +1,4 @@\n start\n one\n-two\n+deux\n end\n",
+        )
+        .unwrap();
+        assert!(edited.edited);
+        assert_eq!(
+            text(&edited),
+            "@@ -1,4 +1,4 @@ fn main() {\n start\n one\n-two\n+deux\n end"
+        );
+
+        let err = |text| apply_edit(&hunk, text).unwrap_err().to_string();
+        assert_eq!(
+            err(" start\n-one\n+ONE\n end\n"),
+            "context and '-' lines must stay as in the original hunk"
+        );
+        assert_eq!(
+            err(" start\n one\n two\n end\n"),
+            "the edited hunk has no changes"
+        );
+        assert_eq!(
+            err(" start\n-one\n-two\n*x\n end\n"),
+            "line 4: expected ' ', '+', '-' or '#'"
+        );
+    }
+
+    #[test]
+    fn test_revert_pieces_rewrites_worktree_to_edit() {
+        let mut edited = apply_edit(&sample(), " start\n-one\n two\n+ONE\n end\n").unwrap();
+        assert_eq!(edited.original_lines.as_ref(), Some(&sample().lines));
+        // An edited hunk cannot be split any more
+        assert!(split_at(&edited, 3).is_none());
+
+        // Pending: the working tree stays as it is
+        assert!(revert_pieces(std::slice::from_ref(&edited)).is_empty());
+
+        // Accepted: the working tree (ONE, TWO) becomes the edited new side (two, ONE)
+        edited.status = ReviewStatus::Accepted;
+        let revert = revert_pieces(std::slice::from_ref(&edited));
+        assert_eq!(revert.len(), 1);
+        let (old, new) = (old_side(&revert[0].lines), new_side(&revert[0].lines));
+        assert_eq!(old, vec!["start", "two", "ONE", "end"]);
+        assert_eq!(new, vec!["start", "ONE", "TWO", "end"]);
+
+        // Rejected: back to the original file
+        edited.status = ReviewStatus::Rejected;
+        let revert = revert_pieces(std::slice::from_ref(&edited));
+        assert_eq!(
+            old_side(&revert[0].lines),
+            vec!["start", "one", "two", "end"]
+        );
+    }
+}
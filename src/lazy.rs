@@ -0,0 +1,385 @@
+//! 큰 diff 지연 로딩
+//!
+//! 파이프 입력을 임시 스풀 파일에 복사하면서 백그라운드에서 파일 단위로 파싱한다.
+//! 본문이 큰 파일은 헌크 헤더와 리뷰 상태만 남기고 라인은 버린 뒤, TUI에서
+//! 그 파일을 열 때 스풀에서 다시 파싱한다.
+
+use crate::model::{FileDiff, Hunk};
+use crate::parse::{DiffReader, ParseError, ParseOptions};
+use crate::rules::{Rule, RuleSet};
+use anyhow::{Context, Result, bail};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// 본문이 이보다 큰 파일은 열 때까지 라인을 메모리에 두지 않는다
+pub const DEFER_BYTES: u64 = 256 * 1024;
+
+/// 입력 사본을 담는 임시 파일. 마지막 참조가 사라지면 지운다.
+#[derive(Debug)]
+pub struct Spool {
+    path: PathBuf,
+    reader: Mutex<File>,
+}
+
+impl Spool {
+    /// 스풀과 거기에 쓸 핸들
+    fn create() -> Result<(Arc<Self>, File)> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "diffy-{}-{}.diff",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let writer = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .with_context(|| format!("failed to create {}", path.display()))?;
+        let reader = File::open(&path)?;
+        let spool = Self {
+            path,
+            reader: Mutex::new(reader),
+        };
+        Ok((Arc::new(spool), writer))
+    }
+
+    fn read(&self, range: Range<u64>) -> Result<String> {
+        let mut file = self
+            .reader
+            .lock()
+            .map_err(|_| anyhow::anyhow!("spool lock poisoned"))?;
+        file.seek(SeekFrom::Start(range.start))?;
+        let mut text = String::new();
+        Read::take(&mut *file, range.end - range.start).read_to_string(&mut text)?;
+        Ok(text)
+    }
+}
+
+impl Drop for Spool {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// 스풀에 남겨 둔 파일 본문
+#[derive(Clone, Debug)]
+pub struct Deferred {
+    spool: Arc<Spool>,
+    range: Range<u64>,
+    /// `range` 앞까지의 입력 줄 수 (다시 파싱할 때 경고의 줄 번호를 맞춘다)
+    first_line: usize,
+    /// 처음 파싱할 때 쓴 옵션 (`--strict`, `-p`)
+    options: ParseOptions,
+    /// 헌크별 추가+삭제 라인 수 (진행률용)
+    pub changed: Vec<usize>,
+    pub added: usize,
+    pub removed: usize,
+}
+
+impl Deferred {
+    /// 파일의 헌크 라인을 버리고 `range`의 스풀 위치만 남긴다
+    fn defer(
+        file: &mut FileDiff,
+        spool: &Arc<Spool>,
+        range: Range<u64>,
+        first_line: usize,
+        options: ParseOptions,
+    ) {
+        let added = file.lines_added();
+        let removed = file.lines_removed();
+        let changed = file
+            .hunks
+            .iter()
+            .map(|h| h.added_lines().count() + h.removed_lines().count())
+            .collect();
+        for hunk in &mut file.hunks {
+            hunk.lines = Vec::new();
+        }
+        file.deferred = Some(Self {
+            spool: Arc::clone(spool),
+            range,
+            first_line,
+            options,
+            changed,
+            added,
+            removed,
+        });
+    }
+
+    /// 본문을 세션의 파싱 옵션으로 다시 파싱해 `stubs`(라인 없는 헌크)의 리뷰 상태를 옮긴 헌크와,
+    /// 그 파싱에서 나온 경고 (줄 번호는 원래 입력 기준)
+    pub fn load(&self, stubs: &[Hunk]) -> Result<(Vec<Hunk>, Vec<ParseError>)> {
+        let text = self.spool.read(self.range.clone())?;
+        let mut reader =
+            DiffReader::with_options(text.as_bytes(), self.options).starting_at(self.first_line);
+        let Some((file, _)) = reader.next_file()? else {
+            bail!("spooled file body is empty");
+        };
+        let warnings = reader.take_warnings();
+        if file.hunks.len() != stubs.len() {
+            bail!(
+                "spooled body of {} has {} hunks, expected {}",
+                file.path(),
+                file.hunks.len(),
+                stubs.len()
+            );
+        }
+        let hunks = file
+            .hunks
+            .into_iter()
+            .zip(stubs)
+            .map(|(mut hunk, stub)| {
+                hunk.status = stub.status;
+                hunk.comments = stub.comments.clone();
+                hunk.rule = stub.rule.clone();
+                hunk
+            })
+            .collect();
+        Ok((hunks, warnings))
+    }
+}
+
+/// 읽은 바이트를 그대로 스풀에 복사하는 reader
+struct Tee<R> {
+    inner: R,
+    copy: BufWriter<File>,
+}
+
+impl<R: Read> Read for Tee<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.copy.write_all(&buf[..n])?;
+        Ok(n)
+    }
+}
+
+/// 로더 스레드가 보내는 것
+enum Loaded {
+    File(FileDiff),
+    Warning(ParseError),
+}
+
+/// 백그라운드에서 입력을 읽어 파싱한 파일을 하나씩 넘겨주는 로더
+pub struct Loader {
+    rx: Receiver<Result<Loaded>>,
+    done: bool,
+    /// 받았지만 아직 꺼내지 않은 파싱 경고
+    warnings: Vec<ParseError>,
+}
+
+impl Loader {
+    /// `input`을 읽기 시작한다. 규칙은 헌크 라인이 메모리에 있을 때 적용한다.
+    pub fn spawn<R: Read + Send + 'static>(
+        input: R,
+        rules: Vec<Rule>,
+        options: ParseOptions,
+    ) -> Result<Self> {
+        RuleSet::compile(&rules)?;
+        let (spool, writer) = Spool::create()?;
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let tee = Tee {
+                inner: input,
+                copy: BufWriter::new(writer),
+            };
+            let send = |loaded| tx.send(Ok(loaded)).is_ok();
+            if let Err(e) = load(BufReader::new(tee), &spool, &rules, options, send) {
+                let _ = tx.send(Err(e));
+            }
+        });
+        Ok(Self {
+            rx,
+            done: false,
+            warnings: Vec::new(),
+        })
+    }
+
+    /// 입력을 끝까지 읽었는지
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// 다음 파일을 기다린다. 입력이 끝나면 None
+    pub fn next_file(&mut self) -> Result<Option<FileDiff>> {
+        while !self.done {
+            match self.rx.recv() {
+                Ok(Ok(Loaded::File(file))) => return Ok(Some(file)),
+                Ok(Ok(Loaded::Warning(warning))) => self.warnings.push(warning),
+                Ok(Err(e)) => {
+                    self.done = true;
+                    return Err(e);
+                }
+                Err(_) => self.done = true,
+            }
+        }
+        Ok(None)
+    }
+
+    /// 지금까지 파싱된 파일 (기다리지 않는다)
+    pub fn try_files(&mut self) -> Result<Vec<FileDiff>> {
+        let mut files = Vec::new();
+        while !self.done {
+            match self.rx.try_recv() {
+                Ok(Ok(Loaded::File(file))) => files.push(file),
+                Ok(Ok(Loaded::Warning(warning))) => self.warnings.push(warning),
+                Ok(Err(e)) => {
+                    self.done = true;
+                    return Err(e);
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => self.done = true,
+            }
+        }
+        Ok(files)
+    }
+
+    /// 지금까지 받은 파싱 경고를 꺼낸다
+    pub fn take_warnings(&mut self) -> Vec<ParseError> {
+        std::mem::take(&mut self.warnings)
+    }
+}
+
+/// 파일을 하나씩 파싱해 `send`로 넘긴다. 큰 본문은 스풀로 미룬다.
+/// `send`가 false를 반환하면 (받는 쪽이 없으면) 멈춘다.
+fn load<R: Read>(
+    reader: BufReader<Tee<R>>,
+    spool: &Arc<Spool>,
+    rules: &[Rule],
+    options: ParseOptions,
+    mut send: impl FnMut(Loaded) -> bool,
+) -> Result<()> {
+    let rules = RuleSet::compile(rules)?;
+    let mut reader = DiffReader::with_options(reader, options);
+    loop {
+        let first_line = reader.line();
+        let next = reader.next_file()?;
+        for warning in reader.take_warnings() {
+            if !send(Loaded::Warning(warning)) {
+                return Ok(());
+            }
+        }
+        let Some((mut file, range)) = next else {
+            break;
+        };
+        rules.apply(&mut file);
+        if range.end - range.start > DEFER_BYTES && !file.hunks.is_empty() {
+            // BufReader가 미리 읽은 만큼은 이미 Tee를 거쳤으니 비우면 본문이 스풀에 있다
+            reader.get_mut().get_mut().copy.flush()?;
+            Deferred::defer(&mut file, spool, range, first_line, options);
+        }
+        if !send(Loaded::File(file)) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{DiffLine, ReviewStatus};
+    use std::fmt::Write as _;
+
+    /// 작은 파일 하나와 본문이 DEFER_BYTES보다 큰 파일 하나
+    fn big_input() -> String {
+        let mut input = String::from(
+            "diff --git a/small.rs b/small.rs\n--- a/small.rs\n+++ b/small.rs\n@@ -1 +1 @@\n-a\n+b\n",
+        );
+        input
+            .push_str("diff --git a/Cargo.lock b/Cargo.lock\n--- a/Cargo.lock\n+++ b/Cargo.lock\n");
+        for h in 0..200 {
+            writeln!(input, "@@ -{0},40 +{0},40 @@", h * 100 + 1).unwrap();
+            for i in 0..40 {
+                writeln!(input, "-name = \"old-{}-{}\"", h, i).unwrap();
+                writeln!(input, "+name = \"new-{}-{}\"", h, i).unwrap();
+            }
+        }
+        input
+    }
+
+    fn load_all(input: String, rules: Vec<Rule>) -> Vec<FileDiff> {
+        let mut loader =
+            Loader::spawn(std::io::Cursor::new(input), rules, ParseOptions::default()).unwrap();
+        let mut files = Vec::new();
+        while let Some(file) = loader.next_file().unwrap() {
+            files.push(file);
+        }
+        files
+    }
+
+    #[test]
+    fn test_large_body_deferred_until_loaded() {
+        let input = big_input();
+        let eager = crate::parse::parse_diff(&input).unwrap();
+        let mut files = load_all(input, Vec::new());
+        assert_eq!(files.len(), 2);
+        assert!(files[0].deferred.is_none());
+
+        let lock = &mut files[1];
+        assert!(lock.deferred.is_some());
+        assert_eq!(lock.hunks.len(), eager.files[1].hunks.len());
+        assert!(lock.hunks.iter().all(|h| h.lines.is_empty()));
+        assert_eq!(lock.lines_added(), 8000);
+        assert_eq!(lock.lines_removed(), 8000);
+
+        // Review state set while deferred survives loading
+        lock.hunks[3].status = ReviewStatus::Accepted;
+        lock.load().unwrap();
+        assert!(lock.deferred.is_none());
+        assert_eq!(lock.hunks[3].status, ReviewStatus::Accepted);
+        assert_eq!(lock.hunks[5].lines, eager.files[1].hunks[5].lines);
+        assert!(matches!(&lock.hunks[0].lines[1], DiffLine::Added(s) if s == "name = \"new-0-0\""));
+    }
+
+    #[test]
+    fn test_reload_uses_session_options() {
+        // 헤더보다 라인이 적은 헌크: 파싱 경고 (strict면 에러)
+        let input = big_input().replace("@@ -101,40 +101,40 @@", "@@ -101,41 +101,41 @@");
+        let eager = crate::parse::parse_diff(&input).unwrap();
+        assert_eq!(eager.warnings.len(), 1);
+
+        let mut loader = Loader::spawn(
+            std::io::Cursor::new(input),
+            Vec::new(),
+            ParseOptions::default(),
+        )
+        .unwrap();
+        let mut files = Vec::new();
+        while let Some(file) = loader.next_file().unwrap() {
+            files.push(file);
+        }
+        assert_eq!(loader.take_warnings(), eager.warnings);
+
+        let mut strict = files[1].clone();
+        if let Some(deferred) = &mut strict.deferred {
+            deferred.options.strict = true;
+        }
+        assert!(strict.load().is_err());
+        assert!(strict.deferred.is_some());
+
+        // 다시 파싱한 경고도 원래 입력의 줄 번호를 가리킨다
+        assert_eq!(files[1].load().unwrap(), eager.warnings);
+    }
+
+    #[test]
+    fn test_rules_applied_before_lines_dropped() {
+        let rules: Vec<Rule> = vec![Rule {
+            added: Some("new-7-".to_string()),
+            action: Some(crate::rules::RuleAction::Accept),
+            ..Rule::default()
+        }];
+        let files = load_all(big_input(), rules);
+        let lock = &files[1];
+        assert!(lock.deferred.is_some());
+        let accepted: Vec<usize> = (0..lock.hunks.len())
+            .filter(|&i| lock.hunks[i].status == ReviewStatus::Accepted)
+            .collect();
+        assert_eq!(accepted.len(), 1);
+    }
+}
@@ -4,9 +4,14 @@ pub mod cli;
 pub mod config;
 pub mod git;
 pub mod hook;
+pub mod hunk_edit;
+pub mod lazy;
 pub mod model;
+pub mod moved;
 pub mod output;
 pub mod parse;
 pub mod revert;
+pub mod rules;
+pub mod script;
 pub mod tty;
 pub mod tui;
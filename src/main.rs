@@ -3,15 +3,17 @@
 //! 사용법:
 //!   git diff | diffy | git apply    (파이프 모드)
 //!   diffy [--staged|--head|--ref REF] [--json] [--hook-mode] [--apply]
+//!   diffy --accept 'src/**' --reject '*.lock' [--decisions FILE]   (스크립트 모드)
+//!   diffy --resume FILE    (이전 리뷰 결과를 불러와 TUI로 이어서 리뷰)
 
-use std::io::{self, Read, Write};
+use std::io::{self, Write};
 use std::process;
 
 use anyhow::Result;
 use clap::Parser;
 
 use diffy_tui::cli::Cli;
-use diffy_tui::{config, git, hook, model, output, parse, revert, tty, tui};
+use diffy_tui::{config, git, hook, lazy, model, output, parse, revert, rules, script, tty, tui};
 
 fn run() -> Result<i32> {
     let cli = Cli::parse();
@@ -21,32 +23,82 @@ fn run() -> Result<i32> {
         return revert::restore();
     }
 
-    // 분기 2: stdin이 파이프 → 기존 파이프 모드 (후방 호환)
-    if !tty::stdin_is_tty() {
+    // 분기 2: `diffy -` 또는 stdin이 파이프 → 기존 파이프 모드 (후방 호환).
+    // 스크립트 모드는 TTY 없이 돌기 때문에 stdin이 파이프여도 diff가 들어왔을 때만 쓴다.
+    // (CI나 훅에서는 stdin이 /dev/null이거나 다른 입력일 수 있다)
+    if cli.reads_stdin() {
         return run_pipe_mode(&cli);
     }
+    if !tty::stdin_is_tty() && !cli.has_diff_range() {
+        if !cli.is_scripted() {
+            return run_pipe_mode(&cli);
+        }
+        let diff = parse::parse_reader(io::stdin().lock(), cli.parse_options())?;
+        if !diff.files.is_empty() {
+            return run_parsed(diff, &cli);
+        }
+    }
 
     // 분기 3: CLI 모드 → git diff 내부 실행
     run_cli_mode(&cli)
 }
 
 /// 파이프 모드: git diff | diffy | git apply
+///
+/// TUI는 첫 파일이 파싱되는 대로 띄우고 나머지는 백그라운드에서 읽는다.
+/// 스크립트 모드와 `--resume`은 결정을 모든 파일에 적용해야 하므로 한 번에 파싱한다.
 fn run_pipe_mode(cli: &Cli) -> Result<i32> {
-    let mut input = String::new();
-    io::stdin().read_to_string(&mut input)?;
+    if cli.is_scripted() || cli.resume.is_some() {
+        let diff = parse::parse_reader(io::stdin().lock(), cli.parse_options())?;
+        if diff.files.is_empty() {
+            eprintln!("[diffy] No changes to review.");
+            return Ok(0);
+        }
+        return run_parsed(diff, cli);
+    }
 
-    let diff = parse::parse_diff(&input)?;
+    let config = cli.load_config();
+    warn_apply_ignored(cli);
 
-    if diff.files.is_empty() {
+    // 정책 규칙은 로더가 헌크 라인이 메모리에 있을 때 적용한다
+    let mut loader = lazy::Loader::spawn(io::stdin(), config.rules.clone(), cli.parse_options())?;
+    let Some(first) = loader.next_file()? else {
+        print_warnings(&loader.take_warnings());
         eprintln!("[diffy] No changes to review.");
         return Ok(0);
+    };
+    let mut files = vec![first];
+    files.extend(loader.try_files()?);
+    let mut diff = model::Diff::new(files);
+    diff.warnings = loader.take_warnings();
+
+    let mut reviewed_diff = tui::run(diff, &config, None, Some(&mut loader))?;
+    // 리뷰가 끝날 때까지 도착하지 않은 파일은 pending으로 남는다
+    while let Some(file) = loader.next_file()? {
+        reviewed_diff.files.push(file);
     }
+    reviewed_diff.warnings.extend(loader.take_warnings());
+    print_warnings(&reviewed_diff.warnings);
+
+    let total_hunks: usize = reviewed_diff.files.iter().map(|f| f.hunks.len()).sum();
+    finish(&reviewed_diff, cli, &config, total_hunks)
+}
 
+/// 파이프로 받아 한 번에 파싱한 diff를 리뷰한다
+fn run_parsed(diff: model::Diff, cli: &Cli) -> Result<i32> {
+    let config = cli.load_config();
+    warn_apply_ignored(cli);
     let total_hunks: usize = diff.files.iter().map(|f| f.hunks.len()).sum();
-    let config = config::load();
-    let reviewed_diff = tui::run(diff, &config)?;
+    let reviewed_diff = review(diff, cli, &config, None)?;
+    print_warnings(&reviewed_diff.warnings);
+    finish(&reviewed_diff, cli, &config, total_hunks)
+}
 
-    write_output(&reviewed_diff, cli, total_hunks)
+/// 파이프 입력은 작업 트리와 맞는다는 보장이 없어서 되돌리지 않는다
+fn warn_apply_ignored(cli: &Cli) {
+    if cli.apply {
+        eprintln!("[diffy] warning: --apply is ignored for piped input.");
+    }
 }
 
 /// CLI 모드: diffy --staged / diffy --head / diffy --ref REF
@@ -69,7 +121,7 @@ fn run_cli_mode(cli: &Cli) -> Result<i32> {
         return Ok(0);
     }
 
-    let diff = parse::parse_diff(&diff_text)?;
+    let diff = parse::parse_diff_with(&diff_text, cli.parse_options())?;
 
     if diff.files.is_empty() {
         eprintln!("[diffy] No changes to review.");
@@ -83,8 +135,16 @@ fn run_cli_mode(cli: &Cli) -> Result<i32> {
         revert::backup()?;
     }
 
-    let config = config::load();
-    let reviewed_diff = tui::run(diff, &config)?;
+    let config = cli.load_config();
+    // TUI에서 파일을 편집한 뒤 diff를 다시 만든다
+    let rediff = || -> Result<model::Diff> {
+        let text = git::git_diff(&mode, cli.path.as_deref())?;
+        let mut diff = parse::parse_diff_with(&text, cli.parse_options())?;
+        rules::apply_rules(&mut diff, &config.rules)?;
+        Ok(diff)
+    };
+    let reviewed_diff = review(diff, cli, &config, Some(&rediff))?;
+    print_warnings(&reviewed_diff.warnings);
 
     // --apply: rejected 헌크 되돌리기
     if cli.apply {
@@ -94,13 +154,51 @@ fn run_cli_mode(cli: &Cli) -> Result<i32> {
         }
     }
 
-    // --hook-mode: stderr 피드백
+    finish(&reviewed_diff, cli, &config, total_hunks)
+}
+
+/// 리뷰 결과를 `--hook-mode`면 stderr 피드백으로, 아니면 stdout으로 내보낸다
+fn finish(
+    diff: &model::Diff,
+    cli: &Cli,
+    config: &config::Config,
+    total_hunks: usize,
+) -> Result<i32> {
     if cli.hook_mode {
-        let all_accepted = hook::write_feedback(&reviewed_diff, &mut io::stderr())?;
+        let all_accepted = hook::write_feedback(diff, &mut io::stderr(), &config.hook)?;
         return if all_accepted { Ok(0) } else { Ok(2) };
     }
+    write_output(diff, cli, total_hunks)
+}
 
-    write_output(&reviewed_diff, cli, total_hunks)
+/// 정책 규칙과 이전 결정(`--decisions`/`--resume`)을 적용한 뒤 TUI로 리뷰하거나,
+/// 스크립트 모드면 결정만 적용한다.
+/// `rediff`는 TUI에서 편집한 뒤 diff를 다시 만드는 함수 (git 모드만)
+fn review(
+    mut diff: model::Diff,
+    cli: &Cli,
+    config: &config::Config,
+    rediff: Option<&dyn Fn() -> Result<model::Diff>>,
+) -> Result<model::Diff> {
+    rules::apply_rules(&mut diff, &config.rules)?;
+
+    // 적용 순서: --decisions/--resume → --accept → --reject (reject가 우선)
+    cli.apply_saved_decisions(&mut diff)?;
+
+    if !cli.is_scripted() {
+        return tui::run(diff, config, rediff, None);
+    }
+
+    script::apply_globs(&mut diff, &cli.accept, model::ReviewStatus::Accepted)?;
+    script::apply_globs(&mut diff, &cli.reject, model::ReviewStatus::Rejected)?;
+    Ok(diff)
+}
+
+/// 건너뛴 형식 오류를 stderr로 알린다 (TUI에서는 상태 줄에도 표시)
+fn print_warnings(warnings: &[parse::ParseError]) {
+    for warning in warnings {
+        eprintln!("[diffy] warning: {warning}");
+    }
 }
 
 /// 리뷰 결과 출력 (diff 또는 JSON)
@@ -1,6 +1,11 @@
 //! 핵심 데이터 구조체
 
-use serde::Serialize;
+use crate::lazy::Deferred;
+use crate::parse::ParseError;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
 
 /// 리뷰 상태
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
@@ -10,8 +15,113 @@ pub enum ReviewStatus {
     Rejected,
 }
 
+/// 코멘트 심각도
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    #[default]
+    Blocking,
+    Nit,
+    Question,
+    Praise,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Blocking => "blocking",
+            Severity::Nit => "nit",
+            Severity::Question => "question",
+            Severity::Praise => "praise",
+        }
+    }
+
+    /// 다음 심각도 (코멘트 편집기에서 순환용)
+    pub fn next(self) -> Self {
+        match self {
+            Severity::Blocking => Severity::Nit,
+            Severity::Nit => Severity::Question,
+            Severity::Question => Severity::Praise,
+            Severity::Praise => Severity::Blocking,
+        }
+    }
+}
+
+/// 코멘트가 고정된 diff 라인 위치
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct LineAnchor {
+    pub line_index: usize,     // Hunk::lines 인덱스
+    pub old_line: Option<u32>, // Added 라인이면 None
+    pub new_line: Option<u32>, // Removed 라인이면 None
+}
+
+/// 헌크에 달린 리뷰 코멘트
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Comment {
+    pub text: String,
+    pub severity: Severity,
+    pub tags: Vec<String>,
+    pub anchor: Option<LineAnchor>, // None이면 헌크 전체에 대한 코멘트
+}
+
+impl Comment {
+    /// 입력 문자열에서 코멘트 생성. `#tag` 형태의 단어는 태그로 분리된다.
+    pub fn from_input(input: &str, severity: Severity) -> Self {
+        let mut words = Vec::new();
+        let mut tags = Vec::new();
+        for word in input.split_whitespace() {
+            match word.strip_prefix('#') {
+                Some(tag) if is_tag(tag) => {
+                    if !tags.iter().any(|t| t == tag) {
+                        tags.push(tag.to_string());
+                    }
+                }
+                _ => words.push(word),
+            }
+        }
+        Self {
+            text: words.join(" "),
+            severity,
+            tags,
+            anchor: None,
+        }
+    }
+
+    pub fn with_anchor(mut self, anchor: Option<LineAnchor>) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    pub fn line_index(&self) -> Option<usize> {
+        self.anchor.map(|a| a.line_index)
+    }
+
+    /// 편집기 프리필용 문자열 (`from_input`의 역변환)
+    pub fn to_input(&self) -> String {
+        let mut input = self.text.clone();
+        for tag in &self.tags {
+            if !input.is_empty() {
+                input.push(' ');
+            }
+            input.push('#');
+            input.push_str(tag);
+        }
+        input
+    }
+
+    pub fn is_blocking(&self) -> bool {
+        self.severity == Severity::Blocking
+    }
+}
+
+fn is_tag(s: &str) -> bool {
+    !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
 /// diff 한 줄
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
 pub enum DiffLine {
     Context(String), // ' '로 시작
     Added(String),   // '+'로 시작
@@ -19,6 +129,39 @@ pub enum DiffLine {
     NoNewline,       // '\ No newline at end of file'
 }
 
+/// 공백 무시 옵션 (`-w`, `--ignore-blank-lines`)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WhitespaceIgnore {
+    /// 라인 안의 공백 차이를 무시 (git diff -w와 같은 의미)
+    pub all_space: bool,
+    /// 빈 라인 추가/삭제를 무시
+    pub blank_lines: bool,
+}
+
+impl WhitespaceIgnore {
+    /// 둘 다 무시 (공백만 바뀐 헌크 일괄 accept의 기본값)
+    pub const ALL: Self = Self {
+        all_space: true,
+        blank_lines: true,
+    };
+
+    pub fn is_active(self) -> bool {
+        self.all_space || self.blank_lines
+    }
+
+    /// 비교용 정규화. 무시할 라인이면 None
+    fn normalize(self, line: &str) -> Option<String> {
+        if self.blank_lines && line.trim().is_empty() {
+            return None;
+        }
+        if self.all_space {
+            Some(line.chars().filter(|c| !c.is_whitespace()).collect())
+        } else {
+            Some(line.to_string())
+        }
+    }
+}
+
 /// 헌크 하나
 #[derive(Clone, Debug, Serialize)]
 pub struct Hunk {
@@ -29,7 +172,163 @@ pub struct Hunk {
     pub new_count: u32,
     pub lines: Vec<DiffLine>,
     pub status: ReviewStatus,
-    pub comment: Option<String>,
+    pub comments: Vec<Comment>,
+    /// 상태나 코멘트를 미리 정한 정책 규칙 이름 (리뷰어가 덮어쓰면 None)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    /// 리뷰 중에 리뷰어가 에디터로 직접 고친 헌크
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub edited: bool,
+    /// `Ctrl-e`로 고치기 전의 라인. 작업 트리에는 아직 이 내용이 있다 (`--apply`에서 사용)
+    #[serde(skip)]
+    pub original_lines: Option<Vec<DiffLine>>,
+}
+
+impl Hunk {
+    /// 에이전트가 반드시 처리해야 하는 피드백인지 (rejected 또는 blocking 코멘트)
+    pub fn is_blocking(&self) -> bool {
+        self.status == ReviewStatus::Rejected || self.comments.iter().any(Comment::is_blocking)
+    }
+
+    /// 추가된 라인 텍스트
+    pub fn added_lines(&self) -> impl Iterator<Item = &str> {
+        self.lines.iter().filter_map(|l| match l {
+            DiffLine::Added(s) => Some(s.as_str()),
+            _ => None,
+        })
+    }
+
+    /// 삭제된 라인 텍스트
+    pub fn removed_lines(&self) -> impl Iterator<Item = &str> {
+        self.lines.iter().filter_map(|l| match l {
+            DiffLine::Removed(s) => Some(s.as_str()),
+            _ => None,
+        })
+    }
+
+    /// `ignore` 기준으로 변경 내용이 모두 사라지는 헌크인지 (공백만 바뀐 헌크)
+    pub fn is_whitespace_only(&self, ignore: WhitespaceIgnore) -> bool {
+        if !ignore.is_active()
+            || self
+                .added_lines()
+                .chain(self.removed_lines())
+                .next()
+                .is_none()
+        {
+            return false;
+        }
+        let removed: Vec<String> = self
+            .removed_lines()
+            .filter_map(|l| ignore.normalize(l))
+            .collect();
+        let added: Vec<String> = self
+            .added_lines()
+            .filter_map(|l| ignore.normalize(l))
+            .collect();
+        removed == added
+    }
+
+    /// accept되었지만 nit/question 코멘트가 달린 헌크 (에이전트에 전달할 후속 작업).
+    /// praise 코멘트는 후속 작업이 아니다.
+    pub fn is_follow_up(&self) -> bool {
+        self.status == ReviewStatus::Accepted
+            && !self.is_blocking()
+            && self
+                .comments
+                .iter()
+                .any(|c| matches!(c.severity, Severity::Nit | Severity::Question))
+    }
+
+    /// 각 라인의 (old, new) 라인 번호
+    pub fn line_numbers(&self) -> Vec<(Option<u32>, Option<u32>)> {
+        let mut old_line = self.old_start;
+        let mut new_line = self.new_start;
+        self.lines
+            .iter()
+            .map(|line| match line {
+                DiffLine::Context(_) => {
+                    old_line += 1;
+                    new_line += 1;
+                    (Some(old_line - 1), Some(new_line - 1))
+                }
+                DiffLine::Added(_) => {
+                    new_line += 1;
+                    (None, Some(new_line - 1))
+                }
+                DiffLine::Removed(_) => {
+                    old_line += 1;
+                    (Some(old_line - 1), None)
+                }
+                DiffLine::NoNewline => (None, None),
+            })
+            .collect()
+    }
+
+    /// 라인 인덱스에 대한 앵커 생성
+    pub fn anchor_at(&self, line_index: usize) -> Option<LineAnchor> {
+        let (old_line, new_line) = *self.line_numbers().get(line_index)?;
+        Some(LineAnchor {
+            line_index,
+            old_line,
+            new_line,
+        })
+    }
+
+    /// 헌크 전체에 대한 코멘트 (라인 앵커 없음)
+    pub fn hunk_comments(&self) -> impl Iterator<Item = &Comment> {
+        self.comments.iter().filter(|c| c.anchor.is_none())
+    }
+
+    /// 특정 라인에 고정된 코멘트
+    pub fn line_comments(&self, line_index: usize) -> impl Iterator<Item = &Comment> {
+        self.comments
+            .iter()
+            .filter(move |c| c.line_index() == Some(line_index))
+    }
+
+    /// 라인에서 old/new 라인 수를 다시 세고 헤더를 다시 만든다.
+    /// 헤더 뒤의 함수 이름 등 (`@@ ... @@ fn main()`)은 그대로 둔다.
+    pub fn recount(&mut self) {
+        self.old_count = 0;
+        self.new_count = 0;
+        for line in &self.lines {
+            match line {
+                DiffLine::Context(_) => {
+                    self.old_count += 1;
+                    self.new_count += 1;
+                }
+                DiffLine::Removed(_) => self.old_count += 1,
+                DiffLine::Added(_) => self.new_count += 1,
+                DiffLine::NoNewline => {}
+            }
+        }
+        let section = self
+            .header
+            .strip_prefix("@@")
+            .and_then(|rest| rest.split_once("@@"))
+            .map_or("", |(_, section)| section);
+        self.header = format!(
+            "@@ -{},{} +{},{} @@{}",
+            self.old_start, self.old_count, self.new_start, self.new_count, section
+        );
+    }
+
+    /// 헌크 내용(라인)의 지문. 위쪽이 편집돼 라인 번호만 밀린 헌크는 지문이 같다.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.lines.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// 파일 변경 종류
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Deleted,
+    Modified,
+    Renamed,
 }
 
 /// 파일 하나의 diff
@@ -41,10 +340,58 @@ pub struct FileDiff {
     pub raw_new_path: String, // 출력용 원본 경로: "b/src/main.rs"
     pub hunks: Vec<Hunk>,
     pub is_binary: bool, // 바이너리 파일 여부
+    /// "Viewed" 표시 (헌크 결정과 별개로 리뷰어가 다 봤다고 표시한 파일)
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub viewed: bool,
+    /// 아직 읽지 않은 큰 파일 본문. 있으면 `hunks`에는 헤더와 리뷰 상태만 있고
+    /// 라인은 비어 있다 (`load`로 채운다)
+    #[serde(skip)]
+    pub deferred: Option<Deferred>,
 }
 
 impl FileDiff {
+    pub fn change_kind(&self) -> ChangeKind {
+        if self.old_path == "/dev/null" {
+            ChangeKind::Added
+        } else if self.new_path == "/dev/null" {
+            ChangeKind::Deleted
+        } else if self.old_path != self.new_path {
+            ChangeKind::Renamed
+        } else {
+            ChangeKind::Modified
+        }
+    }
+
+    /// 표시용 경로 (삭제된 파일은 old 경로)
+    pub fn path(&self) -> &str {
+        if self.new_path == "/dev/null" {
+            &self.old_path
+        } else {
+            &self.new_path
+        }
+    }
+
+    /// 본문을 스풀에서 다시 파싱해 헌크 라인을 채우고 파싱 경고를 반환한다 (이미 읽었으면 그대로)
+    pub fn load(&mut self) -> Result<Vec<ParseError>> {
+        let Some(deferred) = self.deferred.take() else {
+            return Ok(Vec::new());
+        };
+        match deferred.load(&self.hunks) {
+            Ok((hunks, warnings)) => {
+                self.hunks = hunks;
+                Ok(warnings)
+            }
+            Err(e) => {
+                self.deferred = Some(deferred);
+                Err(e)
+            }
+        }
+    }
+
     pub fn lines_added(&self) -> usize {
+        if let Some(deferred) = &self.deferred {
+            return deferred.added;
+        }
         self.hunks
             .iter()
             .flat_map(|h| &h.lines)
@@ -53,6 +400,9 @@ impl FileDiff {
     }
 
     pub fn lines_removed(&self) -> usize {
+        if let Some(deferred) = &self.deferred {
+            return deferred.removed;
+        }
         self.hunks
             .iter()
             .flat_map(|h| &h.lines)
@@ -93,10 +443,152 @@ pub enum FileReviewSummary {
     Empty,
 }
 
+/// 리뷰 진행률 (본 파일, 결정한 헌크, 결정한 헌크의 변경 라인)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ReviewProgress {
+    pub files: usize,
+    pub viewed_files: usize,
+    pub hunks: usize,
+    pub reviewed_hunks: usize,
+    pub lines: usize,
+    pub reviewed_lines: usize,
+}
+
+/// 다시 만든 diff에 이전 리뷰를 옮긴 결과
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CarryOver {
+    /// 내용이 같아 상태와 코멘트를 그대로 가져온 헌크 수
+    pub kept: usize,
+    /// 새로 생기거나 바뀌어 리뷰어가 고친 것으로 표시한 헌크 수
+    pub edited: usize,
+    /// 새 diff에서 사라진 이전 헌크 수
+    pub dropped: usize,
+}
+
 /// 전체 diff
 #[derive(Clone, Debug, Serialize)]
 pub struct Diff {
     pub files: Vec<FileDiff>,
+    pub summary: Option<String>, // 리뷰 전체에 대한 요약 메시지
+    /// 파싱하면서 건너뛴 형식 오류 (strict 모드가 아닐 때)
+    #[serde(skip)]
+    pub warnings: Vec<ParseError>,
+}
+
+impl Diff {
+    pub fn new(files: Vec<FileDiff>) -> Self {
+        Self {
+            files,
+            summary: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// 심각도별 코멘트 수
+    pub fn comment_count(&self, severity: Severity) -> usize {
+        self.files
+            .iter()
+            .flat_map(|f| &f.hunks)
+            .flat_map(|h| &h.comments)
+            .filter(|c| c.severity == severity)
+            .count()
+    }
+
+    /// 에디터로 고친 뒤 다시 만든 diff에 이전 리뷰 결과를 옮긴다.
+    /// 같은 경로에서 지문이 같은 헌크는 상태, 코멘트, 규칙을 그대로 가져오고
+    /// (라인 앵커는 새 라인 번호로 다시 계산), 나머지는 `edited`로 표시한다.
+    /// 헌크가 바뀐 파일은 "viewed" 표시도 지운다.
+    pub fn carry_review_from(&mut self, old: &Diff) -> CarryOver {
+        let mut report = CarryOver::default();
+        let mut used: HashMap<&str, Vec<bool>> = old
+            .files
+            .iter()
+            .map(|f| (f.path(), vec![false; f.hunks.len()]))
+            .collect();
+
+        for file in &mut self.files {
+            let old_file = old.files.iter().find(|f| f.path() == file.path());
+            let mut changed = false;
+            for hunk in &mut file.hunks {
+                let fingerprint = hunk.fingerprint();
+                let found = old_file.and_then(|of| {
+                    let used = used.get_mut(of.path())?;
+                    let hi = of
+                        .hunks
+                        .iter()
+                        .enumerate()
+                        .position(|(i, h)| !used[i] && h.fingerprint() == fingerprint)?;
+                    used[hi] = true;
+                    Some(&of.hunks[hi])
+                });
+                let Some(prev) = found else {
+                    hunk.edited = true;
+                    changed = true;
+                    report.edited += 1;
+                    continue;
+                };
+                hunk.status = prev.status;
+                hunk.rule = prev.rule.clone();
+                hunk.edited = prev.edited;
+                hunk.comments = prev
+                    .comments
+                    .iter()
+                    .map(|c| {
+                        let anchor = c.line_index().and_then(|li| hunk.anchor_at(li));
+                        c.clone().with_anchor(anchor)
+                    })
+                    .collect();
+                report.kept += 1;
+            }
+            let dropped = old_file
+                .and_then(|f| used.get(f.path()))
+                .is_some_and(|u| u.contains(&false));
+            file.viewed = !changed && !dropped && old_file.is_some_and(|f| f.viewed);
+        }
+
+        report.dropped = used.values().flatten().filter(|u| !**u).count();
+        if self.summary.is_none() {
+            self.summary = old.summary.clone();
+        }
+        report
+    }
+
+    /// 리뷰 진행률. 라인은 추가/삭제 라인만 센다.
+    pub fn progress(&self) -> ReviewProgress {
+        let mut progress = ReviewProgress {
+            files: self.files.len(),
+            viewed_files: self.files.iter().filter(|f| f.viewed).count(),
+            ..Default::default()
+        };
+        for file in &self.files {
+            for (hi, hunk) in file.hunks.iter().enumerate() {
+                let changed = match &file.deferred {
+                    Some(deferred) => deferred.changed[hi],
+                    None => hunk
+                        .lines
+                        .iter()
+                        .filter(|l| matches!(l, DiffLine::Added(_) | DiffLine::Removed(_)))
+                        .count(),
+                };
+                progress.hunks += 1;
+                progress.lines += changed;
+                if hunk.status != ReviewStatus::Pending {
+                    progress.reviewed_hunks += 1;
+                    progress.reviewed_lines += changed;
+                }
+            }
+        }
+        progress
+    }
+
+    /// 지연 로딩된 파일을 모두 읽는다 (모든 라인이 필요한 검색, 일괄 처리용)
+    pub fn load_all(&mut self) -> Result<Vec<ParseError>> {
+        let mut warnings = Vec::new();
+        for file in &mut self.files {
+            warnings.extend(file.load()?);
+        }
+        Ok(warnings)
+    }
 }
 
 #[cfg(test)]
@@ -112,7 +604,10 @@ mod tests {
             new_count: 1,
             lines,
             status,
-            comment: None,
+            comments: Vec::new(),
+            rule: None,
+            edited: false,
+            original_lines: None,
         }
     }
 
@@ -124,6 +619,8 @@ mod tests {
             raw_new_path: "b/test.rs".to_string(),
             hunks,
             is_binary: false,
+            viewed: false,
+            deferred: None,
         }
     }
 
@@ -188,6 +685,237 @@ mod tests {
         assert_eq!(file.review_summary(), FileReviewSummary::Partial);
     }
 
+    #[test]
+    fn test_comment_from_input_extracts_tags() {
+        let comment = Comment::from_input("rename this #naming later #naming", Severity::Nit);
+        assert_eq!(comment.text, "rename this later");
+        assert_eq!(comment.tags, vec!["naming".to_string()]);
+        assert_eq!(comment.severity, Severity::Nit);
+        assert_eq!(comment.to_input(), "rename this later #naming");
+    }
+
+    #[test]
+    fn test_comment_keeps_non_tag_hashes() {
+        let comment = Comment::from_input("add #[derive(Debug)] here", Severity::Blocking);
+        assert_eq!(comment.text, "add #[derive(Debug)] here");
+        assert!(comment.tags.is_empty());
+    }
+
+    #[test]
+    fn test_hunk_is_blocking() {
+        let mut hunk = make_hunk(vec![], ReviewStatus::Accepted);
+        assert!(!hunk.is_blocking());
+        hunk.comments
+            .push(Comment::from_input("nit", Severity::Nit));
+        assert!(!hunk.is_blocking());
+        hunk.comments
+            .push(Comment::from_input("must fix", Severity::Blocking));
+        assert!(hunk.is_blocking());
+        hunk.comments.clear();
+        hunk.status = ReviewStatus::Rejected;
+        assert!(hunk.is_blocking());
+    }
+
+    #[test]
+    fn test_hunk_is_whitespace_only() {
+        let reindent = make_hunk(
+            vec![
+                DiffLine::Removed("\tlet x = 1;".to_string()),
+                DiffLine::Added("    let x  = 1;\r".to_string()),
+                DiffLine::Context("}".to_string()),
+            ],
+            ReviewStatus::Pending,
+        );
+        let blank = make_hunk(
+            vec![DiffLine::Added("  ".to_string())],
+            ReviewStatus::Pending,
+        );
+        let real = make_hunk(
+            vec![
+                DiffLine::Removed("let x = 1;".to_string()),
+                DiffLine::Added("let x = 2;".to_string()),
+            ],
+            ReviewStatus::Pending,
+        );
+        let space_only = WhitespaceIgnore {
+            all_space: true,
+            blank_lines: false,
+        };
+        let blank_only = WhitespaceIgnore {
+            all_space: false,
+            blank_lines: true,
+        };
+
+        assert!(!reindent.is_whitespace_only(WhitespaceIgnore::default()));
+        assert!(reindent.is_whitespace_only(space_only));
+        assert!(!reindent.is_whitespace_only(blank_only));
+        // -w alone does not ignore added blank lines (same as git)
+        assert!(!blank.is_whitespace_only(space_only));
+        assert!(blank.is_whitespace_only(blank_only));
+        assert!(!real.is_whitespace_only(WhitespaceIgnore::ALL));
+        // Context-only hunks have no changes to ignore
+        let context = make_hunk(
+            vec![DiffLine::Context("x".to_string())],
+            ReviewStatus::Pending,
+        );
+        assert!(!context.is_whitespace_only(WhitespaceIgnore::ALL));
+    }
+
+    #[test]
+    fn test_hunk_is_follow_up() {
+        let mut hunk = make_hunk(vec![], ReviewStatus::Accepted);
+        assert!(!hunk.is_follow_up());
+        hunk.comments
+            .push(Comment::from_input("rename later", Severity::Nit));
+        assert!(hunk.is_follow_up());
+        hunk.status = ReviewStatus::Pending;
+        assert!(!hunk.is_follow_up());
+        hunk.status = ReviewStatus::Accepted;
+        hunk.comments[0].severity = Severity::Praise;
+        assert!(!hunk.is_follow_up());
+        hunk.comments[0].severity = Severity::Question;
+        assert!(hunk.is_follow_up());
+        hunk.comments
+            .push(Comment::from_input("must fix", Severity::Blocking));
+        assert!(!hunk.is_follow_up());
+    }
+
+    #[test]
+    fn test_line_numbers_and_anchor() {
+        let mut hunk = make_hunk(
+            vec![
+                DiffLine::Context("ctx".to_string()),
+                DiffLine::Removed("old".to_string()),
+                DiffLine::Added("new".to_string()),
+                DiffLine::Context("ctx2".to_string()),
+            ],
+            ReviewStatus::Pending,
+        );
+        hunk.old_start = 10;
+        hunk.new_start = 20;
+        assert_eq!(
+            hunk.line_numbers(),
+            vec![
+                (Some(10), Some(20)),
+                (Some(11), None),
+                (None, Some(21)),
+                (Some(12), Some(22)),
+            ]
+        );
+        let anchor = hunk.anchor_at(2).unwrap();
+        assert_eq!(anchor.line_index, 2);
+        assert_eq!(anchor.old_line, None);
+        assert_eq!(anchor.new_line, Some(21));
+        assert!(hunk.anchor_at(4).is_none());
+
+        hunk.comments
+            .push(Comment::from_input("on new", Severity::Nit).with_anchor(Some(anchor)));
+        hunk.comments
+            .push(Comment::from_input("overall", Severity::Nit));
+        assert_eq!(hunk.line_comments(2).count(), 1);
+        assert_eq!(hunk.line_comments(1).count(), 0);
+        assert_eq!(hunk.hunk_comments().count(), 1);
+    }
+
+    #[test]
+    fn test_diff_progress() {
+        let lines = || {
+            vec![
+                DiffLine::Context("ctx".to_string()),
+                DiffLine::Removed("old".to_string()),
+                DiffLine::Added("new".to_string()),
+                DiffLine::NoNewline,
+            ]
+        };
+        let mut viewed = make_file(vec![make_hunk(lines(), ReviewStatus::Accepted)]);
+        viewed.viewed = true;
+        let diff = Diff::new(vec![
+            viewed,
+            make_file(vec![
+                make_hunk(lines(), ReviewStatus::Pending),
+                make_hunk(lines(), ReviewStatus::Rejected),
+            ]),
+        ]);
+        assert_eq!(
+            diff.progress(),
+            ReviewProgress {
+                files: 2,
+                viewed_files: 1,
+                hunks: 3,
+                reviewed_hunks: 2,
+                lines: 6,
+                reviewed_lines: 4,
+            }
+        );
+    }
+
+    #[test]
+    fn test_recount_keeps_section() {
+        let mut hunk = make_hunk(
+            vec![
+                DiffLine::Context("ctx".to_string()),
+                DiffLine::Removed("old".to_string()),
+                DiffLine::Added("new".to_string()),
+                DiffLine::Added("more".to_string()),
+            ],
+            ReviewStatus::Pending,
+        );
+        hunk.header = "@@ -7,1 +7,1 @@ fn main() {".to_string();
+        hunk.old_start = 7;
+        hunk.new_start = 7;
+        hunk.recount();
+        assert_eq!(hunk.header, "@@ -7,2 +7,3 @@ fn main() {");
+        assert_eq!((hunk.old_count, hunk.new_count), (2, 3));
+    }
+
+    #[test]
+    fn test_carry_review_from() {
+        let kept_lines = || {
+            vec![
+                DiffLine::Context("ctx".to_string()),
+                DiffLine::Added("new".to_string()),
+            ]
+        };
+        let mut kept = make_hunk(kept_lines(), ReviewStatus::Accepted);
+        let anchor = kept.anchor_at(1);
+        kept.comments
+            .push(Comment::from_input("ok", Severity::Nit).with_anchor(anchor));
+        let gone = make_hunk(
+            vec![DiffLine::Removed("old".to_string())],
+            ReviewStatus::Rejected,
+        );
+        let mut old_file = make_file(vec![kept, gone]);
+        old_file.viewed = true;
+        let mut old = Diff::new(vec![old_file]);
+        old.summary = Some("wip".to_string());
+
+        // Same hunk moved down by an edit above it, plus the reviewer's own change
+        let mut shifted = make_hunk(kept_lines(), ReviewStatus::Pending);
+        shifted.new_start = 5;
+        let fixed = make_hunk(
+            vec![DiffLine::Added("fixed".to_string())],
+            ReviewStatus::Pending,
+        );
+        let mut diff = Diff::new(vec![make_file(vec![shifted, fixed])]);
+        assert_eq!(
+            diff.carry_review_from(&old),
+            CarryOver {
+                kept: 1,
+                edited: 1,
+                dropped: 1
+            }
+        );
+
+        let file = &diff.files[0];
+        assert_eq!(file.hunks[0].status, ReviewStatus::Accepted);
+        assert!(!file.hunks[0].edited);
+        assert_eq!(file.hunks[0].comments[0].anchor.unwrap().new_line, Some(6));
+        assert_eq!(file.hunks[1].status, ReviewStatus::Pending);
+        assert!(file.hunks[1].edited);
+        assert!(!file.viewed);
+        assert_eq!(diff.summary.as_deref(), Some("wip"));
+    }
+
     #[test]
     fn test_review_summary_empty() {
         let file = make_file(vec![]);
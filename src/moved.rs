@@ -0,0 +1,389 @@
+//! 이동된 코드 감지 (git `--color-moved`와 비슷)
+//!
+//! 삭제된 라인 묶음과 추가된 라인 묶음이 (파일을 넘나들어도) 같은 내용이면
+//! 이동으로 본다. 공백 차이는 무시하고, 조금 고친 라인은 `edited`로 표시한다.
+
+use crate::model::{Diff, DiffLine};
+use std::collections::{HashMap, HashSet};
+
+/// 이동으로 인정할 최소 라인 수
+const MIN_BLOCK_LINES: usize = 3;
+/// 이동으로 인정할 최소 영숫자 개수 (git과 같은 기준)
+const MIN_BLOCK_ALNUM: usize = 20;
+/// 블록을 시작할 수 있는 라인의 최소 영숫자 개수 (`}` 같은 라인 제외)
+const MIN_SEED_ALNUM: usize = 3;
+/// 조금 고친 라인으로 볼 최소 유사도 (0.0 ~ 1.0)
+const MIN_SIMILARITY: f64 = 0.75;
+/// 유사도를 계산할 최대 라인 길이 (그보다 길면 완전히 같아야 함)
+const MAX_FUZZY_LEN: usize = 300;
+/// 삭제된 쪽에 이보다 많이 나오는 라인은 블록을 시작하지 않는다
+/// (git처럼 반복되는 라인에서 후보 비교가 폭증하지 않게)
+const MAX_SEED_CANDIDATES: usize = 16;
+
+/// diff 안의 라인 위치 (파일, 헌크, 라인 인덱스)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LinePos {
+    pub file: usize,
+    pub hunk: usize,
+    pub line: usize,
+}
+
+/// 이동 블록에 속한 라인 하나
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MovedLine {
+    /// 반대편 라인 (추가 라인이면 원래 삭제된 라인, 삭제 라인이면 옮겨간 라인)
+    pub counterpart: LinePos,
+    /// 옮기면서 내용이 바뀐 라인
+    pub edited: bool,
+    /// 블록의 첫 라인 ("moved from" 표시 위치)
+    pub block_start: bool,
+    /// 블록 번호 (이웃한 블록 색 구분용)
+    pub block: usize,
+}
+
+/// diff 전체의 이동 감지 결과
+#[derive(Clone, Debug, Default)]
+pub struct MovedLines {
+    lines: HashMap<LinePos, MovedLine>,
+    blocks: usize,
+}
+
+impl MovedLines {
+    pub fn get(&self, file: usize, hunk: usize, line: usize) -> Option<&MovedLine> {
+        self.lines.get(&LinePos { file, hunk, line })
+    }
+
+    /// 감지된 이동 블록 수
+    pub fn blocks(&self) -> usize {
+        self.blocks
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks == 0
+    }
+}
+
+/// 비교용 라인: 공백을 한 칸으로 줄이고 양끝 공백 제거
+struct Entry {
+    pos: LinePos,
+    text: String,
+}
+
+fn normalize(line: &str) -> String {
+    line.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn alnum_count(s: &str) -> usize {
+    s.chars().filter(|c| c.is_alphanumeric()).count()
+}
+
+/// 두 라인의 문자 단위 LCS 기반 유사도
+fn similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    let mut prev = vec![0usize; b.len() + 1];
+    for &ca in &a {
+        let mut cur = vec![0usize; b.len() + 1];
+        for (j, &cb) in b.iter().enumerate() {
+            cur[j + 1] = if ca == cb {
+                prev[j] + 1
+            } else {
+                prev[j + 1].max(cur[j])
+            };
+        }
+        prev = cur;
+    }
+    2.0 * prev[b.len()] as f64 / (a.len() + b.len()) as f64
+}
+
+/// 같으면 Some(false), 조금 다르면 Some(true), 다르면 None
+fn compare(a: &str, b: &str) -> Option<bool> {
+    if a == b {
+        return Some(false);
+    }
+    if a.len() > MAX_FUZZY_LEN || b.len() > MAX_FUZZY_LEN || a.is_empty() || b.is_empty() {
+        return None;
+    }
+    (similarity(a, b) >= MIN_SIMILARITY).then_some(true)
+}
+
+/// 헌크 안에서 연속된 추가(또는 삭제) 라인 묶음
+fn runs(diff: &Diff, added: bool) -> Vec<Vec<Entry>> {
+    let mut runs = Vec::new();
+    for (fi, file) in diff.files.iter().enumerate() {
+        for (hi, hunk) in file.hunks.iter().enumerate() {
+            let mut run: Vec<Entry> = Vec::new();
+            for (li, line) in hunk.lines.iter().enumerate() {
+                let text = match (line, added) {
+                    (DiffLine::Added(s), true) | (DiffLine::Removed(s), false) => s,
+                    _ => {
+                        if !run.is_empty() {
+                            runs.push(std::mem::take(&mut run));
+                        }
+                        continue;
+                    }
+                };
+                run.push(Entry {
+                    pos: LinePos {
+                        file: fi,
+                        hunk: hi,
+                        line: li,
+                    },
+                    text: normalize(text),
+                });
+            }
+            if !run.is_empty() {
+                runs.push(run);
+            }
+        }
+    }
+    runs
+}
+
+/// diff 전체에서 이동된 블록을 찾는다.
+/// 같은 헌크 안의 삭제/추가 쌍은 수정이지 이동이 아니므로 제외한다.
+pub fn detect_moves(diff: &Diff) -> MovedLines {
+    let removed = runs(diff, false);
+    let added = runs(diff, true);
+
+    // 정규화된 텍스트 → (삭제 묶음, 오프셋)
+    let mut index: HashMap<&str, Vec<(usize, usize)>> = HashMap::new();
+    for (ri, run) in removed.iter().enumerate() {
+        for (off, entry) in run.iter().enumerate() {
+            if alnum_count(&entry.text) >= MIN_SEED_ALNUM {
+                index.entry(&entry.text).or_default().push((ri, off));
+            }
+        }
+    }
+
+    let mut result = MovedLines::default();
+    let mut used: HashSet<LinePos> = HashSet::new();
+    for run in &added {
+        // Lines before `floor` already belong to a block
+        let mut floor = 0;
+        let mut i = 0;
+        while i < run.len() {
+            let seed = &run[i];
+            let candidates = index.get(seed.text.as_str()).map_or(&[][..], Vec::as_slice);
+            if candidates.len() > MAX_SEED_CANDIDATES {
+                i += 1;
+                continue;
+            }
+            // Grow a block around the seed; returns (added start, per-line edited)
+            let extend = |ri: usize, off: usize, fuzzy: bool| {
+                let from = &removed[ri];
+                let pair = |a: usize, r: usize| {
+                    let r = &from[r];
+                    if used.contains(&r.pos) {
+                        return None;
+                    }
+                    if fuzzy {
+                        compare(&r.text, &run[a].text)
+                    } else {
+                        (r.text == run[a].text).then_some(false)
+                    }
+                };
+                // Extend backwards over edited lines (e.g. a changed signature)
+                let mut back = Vec::new();
+                while back.len() < i - floor && back.len() < off {
+                    match pair(i - back.len() - 1, off - back.len() - 1) {
+                        Some(e) => back.push(e),
+                        None => break,
+                    }
+                }
+                let mut edited: Vec<bool> = back.iter().rev().copied().collect();
+                let start = edited.len();
+                while i + edited.len() - start < run.len()
+                    && off + edited.len() - start < from.len()
+                {
+                    match pair(i + edited.len() - start, off + edited.len() - start) {
+                        Some(e) => edited.push(e),
+                        None => break,
+                    }
+                }
+                (start, edited)
+            };
+
+            // Rank candidates by exact matches; only the winner is grown over edited lines
+            let mut best: Option<(usize, usize, usize)> = None;
+            for &(ri, off) in candidates {
+                let from = &removed[ri][off].pos;
+                if from.file == seed.pos.file && from.hunk == seed.pos.hunk {
+                    continue;
+                }
+                let (_, edited) = extend(ri, off, false);
+                if best.is_none_or(|b| edited.len() > b.2) {
+                    best = Some((ri, off, edited.len()));
+                }
+            }
+            let best = best.map(|(ri, off, _)| {
+                let (start, edited) = extend(ri, off, true);
+                (i - start, ri, off - start, edited)
+            });
+
+            let Some((start, ri, off, edited)) = best else {
+                i += 1;
+                continue;
+            };
+            let len = edited.len();
+            let alnum: usize = run[start..start + len]
+                .iter()
+                .map(|e| alnum_count(&e.text))
+                .sum();
+            if len < MIN_BLOCK_LINES || alnum < MIN_BLOCK_ALNUM {
+                i += 1;
+                continue;
+            }
+
+            let block = result.blocks;
+            result.blocks += 1;
+            for (k, &edited) in edited.iter().enumerate() {
+                let to = run[start + k].pos;
+                let from = removed[ri][off + k].pos;
+                used.insert(from);
+                let moved = |counterpart| MovedLine {
+                    counterpart,
+                    edited,
+                    block_start: k == 0,
+                    block,
+                };
+                result.lines.insert(to, moved(from));
+                result.lines.insert(from, moved(to));
+            }
+            i = start + len;
+            floor = i;
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse_diff;
+    use indoc::indoc;
+
+    const MOVE_DIFF: &str = indoc! {"
+        diff --git a/src/a.rs b/src/a.rs
+        --- a/src/a.rs
+        +++ b/src/a.rs
+        @@ -1,7 +1,2 @@
+         use std::fmt;
+        -fn helper(x: u32) -> u32 {
+        -    let doubled = x * 2;
+        -    doubled + 1
+        -}
+        -
+         fn main() {}
+        diff --git a/src/b.rs b/src/b.rs
+        --- a/src/b.rs
+        +++ b/src/b.rs
+        @@ -1,2 +1,7 @@
+         use std::io;
+        +
+        +fn helper(x: u64) -> u64 {
+        +        let doubled = x * 2;
+        +    doubled + 1
+        +}
+         fn other() {}
+    "};
+
+    #[test]
+    fn test_detects_move_across_files() {
+        let diff = parse_diff(MOVE_DIFF).unwrap();
+        let moved = detect_moves(&diff);
+        assert_eq!(moved.blocks(), 1);
+
+        // b.rs: blank line (index 1) does not seed, the block starts at `fn helper`
+        assert!(moved.get(1, 0, 1).is_none());
+        let start = moved.get(1, 0, 2).unwrap();
+        assert!(start.block_start);
+        assert_eq!(
+            start.counterpart,
+            LinePos {
+                file: 0,
+                hunk: 0,
+                line: 1
+            }
+        );
+        // Signature changed u32 → u64: edited; re-indented line is not
+        assert!(start.edited);
+        assert!(!moved.get(1, 0, 3).unwrap().edited);
+        assert!(!moved.get(1, 0, 5).unwrap().block_start);
+
+        // Removed side points at the destination
+        assert_eq!(moved.get(0, 0, 4).unwrap().counterpart.file, 1);
+        // The trailing blank removed line was not paired
+        assert!(moved.get(0, 0, 5).is_none());
+    }
+
+    #[test]
+    fn test_short_blocks_and_in_place_edits_are_not_moves() {
+        let diff = parse_diff(indoc! {"
+            diff --git a/a.rs b/a.rs
+            --- a/a.rs
+            +++ b/a.rs
+            @@ -1,4 +1,4 @@
+            -let first_value = compute();
+            -let second_value = compute();
+            -let third_value = compute();
+            +let first_value = compute();
+            +let second_value = compute();
+            +let third_value = compute();
+             end();
+            diff --git a/b.rs b/b.rs
+            --- a/b.rs
+            +++ b/b.rs
+            @@ -1,2 +1,2 @@
+            -x();
+            -y();
+            +x();
+            +y();
+        "})
+        .unwrap();
+        assert!(detect_moves(&diff).is_empty());
+    }
+
+    #[test]
+    fn test_repetitive_move_is_detected() {
+        // A lockfile-like block where most lines repeat
+        let mut input = String::from("diff --git a/a.lock b/a.lock\n--- a/a.lock\n+++ b/a.lock\n");
+        let body: Vec<String> = (0..2000)
+            .flat_map(|n| {
+                [
+                    "[[package]]".to_string(),
+                    format!("name = \"crate-{n}\""),
+                    "version = \"1.0.0\"".to_string(),
+                    "dependencies = [".to_string(),
+                ]
+            })
+            .collect();
+        input += &format!("@@ -1,{} +0,0 @@\n", body.len());
+        for line in &body {
+            input += &format!("-{line}\n");
+        }
+        input += "diff --git a/b.lock b/b.lock\n--- a/b.lock\n+++ b/b.lock\n";
+        input += &format!("@@ -0,0 +1,{} @@\n", body.len());
+        for line in &body {
+            input += &format!("+{line}\n");
+        }
+        let diff = parse_diff(&input).unwrap();
+        let moved = detect_moves(&diff);
+        assert!(!moved.is_empty());
+        assert!(moved.get(1, 0, 0).is_some());
+        assert!(moved.get(1, 0, body.len() - 1).is_some());
+    }
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(compare("a b", "a b"), Some(false));
+        assert_eq!(
+            compare("let total = a + b;", "let total = a + c;"),
+            Some(true)
+        );
+        assert_eq!(compare("let total = a + b;", "return None;"), None);
+    }
+}
@@ -1,6 +1,7 @@
 //! accept된 헌크 재조립 및 JSON 출력
 
-use crate::model::{Diff, DiffLine, ReviewStatus};
+use crate::hunk_edit::{self, Base};
+use crate::model::{Diff, DiffLine, ReviewStatus, Severity};
 use anyhow::Result;
 use serde::Serialize;
 use std::io::Write;
@@ -16,12 +17,28 @@ pub fn write_diff<W: Write>(diff: &Diff, writer: &mut W) -> Result<bool> {
             continue;
         }
 
-        // accepted 헌크만 필터링
-        let accepted_hunks: Vec<_> = file
-            .hunks
-            .iter()
-            .filter(|h| h.status == ReviewStatus::Accepted)
-            .collect();
+        // 지연 로딩된 파일은 accepted 헌크가 있을 때만 본문을 읽는다
+        let loaded;
+        let file = if file.deferred.is_some()
+            && file
+                .hunks
+                .iter()
+                .any(|h| h.status == ReviewStatus::Accepted)
+        {
+            let mut copy = file.clone();
+            copy.load()?; // 경고는 처음 파싱할 때 이미 보고했다
+            loaded = copy;
+            &loaded
+        } else {
+            file
+        };
+
+        // accepted 헌크만 필터링 (수동 분할된 조각은 다시 합침)
+        let accepted_hunks = hunk_edit::join_pieces(
+            &file.hunks,
+            |h| h.status == ReviewStatus::Accepted,
+            Base::Old,
+        );
 
         // 파일에 accepted 헌크가 없으면 생략
         if accepted_hunks.is_empty() {
@@ -33,7 +50,7 @@ pub fn write_diff<W: Write>(diff: &Diff, writer: &mut W) -> Result<bool> {
         writeln!(writer, "+++ {}", file.raw_new_path)?;
 
         // 각 accepted 헌크 출력
-        for hunk in accepted_hunks {
+        for hunk in &accepted_hunks {
             writeln!(writer, "{}", hunk.header)?;
 
             for line in &hunk.lines {
@@ -57,6 +74,8 @@ pub fn write_diff<W: Write>(diff: &Diff, writer: &mut W) -> Result<bool> {
 struct JsonOutput<'a> {
     version: &'static str,
     summary: JsonSummary,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    review_summary: Option<&'a str>,
     files: Vec<JsonFile<'a>>,
 }
 
@@ -67,20 +86,59 @@ struct JsonSummary {
     accepted: usize,
     rejected: usize,
     pending: usize,
+    blocking: usize,
+    follow_ups: usize,
+    comments: JsonCommentCounts,
+}
+
+#[derive(Serialize)]
+struct JsonCommentCounts {
+    blocking: usize,
+    nit: usize,
+    question: usize,
+    praise: usize,
 }
 
 #[derive(Serialize)]
 struct JsonFile<'a> {
     path: &'a str,
-    hunks: Vec<JsonHunk>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    viewed: bool,
+    hunks: Vec<JsonHunk<'a>>,
 }
 
 #[derive(Serialize)]
-struct JsonHunk {
-    header: String,
+struct JsonHunk<'a> {
+    header: &'a str,
     status: String,
+    /// accept되었지만 non-blocking 코멘트가 남은 헌크
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    follow_up: bool,
+    /// 상태를 미리 정한 정책 규칙
     #[serde(skip_serializing_if = "Option::is_none")]
-    comment: Option<String>,
+    rule: Option<&'a str>,
+    /// 리뷰어가 에디터로 직접 고친 헌크
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    edited: bool,
+    /// 첫 헌크 단위 코멘트 (`comments` 이전 형식을 읽는 도구와의 호환용)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<&'a str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    comments: Vec<JsonComment<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonComment<'a> {
+    text: &'a str,
+    severity: &'static str,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    tags: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line_index: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    old_line: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    new_line: Option<u32>,
 }
 
 /// 리뷰 결과를 JSON으로 출력한다.
@@ -88,6 +146,8 @@ pub fn write_json<W: Write>(diff: &Diff, writer: &mut W) -> Result<()> {
     let mut accepted = 0usize;
     let mut rejected = 0usize;
     let mut pending = 0usize;
+    let mut blocking = 0usize;
+    let mut follow_ups = 0usize;
 
     let files: Vec<JsonFile> = diff
         .files
@@ -102,15 +162,38 @@ pub fn write_json<W: Write>(diff: &Diff, writer: &mut W) -> Result<()> {
                         ReviewStatus::Rejected => rejected += 1,
                         ReviewStatus::Pending => pending += 1,
                     }
+                    if h.is_blocking() {
+                        blocking += 1;
+                    }
+                    let follow_up = h.is_follow_up();
+                    if follow_up {
+                        follow_ups += 1;
+                    }
                     JsonHunk {
-                        header: h.header.clone(),
+                        header: &h.header,
                         status: format!("{:?}", h.status).to_lowercase(),
-                        comment: h.comment.clone(),
+                        follow_up,
+                        rule: h.rule.as_deref(),
+                        edited: h.edited,
+                        comment: h.hunk_comments().next().map(|c| c.text.as_str()),
+                        comments: h
+                            .comments
+                            .iter()
+                            .map(|c| JsonComment {
+                                text: &c.text,
+                                severity: c.severity.as_str(),
+                                tags: &c.tags,
+                                line_index: c.anchor.map(|a| a.line_index),
+                                old_line: c.anchor.and_then(|a| a.old_line),
+                                new_line: c.anchor.and_then(|a| a.new_line),
+                            })
+                            .collect(),
                     }
                 })
                 .collect();
             JsonFile {
-                path: &f.new_path,
+                path: f.path(),
+                viewed: f.viewed,
                 hunks,
             }
         })
@@ -124,7 +207,16 @@ pub fn write_json<W: Write>(diff: &Diff, writer: &mut W) -> Result<()> {
             accepted,
             rejected,
             pending,
+            blocking,
+            follow_ups,
+            comments: JsonCommentCounts {
+                blocking: diff.comment_count(Severity::Blocking),
+                nit: diff.comment_count(Severity::Nit),
+                question: diff.comment_count(Severity::Question),
+                praise: diff.comment_count(Severity::Praise),
+            },
         },
+        review_summary: diff.summary.as_deref(),
         files,
     };
 
@@ -136,7 +228,7 @@ pub fn write_json<W: Write>(diff: &Diff, writer: &mut W) -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::model::{FileDiff, Hunk};
+    use crate::model::{Comment, FileDiff, Hunk};
     use indoc::indoc;
     use serde_json::Value;
 
@@ -158,7 +250,10 @@ mod tests {
             new_count,
             lines,
             status,
-            comment: None,
+            comments: Vec::new(),
+            rule: None,
+            edited: false,
+            original_lines: None,
         }
     }
 
@@ -177,6 +272,8 @@ mod tests {
             raw_new_path: new_path.to_string(),
             hunks,
             is_binary,
+            viewed: false,
+            deferred: None,
         }
     }
 
@@ -198,7 +295,7 @@ mod tests {
         );
 
         let file = make_file("a/file.txt", "b/file.txt", vec![hunk], false);
-        let diff = Diff { files: vec![file] };
+        let diff = Diff::new(vec![file]);
 
         let mut output = Vec::new();
         let result = write_diff(&diff, &mut output).unwrap();
@@ -236,7 +333,7 @@ mod tests {
         );
 
         let file = make_file("a/file.txt", "b/file.txt", vec![hunk], false);
-        let diff = Diff { files: vec![file] };
+        let diff = Diff::new(vec![file]);
 
         let mut output = Vec::new();
         let result = write_diff(&diff, &mut output).unwrap();
@@ -287,7 +384,7 @@ mod tests {
         );
 
         let file = make_file("a/file.txt", "b/file.txt", vec![hunk1, hunk2, hunk3], false);
-        let diff = Diff { files: vec![file] };
+        let diff = Diff::new(vec![file]);
 
         let mut output = Vec::new();
         let result = write_diff(&diff, &mut output).unwrap();
@@ -326,7 +423,7 @@ mod tests {
         );
 
         let file = make_file("a/file.txt", "b/file.txt", vec![hunk], false);
-        let diff = Diff { files: vec![file] };
+        let diff = Diff::new(vec![file]);
 
         let mut output = Vec::new();
         let result = write_diff(&diff, &mut output).unwrap();
@@ -362,7 +459,7 @@ mod tests {
         );
 
         let file = make_file("a/image.png", "b/image.png", vec![hunk], true);
-        let diff = Diff { files: vec![file] };
+        let diff = Diff::new(vec![file]);
 
         let mut output = Vec::new();
         let result = write_diff(&diff, &mut output).unwrap();
@@ -412,9 +509,7 @@ mod tests {
         );
         let file3 = make_file("a/file3.txt", "b/file3.txt", vec![file3_hunk], false);
 
-        let diff = Diff {
-            files: vec![file1, file2, file3],
-        };
+        let diff = Diff::new(vec![file1, file2, file3]);
 
         let mut output = Vec::new();
         let result = write_diff(&diff, &mut output).unwrap();
@@ -455,7 +550,7 @@ mod tests {
             ReviewStatus::Accepted,
         );
         let file = make_file("a/file.txt", "b/file.txt", vec![hunk], false);
-        let diff = Diff { files: vec![file] };
+        let diff = Diff::new(vec![file]);
 
         let mut buf = Vec::new();
         write_json(&diff, &mut buf).unwrap();
@@ -481,7 +576,7 @@ mod tests {
             ReviewStatus::Rejected,
         );
         let file = make_file("a/f.rs", "b/f.rs", vec![hunk], false);
-        let diff = Diff { files: vec![file] };
+        let diff = Diff::new(vec![file]);
 
         let mut buf = Vec::new();
         write_json(&diff, &mut buf).unwrap();
@@ -513,7 +608,7 @@ mod tests {
             ReviewStatus::Rejected,
         );
         let file = make_file("a/mix.rs", "b/mix.rs", vec![h1, h2], false);
-        let diff = Diff { files: vec![file] };
+        let diff = Diff::new(vec![file]);
 
         let mut buf = Vec::new();
         write_json(&diff, &mut buf).unwrap();
@@ -526,7 +621,7 @@ mod tests {
 
     #[test]
     fn test_json_empty() {
-        let diff = Diff { files: vec![] };
+        let diff = Diff::new(vec![]);
 
         let mut buf = Vec::new();
         write_json(&diff, &mut buf).unwrap();
@@ -536,4 +631,45 @@ mod tests {
         assert_eq!(json["summary"]["total_hunks"], 0);
         assert_eq!(json["files"].as_array().unwrap().len(), 0);
     }
+
+    #[test]
+    fn test_json_comment_severity_and_summary() {
+        let mut hunk = make_hunk(
+            "@@ -1,1 +1,1 @@",
+            1,
+            1,
+            1,
+            1,
+            vec![DiffLine::Added("x".to_string())],
+            ReviewStatus::Accepted,
+        );
+        let anchor = hunk.anchor_at(0);
+        hunk.comments
+            .push(Comment::from_input("rename later #naming", Severity::Nit).with_anchor(anchor));
+        hunk.comments
+            .push(Comment::from_input("nice", Severity::Praise));
+        let file = make_file("a/f.rs", "b/f.rs", vec![hunk], false);
+        let mut diff = Diff::new(vec![file]);
+        diff.summary = Some("solid change".to_string());
+
+        let mut buf = Vec::new();
+        write_json(&diff, &mut buf).unwrap();
+        let json: Value = serde_json::from_slice(&buf).unwrap();
+
+        assert_eq!(json["review_summary"], "solid change");
+        assert_eq!(json["summary"]["blocking"], 0);
+        assert_eq!(json["summary"]["follow_ups"], 1);
+        assert_eq!(json["files"][0]["hunks"][0]["follow_up"], true);
+        assert_eq!(json["summary"]["comments"]["nit"], 1);
+        assert_eq!(json["summary"]["comments"]["praise"], 1);
+        let comments = &json["files"][0]["hunks"][0]["comments"];
+        assert_eq!(comments[0]["text"], "rename later");
+        assert_eq!(comments[0]["severity"], "nit");
+        assert_eq!(comments[0]["tags"][0], "naming");
+        assert_eq!(comments[0]["line_index"], 0);
+        assert_eq!(comments[0]["new_line"], 1);
+        assert!(comments[0].get("old_line").is_none());
+        assert_eq!(comments[1]["text"], "nice");
+        assert!(comments[1].get("line_index").is_none());
+    }
 }
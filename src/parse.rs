@@ -1,83 +1,502 @@
 //! unified diff 파서
+//!
+//! 기본은 관대하게 파싱한다: 깨진 헌크 헤더, 헌크 안의 알 수 없는 줄, 헤더와 맞지 않는
+//! 라인 수는 [`ParseError`] 경고로 `Diff::warnings`에 모으고 계속 진행한다.
+//! [`ParseOptions::strict`]면 첫 경고에서 에러로 멈춘다.
 
 use crate::model::{Diff, DiffLine, FileDiff, Hunk, ReviewStatus};
 use anyhow::Result;
+use std::fmt;
+use std::io::BufRead;
+use std::ops::Range;
+
+/// 파싱 옵션
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ParseOptions {
+    /// 형식 오류를 경고 대신 에러로 처리한다
+    pub strict: bool,
+    /// 경로 앞에서 뗄 디렉터리 수 (`patch -pN`). None이면 git의 `a/`·`b/` 같은 접두사나
+    /// `diff -r`의 서로 다른 최상위 디렉터리를 알아서 뗀다.
+    pub strip: Option<usize>,
+}
+
+/// 입력의 형식 오류. strict 모드가 아니면 경고로 모인다.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// 1부터 시작하는 입력 줄 번호
+    pub line: usize,
+    pub kind: ParseErrorKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// `@@ -a,b +c,d @@` 형태가 아닌 헌크 헤더
+    InvalidHunkHeader(String),
+    /// 헌크 안에서 ` `, `+`, `-`, `\`로 시작하지 않는 줄
+    UnexpectedLine(String),
+    /// 헌크의 (old, new) 라인 수가 헤더와 다름
+    LineCountMismatch {
+        header: String,
+        expected: (u32, u32),
+        found: (u32, u32),
+    },
+    /// `---`/`+++` 파일 헤더 없이 나온 헌크
+    HunkWithoutFile(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            ParseErrorKind::InvalidHunkHeader(header) => {
+                write!(f, "invalid hunk header `{}`", header)
+            }
+            ParseErrorKind::UnexpectedLine(line) => write!(f, "unexpected line `{}` in hunk", line),
+            ParseErrorKind::LineCountMismatch {
+                header,
+                expected,
+                found,
+            } => write!(
+                f,
+                "hunk `{}` has {} old / {} new lines, header says {} / {}",
+                header, found.0, found.1, expected.0, expected.1
+            ),
+            ParseErrorKind::HunkWithoutFile(header) => {
+                write!(f, "hunk `{}` has no file header", header)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
 
 /// unified diff 텍스트를 파싱한다
 pub fn parse_diff(input: &str) -> Result<Diff> {
-    let mut files = Vec::new();
-    let lines: Vec<&str> = input.lines().collect();
-    let mut i = 0;
+    parse_diff_with(input, ParseOptions::default())
+}
+
+pub fn parse_diff_with(input: &str, options: ParseOptions) -> Result<Diff> {
+    parse_reader(input.as_bytes(), options)
+}
+
+/// 입력 전체를 메모리에 올리지 않고 한 줄씩 읽으며 파싱한다
+pub fn parse_reader<R: BufRead>(reader: R, options: ParseOptions) -> Result<Diff> {
+    let mut reader = DiffReader::with_options(reader, options);
+    let files = reader.by_ref().collect::<Result<Vec<_>>>()?;
+    let mut diff = Diff::new(files);
+    diff.warnings = reader.take_warnings();
+    Ok(diff)
+}
+
+/// 스트리밍 diff 파서. 파일 하나씩 파싱해서 돌려준다.
+/// 다음 줄 하나만 미리 읽어 두므로 입력 크기와 관계없이 메모리는 파일 하나만큼만 쓴다.
+pub struct DiffReader<R> {
+    reader: R,
+    options: ParseOptions,
+    /// 미리 읽었지만 아직 소비하지 않은 줄
+    peeked: Option<String>,
+    /// 소비한 줄까지의 입력 바이트 수
+    consumed: u64,
+    /// 미리 읽은 줄의 바이트 수 (줄바꿈 포함)
+    peeked_len: u64,
+    /// 소비한 줄 수
+    line: usize,
+    warnings: Vec<ParseError>,
+    /// `diff -r`로 비교한 두 최상위 디렉터리 (`Only in` 줄이 어느 쪽인지 가리는 데 쓴다)
+    roots: Option<(String, String)>,
+}
+
+impl<R: BufRead> DiffReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_options(reader, ParseOptions::default())
+    }
+
+    pub fn with_options(reader: R, options: ParseOptions) -> Self {
+        Self {
+            reader,
+            options,
+            peeked: None,
+            consumed: 0,
+            peeked_len: 0,
+            line: 0,
+            warnings: Vec::new(),
+            roots: None,
+        }
+    }
 
-    while i < lines.len() {
-        // diff --git 라인을 찾거나 --- 라인 찾기
-        if lines[i].starts_with("diff --git ") {
-            i += 1; // diff --git 라인 건너뛰기
+    /// 입력의 첫 줄을 `line + 1`번째 줄로 센다 (스풀에 남긴 일부를 다시 파싱할 때)
+    pub fn starting_at(mut self, line: usize) -> Self {
+        self.line = line;
+        self
+    }
+
+    /// 지금까지 소비한 줄 수
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// 지금까지 모인 경고를 꺼낸다
+    pub fn take_warnings(&mut self) -> Vec<ParseError> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// 다음 줄 (소비하지 않음). `str::lines`처럼 끝의 `\n`/`\r\n`은 뺀다.
+    fn peek(&mut self) -> Result<Option<&str>> {
+        if self.peeked.is_none() {
+            let mut line = String::new();
+            let n = self.reader.read_line(&mut line)?;
+            if n == 0 {
+                return Ok(None);
+            }
+            if line.ends_with('\n') {
+                line.pop();
+                if line.ends_with('\r') {
+                    line.pop();
+                }
+            }
+            self.peeked = Some(line);
+            self.peeked_len = n as u64;
+        }
+        Ok(self.peeked.as_deref())
+    }
+
+    /// 미리 읽은 줄을 소비한다
+    fn bump(&mut self) -> Option<String> {
+        self.consumed += self.peeked_len;
+        self.peeked_len = 0;
+        let line = self.peeked.take();
+        if line.is_some() {
+            self.line += 1;
+        }
+        line
+    }
+
+    /// 형식 오류: strict 모드면 에러, 아니면 경고로 남긴다
+    fn report(&mut self, line: usize, kind: ParseErrorKind) -> Result<()> {
+        let error = ParseError { line, kind };
+        if self.options.strict {
+            return Err(error.into());
+        }
+        self.warnings.push(error);
+        Ok(())
+    }
+
+    /// 다음 줄이 `prefix`로 시작하는지
+    fn at(&mut self, prefix: &str) -> Result<bool> {
+        Ok(self.peek()?.is_some_and(|l| l.starts_with(prefix)))
+    }
+
+    /// 다음 파일과 그 파일이 차지한 입력 바이트 범위.
+    /// 범위는 앞의 파일이 끝난 곳부터라서, 그 사이의 다른 줄도 포함할 수 있다.
+    pub fn next_file(&mut self) -> Result<Option<(FileDiff, Range<u64>)>> {
+        let start = self.consumed;
+        // 바로 앞 줄이 `diff -ruN old/x new/x` 같은 (git이 아닌) diff 명령 줄인지
+        let mut command = false;
+        loop {
+            let Some(line) = self.peek()? else {
+                return Ok(None);
+            };
+
+            // diff --git 라인을 찾거나 --- 라인 찾기
+            if line.starts_with("diff --git ") {
+                self.bump(); // diff --git 라인 건너뛰기
+
+                // --- 라인 찾기
+                while self.peek()?.is_some()
+                    && !self.at("---")?
+                    && !self.at("Binary files")?
+                    && !self.at("@@ ")?
+                {
+                    self.bump();
+                }
+
+                if self.peek()?.is_none() {
+                    return Ok(None);
+                }
+            }
+
+            // 바이너리 파일 체크
+            if self.at("Binary files")? {
+                let line = self.bump().unwrap_or_default();
+                if let Some(file_diff) = self.parse_binary_file(&line, command) {
+                    return Ok(Some((file_diff, start..self.consumed)));
+                }
+                continue;
+            }
+
+            // diff -r: 한쪽 디렉터리에만 있는 파일 (내용 없음)
+            if self.at("Only in ")? {
+                let line = self.bump().unwrap_or_default();
+                if let Some(file_diff) = self.parse_only_in(&line) {
+                    return Ok(Some((file_diff, start..self.consumed)));
+                }
+                continue;
+            }
+
+            // 파일 헤더 없는 헌크는 건너뛴다 (잘린 입력)
+            if self.at("@@ ")? {
+                let line_no = self.line + 1;
+                let header = self.bump().unwrap_or_default();
+                self.report(line_no, ParseErrorKind::HunkWithoutFile(header))?;
+                continue;
+            }
 
             // --- 라인 찾기
-            while i < lines.len()
-                && !lines[i].starts_with("---")
-                && !lines[i].starts_with("Binary files")
-            {
-                i += 1;
+            if !self.at("---")? {
+                command = self.bump().is_some_and(|l| l.starts_with("diff "));
+                continue;
             }
 
-            if i >= lines.len() {
-                break;
+            let line = self.bump().unwrap_or_default();
+            let raw_old_path = line.trim_start_matches("---").trim().to_string();
+
+            if self.peek()?.is_none() {
+                return Ok(None);
             }
-        }
 
-        // 바이너리 파일 체크
-        if lines[i].starts_with("Binary files") {
-            if let Some(file_diff) = parse_binary_file(lines[i]) {
-                files.push(file_diff);
+            // +++ 라인 찾기
+            if !self.at("+++")? {
+                continue;
             }
-            i += 1;
-            continue;
+
+            let line = self.bump().unwrap_or_default();
+            let raw_new_path = line.trim_start_matches("+++").trim().to_string();
+
+            // 헌크들 파싱
+            let mut hunks = Vec::new();
+            while self.at("@@")? {
+                let hunk = self.parse_hunk()?;
+                hunks.extend(split_hunk_on_context(&hunk));
+            }
+            self.check_after_hunks()?;
+
+            let (old, old_stamp) = split_header_path(&raw_old_path);
+            let (new, new_stamp) = split_header_path(&raw_new_path);
+            let (mut old_path, mut new_path) = self.strip_paths(&old, &new, command);
+            // GNU diff -N과 svn은 없는 쪽을 /dev/null 대신 타임스탬프로 표시한다
+            let empty = |side: fn(&Hunk) -> (u32, u32)| {
+                !hunks.is_empty() && hunks.iter().all(|h| side(h) == (0, 0))
+            };
+            if is_missing_side(old_stamp, empty(|h| (h.old_start, h.old_count))) {
+                old_path = "/dev/null".to_string();
+            }
+            if is_missing_side(new_stamp, empty(|h| (h.new_start, h.new_count))) {
+                new_path = "/dev/null".to_string();
+            }
+
+            let file = FileDiff {
+                old_path,
+                new_path,
+                raw_old_path,
+                raw_new_path,
+                hunks,
+                is_binary: false,
+                viewed: false,
+                deferred: None,
+            };
+            return Ok(Some((file, start..self.consumed)));
         }
+    }
 
-        // --- 라인 찾기
-        if !lines[i].starts_with("---") {
-            i += 1;
-            continue;
+    /// 헌크 하나 파싱 (다음 줄이 `@@` 헤더).
+    /// 헤더의 라인 수만큼 읽으므로 `---`로 시작하는 삭제 줄도 헌크에 들어간다.
+    /// 헤더가 깨졌으면 다음 헌크나 파일 헤더까지 읽는다.
+    fn parse_hunk(&mut self) -> Result<Hunk> {
+        let line_no = self.line + 1;
+        let header = self.bump().unwrap_or_default();
+        let counts = parse_hunk_header(&header);
+        if counts.is_none() {
+            self.report(line_no, ParseErrorKind::InvalidHunkHeader(header.clone()))?;
         }
+        let (old_start, old_count, new_start, new_count) = counts.unwrap_or_default();
+
+        let mut hunk_lines = Vec::new();
+        let (mut old_seen, mut new_seen) = (0, 0);
+
+        while let Some(line) = self.peek()? {
+            let complete = counts.is_some() && old_seen >= old_count && new_seen >= new_count;
+            if line.starts_with("\\") {
+                // No newline 마커 (앞 줄에 붙어 있어 헌크가 끝난 뒤에도 나온다)
+                hunk_lines.push(DiffLine::NoNewline);
+                self.bump();
+                continue;
+            }
+            // 다음 헌크나 파일로 진입
+            if complete || line.starts_with("@@") || line.starts_with("diff ") {
+                break;
+            }
+            if line.starts_with("---") && (counts.is_none() || old_seen >= old_count) {
+                break;
+            }
 
-        let raw_old_path = lines[i].trim_start_matches("---").trim().to_string();
-        let old_path = parse_path_from_header(lines[i]);
-        i += 1;
+            if line.is_empty() {
+                // 끝 공백이 잘린 빈 context 줄
+                hunk_lines.push(DiffLine::Context(String::new()));
+                old_seen += 1;
+                new_seen += 1;
+            } else {
+                match line.chars().next().unwrap_or(' ') {
+                    ' ' => {
+                        hunk_lines.push(DiffLine::Context(line[1..].to_string()));
+                        old_seen += 1;
+                        new_seen += 1;
+                    }
+                    '+' => {
+                        hunk_lines.push(DiffLine::Added(line[1..].to_string()));
+                        new_seen += 1;
+                    }
+                    '-' => {
+                        hunk_lines.push(DiffLine::Removed(line[1..].to_string()));
+                        old_seen += 1;
+                    }
+                    _ => {
+                        // 알 수 없는 라인은 경고만 남기고 무시 (permissive)
+                        let line = line.to_string();
+                        self.report(self.line + 1, ParseErrorKind::UnexpectedLine(line))?;
+                    }
+                }
+            }
 
-        if i >= lines.len() {
-            break;
+            self.bump();
         }
 
-        // +++ 라인 찾기
-        if !lines[i].starts_with("+++") {
-            continue;
+        let truncated = counts.is_some() && (old_seen, new_seen) != (old_count, new_count);
+        if truncated {
+            self.report(
+                line_no,
+                ParseErrorKind::LineCountMismatch {
+                    header: header.clone(),
+                    expected: (old_count, new_count),
+                    found: (old_seen, new_seen),
+                },
+            )?;
         }
 
-        let raw_new_path = lines[i].trim_start_matches("+++").trim().to_string();
-        let new_path = parse_path_from_header(lines[i]);
-        i += 1;
+        let mut hunk = Hunk {
+            header,
+            old_start,
+            old_count,
+            new_start,
+            new_count,
+            lines: hunk_lines,
+            status: ReviewStatus::Pending,
+            comments: Vec::new(),
+            rule: None,
+            edited: false,
+            original_lines: None,
+        };
+        if truncated {
+            // 출력 패치가 `git apply`에서 깨진 패치로 거부되지 않도록 실제 라인 수로 헤더를 고친다
+            hunk.recount();
+        }
+        Ok(hunk)
+    }
 
-        // 헌크들 파싱
-        let mut hunks = Vec::new();
-        while i < lines.len() && lines[i].starts_with("@@") {
-            let (hunk, next_i) = parse_hunk(&lines, i);
-            hunks.extend(split_hunk_on_context(&hunk));
-            i = next_i;
+    /// 헤더의 두 경로에서 접두사를 뗀 표시용 경로.
+    /// `command`는 바로 앞에 `diff -r` 명령 줄이 있었는지 (최상위 디렉터리를 떼도 되는 근거)
+    fn strip_paths(&mut self, old: &str, new: &str, command: bool) -> (String, String) {
+        let mut diff_r = false;
+        if let (Some((o, old_rest)), Some((n, new_rest))) =
+            (old.split_once('/'), new.split_once('/'))
+            && o != n
+            && old_rest == new_rest
+        {
+            // 앞에서 본 `diff -r` 디렉터리면 명령 줄이 없어도 (`Binary files ...`) 같은 비교다
+            let known = self
+                .roots
+                .as_ref()
+                .is_some_and(|(ro, rn)| ro == o && rn == n);
+            if command || known {
+                self.roots = Some((o.to_string(), n.to_string()));
+                diff_r = true;
+            }
         }
+        let depth = self
+            .options
+            .strip
+            .unwrap_or_else(|| if diff_r { 1 } else { auto_strip(old, new) });
+        (strip_components(old, depth), strip_components(new, depth))
+    }
+
+    /// "Binary files a/path and b/path differ" 줄 파싱
+    fn parse_binary_file(&mut self, line: &str, command: bool) -> Option<FileDiff> {
+        let paths = line
+            .strip_prefix("Binary files ")?
+            .strip_suffix(" differ")?;
+        let (old, new) = paths.split_once(" and ")?;
+        let (old_path, new_path) = self.strip_paths(old, new, command);
 
-        files.push(FileDiff {
+        Some(FileDiff {
             old_path,
             new_path,
-            raw_old_path,
-            raw_new_path,
-            hunks,
+            raw_old_path: old.to_string(),
+            raw_new_path: new.to_string(),
+            hunks: Vec::new(),
+            is_binary: true,
+            viewed: false,
+            deferred: None,
+        })
+    }
+
+    /// "Only in DIR: NAME" 줄 파싱. 앞에서 본 `diff -r` 디렉터리로 어느 쪽인지 정하고,
+    /// 모르면 양쪽 경로를 같게 둔다.
+    fn parse_only_in(&self, line: &str) -> Option<FileDiff> {
+        let (dir, name) = line.strip_prefix("Only in ")?.split_once(": ")?;
+        let raw = format!("{}/{}", dir.trim_end_matches('/'), name);
+        let depth = self
+            .options
+            .strip
+            .unwrap_or(usize::from(self.roots.is_some()));
+        let path = strip_components(&raw, depth);
+        let root = raw.split('/').next().unwrap_or_default();
+        let (old_path, new_path) = match &self.roots {
+            Some((old, _)) if old == root => (path, "/dev/null".to_string()),
+            Some((_, new)) if new == root => ("/dev/null".to_string(), path),
+            _ => (path.clone(), path),
+        };
+
+        Some(FileDiff {
+            old_path,
+            new_path,
+            raw_old_path: raw.clone(),
+            raw_new_path: raw,
+            hunks: Vec::new(),
             is_binary: false,
-        });
+            viewed: false,
+            deferred: None,
+        })
+    }
+
+    /// 파일의 마지막 헌크가 헤더 라인 수만큼 읽혀 끝났는데 바로 다음 줄이 diff
+    /// 라인처럼 보이면 헤더가 실제보다 짧은 것이다
+    fn check_after_hunks(&mut self) -> Result<()> {
+        let Some(line) = self.peek()? else {
+            return Ok(());
+        };
+        // `-- `는 git format-patch의 서명 구분선
+        let stray = line.starts_with(' ')
+            || (line.starts_with('+') && !line.starts_with("+++"))
+            || (line.starts_with('-') && !line.starts_with("---") && line != "-- ");
+        if stray {
+            let line = line.to_string();
+            self.report(self.line + 1, ParseErrorKind::UnexpectedLine(line))?;
+        }
+        Ok(())
     }
+}
+
+impl<R: BufRead> Iterator for DiffReader<R> {
+    type Item = Result<FileDiff>;
 
-    Ok(Diff { files })
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_file()
+            .map(|f| f.map(|(file, _)| file))
+            .transpose()
+    }
 }
 
 /// 헌크를 변경 그룹별로 분할
@@ -227,146 +646,134 @@ fn split_hunk_on_context(hunk: &Hunk) -> Vec<Hunk> {
             new_count: new_new_count,
             lines: new_lines,
             status: hunk.status,
-            comment: hunk.comment.clone(),
+            comments: Vec::new(),
+            rule: None,
+            edited: false,
+            original_lines: None,
         });
     }
 
     result
 }
 
-/// 바이너리 파일 라인 파싱
-fn parse_binary_file(line: &str) -> Option<FileDiff> {
-    // "Binary files a/path and b/path differ" 형태
-    let parts: Vec<&str> = line.split_whitespace().collect();
-    if parts.len() < 5 {
-        return None;
-    }
-
-    let raw_old_path = parts[2].to_string();
-    let raw_new_path = parts[4].to_string();
-    let old_path = parts[2].trim_start_matches("a/").to_string();
-    let new_path = parts[4].trim_start_matches("b/").to_string();
-
-    Some(FileDiff {
-        old_path,
-        new_path,
-        raw_old_path,
-        raw_new_path,
-        hunks: Vec::new(),
-        is_binary: true,
-    })
-}
-
-/// --- 또는 +++ 라인에서 경로 추출
-fn parse_path_from_header(line: &str) -> String {
-    let path = if line.starts_with("---") {
-        line.trim_start_matches("---").trim()
-    } else if line.starts_with("+++") {
-        line.trim_start_matches("+++").trim()
-    } else {
-        return String::new();
+/// `---`/`+++` 뒤의 경로와 탭 뒤에 붙은 타임스탬프나 리비전.
+/// GNU diff는 `file.c\t2024-01-01 ...`, svn은 `file.c\t(revision 12)`,
+/// git은 특수문자가 든 경로를 따옴표로 감싼다.
+fn split_header_path(raw: &str) -> (String, Option<&str>) {
+    let (path, stamp) = match raw.split_once('\t') {
+        Some((path, stamp)) => (path.trim_end(), Some(stamp.trim())),
+        None => (raw, None),
     };
-
-    // /dev/null 체크
-    if path == "/dev/null" {
-        return path.to_string();
-    }
-
-    // a/ 또는 b/ 접두사 제거
-    path.trim_start_matches("a/")
-        .trim_start_matches("b/")
-        .to_string()
+    (unquote(path), stamp)
 }
 
-/// 헌크 하나 파싱
-fn parse_hunk(lines: &[&str], start: usize) -> (Hunk, usize) {
-    let header = lines[start].to_string();
-    let (old_start, old_count, new_start, new_count) = parse_hunk_header(&header);
-
-    let mut hunk_lines = Vec::new();
-    let mut i = start + 1;
-
-    while i < lines.len() {
-        let line = lines[i];
-
-        // 다음 헌크나 파일로 진입
-        if line.starts_with("@@") || line.starts_with("diff --git") || line.starts_with("---") {
-            break;
-        }
-
-        // No newline 마커
-        if line.starts_with("\\ No newline") {
-            hunk_lines.push(DiffLine::NoNewline);
-            i += 1;
+/// git의 C 스타일 따옴표 경로 (`"a/tab\there"`, 8진수 UTF-8 바이트)를 푼다
+fn unquote(path: &str) -> String {
+    let Some(inner) = path.strip_prefix('"').and_then(|p| p.strip_suffix('"')) else {
+        return path.to_string();
+    };
+    let mut bytes = Vec::with_capacity(inner.len());
+    let mut chars = inner.bytes().peekable();
+    while let Some(b) = chars.next() {
+        if b != b'\\' {
+            bytes.push(b);
             continue;
         }
-
-        // 일반 diff 라인
-        if line.is_empty() {
-            hunk_lines.push(DiffLine::Context(String::new()));
-            i += 1;
-            continue;
+        match chars.next() {
+            Some(b't') => bytes.push(b'\t'),
+            Some(b'n') => bytes.push(b'\n'),
+            Some(d @ b'0'..=b'7') => {
+                let mut value = u32::from(d - b'0');
+                for _ in 0..2 {
+                    if let Some(&d @ b'0'..=b'7') = chars.peek() {
+                        value = value * 8 + u32::from(d - b'0');
+                        chars.next();
+                    }
+                }
+                bytes.push(value as u8);
+            }
+            Some(other) => bytes.push(other),
+            None => bytes.push(b'\\'),
         }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
 
-        let first_char = line.chars().next().unwrap_or(' ');
-        match first_char {
-            ' ' => {
-                hunk_lines.push(DiffLine::Context(line[1..].to_string()));
-            }
-            '+' => {
-                hunk_lines.push(DiffLine::Added(line[1..].to_string()));
-            }
-            '-' => {
-                hunk_lines.push(DiffLine::Removed(line[1..].to_string()));
+/// git이 붙이는 경로 접두사 쌍: 기본 `a/`·`b/`와 `diff.mnemonicPrefix`의
+/// (c)ommit·(i)ndex·(w)ork tree·(o)bject, `--no-index`의 `1/`·`2/`
+const GIT_PREFIXES: [(&str, &str); 6] = [
+    ("a", "b"),
+    ("i", "w"),
+    ("c", "w"),
+    ("c", "i"),
+    ("o", "w"),
+    ("1", "2"),
+];
+
+/// `-p`가 없고 `diff -r`도 아닐 때 뗄 디렉터리 수: git/hg가 붙이는 접두사 쌍이면 1.
+/// `--no-prefix`의 `src/x.rs → lib/x.rs` 같은 이름 변경은 그대로 둔다.
+fn auto_strip(old: &str, new: &str) -> usize {
+    match (old, new) {
+        ("/dev/null", path) => usize::from(path.starts_with("b/")),
+        (path, "/dev/null") => usize::from(path.starts_with("a/")),
+        _ => match (old.split_once('/'), new.split_once('/')) {
+            (Some((o, _)), Some((n, _))) => {
+                usize::from(GIT_PREFIXES.contains(&(o, n)) || GIT_PREFIXES.contains(&(n, o)))
             }
-            _ => {
-                // 알 수 없는 라인은 무시 (permissive)
-            }
-        }
+            _ => 0,
+        },
+    }
+}
 
-        i += 1;
+/// 경로 앞의 디렉터리 `depth`개를 뗀다 (`patch -pN`). 파일 이름은 남긴다.
+fn strip_components(path: &str, depth: usize) -> String {
+    if path == "/dev/null" {
+        return path.to_string();
+    }
+    let mut rest = path;
+    for _ in 0..depth {
+        match rest.split_once('/') {
+            Some((_, tail)) if !tail.is_empty() => rest = tail,
+            _ => break,
+        }
     }
+    rest.to_string()
+}
 
-    let hunk = Hunk {
-        header,
-        old_start,
-        old_count,
-        new_start,
-        new_count,
-        lines: hunk_lines,
-        status: ReviewStatus::Pending,
-        comment: None,
+/// 타임스탬프로 표시된 없는 쪽 파일: svn의 `(nonexistent)`, 또는 헌크가 모두
+/// `-0,0`(`+0,0`)인데 시각이 유닉스 기원인 GNU `diff -N` 출력
+fn is_missing_side(stamp: Option<&str>, empty: bool) -> bool {
+    let Some(stamp) = stamp else {
+        return false;
     };
-
-    (hunk, i)
+    stamp.contains("(nonexistent)")
+        || (empty
+            && (stamp.starts_with("1970-01-01")
+                || stamp.starts_with("1969-12-31")
+                || stamp.contains("(revision 0)")))
 }
 
-/// @@ -a,b +c,d @@ 헤더 파싱
-fn parse_hunk_header(header: &str) -> (u32, u32, u32, u32) {
+/// @@ -a,b +c,d @@ 헤더 파싱. 형식이 틀리면 None
+fn parse_hunk_header(header: &str) -> Option<(u32, u32, u32, u32)> {
     // @@ -1,3 +1,4 @@ 형태에서 숫자 추출
-    let parts: Vec<&str> = header.split_whitespace().collect();
-    if parts.len() < 3 {
-        return (0, 0, 0, 0);
+    let mut parts = header.split_whitespace();
+    if parts.next()? != "@@" {
+        return None;
+    }
+    let (old_start, old_count) = parse_range(parts.next()?.strip_prefix('-')?)?;
+    let (new_start, new_count) = parse_range(parts.next()?.strip_prefix('+')?)?;
+    if parts.next()? != "@@" {
+        return None;
     }
 
-    let old_part = parts[1].trim_start_matches('-');
-    let new_part = parts[2].trim_start_matches('+');
-
-    let (old_start, old_count) = parse_range(old_part);
-    let (new_start, new_count) = parse_range(new_part);
-
-    (old_start, old_count, new_start, new_count)
+    Some((old_start, old_count, new_start, new_count))
 }
 
 /// "a,b" 또는 "a" 형태 파싱
-fn parse_range(range: &str) -> (u32, u32) {
-    if let Some(comma_pos) = range.find(',') {
-        let start = range[..comma_pos].parse().unwrap_or(0);
-        let count = range[comma_pos + 1..].parse().unwrap_or(0);
-        (start, count)
-    } else {
-        let start = range.parse().unwrap_or(0);
-        (start, 1) // count 생략 시 1로 간주
+fn parse_range(range: &str) -> Option<(u32, u32)> {
+    match range.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)), // count 생략 시 1로 간주
     }
 }
 
@@ -375,6 +782,11 @@ mod tests {
     use super::*;
     use indoc::indoc;
 
+    const STRICT: ParseOptions = ParseOptions {
+        strict: true,
+        strip: None,
+    };
+
     #[test]
     fn test_empty_input() {
         let diff = parse_diff("").unwrap();
@@ -399,7 +811,7 @@ mod tests {
         let file = &diff.files[0];
         assert_eq!(file.old_path, "file.txt");
         assert_eq!(file.new_path, "file.txt");
-        assert_eq!(file.is_binary, false);
+        assert!(!file.is_binary);
         assert_eq!(file.hunks.len(), 1);
 
         let hunk = &file.hunks[0];
@@ -424,7 +836,7 @@ mod tests {
              line1
             +added line
              line2
-            @@ -10,2 +11,2 @@
+            @@ -10,2 +11,1 @@
             -removed line
              line10
         "};
@@ -497,7 +909,7 @@ mod tests {
         let file = &diff.files[0];
         assert_eq!(file.old_path, "image.png");
         assert_eq!(file.new_path, "image.png");
-        assert_eq!(file.is_binary, true);
+        assert!(file.is_binary);
         assert_eq!(file.hunks.len(), 0);
     }
 
@@ -674,7 +1086,7 @@ mod tests {
         let input = indoc! {"
             --- a/file.txt
             +++ b/file.txt
-            @@ -1,20 +1,17 @@
+            @@ -1,15 +1,14 @@
              line1
              line2
             -deleted1
@@ -702,15 +1114,25 @@ mod tests {
 
         // 첫 번째 헌크: deleted1
         let hunk1 = &file.hunks[0];
-        assert!(matches!(hunk1.lines.iter().find(|l| matches!(l, DiffLine::Removed(_))), Some(_)));
+        assert!(
+            hunk1
+                .lines
+                .iter()
+                .any(|l| matches!(l, DiffLine::Removed(_)))
+        );
 
         // 두 번째 헌크: added1
         let hunk2 = &file.hunks[1];
-        assert!(matches!(hunk2.lines.iter().find(|l| matches!(l, DiffLine::Added(_))), Some(_)));
+        assert!(hunk2.lines.iter().any(|l| matches!(l, DiffLine::Added(_))));
 
         // 세 번째 헌크: deleted2
         let hunk3 = &file.hunks[2];
-        assert!(matches!(hunk3.lines.iter().find(|l| matches!(l, DiffLine::Removed(_))), Some(_)));
+        assert!(
+            hunk3
+                .lines
+                .iter()
+                .any(|l| matches!(l, DiffLine::Removed(_)))
+        );
     }
 
     #[test]
@@ -718,7 +1140,7 @@ mod tests {
         let input = indoc! {"
             --- a/file.txt
             +++ b/file.txt
-            @@ -1,10 +1,9 @@
+            @@ -1,9 +1,7 @@
              line1
              line2
             -deleted1
@@ -742,11 +1164,21 @@ mod tests {
         let hunk1 = &file.hunks[0];
         // NoNewline이 변경 그룹에 포함되어야 함
         assert!(hunk1.lines.iter().any(|l| matches!(l, DiffLine::NoNewline)));
-        assert!(hunk1.lines.iter().any(|l| matches!(l, DiffLine::Removed(_))));
+        assert!(
+            hunk1
+                .lines
+                .iter()
+                .any(|l| matches!(l, DiffLine::Removed(_)))
+        );
 
         // 두 번째 헌크: deleted2
         let hunk2 = &file.hunks[1];
-        assert!(hunk2.lines.iter().any(|l| matches!(l, DiffLine::Removed(_))));
+        assert!(
+            hunk2
+                .lines
+                .iter()
+                .any(|l| matches!(l, DiffLine::Removed(_)))
+        );
     }
 
     #[test]
@@ -756,7 +1188,7 @@ mod tests {
             diff --git a/test.txt b/test.txt
             --- a/test.txt
             +++ b/test.txt
-            @@ -10,12 +10,10 @@
+            @@ -10,9 +10,9 @@
              context10
              context11
             +added12
@@ -781,7 +1213,305 @@ mod tests {
         assert!(file.hunks[1].old_start >= 10);
 
         // 각 헌크가 변경 사항을 포함하는지 확인
-        assert!(file.hunks[0].lines.iter().any(|l| matches!(l, DiffLine::Added(_))));
-        assert!(file.hunks[1].lines.iter().any(|l| matches!(l, DiffLine::Removed(_))));
+        assert!(
+            file.hunks[0]
+                .lines
+                .iter()
+                .any(|l| matches!(l, DiffLine::Added(_)))
+        );
+        assert!(
+            file.hunks[1]
+                .lines
+                .iter()
+                .any(|l| matches!(l, DiffLine::Removed(_)))
+        );
+    }
+
+    #[test]
+    fn test_reader_ranges_cover_each_file() {
+        let input = indoc! {"
+            diff --git a/file1.txt b/file1.txt
+            --- a/file1.txt
+            +++ b/file1.txt
+            @@ -1,1 +1,2 @@
+             line1
+            +added to file1
+            diff --git a/file2.txt b/file2.txt
+            --- a/file2.txt
+            +++ b/file2.txt
+            @@ -1,1 +1,2 @@
+             line1
+            +added to file2
+        "};
+
+        let mut reader = DiffReader::new(input.as_bytes());
+        let (first, range1) = reader.next_file().unwrap().unwrap();
+        let (second, range2) = reader.next_file().unwrap().unwrap();
+        assert!(reader.next_file().unwrap().is_none());
+
+        assert_eq!(range1, 0..range2.start);
+        assert_eq!(range2.end, input.len() as u64);
+        // 범위만 다시 파싱해도 같은 파일이 나온다
+        let again = parse_diff(&input[range2.start as usize..]).unwrap();
+        assert_eq!(again.files[0].hunks[0].lines, second.hunks[0].lines);
+        assert_eq!(first.new_path, "file1.txt");
+    }
+
+    #[test]
+    fn test_reader_strips_crlf() {
+        let input = "--- a/f.txt\r\n+++ b/f.txt\r\n@@ -1 +1 @@\r\n-old\r\n+new\r\n";
+        let diff = parse_diff(input).unwrap();
+        let hunk = &diff.files[0].hunks[0];
+        assert_eq!(diff.files[0].new_path, "f.txt");
+        assert!(matches!(&hunk.lines[0], DiffLine::Removed(s) if s == "old"));
+        assert!(matches!(&hunk.lines[1], DiffLine::Added(s) if s == "new"));
+    }
+
+    #[test]
+    fn test_removed_line_starting_with_dashes() {
+        // 헤더의 라인 수만큼 읽으므로 `-- comment`를 지운 줄도 헌크에 들어간다
+        let input = indoc! {"
+            --- a/schema.sql
+            +++ b/schema.sql
+            @@ -1,2 +1,1 @@
+            --- old comment
+             SELECT 1;
+        "};
+
+        let diff = parse_diff_with(input, STRICT).unwrap();
+        let hunk = &diff.files[0].hunks[0];
+        assert_eq!(hunk.lines.len(), 2);
+        assert!(matches!(&hunk.lines[0], DiffLine::Removed(s) if s == "-- old comment"));
+    }
+
+    #[test]
+    fn test_format_patch_signature_is_not_a_warning() {
+        let input = indoc! {"
+            Subject: [PATCH] change
+            ---
+             a.txt | 2 +-
+            diff --git a/a.txt b/a.txt
+            --- a/a.txt
+            +++ b/a.txt
+            @@ -1 +1 @@
+            -a
+            +b
+            -- 
+            2.43.0
+        "};
+
+        let diff = parse_diff(input).unwrap();
+        assert!(diff.warnings.is_empty(), "{:?}", diff.warnings);
+        assert_eq!(diff.files[0].hunks[0].lines.len(), 2);
+    }
+
+    #[test]
+    fn test_invalid_hunk_header_is_a_warning() {
+        let input = indoc! {"
+            --- a/a.txt
+            +++ b/a.txt
+            @@ -1,x +1 @@
+            +b
+        "};
+
+        let diff = parse_diff(input).unwrap();
+        assert_eq!(diff.files[0].hunks[0].old_start, 0);
+        assert_eq!(diff.files[0].hunks[0].lines.len(), 1);
+        assert_eq!(
+            diff.warnings,
+            vec![ParseError {
+                line: 3,
+                kind: ParseErrorKind::InvalidHunkHeader("@@ -1,x +1 @@".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_strict_line_count_mismatch() {
+        let input = "--- a/a.txt\n+++ b/a.txt\n@@ -1,3 +1,3 @@\n a\n-b\n";
+        let diff = parse_diff(input).unwrap();
+        assert_eq!(diff.warnings.len(), 1);
+        // 관대한 모드에서는 읽은 라인 수로 헤더를 다시 만든다
+        let hunk = &diff.files[0].hunks[0];
+        assert_eq!(hunk.header, "@@ -1,2 +1,1 @@");
+        assert_eq!((hunk.old_count, hunk.new_count), (2, 1));
+
+        let err = parse_diff_with(input, STRICT).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 3: hunk `@@ -1,3 +1,3 @@` has 2 old / 1 new lines, header says 3 / 3"
+        );
+    }
+
+    #[test]
+    fn test_parse_hunk_header() {
+        assert_eq!(
+            parse_hunk_header("@@ -1,3 +1,4 @@ fn main()"),
+            Some((1, 3, 1, 4))
+        );
+        assert_eq!(parse_hunk_header("@@ -5 +0,0 @@"), Some((5, 1, 0, 0)));
+        assert_eq!(parse_hunk_header("@@ -1,3 +1,4"), None);
+        assert_eq!(parse_hunk_header("@@ 1,3 +1,4 @@"), None);
+        assert_eq!(parse_hunk_header("@@@ -1 -1 +1 @@@"), None);
+    }
+
+    #[test]
+    fn test_gnu_diff_timestamps() {
+        let input = "--- file.c\t2024-01-01 10:00:00.000000000 +0100\n\
+                     +++ file.c\t2024-01-02 11:30:00.000000000 +0100\n\
+                     @@ -1 +1 @@\n-a\n+b\n";
+
+        let diff = parse_diff(input).unwrap();
+        let file = &diff.files[0];
+        assert_eq!(file.old_path, "file.c");
+        assert_eq!(file.new_path, "file.c");
+        // 출력은 입력 헤더 그대로
+        assert_eq!(
+            file.raw_old_path,
+            "file.c\t2024-01-01 10:00:00.000000000 +0100"
+        );
+    }
+
+    #[test]
+    fn test_diff_ru_output() {
+        let input = indoc! {"
+            diff -ruN old/src/main.c new/src/main.c
+            --- old/src/main.c\t2024-01-01 10:00:00.000000000 +0100
+            +++ new/src/main.c\t2024-01-02 10:00:00.000000000 +0100
+            @@ -1 +1 @@
+            -int x;
+            +long x;
+            diff -ruN old/src/util.c new/src/util.c
+            --- old/src/util.c\t1970-01-01 01:00:00.000000000 +0100
+            +++ new/src/util.c\t2024-01-02 10:00:00.000000000 +0100
+            @@ -0,0 +1,2 @@
+            +int util(void);
+            +int util2(void);
+            Only in old/docs: notes.txt
+            Only in new: README
+            Common subdirectories: old/tests and new/tests
+            Binary files old/logo.png and new/logo.png differ
+        "};
+
+        let diff = parse_diff_with(input, STRICT).unwrap();
+        let paths: Vec<(&str, &str)> = diff
+            .files
+            .iter()
+            .map(|f| (f.old_path.as_str(), f.new_path.as_str()))
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                ("src/main.c", "src/main.c"),
+                ("/dev/null", "src/util.c"),
+                ("docs/notes.txt", "/dev/null"),
+                ("/dev/null", "README"),
+                ("logo.png", "logo.png"),
+            ]
+        );
+        assert!(diff.files[2].hunks.is_empty());
+        assert!(diff.files[4].is_binary);
+        assert_eq!(
+            diff.files[0].raw_old_path.split('\t').next(),
+            Some("old/src/main.c")
+        );
+    }
+
+    #[test]
+    fn test_strip_option() {
+        let input = "--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1 +1 @@\n-a\n+b\n";
+        let strip = |n| {
+            let options = ParseOptions {
+                strip: Some(n),
+                ..ParseOptions::default()
+            };
+            parse_diff_with(input, options).unwrap().files[0]
+                .new_path
+                .clone()
+        };
+        assert_eq!(strip(0), "b/src/lib.rs");
+        assert_eq!(strip(1), "src/lib.rs");
+        assert_eq!(strip(2), "lib.rs");
+        // 파일 이름은 떼지 않는다
+        assert_eq!(strip(5), "lib.rs");
+    }
+
+    #[test]
+    fn test_svn_diff_headers() {
+        let input = indoc! {"
+            Index: trunk/src/app.c
+            ===================================================================
+            --- trunk/src/app.c\t(revision 1234)
+            +++ trunk/src/app.c\t(working copy)
+            @@ -1 +1 @@
+            -old
+            +new
+            Index: trunk/src/new.c
+            ===================================================================
+            --- trunk/src/new.c\t(nonexistent)
+            +++ trunk/src/new.c\t(working copy)
+            @@ -0,0 +1 @@
+            +int main(void);
+        "};
+
+        let diff = parse_diff_with(input, STRICT).unwrap();
+        assert_eq!(diff.files.len(), 2);
+        assert_eq!(diff.files[0].old_path, "trunk/src/app.c");
+        assert_eq!(diff.files[0].new_path, "trunk/src/app.c");
+        assert_eq!(diff.files[1].old_path, "/dev/null");
+        assert_eq!(diff.files[1].new_path, "trunk/src/new.c");
+    }
+
+    #[test]
+    fn test_hg_diff_headers() {
+        let input = indoc! {"
+            diff -r 9117c6561b0b src/app.py
+            --- a/src/app.py\tThu Jan 01 00:00:00 1970 +0000
+            +++ b/src/app.py\tMon Mar 04 10:00:00 2024 +0100
+            @@ -1 +1 @@
+            -x = 1
+            +x = 2
+        "};
+
+        let diff = parse_diff_with(input, STRICT).unwrap();
+        assert_eq!(diff.files[0].old_path, "src/app.py");
+        assert_eq!(diff.files[0].new_path, "src/app.py");
+    }
+
+    #[test]
+    fn test_path_prefixes_only_stripped_in_pairs() {
+        // git diff --no-prefix에서 `a`라는 디렉터리
+        let input = "--- a/x.txt\n+++ a/x.txt\n@@ -1 +1 @@\n-a\n+b\n";
+        assert_eq!(parse_diff(input).unwrap().files[0].new_path, "a/x.txt");
+    }
+
+    #[test]
+    fn test_no_prefix_rename_is_not_stripped() {
+        // git diff --no-prefix의 디렉터리 이동
+        let input = "--- src/x.rs\n+++ lib/x.rs\n@@ -1 +1 @@\n-a\n+b\n";
+        let file = &parse_diff(input).unwrap().files[0];
+        assert_eq!(file.old_path, "src/x.rs");
+        assert_eq!(file.new_path, "lib/x.rs");
+
+        // mnemonicPrefix 접두사는 뗀다
+        let input = "--- i/src/x.rs\n+++ w/src/x.rs\n@@ -1 +1 @@\n-a\n+b\n";
+        assert_eq!(parse_diff(input).unwrap().files[0].new_path, "src/x.rs");
+    }
+
+    #[test]
+    fn test_quoted_git_paths() {
+        assert_eq!(
+            split_header_path("\"a/with\\ttab.txt\"").0,
+            "a/with\ttab.txt"
+        );
+        assert_eq!(split_header_path("\"b/caf\\303\\251.txt\"").0, "b/café.txt");
+        assert_eq!(
+            split_header_path("\"a/say \\\"hi\\\".txt\"").0,
+            "a/say \"hi\".txt"
+        );
+        assert_eq!(
+            split_header_path("a/plain.txt\t2024-01-01"),
+            ("a/plain.txt".to_string(), Some("2024-01-01"))
+        );
     }
 }
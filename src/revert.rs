@@ -6,7 +6,8 @@ use std::path::PathBuf;
 use std::process::Command;
 
 use crate::git;
-use crate::model::{Diff, DiffLine, ReviewStatus};
+use crate::hunk_edit;
+use crate::model::{Diff, DiffLine};
 
 const MAX_BACKUP_REFS: usize = 10;
 
@@ -112,11 +113,8 @@ pub fn generate_reverse_patch(diff: &Diff) -> String {
             continue;
         }
 
-        let rejected_hunks: Vec<_> = file
-            .hunks
-            .iter()
-            .filter(|h| h.status == ReviewStatus::Rejected)
-            .collect();
+        // 수동 분할된 조각은 작업 트리 쪽 내용으로 다시 합치고, 고친 헌크는 고친 대로 맞춤
+        let rejected_hunks = hunk_edit::revert_pieces(&file.hunks);
 
         if rejected_hunks.is_empty() {
             continue;
@@ -125,7 +123,7 @@ pub fn generate_reverse_patch(diff: &Diff) -> String {
         output.push_str(&format!("--- {}\n", file.raw_new_path.replace("b/", "a/")));
         output.push_str(&format!("+++ {}\n", file.raw_new_path));
 
-        for hunk in rejected_hunks {
+        for hunk in &rejected_hunks {
             // 역방향: old ↔ new 교환
             let rev_header = format!(
                 "@@ -{},{} +{},{} @@",
@@ -188,7 +186,7 @@ pub fn apply_reverse(patch: &str) -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::model::{FileDiff, Hunk};
+    use crate::model::{FileDiff, Hunk, ReviewStatus};
 
     fn make_hunk(
         old_start: u32,
@@ -209,7 +207,10 @@ mod tests {
             new_count,
             lines,
             status,
-            comment: None,
+            comments: Vec::new(),
+            rule: None,
+            edited: false,
+            original_lines: None,
         }
     }
 
@@ -221,29 +222,29 @@ mod tests {
             raw_new_path: format!("b/{}", path),
             hunks,
             is_binary: false,
+            viewed: false,
+            deferred: None,
         }
     }
 
     #[test]
     fn test_reverse_single_add() {
-        let diff = Diff {
-            files: vec![make_file(
-                "src/main.rs",
-                vec![make_hunk(
-                    1,
-                    3,
-                    1,
-                    4,
-                    vec![
-                        DiffLine::Context("line1".to_string()),
-                        DiffLine::Context("line2".to_string()),
-                        DiffLine::Added("new line".to_string()),
-                        DiffLine::Context("line3".to_string()),
-                    ],
-                    ReviewStatus::Rejected,
-                )],
+        let diff = Diff::new(vec![make_file(
+            "src/main.rs",
+            vec![make_hunk(
+                1,
+                3,
+                1,
+                4,
+                vec![
+                    DiffLine::Context("line1".to_string()),
+                    DiffLine::Context("line2".to_string()),
+                    DiffLine::Added("new line".to_string()),
+                    DiffLine::Context("line3".to_string()),
+                ],
+                ReviewStatus::Rejected,
             )],
-        };
+        )]);
 
         let patch = generate_reverse_patch(&diff);
         assert!(patch.contains("@@ -1,4 +1,3 @@"));
@@ -253,24 +254,22 @@ mod tests {
 
     #[test]
     fn test_reverse_single_remove() {
-        let diff = Diff {
-            files: vec![make_file(
-                "src/main.rs",
-                vec![make_hunk(
-                    1,
-                    4,
-                    1,
-                    3,
-                    vec![
-                        DiffLine::Context("line1".to_string()),
-                        DiffLine::Removed("deleted line".to_string()),
-                        DiffLine::Context("line2".to_string()),
-                        DiffLine::Context("line3".to_string()),
-                    ],
-                    ReviewStatus::Rejected,
-                )],
+        let diff = Diff::new(vec![make_file(
+            "src/main.rs",
+            vec![make_hunk(
+                1,
+                4,
+                1,
+                3,
+                vec![
+                    DiffLine::Context("line1".to_string()),
+                    DiffLine::Removed("deleted line".to_string()),
+                    DiffLine::Context("line2".to_string()),
+                    DiffLine::Context("line3".to_string()),
+                ],
+                ReviewStatus::Rejected,
             )],
-        };
+        )]);
 
         let patch = generate_reverse_patch(&diff);
         assert!(patch.contains("@@ -1,3 +1,4 @@"));
@@ -279,24 +278,22 @@ mod tests {
 
     #[test]
     fn test_reverse_mixed() {
-        let diff = Diff {
-            files: vec![make_file(
-                "src/main.rs",
-                vec![make_hunk(
-                    1,
-                    3,
-                    1,
-                    3,
-                    vec![
-                        DiffLine::Context("line1".to_string()),
-                        DiffLine::Removed("old".to_string()),
-                        DiffLine::Added("new".to_string()),
-                        DiffLine::Context("line3".to_string()),
-                    ],
-                    ReviewStatus::Rejected,
-                )],
+        let diff = Diff::new(vec![make_file(
+            "src/main.rs",
+            vec![make_hunk(
+                1,
+                3,
+                1,
+                3,
+                vec![
+                    DiffLine::Context("line1".to_string()),
+                    DiffLine::Removed("old".to_string()),
+                    DiffLine::Added("new".to_string()),
+                    DiffLine::Context("line3".to_string()),
+                ],
+                ReviewStatus::Rejected,
             )],
-        };
+        )]);
 
         let patch = generate_reverse_patch(&diff);
         assert!(patch.contains("@@ -1,3 +1,3 @@"));
@@ -306,19 +303,17 @@ mod tests {
 
     #[test]
     fn test_reverse_header_recalc() {
-        let diff = Diff {
-            files: vec![make_file(
-                "src/main.rs",
-                vec![make_hunk(
-                    10,
-                    5,
-                    10,
-                    8,
-                    vec![DiffLine::Added("a".to_string())],
-                    ReviewStatus::Rejected,
-                )],
+        let diff = Diff::new(vec![make_file(
+            "src/main.rs",
+            vec![make_hunk(
+                10,
+                5,
+                10,
+                8,
+                vec![DiffLine::Added("a".to_string())],
+                ReviewStatus::Rejected,
             )],
-        };
+        )]);
 
         let patch = generate_reverse_patch(&diff);
         // new→old, old→new 교환
@@ -327,29 +322,27 @@ mod tests {
 
     #[test]
     fn test_reverse_only_rejected() {
-        let diff = Diff {
-            files: vec![make_file(
-                "src/main.rs",
-                vec![
-                    make_hunk(
-                        1,
-                        2,
-                        1,
-                        3,
-                        vec![DiffLine::Added("accepted".to_string())],
-                        ReviewStatus::Accepted,
-                    ),
-                    make_hunk(
-                        10,
-                        2,
-                        11,
-                        3,
-                        vec![DiffLine::Added("rejected".to_string())],
-                        ReviewStatus::Rejected,
-                    ),
-                ],
-            )],
-        };
+        let diff = Diff::new(vec![make_file(
+            "src/main.rs",
+            vec![
+                make_hunk(
+                    1,
+                    2,
+                    1,
+                    3,
+                    vec![DiffLine::Added("accepted".to_string())],
+                    ReviewStatus::Accepted,
+                ),
+                make_hunk(
+                    10,
+                    2,
+                    11,
+                    3,
+                    vec![DiffLine::Added("rejected".to_string())],
+                    ReviewStatus::Rejected,
+                ),
+            ],
+        )]);
 
         let patch = generate_reverse_patch(&diff);
         assert!(!patch.contains("accepted"));
@@ -393,19 +386,17 @@ mod tests {
 
     #[test]
     fn test_reverse_empty_when_all_accepted() {
-        let diff = Diff {
-            files: vec![make_file(
-                "src/main.rs",
-                vec![make_hunk(
-                    1,
-                    2,
-                    1,
-                    3,
-                    vec![DiffLine::Added("line".to_string())],
-                    ReviewStatus::Accepted,
-                )],
+        let diff = Diff::new(vec![make_file(
+            "src/main.rs",
+            vec![make_hunk(
+                1,
+                2,
+                1,
+                3,
+                vec![DiffLine::Added("line".to_string())],
+                ReviewStatus::Accepted,
             )],
-        };
+        )]);
 
         let patch = generate_reverse_patch(&diff);
         assert!(patch.is_empty());
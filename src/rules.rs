@@ -0,0 +1,335 @@
+//! 자동 accept/reject 정책 규칙 (config.toml의 `[[rules]]`)
+
+use crate::model::{ChangeKind, Comment, Diff, FileDiff, Hunk, ReviewStatus, Severity};
+use anyhow::{Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use regex::Regex;
+use serde::Deserialize;
+
+/// 규칙이 헌크에 적용할 결정
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleAction {
+    Accept,
+    Reject,
+}
+
+/// config.toml의 `[[rules]]` 항목.
+/// 지정된 조건은 모두 만족해야 하며(AND), 비어 있는 조건은 항상 참이다.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Rule {
+    /// TUI와 피드백에 표시할 이름
+    pub name: Option<String>,
+    /// 경로 glob 목록 (하나라도 일치하면 참)
+    pub paths: Vec<String>,
+    /// 파일 변경 종류
+    pub kind: Option<ChangeKind>,
+    /// 추가된 라인 중 하나라도 일치해야 하는 정규식
+    pub added: Option<String>,
+    /// 삭제된 라인 중 하나라도 일치해야 하는 정규식
+    pub removed: Option<String>,
+    pub action: Option<RuleAction>,
+    /// 헌크에 붙일 코멘트
+    pub comment: Option<String>,
+    /// 코멘트 심각도 (기본: reject면 blocking, 그 외 nit)
+    pub severity: Option<Severity>,
+}
+
+/// glob/정규식을 미리 컴파일한 규칙
+struct CompiledRule<'a> {
+    name: String,
+    rule: &'a Rule,
+    paths: Option<GlobSet>,
+    added: Option<Regex>,
+    removed: Option<Regex>,
+}
+
+impl CompiledRule<'_> {
+    fn matches(&self, file: &FileDiff, hunk: &Hunk) -> bool {
+        if let Some(paths) = &self.paths
+            && !paths.is_match(file.path())
+        {
+            return false;
+        }
+        if let Some(kind) = self.rule.kind
+            && kind != file.change_kind()
+        {
+            return false;
+        }
+        if let Some(re) = &self.added
+            && !hunk.added_lines().any(|l| re.is_match(l))
+        {
+            return false;
+        }
+        if let Some(re) = &self.removed
+            && !hunk.removed_lines().any(|l| re.is_match(l))
+        {
+            return false;
+        }
+        true
+    }
+
+    fn apply(&self, hunk: &mut Hunk) {
+        if let Some(action) = self.rule.action {
+            hunk.status = match action {
+                RuleAction::Accept => ReviewStatus::Accepted,
+                RuleAction::Reject => ReviewStatus::Rejected,
+            };
+        }
+        if let Some(text) = &self.rule.comment {
+            let severity = self.rule.severity.unwrap_or(match self.rule.action {
+                Some(RuleAction::Reject) => Severity::Blocking,
+                _ => Severity::Nit,
+            });
+            hunk.comments.push(Comment::from_input(text, severity));
+        }
+        hunk.rule = Some(self.name.clone());
+    }
+}
+
+/// 경로 glob 목록을 하나의 GlobSet으로 컴파일
+pub fn build_globset(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob =
+            Glob::new(pattern).with_context(|| format!("invalid path glob '{}'", pattern))?;
+        builder.add(glob);
+    }
+    Ok(builder.build()?)
+}
+
+fn compile(index: usize, rule: &Rule) -> Result<CompiledRule<'_>> {
+    let name = rule
+        .name
+        .clone()
+        .unwrap_or_else(|| format!("rule #{}", index + 1));
+
+    let paths = if rule.paths.is_empty() {
+        None
+    } else {
+        Some(build_globset(&rule.paths).with_context(|| name.clone())?)
+    };
+
+    let regex = |pattern: &Option<String>| -> Result<Option<Regex>> {
+        pattern
+            .as_deref()
+            .map(|p| Regex::new(p).with_context(|| format!("{}: invalid regex '{}'", name, p)))
+            .transpose()
+    };
+    let added = regex(&rule.added)?;
+    let removed = regex(&rule.removed)?;
+
+    Ok(CompiledRule {
+        name,
+        rule,
+        paths,
+        added,
+        removed,
+    })
+}
+
+/// 컴파일한 규칙 목록 (파일을 하나씩 받는 지연 로딩용)
+pub struct RuleSet<'a>(Vec<CompiledRule<'a>>);
+
+impl<'a> RuleSet<'a> {
+    pub fn compile(rules: &'a [Rule]) -> Result<Self> {
+        rules
+            .iter()
+            .enumerate()
+            .map(|(i, r)| compile(i, r))
+            .collect::<Result<Vec<_>>>()
+            .map(Self)
+    }
+
+    /// 파일의 각 헌크에 처음 일치한 규칙을 적용하고, 적용한 헌크 수를 반환한다
+    pub fn apply(&self, file: &mut FileDiff) -> usize {
+        let mut applied = 0;
+        for hi in 0..file.hunks.len() {
+            if let Some(rule) = self.0.iter().find(|r| r.matches(file, &file.hunks[hi])) {
+                rule.apply(&mut file.hunks[hi]);
+                applied += 1;
+            }
+        }
+        applied
+    }
+}
+
+/// 규칙을 순서대로 평가해 각 헌크에 처음 일치한 규칙을 적용한다.
+/// 규칙이 적용된 헌크 수를 반환한다.
+pub fn apply_rules(diff: &mut Diff, rules: &[Rule]) -> Result<usize> {
+    let rules = RuleSet::compile(rules)?;
+    Ok(diff.files.iter_mut().map(|f| rules.apply(f)).sum())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::DiffLine;
+
+    fn make_hunk(lines: Vec<DiffLine>) -> Hunk {
+        Hunk {
+            header: "@@ -1,1 +1,1 @@".to_string(),
+            old_start: 1,
+            old_count: 1,
+            new_start: 1,
+            new_count: 1,
+            lines,
+            status: ReviewStatus::Pending,
+            comments: Vec::new(),
+            rule: None,
+            edited: false,
+            original_lines: None,
+        }
+    }
+
+    fn make_file(old: &str, new: &str, hunks: Vec<Hunk>) -> FileDiff {
+        FileDiff {
+            old_path: old.to_string(),
+            new_path: new.to_string(),
+            raw_old_path: format!("a/{}", old),
+            raw_new_path: format!("b/{}", new),
+            hunks,
+            is_binary: false,
+            viewed: false,
+            deferred: None,
+        }
+    }
+
+    fn parse_rules(toml_str: &str) -> Vec<Rule> {
+        #[derive(Deserialize)]
+        struct Rules {
+            rules: Vec<Rule>,
+        }
+        toml::from_str::<Rules>(toml_str).unwrap().rules
+    }
+
+    #[test]
+    fn test_accept_by_path_glob() {
+        let rules = parse_rules(
+            r#"
+[[rules]]
+name = "lockfiles"
+paths = ["*.lock", "**/__snapshots__/**"]
+action = "accept"
+"#,
+        );
+        let mut diff = Diff::new(vec![
+            make_file("Cargo.lock", "Cargo.lock", vec![make_hunk(vec![])]),
+            make_file(
+                "web/__snapshots__/a.snap",
+                "web/__snapshots__/a.snap",
+                vec![make_hunk(vec![])],
+            ),
+            make_file("src/main.rs", "src/main.rs", vec![make_hunk(vec![])]),
+        ]);
+
+        assert_eq!(apply_rules(&mut diff, &rules).unwrap(), 2);
+        assert_eq!(diff.files[0].hunks[0].status, ReviewStatus::Accepted);
+        assert_eq!(diff.files[0].hunks[0].rule.as_deref(), Some("lockfiles"));
+        assert_eq!(diff.files[1].hunks[0].status, ReviewStatus::Accepted);
+        assert_eq!(diff.files[2].hunks[0].status, ReviewStatus::Pending);
+        assert_eq!(diff.files[2].hunks[0].rule, None);
+    }
+
+    #[test]
+    fn test_reject_added_unwrap_in_src() {
+        let rules = parse_rules(
+            r#"
+[[rules]]
+paths = ["src/**"]
+added = 'unwrap\(\)'
+action = "reject"
+comment = "no unwrap() in library code #errors"
+"#,
+        );
+        let mut diff = Diff::new(vec![
+            make_file(
+                "src/lib.rs",
+                "src/lib.rs",
+                vec![
+                    make_hunk(vec![DiffLine::Added("let x = y.unwrap();".to_string())]),
+                    make_hunk(vec![DiffLine::Removed("let x = y.unwrap();".to_string())]),
+                ],
+            ),
+            make_file(
+                "tests/it.rs",
+                "tests/it.rs",
+                vec![make_hunk(vec![DiffLine::Added("y.unwrap();".to_string())])],
+            ),
+        ]);
+
+        assert_eq!(apply_rules(&mut diff, &rules).unwrap(), 1);
+        let hunk = &diff.files[0].hunks[0];
+        assert_eq!(hunk.status, ReviewStatus::Rejected);
+        assert_eq!(hunk.rule.as_deref(), Some("rule #1"));
+        assert_eq!(hunk.comments[0].text, "no unwrap() in library code");
+        assert_eq!(hunk.comments[0].severity, Severity::Blocking);
+        assert_eq!(hunk.comments[0].tags, vec!["errors"]);
+        // Removed unwrap() and files outside src/ are untouched
+        assert_eq!(diff.files[0].hunks[1].status, ReviewStatus::Pending);
+        assert_eq!(diff.files[1].hunks[0].status, ReviewStatus::Pending);
+    }
+
+    #[test]
+    fn test_kind_and_comment_only_rule() {
+        let rules = parse_rules(
+            r#"
+[[rules]]
+name = "new files"
+kind = "added"
+comment = "check the license header"
+"#,
+        );
+        let mut diff = Diff::new(vec![
+            make_file("/dev/null", "src/new.rs", vec![make_hunk(vec![])]),
+            make_file("src/old.rs", "src/old.rs", vec![make_hunk(vec![])]),
+        ]);
+
+        assert_eq!(apply_rules(&mut diff, &rules).unwrap(), 1);
+        let hunk = &diff.files[0].hunks[0];
+        assert_eq!(hunk.status, ReviewStatus::Pending);
+        assert_eq!(hunk.comments[0].severity, Severity::Nit);
+        assert_eq!(hunk.rule.as_deref(), Some("new files"));
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let rules = parse_rules(
+            r#"
+[[rules]]
+name = "first"
+paths = ["*.rs"]
+action = "reject"
+
+[[rules]]
+name = "second"
+action = "accept"
+"#,
+        );
+        let mut diff = Diff::new(vec![
+            make_file("a.rs", "a.rs", vec![make_hunk(vec![])]),
+            make_file("a.md", "a.md", vec![make_hunk(vec![])]),
+        ]);
+
+        apply_rules(&mut diff, &rules).unwrap();
+        assert_eq!(diff.files[0].hunks[0].rule.as_deref(), Some("first"));
+        assert_eq!(diff.files[0].hunks[0].status, ReviewStatus::Rejected);
+        assert_eq!(diff.files[1].hunks[0].rule.as_deref(), Some("second"));
+        assert_eq!(diff.files[1].hunks[0].status, ReviewStatus::Accepted);
+    }
+
+    #[test]
+    fn test_invalid_regex_is_error() {
+        let rules = parse_rules(
+            r#"
+[[rules]]
+name = "broken"
+added = "unwrap("
+"#,
+        );
+        let mut diff = Diff::new(vec![]);
+        let err = apply_rules(&mut diff, &rules).unwrap_err();
+        assert!(format!("{:#}", err).contains("broken: invalid regex"));
+    }
+}
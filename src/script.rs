@@ -0,0 +1,313 @@
+//! 비대화형(스크립트) 리뷰: `--accept`/`--reject` glob과 `--decisions` 재생
+
+use crate::model::{Comment, Diff, ReviewStatus, Severity};
+use crate::rules::build_globset;
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use std::io::Read;
+
+/// `output::write_json` 결과 중 재생에 필요한 부분
+#[derive(Deserialize)]
+struct Decisions {
+    #[serde(default)]
+    review_summary: Option<String>,
+    files: Vec<DecisionFile>,
+}
+
+#[derive(Deserialize)]
+struct DecisionFile {
+    path: String,
+    #[serde(default)]
+    viewed: bool,
+    hunks: Vec<DecisionHunk>,
+}
+
+#[derive(Deserialize)]
+struct DecisionHunk {
+    header: String,
+    status: String,
+    #[serde(default)]
+    comments: Vec<DecisionComment>,
+}
+
+#[derive(Deserialize)]
+struct DecisionComment {
+    text: String,
+    severity: Severity,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    line_index: Option<usize>,
+}
+
+fn parse_status(status: &str) -> Result<ReviewStatus> {
+    match status {
+        "accepted" => Ok(ReviewStatus::Accepted),
+        "rejected" => Ok(ReviewStatus::Rejected),
+        "pending" => Ok(ReviewStatus::Pending),
+        other => bail!("unknown hunk status '{}'", other),
+    }
+}
+
+/// 경로 glob에 일치하는 파일의 모든 헌크 상태를 설정한다.
+/// 변경된 헌크 수를 반환한다.
+pub fn apply_globs(diff: &mut Diff, patterns: &[String], status: ReviewStatus) -> Result<usize> {
+    if patterns.is_empty() {
+        return Ok(0);
+    }
+    let globs = build_globset(patterns)?;
+    let mut changed = 0;
+    for file in &mut diff.files {
+        if !globs.is_match(file.path()) {
+            continue;
+        }
+        for hunk in &mut file.hunks {
+            hunk.status = status;
+            hunk.rule = None;
+            changed += 1;
+        }
+    }
+    Ok(changed)
+}
+
+/// `--decisions` 재생 결과
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ReplayReport {
+    /// 현재 diff에 적용된 헌크 결정 수
+    pub applied: usize,
+    /// 현재 diff에서 찾지 못한 헌크 결정 수
+    pub unmatched: usize,
+}
+
+/// 이전에 `--json`으로 내보낸 결과를 읽어 헌크 상태와 코멘트를 복원한다.
+/// 헌크는 파일 경로(`FileDiff::path`, 삭제된 파일은 원래 경로)와 헌크 헤더로 대응시킨다.
+pub fn apply_decisions(diff: &mut Diff, reader: impl Read) -> Result<ReplayReport> {
+    let decisions: Decisions = serde_json::from_reader(reader).context("invalid decisions file")?;
+
+    let mut report = ReplayReport::default();
+    // 파일 항목 하나는 파일 하나에만 대응시킨다 (같은 경로가 여러 번 나와도 겹치지 않게)
+    let mut claimed = vec![false; diff.files.len()];
+    for entry in decisions.files {
+        // 예전 출력은 삭제된 파일을 모두 `/dev/null`로 적었으므로 그런 파일과 순서대로 대응시킨다
+        let found = diff.files.iter().enumerate().position(|(fi, f)| {
+            !claimed[fi]
+                && (f.path() == entry.path
+                    || (f.new_path == "/dev/null" && f.new_path == entry.path))
+        });
+        let Some(fi) = found else {
+            report.unmatched += entry.hunks.len();
+            continue;
+        };
+        claimed[fi] = true;
+        let file = &mut diff.files[fi];
+        file.viewed = entry.viewed;
+        // 같은 헤더가 여러 번 나오면 순서대로 대응시킨다
+        let mut used = vec![false; file.hunks.len()];
+        for decision in entry.hunks {
+            let found = file
+                .hunks
+                .iter()
+                .enumerate()
+                .position(|(i, h)| !used[i] && h.header == decision.header);
+            let Some(hi) = found else {
+                report.unmatched += 1;
+                continue;
+            };
+            used[hi] = true;
+
+            let hunk = &mut file.hunks[hi];
+            hunk.status = parse_status(&decision.status)
+                .with_context(|| format!("{} {}", entry.path, decision.header))?;
+            hunk.rule = None;
+            hunk.comments = decision
+                .comments
+                .into_iter()
+                .map(|c| {
+                    let anchor = c.line_index.and_then(|li| hunk.anchor_at(li));
+                    Comment {
+                        text: c.text,
+                        severity: c.severity,
+                        tags: c.tags,
+                        anchor,
+                    }
+                })
+                .collect();
+            report.applied += 1;
+        }
+    }
+
+    if decisions.review_summary.is_some() {
+        diff.summary = decisions.review_summary;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{DiffLine, FileDiff, Hunk};
+    use crate::output::write_json;
+
+    fn make_hunk(header: &str) -> Hunk {
+        Hunk {
+            header: header.to_string(),
+            old_start: 1,
+            old_count: 1,
+            new_start: 1,
+            new_count: 2,
+            lines: vec![
+                DiffLine::Context("ctx".to_string()),
+                DiffLine::Added("new".to_string()),
+            ],
+            status: ReviewStatus::Pending,
+            comments: Vec::new(),
+            rule: None,
+            edited: false,
+            original_lines: None,
+        }
+    }
+
+    fn make_file(path: &str, hunks: Vec<Hunk>) -> FileDiff {
+        FileDiff {
+            old_path: path.to_string(),
+            new_path: path.to_string(),
+            raw_old_path: format!("a/{}", path),
+            raw_new_path: format!("b/{}", path),
+            hunks,
+            is_binary: false,
+            viewed: false,
+            deferred: None,
+        }
+    }
+
+    fn sample_diff() -> Diff {
+        Diff::new(vec![
+            make_file(
+                "src/lib.rs",
+                vec![make_hunk("@@ -1,1 +1,2 @@"), make_hunk("@@ -10,1 +11,2 @@")],
+            ),
+            make_file("Cargo.lock", vec![make_hunk("@@ -5,1 +5,2 @@")]),
+        ])
+    }
+
+    #[test]
+    fn test_apply_globs() {
+        let mut diff = sample_diff();
+        let changed =
+            apply_globs(&mut diff, &["src/**".to_string()], ReviewStatus::Accepted).unwrap();
+        assert_eq!(changed, 2);
+        let changed =
+            apply_globs(&mut diff, &["*.lock".to_string()], ReviewStatus::Rejected).unwrap();
+        assert_eq!(changed, 1);
+
+        assert_eq!(diff.files[0].hunks[1].status, ReviewStatus::Accepted);
+        assert_eq!(diff.files[1].hunks[0].status, ReviewStatus::Rejected);
+    }
+
+    #[test]
+    fn test_apply_globs_invalid_pattern() {
+        let mut diff = sample_diff();
+        assert!(apply_globs(&mut diff, &["src/[".to_string()], ReviewStatus::Accepted).is_err());
+    }
+
+    #[test]
+    fn test_replay_write_json_roundtrip() {
+        let mut reviewed = sample_diff();
+        reviewed.files[0].hunks[0].status = ReviewStatus::Accepted;
+        reviewed.files[0].hunks[1].status = ReviewStatus::Rejected;
+        let anchor = reviewed.files[0].hunks[1].anchor_at(1);
+        reviewed.files[0].hunks[1]
+            .comments
+            .push(Comment::from_input("wrong #api", Severity::Blocking).with_anchor(anchor));
+        reviewed.summary = Some("needs another pass".to_string());
+        reviewed.files[1].viewed = true;
+
+        let mut json = Vec::new();
+        write_json(&reviewed, &mut json).unwrap();
+
+        let mut diff = sample_diff();
+        let report = apply_decisions(&mut diff, json.as_slice()).unwrap();
+        assert_eq!(
+            report,
+            ReplayReport {
+                applied: 3,
+                unmatched: 0
+            }
+        );
+        assert_eq!(diff.files[0].hunks[0].status, ReviewStatus::Accepted);
+        assert_eq!(diff.files[0].hunks[1].status, ReviewStatus::Rejected);
+        assert_eq!(diff.files[1].hunks[0].status, ReviewStatus::Pending);
+        assert_eq!(
+            diff.files[0].hunks[1].comments,
+            reviewed.files[0].hunks[1].comments
+        );
+        assert_eq!(diff.summary.as_deref(), Some("needs another pass"));
+        assert!(!diff.files[0].viewed);
+        assert!(diff.files[1].viewed);
+    }
+
+    #[test]
+    fn test_replay_deleted_files() {
+        let deleted = |path: &str| FileDiff {
+            new_path: "/dev/null".to_string(),
+            raw_new_path: "/dev/null".to_string(),
+            ..make_file(path, vec![make_hunk("@@ -1,2 +0,0 @@")])
+        };
+        let mut reviewed = Diff::new(vec![deleted("x"), deleted("y")]);
+        reviewed.files[0].hunks[0].status = ReviewStatus::Rejected;
+        reviewed.files[1].hunks[0].status = ReviewStatus::Accepted;
+        let mut json = Vec::new();
+        write_json(&reviewed, &mut json).unwrap();
+        assert!(String::from_utf8_lossy(&json).contains("\"path\": \"y\""));
+
+        let mut diff = Diff::new(vec![deleted("x"), deleted("y")]);
+        let report = apply_decisions(&mut diff, json.as_slice()).unwrap();
+        assert_eq!(report.unmatched, 0);
+        assert_eq!(diff.files[0].hunks[0].status, ReviewStatus::Rejected);
+        assert_eq!(diff.files[1].hunks[0].status, ReviewStatus::Accepted);
+
+        // 같은 경로의 두 번째 항목은 이미 대응한 파일에 다시 적용되지 않는다
+        let saved = r#"{"files": [
+            {"path": "x", "hunks": [{"header": "@@ -1,2 +0,0 @@", "status": "accepted"}]},
+            {"path": "x", "hunks": [{"header": "@@ -1,2 +0,0 @@", "status": "rejected"}]}
+        ]}"#;
+        let mut diff = Diff::new(vec![deleted("x"), deleted("y")]);
+        let report = apply_decisions(&mut diff, saved.as_bytes()).unwrap();
+        assert_eq!(report.unmatched, 1);
+        assert_eq!(diff.files[0].hunks[0].status, ReviewStatus::Accepted);
+        assert_eq!(diff.files[1].hunks[0].status, ReviewStatus::Pending);
+    }
+
+    #[test]
+    fn test_replay_reports_unmatched() {
+        let json = r#"{
+            "files": [
+                {"path": "src/lib.rs", "hunks": [
+                    {"header": "@@ -1,1 +1,2 @@", "status": "accepted"},
+                    {"header": "@@ -99,1 +99,2 @@", "status": "rejected"}
+                ]},
+                {"path": "gone.rs", "hunks": [{"header": "@@ -1 +1 @@", "status": "accepted"}]}
+            ]
+        }"#;
+        let mut diff = sample_diff();
+        let report = apply_decisions(&mut diff, json.as_bytes()).unwrap();
+        assert_eq!(
+            report,
+            ReplayReport {
+                applied: 1,
+                unmatched: 2
+            }
+        );
+        assert_eq!(diff.files[0].hunks[0].status, ReviewStatus::Accepted);
+    }
+
+    #[test]
+    fn test_replay_rejects_bad_status() {
+        let json = r#"{"files": [{"path": "src/lib.rs", "hunks": [
+            {"header": "@@ -1,1 +1,2 @@", "status": "maybe"}
+        ]}]}"#;
+        let mut diff = sample_diff();
+        let err = apply_decisions(&mut diff, json.as_bytes()).unwrap_err();
+        assert!(format!("{:#}", err).contains("unknown hunk status 'maybe'"));
+    }
+}
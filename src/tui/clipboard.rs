@@ -0,0 +1,251 @@
+//! Yanking to the system clipboard with OSC 52. The escape sequence goes to
+//! the terminal over `/dev/tty`, so it also reaches the local clipboard over
+//! SSH and inside tmux (with `set-clipboard on`).
+
+use crate::model::{DiffLine, FileDiff, Hunk, LineAnchor, ReviewStatus};
+
+/// Larger payloads are refused; many terminals drop long OSC 52 sequences silently
+pub(super) const MAX_BYTES: usize = 100_000;
+
+/// What `y` copies
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum YankKind {
+    /// `yy`: the hunk as a patch `git apply` accepts
+    Patch,
+    /// `yp`: `path:line` of the cursor line or hunk
+    Location,
+    /// `yf`: the file path
+    Path,
+    /// `ym`: location, diff and comments as markdown
+    Markdown,
+}
+
+impl YankKind {
+    pub(super) fn label(self) -> &'static str {
+        match self {
+            Self::Patch => "hunk",
+            Self::Location => "location",
+            Self::Path => "path",
+            Self::Markdown => "markdown",
+        }
+    }
+}
+
+/// Text to copy for the hunk; `line` is the line cursor's index in it
+pub(super) fn yank_text(
+    kind: YankKind,
+    file: &FileDiff,
+    hunk: &Hunk,
+    line: Option<usize>,
+) -> String {
+    match kind {
+        YankKind::Patch => format!(
+            "--- {}\n+++ {}\n{}\n{}",
+            file.raw_old_path,
+            file.raw_new_path,
+            hunk.header,
+            patch_lines(&hunk.lines)
+        ),
+        YankKind::Location => format!("{}:{}", file.path(), line_number(file, hunk, line)),
+        YankKind::Path => file.path().to_string(),
+        YankKind::Markdown => markdown(file, hunk),
+    }
+}
+
+/// Line number for `path:line`: the cursor line (old number for removed
+/// lines), else the first line of the hunk
+fn line_number(file: &FileDiff, hunk: &Hunk, line: Option<usize>) -> u32 {
+    let deleted = file.new_path == "/dev/null";
+    line.and_then(|li| hunk.line_numbers().get(li).copied())
+        .and_then(|(old, new)| new.or(old))
+        .unwrap_or(if deleted {
+            hunk.old_start
+        } else {
+            hunk.new_start
+        })
+        .max(1)
+}
+
+fn patch_lines(lines: &[DiffLine]) -> String {
+    let mut out = String::new();
+    for line in lines {
+        match line {
+            DiffLine::Context(s) => out.push_str(&format!(" {}\n", s)),
+            DiffLine::Added(s) => out.push_str(&format!("+{}\n", s)),
+            DiffLine::Removed(s) => out.push_str(&format!("-{}\n", s)),
+            DiffLine::NoNewline => out.push_str("\\ No newline at end of file\n"),
+        }
+    }
+    out
+}
+
+fn markdown(file: &FileDiff, hunk: &Hunk) -> String {
+    let end = hunk.new_start + hunk.new_count.saturating_sub(1);
+    let mut out = format!(
+        "`{}:{}-{}` ({})\n\n```diff\n{}\n{}```\n",
+        file.path(),
+        hunk.new_start,
+        end,
+        match hunk.status {
+            ReviewStatus::Accepted => "accepted",
+            ReviewStatus::Rejected => "rejected",
+            ReviewStatus::Pending => "pending",
+        },
+        hunk.header,
+        patch_lines(&hunk.lines)
+    );
+    if !hunk.comments.is_empty() {
+        out.push('\n');
+    }
+    for comment in &hunk.comments {
+        let location = match comment.anchor {
+            Some(LineAnchor {
+                new_line: Some(n), ..
+            }) => format!(" (line {})", n),
+            Some(LineAnchor {
+                old_line: Some(n), ..
+            }) => format!(" (old line {})", n),
+            _ => String::new(),
+        };
+        out.push_str(&format!(
+            "- **{}**{}: {}\n",
+            comment.severity.as_str(),
+            location,
+            comment.text
+        ));
+    }
+    out
+}
+
+/// OSC 52 sequence setting the clipboard to `text`
+pub(super) fn osc52(text: &str) -> Result<String, String> {
+    let encoded = base64(text.as_bytes());
+    if encoded.len() > MAX_BYTES {
+        return Err(format!(
+            "too large for the clipboard ({} KB, limit {} KB)",
+            encoded.len() / 1024,
+            MAX_BYTES / 1024
+        ));
+    }
+    Ok(format!("\x1b]52;c;{}\x07", encoded))
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Comment, Severity};
+
+    fn file(hunk: Hunk) -> FileDiff {
+        FileDiff {
+            old_path: "src/a.rs".to_string(),
+            new_path: "src/a.rs".to_string(),
+            raw_old_path: "a/src/a.rs".to_string(),
+            raw_new_path: "b/src/a.rs".to_string(),
+            hunks: vec![hunk],
+            is_binary: false,
+            viewed: false,
+            deferred: None,
+        }
+    }
+
+    fn hunk() -> Hunk {
+        Hunk {
+            header: "@@ -10,2 +10,2 @@".to_string(),
+            old_start: 10,
+            old_count: 2,
+            new_start: 10,
+            new_count: 2,
+            lines: vec![
+                DiffLine::Context("a".to_string()),
+                DiffLine::Removed("b".to_string()),
+                DiffLine::Added("c".to_string()),
+                DiffLine::NoNewline,
+            ],
+            status: ReviewStatus::Rejected,
+            comments: Vec::new(),
+            rule: None,
+            edited: false,
+            original_lines: None,
+        }
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64("한".as_bytes()), "7ZWc");
+    }
+
+    #[test]
+    fn test_osc52() {
+        assert_eq!(osc52("foo").unwrap(), "\x1b]52;c;Zm9v\x07");
+        assert!(osc52(&"x".repeat(MAX_BYTES)).is_err());
+    }
+
+    #[test]
+    fn test_patch_and_location() {
+        let hunk = hunk();
+        let file = file(hunk.clone());
+        assert_eq!(
+            yank_text(YankKind::Patch, &file, &hunk, None),
+            "--- a/src/a.rs\n+++ b/src/a.rs\n@@ -10,2 +10,2 @@\n a\n-b\n+c\n\\ No newline at end of file\n"
+        );
+        assert_eq!(
+            yank_text(YankKind::Location, &file, &hunk, None),
+            "src/a.rs:10"
+        );
+        // Removed lines use the old line number
+        assert_eq!(
+            yank_text(YankKind::Location, &file, &hunk, Some(1)),
+            "src/a.rs:11"
+        );
+        assert_eq!(
+            yank_text(YankKind::Location, &file, &hunk, Some(2)),
+            "src/a.rs:11"
+        );
+        assert_eq!(yank_text(YankKind::Path, &file, &hunk, None), "src/a.rs");
+    }
+
+    #[test]
+    fn test_markdown_includes_comments() {
+        let mut hunk = hunk();
+        hunk.comments.push(Comment {
+            text: "why?".to_string(),
+            severity: Severity::Question,
+            tags: Vec::new(),
+            anchor: Some(LineAnchor {
+                line_index: 1,
+                old_line: Some(11),
+                new_line: None,
+            }),
+        });
+        let file = file(hunk.clone());
+        let text = yank_text(YankKind::Markdown, &file, &hunk, None);
+        assert!(
+            text.starts_with("`src/a.rs:10-11` (rejected)\n\n```diff\n@@ -10,2 +10,2 @@\n a\n")
+        );
+        assert!(text.ends_with("```\n\n- **question** (old line 11): why?\n"));
+    }
+}
@@ -0,0 +1,451 @@
+//! `:` command line: parsing, execution and tab completion
+
+use super::input::{self, Action};
+use super::state::{AppState, status_label};
+use crate::model::{ReviewStatus, Severity};
+use crate::output;
+use crate::rules::build_globset;
+use regex::Regex;
+use std::path::PathBuf;
+
+/// Hunks a bulk `:accept`/`:reject` applies to
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) enum Target {
+    /// No argument: the current hunk
+    Current,
+    /// Path glob (`src/parse/**`)
+    Paths(String),
+    /// `/regex/` matched against added and removed lines
+    Lines(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum SetOption {
+    Highlight,
+    FileTree,
+    SideBySide,
+    FullFile,
+    Wrap,
+    Whitespace,
+    IgnoreWhitespace,
+    IgnoreBlankLines,
+    ColorMoved,
+    FoldReviewed,
+    SkipViewed,
+    Panel,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) enum Command {
+    Action(Action),
+    SetStatus {
+        status: ReviewStatus,
+        target: Target,
+    },
+    Comment {
+        text: String,
+        severity: Option<Severity>,
+    },
+    /// `:goto path:line`, `:goto path` or `:line` (current file)
+    Goto {
+        path: Option<String>,
+        line: Option<u32>,
+    },
+    /// `value` None toggles (`:set highlight!`)
+    Set {
+        option: SetOption,
+        value: Option<bool>,
+    },
+    Write(PathBuf),
+}
+
+/// Command names offered by tab completion
+const COMMANDS: &[&str] = &[
+    "accept", "comment", "goto", "help", "quit", "redo", "reject", "set", "stats", "undo", "write",
+];
+
+/// Commands whose argument is completed against file paths
+const PATH_COMMANDS: &[&str] = &["accept", "reject", "goto"];
+
+const SET_OPTIONS: &[(&str, SetOption)] = &[
+    ("highlight", SetOption::Highlight),
+    ("filetree", SetOption::FileTree),
+    ("tree", SetOption::FileTree),
+    ("sidebyside", SetOption::SideBySide),
+    ("fullfile", SetOption::FullFile),
+    ("wrap", SetOption::Wrap),
+    ("list", SetOption::Whitespace),
+    ("whitespace", SetOption::Whitespace),
+    ("ignorews", SetOption::IgnoreWhitespace),
+    ("ignoreblank", SetOption::IgnoreBlankLines),
+    ("moved", SetOption::ColorMoved),
+    ("foldreviewed", SetOption::FoldReviewed),
+    ("skipviewed", SetOption::SkipViewed),
+    ("panel", SetOption::Panel),
+];
+
+fn parse_severity(s: &str) -> Option<Severity> {
+    match s {
+        "blocking" => Some(Severity::Blocking),
+        "nit" => Some(Severity::Nit),
+        "question" => Some(Severity::Question),
+        "praise" => Some(Severity::Praise),
+        _ => None,
+    }
+}
+
+fn parse_target(arg: &str) -> Target {
+    if arg.is_empty() {
+        Target::Current
+    } else if let Some(re) = arg
+        .strip_prefix('/')
+        .and_then(|s| s.strip_suffix('/'))
+        .filter(|s| !s.is_empty())
+    {
+        Target::Lines(re.to_string())
+    } else {
+        Target::Paths(arg.to_string())
+    }
+}
+
+fn parse_set(arg: &str) -> Result<Command, String> {
+    let (name, value) = if let Some(name) = arg.strip_suffix('!') {
+        (name, None)
+    } else if let Some(name) = arg.strip_prefix("no") {
+        (name, Some(false))
+    } else {
+        (arg, Some(true))
+    };
+    SET_OPTIONS
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|&(_, option)| Command::Set { option, value })
+        .ok_or_else(|| format!("unknown option: {}", arg))
+}
+
+/// Parse a command line (without the leading `:`)
+pub(super) fn parse_command(line: &str) -> Result<Command, String> {
+    let line = line.trim();
+    let (name, arg) = line
+        .split_once(char::is_whitespace)
+        .map_or((line, ""), |(n, a)| (n, a.trim()));
+
+    if let Ok(n) = name.parse::<u32>() {
+        return Ok(Command::Goto {
+            path: None,
+            line: Some(n),
+        });
+    }
+
+    match name {
+        "accept" | "reject" => Ok(Command::SetStatus {
+            status: if name == "accept" {
+                ReviewStatus::Accepted
+            } else {
+                ReviewStatus::Rejected
+            },
+            target: parse_target(arg),
+        }),
+        "comment" => {
+            // Optional leading `severity:` word
+            let (severity, text) = match arg.split_once(char::is_whitespace) {
+                Some((first, rest)) => match first.strip_suffix(':').and_then(parse_severity) {
+                    Some(sev) => (Some(sev), rest.trim()),
+                    None => (None, arg),
+                },
+                None => (None, arg),
+            };
+            if text.is_empty() {
+                return Err("usage: comment [severity:] <text>".to_string());
+            }
+            Ok(Command::Comment {
+                text: text.to_string(),
+                severity,
+            })
+        }
+        "goto" => {
+            if arg.is_empty() {
+                return Err("usage: goto <file>[:line]".to_string());
+            }
+            let (path, line) = match arg.rsplit_once(':') {
+                Some((path, n)) if n.parse::<u32>().is_ok() => (path, n.parse().ok()),
+                _ => (arg, None),
+            };
+            Ok(Command::Goto {
+                path: Some(path.to_string()),
+                line,
+            })
+        }
+        "set" => parse_set(arg),
+        "w" | "write" => {
+            if arg.is_empty() {
+                return Err("usage: write <file>".to_string());
+            }
+            Ok(Command::Write(PathBuf::from(arg)))
+        }
+        "u" | "undo" => Ok(Command::Action(Action::Undo)),
+        "red" | "redo" => Ok(Command::Action(Action::Redo)),
+        "q" | "quit" => Ok(Command::Action(Action::RequestQuit)),
+        "help" => Ok(Command::Action(Action::ToggleHelp)),
+        "stats" => Ok(Command::Action(Action::ToggleStats)),
+        "" => Err(String::new()),
+        other => Err(format!("unknown command: {}", other)),
+    }
+}
+
+/// Parse and run a command line, returning the message for the status bar
+pub(super) fn run(line: &str, state: &mut AppState) -> Result<String, String> {
+    match parse_command(line)? {
+        Command::Action(action) => {
+            input::apply_action(action, state);
+            Ok(String::new())
+        }
+        Command::SetStatus { status, target } => {
+            let count = match target {
+                Target::Current => {
+                    state.set_current_status(status);
+                    1
+                }
+                Target::Paths(pattern) => {
+                    let label = format!("{} {}", status_label(status), pattern);
+                    let globs = build_globset(&[pattern]).map_err(|e| format!("{:#}", e))?;
+                    state.set_status_where(&label, status, |file, _| globs.is_match(file.path()))
+                }
+                Target::Lines(pattern) => {
+                    let re = Regex::new(&pattern).map_err(|e| e.to_string())?;
+                    let label = format!("{} /{}/", status_label(status), pattern);
+                    state.set_status_where(&label, status, |_, hunk| {
+                        hunk.added_lines()
+                            .chain(hunk.removed_lines())
+                            .any(|l| re.is_match(l))
+                    })
+                }
+            };
+            let verb = if status == ReviewStatus::Accepted {
+                "accepted"
+            } else {
+                "rejected"
+            };
+            Ok(format!("{} {} hunks", verb, count))
+        }
+        Command::Comment { text, severity } => {
+            state.begin_comment_edit();
+            let severity = severity.unwrap_or(state.comment_severity);
+            state.set_current_comment(text, severity);
+            Ok(String::new())
+        }
+        Command::Goto { path, line } => {
+            state.push_jump();
+            state.goto(path.as_deref(), line)
+        }
+        Command::Set { option, value } => {
+            let flag = match option {
+                SetOption::Highlight => &mut state.show_highlight,
+                SetOption::FileTree => &mut state.show_file_tree,
+                SetOption::FullFile => &mut state.show_full_file,
+                SetOption::Wrap => &mut state.wrap,
+                SetOption::Whitespace => &mut state.show_whitespace,
+                SetOption::IgnoreWhitespace => &mut state.ignore_whitespace.all_space,
+                SetOption::IgnoreBlankLines => &mut state.ignore_whitespace.blank_lines,
+                SetOption::ColorMoved => &mut state.color_moved,
+                SetOption::FoldReviewed => &mut state.fold_reviewed,
+                SetOption::SkipViewed => &mut state.skip_viewed,
+                SetOption::Panel => &mut state.layout.panel,
+                SetOption::SideBySide => {
+                    let side_by_side =
+                        state.diff_view_mode == super::state::DiffViewMode::SideBySide;
+                    if value.unwrap_or(!side_by_side) != side_by_side {
+                        input::apply_action(Action::ToggleDiffView, state);
+                    }
+                    return Ok(String::new());
+                }
+            };
+            *flag = value.unwrap_or(!*flag);
+            if state.wrap {
+                state.hscroll = 0;
+            }
+            if state
+                .current_hunk()
+                .is_some_and(|h| !state.is_expanded(state.hunk_index, h))
+            {
+                state.clear_line_cursor();
+            }
+            if !state.show_file_tree {
+                state.focus = super::state::Focus::DiffView;
+            }
+            state.ensure_visible();
+            Ok(String::new())
+        }
+        Command::Write(path) => {
+            let mut file =
+                std::fs::File::create(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            output::write_json(&state.diff, &mut file).map_err(|e| format!("{:#}", e))?;
+            Ok(format!("wrote {}", path.display()))
+        }
+    }
+}
+
+fn common_prefix<'a>(candidates: &[&'a str]) -> &'a str {
+    let Some(first) = candidates.first() else {
+        return "";
+    };
+    let mut len = first.len();
+    for c in &candidates[1..] {
+        len = first
+            .char_indices()
+            .zip(c.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map_or(0, |((i, a), _)| i + a.len_utf8())
+            .min(len);
+    }
+    &first[..len]
+}
+
+/// Complete the command name or (for path commands) the file path being typed.
+/// Returns the new input and the candidates that matched.
+pub(super) fn complete<'a>(line: &str, paths: &[&'a str]) -> (String, Vec<&'a str>) {
+    let Some((name, arg)) = line.split_once(' ') else {
+        let candidates: Vec<&str> = COMMANDS
+            .iter()
+            .copied()
+            .filter(|c| c.starts_with(line))
+            .collect();
+        return match candidates.as_slice() {
+            [only] => (format!("{} ", only), Vec::new()),
+            [] => (line.to_string(), Vec::new()),
+            _ => (common_prefix(&candidates).to_string(), Vec::new()),
+        };
+    };
+    if !PATH_COMMANDS.contains(&name) || arg.starts_with('/') {
+        return (line.to_string(), Vec::new());
+    }
+    let candidates: Vec<&str> = paths
+        .iter()
+        .copied()
+        .filter(|p| p.starts_with(arg))
+        .collect();
+    let completed = common_prefix(&candidates);
+    if completed.len() > arg.len() {
+        (format!("{} {}", name, completed), candidates)
+    } else {
+        (line.to_string(), candidates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bulk_targets() {
+        assert_eq!(
+            parse_command("accept src/parse/**"),
+            Ok(Command::SetStatus {
+                status: ReviewStatus::Accepted,
+                target: Target::Paths("src/parse/**".to_string())
+            })
+        );
+        assert_eq!(
+            parse_command("reject /unwrap\\(\\)/"),
+            Ok(Command::SetStatus {
+                status: ReviewStatus::Rejected,
+                target: Target::Lines("unwrap\\(\\)".to_string())
+            })
+        );
+        assert_eq!(
+            parse_command(" accept "),
+            Ok(Command::SetStatus {
+                status: ReviewStatus::Accepted,
+                target: Target::Current
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_comment_goto_set_write() {
+        assert_eq!(
+            parse_command("comment blocking: use ? here #errors"),
+            Ok(Command::Comment {
+                text: "use ? here #errors".to_string(),
+                severity: Some(Severity::Blocking)
+            })
+        );
+        assert_eq!(
+            parse_command("comment looks fine"),
+            Ok(Command::Comment {
+                text: "looks fine".to_string(),
+                severity: None
+            })
+        );
+        assert_eq!(
+            parse_command("goto src/main.rs:42"),
+            Ok(Command::Goto {
+                path: Some("src/main.rs".to_string()),
+                line: Some(42)
+            })
+        );
+        assert_eq!(
+            parse_command("120"),
+            Ok(Command::Goto {
+                path: None,
+                line: Some(120)
+            })
+        );
+        assert_eq!(
+            parse_command("set nohighlight"),
+            Ok(Command::Set {
+                option: SetOption::Highlight,
+                value: Some(false)
+            })
+        );
+        assert_eq!(
+            parse_command("set tree!"),
+            Ok(Command::Set {
+                option: SetOption::FileTree,
+                value: None
+            })
+        );
+        assert_eq!(
+            parse_command("w decisions.json"),
+            Ok(Command::Write(PathBuf::from("decisions.json")))
+        );
+        assert_eq!(
+            parse_command("set ignorews"),
+            Ok(Command::Set {
+                option: SetOption::IgnoreWhitespace,
+                value: Some(true)
+            })
+        );
+        assert_eq!(parse_command("q"), Ok(Command::Action(Action::RequestQuit)));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse_command("frobnicate"),
+            Err("unknown command: frobnicate".to_string())
+        );
+        assert_eq!(
+            parse_command("set colour"),
+            Err("unknown option: colour".to_string())
+        );
+        assert!(parse_command("goto").is_err());
+        assert!(parse_command("comment").is_err());
+    }
+
+    #[test]
+    fn test_complete_commands_and_paths() {
+        let paths = ["src/parse/mod.rs", "src/parse/lexer.rs", "src/main.rs"];
+        assert_eq!(complete("acc", &paths).0, "accept ");
+        assert_eq!(complete("s", &paths).0, "s"); // set, stats
+
+        let (line, candidates) = complete("accept src/p", &paths);
+        assert_eq!(line, "accept src/parse/");
+        assert_eq!(candidates.len(), 2);
+        assert_eq!(complete("goto src/m", &paths).0, "goto src/main.rs");
+        // Regex targets and non-path commands are left alone
+        assert_eq!(complete("reject /src", &paths).0, "reject /src");
+        assert_eq!(complete("write src", &paths).0, "write src");
+    }
+}
@@ -0,0 +1,102 @@
+//! Cached render model of the diff pane. The document is built once per
+//! change of what it shows, indexed by row; each frame only slices out the
+//! visible window and draws the line cursor and search matches on top.
+
+use super::highlight;
+use super::mouse::DocRow;
+use super::state::{AppState, DiffViewMode};
+use crate::model::WhitespaceIgnore;
+use ratatui::text::{Line, Span};
+use std::ops::Range;
+
+/// Everything the built document depends on. Cursor and search matches are
+/// left out: they are drawn per frame on the visible rows only.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) struct DocKey {
+    /// History revision, bumped by every change to the diff
+    revision: u64,
+    file_index: usize,
+    hunk_index: usize,
+    width: usize,
+    /// Width of the old column when side by side, `None` when unified
+    pub(super) split: Option<usize>,
+    wrap: bool,
+    hscroll: usize,
+    tab_width: usize,
+    show_whitespace: bool,
+    show_highlight: bool,
+    show_full_file: bool,
+    color_moved: bool,
+    ignore_whitespace: WhitespaceIgnore,
+    /// Fold state of each hunk of the current file
+    folded: Vec<bool>,
+}
+
+impl DocKey {
+    pub(super) fn new(state: &AppState) -> Self {
+        let width = state.viewport_width;
+        // Same fallback as the renderer: side by side needs 100 columns
+        let split = (state.diff_view_mode == DiffViewMode::SideBySide && width >= 100)
+            .then(|| state.layout.split_width(width as u16) as usize);
+        let hunks = state.current_file().map_or(0, |f| f.hunks.len());
+        Self {
+            revision: state.history.revision,
+            file_index: state.file_index,
+            hunk_index: state.hunk_index,
+            width,
+            split,
+            wrap: state.wraps(),
+            hscroll: state.hscroll,
+            tab_width: state.tab_width,
+            show_whitespace: state.show_whitespace,
+            show_highlight: state.show_highlight,
+            show_full_file: state.show_full_file,
+            color_moved: state.color_moved,
+            ignore_whitespace: state.ignore_whitespace,
+            folded: (0..hunks)
+                .map(|hi| state.is_folded(state.file_index, hi))
+                .collect(),
+        }
+    }
+}
+
+/// The last built document with its row map
+#[derive(Debug, Default)]
+pub(super) struct DocCache {
+    key: Option<DocKey>,
+    pub(super) lines: Vec<Line<'static>>,
+    /// What each line shows, for mouse hit-testing and the overlay
+    pub(super) rows: Vec<DocRow>,
+    /// Highlighted lines, kept across rebuilds
+    pub(super) memo: highlight::Memo,
+}
+
+impl DocCache {
+    pub(super) fn is_fresh(&self, key: &DocKey) -> bool {
+        self.key.as_ref() == Some(key)
+    }
+
+    pub(super) fn store(&mut self, key: DocKey, lines: Vec<Line<'_>>, rows: Vec<DocRow>) {
+        self.key = Some(key);
+        self.lines = lines.into_iter().map(into_owned).collect();
+        self.rows = rows;
+    }
+
+    /// Rows `offset..offset + height`, clamped to the document
+    pub(super) fn window(&self, offset: usize, height: usize) -> Range<usize> {
+        let start = offset.min(self.lines.len());
+        start..(start + height).min(self.lines.len())
+    }
+}
+
+fn into_owned(line: Line<'_>) -> Line<'static> {
+    Line {
+        style: line.style,
+        alignment: line.alignment,
+        spans: line
+            .spans
+            .into_iter()
+            .map(|s| Span::styled(s.content.into_owned(), s.style))
+            .collect(),
+    }
+}
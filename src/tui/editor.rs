@@ -0,0 +1,178 @@
+//! Suspend the TUI for `$EDITOR`: edit the file at the current hunk and
+//! re-diff, or edit the hunk text itself (like `git add -p` `e`)
+
+use super::state::AppState;
+use crate::git;
+use crate::hunk_edit;
+use crate::model::Diff;
+use anyhow::Result;
+use ratatui::Terminal;
+use ratatui::backend::CrosstermBackend;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// `$VISUAL`, then `$EDITOR`, then `vi`
+fn editor_from_env() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|e| !e.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string())
+}
+
+/// Command line for the editor: its own words (`code -w`), then `+LINE PATH`,
+/// which vi, vim, nvim, nano, emacs, micro and kakoune all understand
+fn editor_argv(editor: &str, path: &Path, line: u32) -> Vec<String> {
+    let mut argv: Vec<String> = editor.split_whitespace().map(str::to_string).collect();
+    argv.push(format!("+{}", line));
+    argv.push(path.display().to_string());
+    argv
+}
+
+/// Run the editor attached to the terminal (stdin/stdout may be pipes)
+fn run_editor(argv: &[String]) -> std::io::Result<std::process::ExitStatus> {
+    let tty = || -> std::io::Result<Stdio> {
+        let file: File = OpenOptions::new().read(true).write(true).open("/dev/tty")?;
+        Ok(Stdio::from(file))
+    };
+    Command::new(&argv[0])
+        .args(&argv[1..])
+        .stdin(tty()?)
+        .stdout(tty()?)
+        .stderr(tty()?)
+        .status()
+}
+
+/// Run the editor with the TUI suspended; the message describes a failure
+fn suspend_and_run(
+    terminal: &mut Terminal<CrosstermBackend<File>>,
+    argv: &[String],
+) -> Result<Result<(), String>> {
+    super::leave_terminal(terminal.backend_mut())?;
+    let status = run_editor(argv);
+    super::enter_terminal(terminal.backend_mut())?;
+    terminal.clear()?;
+    Ok(match status {
+        Err(e) => Err(format!("failed to run {}: {}", argv[0], e)),
+        Ok(status) if !status.success() => Err(format!("{} exited with {}", argv[0], status)),
+        Ok(_) => Ok(()),
+    })
+}
+
+/// Open the current hunk's file in the editor with the TUI suspended. When
+/// `rediff` is given (git mode), the diff is generated and parsed again
+/// afterwards and the review carries over; the outcome goes to the status bar.
+pub(super) fn edit_current_hunk(
+    terminal: &mut Terminal<CrosstermBackend<File>>,
+    state: &mut AppState,
+    rediff: Option<&dyn Fn() -> Result<Diff>>,
+) -> Result<()> {
+    let Some((path, line)) = state.editor_target() else {
+        state.message = Some("nothing to edit (deleted or binary file)".to_string());
+        return Ok(());
+    };
+    let root = git::repo_root().unwrap_or_else(|_| PathBuf::from("."));
+    let argv = editor_argv(&editor_from_env(), &root.join(&path), line);
+
+    state.message = Some(match suspend_and_run(terminal, &argv)? {
+        Err(e) => e,
+        Ok(()) => match rediff {
+            None => format!("edited {}; diff not refreshed (input was piped)", path),
+            Some(rediff) => match rediff() {
+                Ok(diff) => {
+                    let report = state.replace_diff(diff);
+                    format!(
+                        "re-diffed: {} hunks kept, {} edited, {} gone",
+                        report.kept, report.edited, report.dropped
+                    )
+                }
+                Err(e) => format!("re-diff failed: {:#}", e),
+            },
+        },
+    });
+    Ok(())
+}
+
+/// Edit the current hunk's text in the editor. The result replaces the hunk
+/// if it still applies to the original file; otherwise the hunk is unchanged.
+pub(super) fn edit_hunk_text(
+    terminal: &mut Terminal<CrosstermBackend<File>>,
+    state: &mut AppState,
+) -> Result<()> {
+    let Some(hunk) = state.current_hunk().cloned() else {
+        state.message = Some("no hunk to edit".to_string());
+        return Ok(());
+    };
+    let path = match write_temp_hunk(&hunk_edit::edit_template(&hunk)) {
+        Ok(path) => path,
+        Err(e) => {
+            state.message = Some(format!("cannot create temp file: {}", e));
+            return Ok(());
+        }
+    };
+    // Start on the hunk header, below the instructions
+    let argv = editor_argv(&editor_from_env(), &path, 6);
+
+    let outcome = suspend_and_run(terminal, &argv)?;
+    let text = std::fs::read_to_string(&path);
+    let _ = std::fs::remove_file(&path);
+    state.message = Some(match (outcome, text) {
+        (Err(e), _) => e,
+        (Ok(()), Err(e)) => format!("cannot read edited hunk: {}", e),
+        (Ok(()), Ok(text)) => match hunk_edit::apply_edit(&hunk, &text) {
+            Ok(edited) => {
+                state.replace_current_hunk("hunk edit", vec![edited]);
+                "hunk edited".to_string()
+            }
+            Err(e) => format!("edit discarded: {}", e),
+        },
+    });
+    Ok(())
+}
+
+/// Write the hunk template to a fresh temp file. `create_new` refuses an
+/// existing path, so a planted file or symlink is never written through.
+fn write_temp_hunk(text: &str) -> std::io::Result<PathBuf> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let path = std::env::temp_dir().join(format!(
+        "diffy-hunk-{}-{}.diff",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)?;
+    file.write_all(text.as_bytes())?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_temp_hunk_files_are_fresh() {
+        let first = write_temp_hunk("one").unwrap();
+        let second = write_temp_hunk("two").unwrap();
+        assert_ne!(first, second);
+        assert_eq!(std::fs::read_to_string(&first).unwrap(), "one");
+        let _ = std::fs::remove_file(&first);
+        let _ = std::fs::remove_file(&second);
+    }
+
+    #[test]
+    fn test_editor_argv() {
+        assert_eq!(
+            editor_argv("vim", Path::new("/repo/src/main.rs"), 42),
+            vec!["vim", "+42", "/repo/src/main.rs"]
+        );
+        assert_eq!(
+            editor_argv("code  -w", Path::new("a.rs"), 1),
+            vec!["code", "-w", "+1", "a.rs"]
+        );
+    }
+}
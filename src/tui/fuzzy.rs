@@ -0,0 +1,160 @@
+//! Fuzzy path matching for the file finder (Ctrl-p)
+
+/// A scored match of the query against one path
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) struct FuzzyMatch {
+    pub(super) score: i64,
+    /// Char indices in the path that matched the query (for highlighting)
+    pub(super) positions: Vec<usize>,
+}
+
+const MATCH: i64 = 16;
+const CONSECUTIVE: i64 = 24;
+const WORD_START: i64 = 20;
+const BASENAME: i64 = 8;
+const GAP: i64 = 1;
+
+fn is_separator(c: char) -> bool {
+    matches!(c, '/' | '_' | '-' | '.' | ' ')
+}
+
+/// Score `path` against `query` (case-insensitive subsequence match).
+/// Consecutive runs, matches at word starts (after `/`, `_`, `-`, `.` or a
+/// camelCase hump) and matches inside the file name score higher; gaps cost.
+/// Returns None when the query is not a subsequence of the path.
+pub(super) fn fuzzy_match(query: &str, path: &str) -> Option<FuzzyMatch> {
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    let chars: Vec<char> = path.chars().collect();
+    if query.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            positions: Vec::new(),
+        });
+    }
+
+    let basename_start = chars.iter().rposition(|&c| c == '/').map_or(0, |i| i + 1);
+    let is_word_start = |i: usize| {
+        i == 0
+            || is_separator(chars[i - 1])
+            || (chars[i].is_uppercase() && chars[i - 1].is_lowercase())
+    };
+    let lower: Vec<char> = chars
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+
+    // best[qi][pi]: best score with query[..=qi] matched and query[qi] at path[pi]
+    let n = chars.len();
+    let mut best: Vec<Vec<Option<i64>>> = vec![vec![None; n]; query.len()];
+    let mut from: Vec<Vec<usize>> = vec![vec![0; n]; query.len()];
+    for (qi, &qc) in query.iter().enumerate() {
+        for pi in qi..n {
+            if lower[pi] != qc {
+                continue;
+            }
+            let mut bonus = MATCH;
+            if is_word_start(pi) {
+                bonus += WORD_START;
+            }
+            if pi >= basename_start {
+                bonus += BASENAME;
+            }
+            if qi == 0 {
+                best[qi][pi] = Some(bonus - pi as i64 * GAP);
+                continue;
+            }
+            for prev in (qi - 1)..pi {
+                let Some(prev_score) = best[qi - 1][prev] else {
+                    continue;
+                };
+                let link = if prev + 1 == pi {
+                    CONSECUTIVE
+                } else {
+                    -((pi - prev - 1) as i64) * GAP
+                };
+                let score = prev_score + bonus + link;
+                if best[qi][pi].is_none_or(|s| score > s) {
+                    best[qi][pi] = Some(score);
+                    from[qi][pi] = prev;
+                }
+            }
+        }
+    }
+
+    let last = query.len() - 1;
+    let (end, score) = (0..n)
+        .filter_map(|pi| best[last][pi].map(|s| (pi, s)))
+        .max_by_key(|&(pi, s)| (s, std::cmp::Reverse(pi)))?;
+
+    let mut positions = vec![end; query.len()];
+    for qi in (1..query.len()).rev() {
+        positions[qi - 1] = from[qi][positions[qi]];
+    }
+    Some(FuzzyMatch { score, positions })
+}
+
+/// Rank `paths` against `query`, best match first.
+/// Ties keep shorter paths first, then the original order.
+pub(super) fn rank<'a>(
+    query: &str,
+    paths: impl Iterator<Item = (usize, &'a str)>,
+) -> Vec<(usize, FuzzyMatch)> {
+    let mut results: Vec<(usize, usize, FuzzyMatch)> = paths
+        .filter_map(|(i, path)| fuzzy_match(query, path).map(|m| (i, path.len(), m)))
+        .collect();
+    results.sort_by(|a, b| {
+        b.2.score
+            .cmp(&a.2.score)
+            .then(a.1.cmp(&b.1))
+            .then(a.0.cmp(&b.0))
+    });
+    results.into_iter().map(|(i, _, m)| (i, m)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranked(query: &str, paths: &[&str]) -> Vec<String> {
+        rank(query, paths.iter().copied().enumerate())
+            .into_iter()
+            .map(|(i, _)| paths[i].to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_subsequence_required() {
+        assert!(fuzzy_match("stt", "src/tui/state.rs").is_some());
+        assert!(fuzzy_match("xyz", "src/tui/state.rs").is_none());
+        assert!(fuzzy_match("STATE", "src/tui/state.rs").is_some());
+    }
+
+    #[test]
+    fn test_positions_prefer_consecutive_basename() {
+        let m = fuzzy_match("state", "src/tui/state.rs").unwrap();
+        assert_eq!(m.positions, vec![8, 9, 10, 11, 12]);
+    }
+
+    #[test]
+    fn test_rank_basename_and_word_starts_first() {
+        let paths = [
+            "src/tui/render.rs",
+            "specs/roadmap.md",
+            "src/tui/state.rs",
+            "src/rules.rs",
+        ];
+        assert_eq!(ranked("rs", &paths)[0], "src/rules.rs");
+        assert_eq!(ranked("tuist", &paths), vec!["src/tui/state.rs"]);
+        assert_eq!(ranked("rend", &paths), vec!["src/tui/render.rs"]);
+    }
+
+    #[test]
+    fn test_empty_query_keeps_order() {
+        let paths = ["b.rs", "a.rs", "c.rs"];
+        assert_eq!(ranked("", &paths), vec!["b.rs", "a.rs", "c.rs"]);
+    }
+}
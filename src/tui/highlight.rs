@@ -2,6 +2,10 @@
 
 use ratatui::style::{Color, Style};
 use ratatui::text::Span;
+use std::collections::HashMap;
+
+/// Distinct lines a `Memo` keeps before starting over
+const MEMO_LIMIT: usize = 100_000;
 
 /// Get keywords for a given file extension
 fn get_keywords(ext: &str) -> &'static [&'static str] {
@@ -329,6 +333,41 @@ fn highlight_text_segment<'a>(
     spans
 }
 
+/// Memoised `highlight_line`: each distinct line of a file is tokenised once,
+/// however often the diff pane is rebuilt
+#[derive(Debug, Default)]
+pub(super) struct Memo {
+    ext: String,
+    /// Spans highlighted on the default style, per line text
+    lines: HashMap<String, Vec<Span<'static>>>,
+}
+
+impl Memo {
+    /// Like `highlight_line`; `base_style` is applied under the cached colours
+    pub(super) fn highlight(
+        &mut self,
+        line: &str,
+        ext: &str,
+        base_style: Style,
+    ) -> Vec<Span<'static>> {
+        if self.ext != ext || self.lines.len() >= MEMO_LIMIT {
+            self.ext = ext.to_string();
+            self.lines.clear();
+        }
+        if !self.lines.contains_key(line) {
+            let spans = highlight_line(line, ext, Style::default())
+                .into_iter()
+                .map(|s| Span::styled(s.content.into_owned(), s.style))
+                .collect();
+            self.lines.insert(line.to_string(), spans);
+        }
+        self.lines[line]
+            .iter()
+            .map(|s| Span::styled(s.content.clone(), base_style.patch(s.style)))
+            .collect()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -455,7 +494,7 @@ mod tests {
             .filter(|s| s.style.fg == Some(Color::Yellow))
             .collect();
         // The entire string including escaped quotes should be one span
-        assert!(yellow_spans.len() >= 1);
+        assert!(!yellow_spans.is_empty());
     }
 
     #[test]
@@ -470,4 +509,23 @@ mod tests {
         // Actually: pub, fn, impl
         assert!(magenta_count >= 3, "Should highlight multiple keywords");
     }
+
+    #[test]
+    fn test_memo_matches_highlight_line() {
+        let base = Style::default().fg(Color::Green);
+        let mut memo = Memo::default();
+        for line in ["pub fn test() -> &'static str {", "// note", "plain"] {
+            let direct = highlight_line(line, "rs", base);
+            assert_eq!(memo.highlight(line, "rs", base), direct);
+            // A hit re-applies the base style under the cached colours
+            let red = Style::default().fg(Color::Red);
+            assert_eq!(
+                memo.highlight(line, "rs", red),
+                highlight_line(line, "rs", red)
+            );
+        }
+        assert_eq!(memo.lines.len(), 3);
+        memo.highlight("fn", "py", base);
+        assert_eq!(memo.lines.len(), 1);
+    }
 }
@@ -0,0 +1,379 @@
+//! Undo/redo history. Each action records one group of changes, so a bulk
+//! accept is undone with a single `u`.
+
+use crate::model::{Comment, Diff, Hunk, ReviewStatus};
+
+/// One undoable change, holding the state to restore.
+/// Applying it returns the change that puts the current state back.
+#[derive(Clone, Debug)]
+pub(super) enum Change {
+    /// Review state of one hunk
+    Review {
+        file_index: usize,
+        hunk_index: usize,
+        status: ReviewStatus,
+        comments: Vec<Comment>,
+        rule: Option<String>,
+    },
+    /// Every hunk of a file; splits and edits change the hunk count
+    Hunks {
+        file_index: usize,
+        hunks: Vec<Hunk>,
+    },
+    Viewed {
+        file_index: usize,
+        viewed: bool,
+    },
+    Summary(Option<String>),
+}
+
+impl Change {
+    pub(super) fn review(diff: &Diff, file_index: usize, hunk_index: usize) -> Option<Self> {
+        let hunk = diff.files.get(file_index)?.hunks.get(hunk_index)?;
+        Some(Self::Review {
+            file_index,
+            hunk_index,
+            status: hunk.status,
+            comments: hunk.comments.clone(),
+            rule: hunk.rule.clone(),
+        })
+    }
+
+    pub(super) fn hunks(diff: &Diff, file_index: usize) -> Option<Self> {
+        let file = diff.files.get(file_index)?;
+        Some(Self::Hunks {
+            file_index,
+            hunks: file.hunks.clone(),
+        })
+    }
+
+    pub(super) fn viewed(diff: &Diff, file_index: usize) -> Option<Self> {
+        let file = diff.files.get(file_index)?;
+        Some(Self::Viewed {
+            file_index,
+            viewed: file.viewed,
+        })
+    }
+
+    pub(super) fn summary(diff: &Diff) -> Self {
+        Self::Summary(diff.summary.clone())
+    }
+
+    /// File and hunk the change is about, to move the view there
+    fn location(&self) -> Option<(usize, Option<usize>)> {
+        match self {
+            Self::Review {
+                file_index,
+                hunk_index,
+                ..
+            } => Some((*file_index, Some(*hunk_index))),
+            Self::Hunks { file_index, .. } | Self::Viewed { file_index, .. } => {
+                Some((*file_index, None))
+            }
+            Self::Summary(_) => None,
+        }
+    }
+
+    fn apply(self, diff: &mut Diff) -> Self {
+        match self {
+            Self::Review {
+                file_index,
+                hunk_index,
+                status,
+                comments,
+                rule,
+            } => {
+                let Some(hunk) = diff
+                    .files
+                    .get_mut(file_index)
+                    .and_then(|f| f.hunks.get_mut(hunk_index))
+                else {
+                    return Self::Review {
+                        file_index,
+                        hunk_index,
+                        status,
+                        comments,
+                        rule,
+                    };
+                };
+                Self::Review {
+                    file_index,
+                    hunk_index,
+                    status: std::mem::replace(&mut hunk.status, status),
+                    comments: std::mem::replace(&mut hunk.comments, comments),
+                    rule: std::mem::replace(&mut hunk.rule, rule),
+                }
+            }
+            Self::Hunks { file_index, hunks } => match diff.files.get_mut(file_index) {
+                Some(file) => Self::Hunks {
+                    file_index,
+                    hunks: std::mem::replace(&mut file.hunks, hunks),
+                },
+                None => Self::Hunks { file_index, hunks },
+            },
+            Self::Viewed { file_index, viewed } => match diff.files.get_mut(file_index) {
+                Some(file) => Self::Viewed {
+                    file_index,
+                    viewed: std::mem::replace(&mut file.viewed, viewed),
+                },
+                None => Self::Viewed { file_index, viewed },
+            },
+            Self::Summary(summary) => Self::Summary(std::mem::replace(&mut diff.summary, summary)),
+        }
+    }
+}
+
+/// Changes made by one action, undone and redone together
+#[derive(Clone, Debug)]
+pub(super) struct Group {
+    pub(super) label: String,
+    pub(super) changes: Vec<Change>,
+}
+
+/// What an undo or redo touched, so the view can follow
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) struct Replayed {
+    pub(super) label: String,
+    pub(super) changes: usize,
+    pub(super) location: Option<(usize, Option<usize>)>,
+    /// Files whose hunk list was swapped (folds and search no longer line up)
+    pub(super) reshaped: Vec<usize>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(super) struct History {
+    pub(super) undo: Vec<Group>,
+    pub(super) redo: Vec<Group>,
+    /// Most groups kept; the oldest are dropped beyond it (0 disables undo)
+    limit: usize,
+    /// Groups dropped so far, so a `mark` stays valid while the cap trims
+    dropped: usize,
+    /// Bumped whenever the diff changes (recorded, undone, redone or
+    /// replaced), so caches built from it can tell they are stale
+    pub(super) revision: u64,
+}
+
+impl History {
+    pub(super) fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            ..Self::default()
+        }
+    }
+
+    /// Record the state before an action. A new action clears the redo stack.
+    pub(super) fn record(&mut self, label: impl Into<String>, changes: Vec<Change>) {
+        self.revision += 1;
+        if changes.is_empty() || self.limit == 0 {
+            return;
+        }
+        self.redo.clear();
+        self.undo.push(Group {
+            label: label.into(),
+            changes,
+        });
+        if self.undo.len() > self.limit {
+            let excess = self.undo.len() - self.limit;
+            self.undo.drain(..excess);
+            self.dropped += excess;
+        }
+    }
+
+    /// Start of a transaction; pass it to `merge_since` when the action is done
+    pub(super) fn mark(&self) -> usize {
+        self.dropped + self.undo.len()
+    }
+
+    /// Merge the groups recorded since `mark` into one, labelled like the first
+    pub(super) fn merge_since(&mut self, mark: usize) {
+        let start = mark.saturating_sub(self.dropped);
+        if start + 1 >= self.undo.len() {
+            return;
+        }
+        let mut groups = self.undo.drain(start..);
+        let Some(mut merged) = groups.next() else {
+            return;
+        };
+        for group in groups {
+            merged.changes.extend(group.changes);
+        }
+        self.undo.push(merged);
+    }
+
+    /// Note a change to the diff that is not undoable (files appended or loaded)
+    pub(super) fn touch(&mut self) {
+        self.revision += 1;
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.revision += 1;
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    pub(super) fn undo(&mut self, diff: &mut Diff) -> Option<Replayed> {
+        self.revision += 1;
+        replay(&mut self.undo, &mut self.redo, diff)
+    }
+
+    pub(super) fn redo(&mut self, diff: &mut Diff) -> Option<Replayed> {
+        self.revision += 1;
+        replay(&mut self.redo, &mut self.undo, diff)
+    }
+}
+
+/// Apply the last group of `from` (newest change first) and push its inverse to `to`
+fn replay(from: &mut Vec<Group>, to: &mut Vec<Group>, diff: &mut Diff) -> Option<Replayed> {
+    let group = from.pop()?;
+    let mut inverse: Vec<Change> = group
+        .changes
+        .into_iter()
+        .rev()
+        .map(|change| change.apply(diff))
+        .collect();
+    inverse.reverse();
+    let replayed = Replayed {
+        label: group.label.clone(),
+        changes: inverse.len(),
+        location: inverse.first().and_then(Change::location),
+        reshaped: inverse
+            .iter()
+            .filter_map(|c| match c {
+                Change::Hunks { file_index, .. } => Some(*file_index),
+                _ => None,
+            })
+            .collect(),
+    };
+    to.push(Group {
+        label: group.label,
+        changes: inverse,
+    });
+    Some(replayed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{DiffLine, FileDiff};
+
+    fn diff() -> Diff {
+        let hunk = Hunk {
+            header: "@@ -1,1 +1,1 @@".to_string(),
+            old_start: 1,
+            old_count: 1,
+            new_start: 1,
+            new_count: 1,
+            lines: vec![DiffLine::Added("x".to_string())],
+            status: ReviewStatus::Pending,
+            comments: Vec::new(),
+            rule: None,
+            edited: false,
+            original_lines: None,
+        };
+        Diff::new(vec![FileDiff {
+            old_path: "a.rs".to_string(),
+            new_path: "a.rs".to_string(),
+            raw_old_path: "a/a.rs".to_string(),
+            raw_new_path: "b/a.rs".to_string(),
+            hunks: vec![hunk.clone(), hunk],
+            is_binary: false,
+            viewed: false,
+            deferred: None,
+        }])
+    }
+
+    fn accept_all(history: &mut History, diff: &mut Diff) {
+        let changes = (0..2)
+            .filter_map(|hi| Change::review(diff, 0, hi))
+            .collect();
+        history.record("accept all", changes);
+        for hunk in &mut diff.files[0].hunks {
+            hunk.status = ReviewStatus::Accepted;
+        }
+    }
+
+    #[test]
+    fn test_group_undo_redo() {
+        let mut diff = diff();
+        let mut history = History::new(10);
+        accept_all(&mut history, &mut diff);
+
+        let undone = history.undo(&mut diff).unwrap();
+        assert_eq!(undone.label, "accept all");
+        assert_eq!(undone.changes, 2);
+        assert_eq!(undone.location, Some((0, Some(0))));
+        assert!(
+            diff.files[0]
+                .hunks
+                .iter()
+                .all(|h| h.status == ReviewStatus::Pending)
+        );
+        assert!(history.undo(&mut diff).is_none());
+
+        history.redo(&mut diff).unwrap();
+        assert!(
+            diff.files[0]
+                .hunks
+                .iter()
+                .all(|h| h.status == ReviewStatus::Accepted)
+        );
+        assert!(history.redo(&mut diff).is_none());
+        assert_eq!(history.undo.len(), 1);
+    }
+
+    #[test]
+    fn test_record_clears_redo_and_caps() {
+        let mut diff = diff();
+        let mut history = History::new(2);
+        accept_all(&mut history, &mut diff);
+        history.undo(&mut diff);
+        assert_eq!(history.redo.len(), 1);
+
+        let viewed = Change::viewed(&diff, 0).unwrap();
+        history.record("viewed", vec![viewed]);
+        assert!(history.redo.is_empty());
+
+        history.record("summary", vec![Change::summary(&diff)]);
+        history.record("summary", vec![Change::summary(&diff)]);
+        let labels: Vec<_> = history.undo.iter().map(|g| g.label.as_str()).collect();
+        assert_eq!(labels, vec!["summary", "summary"]);
+
+        let mut off = History::new(0);
+        off.record("viewed", vec![Change::summary(&diff)]);
+        assert!(off.undo.is_empty());
+    }
+
+    #[test]
+    fn test_merge_since_mark() {
+        let mut diff = diff();
+        let mut history = History::new(2);
+        history.record("summary", vec![Change::summary(&diff)]);
+        let mark = history.mark();
+        for hi in 0..2 {
+            let change = Change::review(&diff, 0, hi).unwrap();
+            history.record("accept", vec![change]);
+            diff.files[0].hunks[hi].status = ReviewStatus::Accepted;
+        }
+        // The cap dropped "summary" meanwhile; the mark still finds the start
+        history.merge_since(mark);
+        assert_eq!(history.undo.len(), 1);
+        let undone = history.undo(&mut diff).unwrap();
+        assert_eq!((undone.label.as_str(), undone.changes), ("accept", 2));
+        assert_eq!(diff.files[0].hunks[1].status, ReviewStatus::Pending);
+    }
+
+    #[test]
+    fn test_hunks_change_reports_reshaped_file() {
+        let mut diff = diff();
+        let mut history = History::new(10);
+        history.record("split", vec![Change::hunks(&diff, 0).unwrap()]);
+        diff.files[0].hunks.truncate(1);
+
+        let undone = history.undo(&mut diff).unwrap();
+        assert_eq!(undone.reshaped, vec![0]);
+        assert_eq!(undone.location, Some((0, None)));
+        assert_eq!(diff.files[0].hunks.len(), 2);
+        history.redo(&mut diff);
+        assert_eq!(diff.files[0].hunks.len(), 1);
+    }
+}
@@ -1,9 +1,15 @@
 //! Key input handling
 
+use super::clipboard::YankKind;
+use super::keys::{self, KeySeq, Step};
+use super::mouse;
 use super::state::{AppMode, AppState, Focus};
 use crate::model::ReviewStatus;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
+/// Columns moved by one `<` / `>` press
+pub(super) const HSCROLL_STEP: isize = 8;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(super) enum Action {
     NextHunk,
@@ -14,16 +20,49 @@ pub(super) enum Action {
     Reject,
     Toggle,
     Undo,
+    Redo,
     AcceptAll,
     RejectAll,
     FirstHunk,
     LastHunk,
     NextPending,
-    EnterPendingG,
-    CancelPendingG,
+    ToggleViewed,
+    /// Handled by run_loop: suspends the TUI for `$EDITOR`
+    EditInEditor,
+    /// Handled by run_loop: edit the hunk text in `$EDITOR`
+    EditHunk,
+    SplitHunk,
+    /// A count or prefix key was typed; wait for the rest of the sequence
+    PendingKeys(KeySeq),
+    /// The prefix does not take this key: drop it, then run_loop re-dispatches the key
+    CancelKeys,
+    SetMark(char),
+    JumpMark(char),
+    /// `''`: back to where the latest jump started
+    JumpPrevious,
+    JumpBack,
+    JumpForward,
+    Yank(YankKind),
+    MenuNext,
+    MenuPrev,
+    /// Run the context menu item under the cursor
+    MenuSelect,
+    CloseMenu,
+    /// za / zo / zc on the current hunk (None = toggle)
+    Fold(Option<bool>),
+    FoldFile,
+    FoldAll,
+    UnfoldAll,
     PageUp,
     PageDown,
     ToggleFileTree,
+    /// `]` / `[`: widen or narrow the file tree (taller/shorter when stacked)
+    ResizeTree(bool),
+    /// `}` / `{`: grow or shrink the bottom panel
+    ResizePanel(bool),
+    TogglePanel,
+    /// `\`: tree beside or above the diff
+    ToggleLayout,
     EnterSearch,
     SubmitSearch,
     CancelSearch,
@@ -38,9 +77,45 @@ pub(super) enum Action {
     SubmitComment,
     CancelComment,
     CommentBackspace,
+    CycleSeverity,
+    NextComment,
+    PrevComment,
+    DeleteComment,
+    LineCursorDown,
+    LineCursorUp,
+    ClearLineCursor,
+    EnterSummary,
+    SubmitSummary,
+    CancelSummary,
+    SummaryBackspace,
     FocusFileTree,
     FocusDiffView,
+    CycleTreeSort,
+    OpenFinder,
+    EnterCommand,
+    SubmitCommand,
+    CancelCommand,
+    CommandBackspace,
+    CompleteCommand,
+    CommandHistoryPrev,
+    CommandHistoryNext,
+    FinderNext,
+    FinderPrev,
+    FinderJump,
+    CancelFinder,
+    FinderBackspace,
+    EnterTreeFilter,
+    SubmitTreeFilter,
+    CancelTreeFilter,
+    TreeFilterBackspace,
     ToggleFullFile,
+    ToggleWrap,
+    ToggleWhitespace,
+    JumpMoved,
+    AcceptWhitespace,
+    ScrollLeft,
+    ScrollRight,
+    ScrollHome,
     RequestQuit,
     ConfirmQuit,
     CancelQuit,
@@ -50,62 +125,18 @@ pub(super) enum Action {
 /// Map key event to action based on current mode
 pub(super) fn handle_key(key: &KeyEvent, state: &AppState) -> Action {
     match state.mode {
-        AppMode::Normal => {
-            // Check Ctrl+key combos first
-            if key.modifiers.contains(KeyModifiers::CONTROL) {
-                return match key.code {
-                    KeyCode::Char('u') => Action::PageUp,
-                    KeyCode::Char('d') => Action::PageDown,
-                    KeyCode::Char('n') => Action::NextFile,
-                    KeyCode::Char('p') => Action::PrevFile,
-                    _ => Action::None,
-                };
+        AppMode::Normal => match keys::step(state.keys, key) {
+            Step::Pending(seq) => Action::PendingKeys(seq),
+            // Esc abandons a count or prefix instead of quitting
+            Step::Complete { key, .. } if key.code == KeyCode::Esc && !state.keys.is_empty() => {
+                Action::None
             }
-            match key.code {
-                KeyCode::Char('j') | KeyCode::Down => Action::NextHunk,
-                KeyCode::Char('k') | KeyCode::Up => Action::PrevHunk,
-                KeyCode::Char('n') => {
-                    if state.has_active_search() {
-                        Action::NextMatch
-                    } else {
-                        Action::NextFile
-                    }
-                }
-                KeyCode::Char('N') => {
-                    if state.has_active_search() {
-                        Action::PrevMatch
-                    } else {
-                        Action::PrevFile
-                    }
-                }
-                KeyCode::Char('a') => Action::Accept,
-                KeyCode::Char('r') => Action::Reject,
-                KeyCode::Char(' ') | KeyCode::Enter => Action::Toggle,
-                KeyCode::Char('u') => Action::Undo,
-                KeyCode::Char('A') => Action::AcceptAll,
-                KeyCode::Char('R') => Action::RejectAll,
-                KeyCode::Char('g') => Action::EnterPendingG,
-                KeyCode::Char('G') => Action::LastHunk,
-                KeyCode::Tab => Action::NextPending,
-                KeyCode::PageUp => Action::PageUp,
-                KeyCode::PageDown => Action::PageDown,
-                KeyCode::Char('/') => Action::EnterSearch,
-                KeyCode::Char('c') => Action::EnterComment,
-                KeyCode::Char('d') => Action::ToggleDiffView,
-                KeyCode::Char('e') => Action::ToggleFullFile,
-                KeyCode::Char('f') => Action::ToggleFileTree,
-                KeyCode::Char('h') | KeyCode::Left => Action::FocusFileTree,
-                KeyCode::Char('l') | KeyCode::Right => Action::FocusDiffView,
-                KeyCode::Char('H') => Action::ToggleHighlight,
-                KeyCode::Char('s') => Action::ToggleStats,
-                KeyCode::Char('?') => Action::ToggleHelp,
-                KeyCode::Char('q') | KeyCode::Esc => Action::RequestQuit,
-                _ => Action::None,
-            }
-        }
-        AppMode::PendingG => match key.code {
-            KeyCode::Char('g') => Action::FirstHunk,
-            _ => Action::CancelPendingG,
+            Step::Complete {
+                prefix: Some(prefix),
+                key,
+                ..
+            } => prefixed_action(prefix, &key),
+            Step::Complete { key, .. } => normal_action(&key, state),
         },
         AppMode::Search => match key.code {
             KeyCode::Enter => Action::SubmitSearch,
@@ -114,6 +145,39 @@ pub(super) fn handle_key(key: &KeyEvent, state: &AppState) -> Action {
             KeyCode::Char(_) => Action::None, // char input handled in run_loop
             _ => Action::None,
         },
+        AppMode::TreeFilter => match key.code {
+            KeyCode::Enter => Action::SubmitTreeFilter,
+            KeyCode::Esc => Action::CancelTreeFilter,
+            KeyCode::Backspace => Action::TreeFilterBackspace,
+            KeyCode::Char(_) => Action::None, // char input handled in run_loop
+            _ => Action::None,
+        },
+        AppMode::FileFinder => match key.code {
+            KeyCode::Enter => Action::FinderJump,
+            KeyCode::Esc => Action::CancelFinder,
+            KeyCode::Backspace => Action::FinderBackspace,
+            KeyCode::Down | KeyCode::Tab => Action::FinderNext,
+            KeyCode::Up | KeyCode::BackTab => Action::FinderPrev,
+            KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Action::FinderNext
+            }
+            KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Action::FinderPrev
+            }
+            KeyCode::Char(_) => Action::None, // char input handled in run_loop
+            _ => Action::None,
+        },
+        AppMode::Command => match key.code {
+            KeyCode::Enter => Action::SubmitCommand,
+            KeyCode::Esc => Action::CancelCommand,
+            KeyCode::Backspace if state.command_input.is_empty() => Action::CancelCommand,
+            KeyCode::Backspace => Action::CommandBackspace,
+            KeyCode::Tab => Action::CompleteCommand,
+            KeyCode::Up => Action::CommandHistoryPrev,
+            KeyCode::Down => Action::CommandHistoryNext,
+            KeyCode::Char(_) => Action::None, // char input handled in run_loop
+            _ => Action::None,
+        },
         AppMode::Help => Action::ToggleHelp,
         AppMode::Stats => match key.code {
             KeyCode::Char('j') | KeyCode::Down => Action::NextHunk,
@@ -126,25 +190,230 @@ pub(super) fn handle_key(key: &KeyEvent, state: &AppState) -> Action {
             KeyCode::Enter => Action::SubmitComment,
             KeyCode::Esc => Action::CancelComment,
             KeyCode::Backspace => Action::CommentBackspace,
+            KeyCode::Tab => Action::CycleSeverity,
+            KeyCode::Down => Action::NextComment,
+            KeyCode::Up => Action::PrevComment,
+            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Action::DeleteComment
+            }
+            KeyCode::Char(_) => Action::None, // char input handled in run_loop
+            _ => Action::None,
+        },
+        AppMode::SummaryEdit => match key.code {
+            KeyCode::Enter => Action::SubmitSummary,
+            KeyCode::Esc => Action::CancelSummary,
+            KeyCode::Backspace => Action::SummaryBackspace,
             KeyCode::Char(_) => Action::None, // char input handled in run_loop
             _ => Action::None,
         },
+        AppMode::Menu => match key.code {
+            KeyCode::Char('j') | KeyCode::Down | KeyCode::Tab => Action::MenuNext,
+            KeyCode::Char('k') | KeyCode::Up | KeyCode::BackTab => Action::MenuPrev,
+            KeyCode::Enter | KeyCode::Char(' ') => Action::MenuSelect,
+            KeyCode::Esc | KeyCode::Char('q') => Action::CloseMenu,
+            _ => Action::None,
+        },
         AppMode::ConfirmQuit => match key.code {
             KeyCode::Char('y') | KeyCode::Enter => Action::ConfirmQuit,
             KeyCode::Char('n') | KeyCode::Esc => Action::CancelQuit,
+            KeyCode::Char('m') => Action::EnterSummary,
             _ => Action::None,
         },
     }
 }
 
+/// Single keys in Normal mode
+fn normal_action(key: &KeyEvent, state: &AppState) -> Action {
+    // Check Ctrl+key combos first
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        return match key.code {
+            KeyCode::Char('u') => Action::PageUp,
+            KeyCode::Char('d') => Action::PageDown,
+            KeyCode::Char('n') => Action::NextFile,
+            KeyCode::Char('p') => Action::OpenFinder,
+            KeyCode::Char('e') => Action::EditHunk,
+            KeyCode::Char('r') => Action::Redo,
+            KeyCode::Char('o') => Action::JumpBack,
+            KeyCode::Char('i') => Action::JumpForward,
+            _ => Action::None,
+        };
+    }
+    match key.code {
+        KeyCode::Char('j') | KeyCode::Down => Action::NextHunk,
+        KeyCode::Char('k') | KeyCode::Up => Action::PrevHunk,
+        KeyCode::Char('J') => Action::LineCursorDown,
+        KeyCode::Char('K') => Action::LineCursorUp,
+        KeyCode::Char('n') => {
+            if state.has_active_search() {
+                Action::NextMatch
+            } else {
+                Action::NextFile
+            }
+        }
+        KeyCode::Char('N') => {
+            if state.has_active_search() {
+                Action::PrevMatch
+            } else {
+                Action::PrevFile
+            }
+        }
+        KeyCode::Char('a') => Action::Accept,
+        KeyCode::Char('r') => Action::Reject,
+        KeyCode::Char(' ') | KeyCode::Enter => Action::Toggle,
+        KeyCode::Char('u') => Action::Undo,
+        KeyCode::Char('A') => Action::AcceptAll,
+        KeyCode::Char('R') => Action::RejectAll,
+        KeyCode::Char('G') => Action::LastHunk,
+        KeyCode::Tab => Action::NextPending,
+        KeyCode::Char('v') => Action::ToggleViewed,
+        KeyCode::Char('E') => Action::EditInEditor,
+        KeyCode::Char('S') => Action::SplitHunk,
+        KeyCode::PageUp => Action::PageUp,
+        KeyCode::PageDown => Action::PageDown,
+        KeyCode::Char('/') if state.focus == Focus::FileTree => Action::EnterTreeFilter,
+        KeyCode::Char('/') => Action::EnterSearch,
+        KeyCode::Char('c') => Action::EnterComment,
+        KeyCode::Char('d') => Action::ToggleDiffView,
+        KeyCode::Char('e') => Action::ToggleFullFile,
+        KeyCode::Char('f') => Action::ToggleFileTree,
+        KeyCode::Char(']') => Action::ResizeTree(true),
+        KeyCode::Char('[') => Action::ResizeTree(false),
+        KeyCode::Char('}') => Action::ResizePanel(true),
+        KeyCode::Char('{') => Action::ResizePanel(false),
+        KeyCode::Char('b') => Action::TogglePanel,
+        KeyCode::Char('\\') => Action::ToggleLayout,
+        KeyCode::Char('h') | KeyCode::Left => Action::FocusFileTree,
+        KeyCode::Char('l') | KeyCode::Right => Action::FocusDiffView,
+        KeyCode::Char('H') => Action::ToggleHighlight,
+        KeyCode::Char('w') => Action::ToggleWrap,
+        KeyCode::Char('i') => Action::ToggleWhitespace,
+        KeyCode::Char('W') => Action::AcceptWhitespace,
+        KeyCode::Char('M') => Action::JumpMoved,
+        KeyCode::Char('<') => Action::ScrollLeft,
+        KeyCode::Char('>') => Action::ScrollRight,
+        KeyCode::Char('0') => Action::ScrollHome,
+        KeyCode::Char('o') => Action::CycleTreeSort,
+        KeyCode::Char(':') => Action::EnterCommand,
+        KeyCode::Char('s') => Action::ToggleStats,
+        KeyCode::Char('?') => Action::ToggleHelp,
+        KeyCode::Esc if state.cursor_line().is_some() => Action::ClearLineCursor,
+        KeyCode::Char('q') | KeyCode::Esc => Action::RequestQuit,
+        _ => Action::None,
+    }
+}
+
+/// Keys after a prefix: `gg`, `z` folds, `m` marks, `'` / `` ` `` jumps
+fn prefixed_action(prefix: char, key: &KeyEvent) -> Action {
+    let KeyCode::Char(c) = key.code else {
+        return Action::CancelKeys;
+    };
+    match (prefix, c) {
+        ('g', 'g') => Action::FirstHunk,
+        ('z', 'a') => Action::Fold(None),
+        ('z', 'c') => Action::Fold(Some(true)),
+        ('z', 'o') => Action::Fold(Some(false)),
+        ('z', 'A') => Action::FoldFile,
+        ('z', 'M') => Action::FoldAll,
+        ('z', 'R') => Action::UnfoldAll,
+        ('m', c) if c.is_ascii_lowercase() => Action::SetMark(c),
+        ('y', 'y') => Action::Yank(YankKind::Patch),
+        ('y', 'p') => Action::Yank(YankKind::Location),
+        ('y', 'f') => Action::Yank(YankKind::Path),
+        ('y', 'm') => Action::Yank(YankKind::Markdown),
+        ('\'' | '`', '\'' | '`') => Action::JumpPrevious,
+        ('\'' | '`', c) if c.is_ascii_lowercase() => Action::JumpMark(c),
+        _ => Action::CancelKeys,
+    }
+}
+
 /// Apply action to state; returns the action for re-dispatch check
 pub(super) fn apply_action(action: Action, state: &mut AppState) {
+    if let Action::PendingKeys(seq) = action {
+        state.keys = seq;
+        return;
+    }
+    let count = std::mem::take(&mut state.keys).count;
+    if action.is_jump() {
+        state.push_jump();
+    }
+    let Some(n) = count else {
+        apply(action, state);
+        return;
+    };
+    match action {
+        Action::FirstHunk | Action::LastHunk => state.goto_flat_hunk(n),
+        // `3a`: decide this hunk and the next two, undone in one step
+        Action::Accept | Action::Reject | Action::Toggle
+            if state.mode == AppMode::Normal && state.focus == Focus::DiffView =>
+        {
+            state.as_one_step(|state| {
+                for i in 0..n {
+                    let hi = state.hunk_index;
+                    if i > 0 {
+                        state.next_hunk();
+                        if state.hunk_index == hi {
+                            break;
+                        }
+                    }
+                    apply(action, state);
+                }
+            });
+        }
+        _ if action.repeats() => {
+            for _ in 0..n {
+                apply(action, state);
+            }
+        }
+        _ => apply(action, state),
+    }
+}
+
+impl Action {
+    /// Moves that leave an entry in the jump list (Ctrl-o returns to it)
+    fn is_jump(self) -> bool {
+        matches!(
+            self,
+            Action::FirstHunk
+                | Action::LastHunk
+                | Action::NextMatch
+                | Action::PrevMatch
+                | Action::JumpMoved
+                | Action::FinderJump
+        )
+    }
+
+    /// Actions a count repeats (`5j`, `3u`)
+    fn repeats(self) -> bool {
+        matches!(
+            self,
+            Action::NextHunk
+                | Action::PrevHunk
+                | Action::NextFile
+                | Action::PrevFile
+                | Action::NextPending
+                | Action::LineCursorDown
+                | Action::LineCursorUp
+                | Action::NextMatch
+                | Action::PrevMatch
+                | Action::ScrollLeft
+                | Action::ScrollRight
+                | Action::Undo
+                | Action::Redo
+                | Action::JumpBack
+                | Action::JumpForward
+                | Action::ResizeTree(_)
+                | Action::ResizePanel(_)
+        )
+    }
+}
+
+fn apply(action: Action, state: &mut AppState) {
     match action {
         Action::NextHunk => {
             if state.mode == AppMode::Stats {
                 state.stats_cursor_down();
             } else if state.focus == Focus::FileTree {
-                state.next_file();
+                state.tree_move(true);
             } else {
                 state.next_hunk();
             }
@@ -153,7 +422,7 @@ pub(super) fn apply_action(action: Action, state: &mut AppState) {
             if state.mode == AppMode::Stats {
                 state.stats_cursor_up();
             } else if state.focus == Focus::FileTree {
-                state.prev_file();
+                state.tree_move(false);
             } else {
                 state.prev_hunk();
             }
@@ -162,6 +431,7 @@ pub(super) fn apply_action(action: Action, state: &mut AppState) {
         Action::PrevFile => state.prev_file(),
         Action::Accept => {
             if state.mode == AppMode::Stats {
+                state.push_jump();
                 state.stats_navigate_to_cursor();
             } else {
                 state.set_current_status(ReviewStatus::Accepted);
@@ -170,29 +440,63 @@ pub(super) fn apply_action(action: Action, state: &mut AppState) {
         Action::Reject => state.set_current_status(ReviewStatus::Rejected),
         Action::Toggle => {
             if state.focus == Focus::FileTree {
-                state.focus = Focus::DiffView;
+                state.tree_activate();
             } else {
                 state.toggle_current_status();
             }
         }
-        Action::Undo => state.undo(),
+        Action::Undo => state.message = Some(state.undo()),
+        Action::Redo => state.message = Some(state.redo()),
         Action::AcceptAll => state.set_all_status(ReviewStatus::Accepted),
         Action::RejectAll => state.set_all_status(ReviewStatus::Rejected),
-        Action::FirstHunk => {
-            state.first_hunk();
-            state.mode = AppMode::Normal;
-        }
+        Action::FirstHunk => state.first_hunk(),
         Action::LastHunk => state.last_hunk(),
         Action::NextPending => {
             state.next_pending();
         }
-        Action::EnterPendingG => {
-            state.mode = AppMode::PendingG;
+        Action::ToggleViewed => {
+            state.message = Some(state.toggle_viewed());
         }
-        Action::CancelPendingG => {
+        Action::SplitHunk => {
+            state.message = Some(match state.split_current_hunk() {
+                Ok(msg) | Err(msg) => msg,
+            });
+        }
+        // Keys are stored/cleared by apply_action; for CancelKeys the caller
+        // (run_loop) re-dispatches the key on its own
+        Action::PendingKeys(_) | Action::CancelKeys => {}
+        Action::SetMark(mark) => state.message = Some(state.set_mark(mark)),
+        Action::JumpMark(mark) => state.message = state.jump_to_mark(mark).err(),
+        Action::MenuNext | Action::MenuPrev => {
+            if let Some(menu) = &mut state.menu {
+                let len = mouse::MENU_ITEMS.len();
+                let step = if action == Action::MenuNext {
+                    1
+                } else {
+                    len - 1
+                };
+                menu.cursor = (menu.cursor + step) % len;
+            }
+        }
+        Action::MenuSelect => {
+            state.mode = AppMode::Normal;
+            if let Some(menu) = state.menu.take() {
+                apply_action(mouse::MENU_ITEMS[menu.cursor].1, state);
+            }
+        }
+        Action::CloseMenu => {
+            state.menu = None;
             state.mode = AppMode::Normal;
-            // The caller (run_loop) will re-dispatch this key in Normal mode
         }
+        Action::Yank(kind) => {
+            state.message = Some(state.yank(kind).unwrap_or_else(|e| e));
+        }
+        Action::JumpPrevious => state.message = state.jump_previous().err(),
+        Action::JumpBack => state.message = state.jump_back(true).err(),
+        Action::JumpForward => state.message = state.jump_back(false).err(),
+        Action::Fold(folded) => state.fold_current(folded),
+        Action::FoldFile => state.toggle_file_fold(),
+        Action::FoldAll | Action::UnfoldAll => state.fold_all(action == Action::FoldAll),
         Action::PageUp => state.scroll_up(state.viewport_height / 2),
         Action::PageDown => state.scroll_down(state.viewport_height / 2),
         Action::ToggleFileTree => {
@@ -201,24 +505,38 @@ pub(super) fn apply_action(action: Action, state: &mut AppState) {
                 state.focus = Focus::DiffView;
             }
         }
-        Action::EnterSearch => {
-            state.search_query.clear();
-            state.mode = AppMode::Search;
+        Action::ResizeTree(grow) => {
+            if !state.show_file_tree {
+                state.show_file_tree = true;
+            } else {
+                state.layout.resize_tree(if grow { 1 } else { -1 });
+            }
+        }
+        Action::ResizePanel(grow) => {
+            if !state.layout.panel {
+                state.layout.panel = true;
+            } else {
+                state.layout.resize_panel(if grow { 1 } else { -1 });
+            }
         }
+        Action::TogglePanel => state.layout.panel = !state.layout.panel,
+        Action::ToggleLayout => state.message = Some(state.layout.toggle_orientation().to_string()),
+        Action::EnterSearch => state.begin_search(),
         Action::SubmitSearch => {
-            state.execute_search();
-            state.mode = AppMode::Normal;
-            if let Some(0) = state.search_index {
-                state.goto_match(0);
+            // Matches are already live apart from spooled bodies, searched now; keep
+            // the position incremental search reached. The search started from the
+            // origin, so that is the jump.
+            state.submit_search();
+            if let Some((fi, hi, _)) = state.search_origin.take() {
+                state.push_jump_from(fi, hi);
             }
+            state.mode = AppMode::Normal;
         }
         Action::CancelSearch => {
-            state.clear_search();
+            state.cancel_search();
             state.mode = AppMode::Normal;
         }
-        Action::SearchBackspace => {
-            state.search_query.pop();
-        }
+        Action::SearchBackspace => state.search_backspace(),
         Action::NextMatch => {
             state.next_match();
         }
@@ -250,25 +568,52 @@ pub(super) fn apply_action(action: Action, state: &mut AppState) {
             };
         }
         Action::EnterComment => {
-            // Pre-fill with existing comment
-            if let Some(hunk) = state.current_hunk() {
-                state.comment_input = hunk.comment.clone().unwrap_or_default();
-            }
+            state.begin_comment_edit();
             state.mode = AppMode::CommentEdit;
         }
         Action::SubmitComment => {
             let comment = state.comment_input.clone();
-            state.set_current_comment(comment);
+            state.set_current_comment(comment, state.comment_severity);
             state.comment_input.clear();
             state.mode = AppMode::Normal;
         }
         Action::CancelComment => {
             state.comment_input.clear();
+            state.comment_edit_index = None;
             state.mode = AppMode::Normal;
         }
         Action::CommentBackspace => {
             state.comment_input.pop();
         }
+        Action::CycleSeverity => {
+            state.comment_severity = state.comment_severity.next();
+        }
+        Action::NextComment => state.cycle_comment_edit(true),
+        Action::PrevComment => state.cycle_comment_edit(false),
+        Action::DeleteComment => {
+            state.delete_edited_comment();
+            state.comment_input.clear();
+            state.mode = AppMode::Normal;
+        }
+        Action::LineCursorDown => state.move_line_cursor(true),
+        Action::LineCursorUp => state.move_line_cursor(false),
+        Action::ClearLineCursor => state.clear_line_cursor(),
+        Action::EnterSummary => {
+            state.summary_input = state.diff.summary.clone().unwrap_or_default();
+            state.mode = AppMode::SummaryEdit;
+        }
+        Action::SubmitSummary => {
+            let summary = std::mem::take(&mut state.summary_input);
+            state.set_summary(summary);
+            state.mode = AppMode::ConfirmQuit;
+        }
+        Action::CancelSummary => {
+            state.summary_input.clear();
+            state.mode = AppMode::ConfirmQuit;
+        }
+        Action::SummaryBackspace => {
+            state.summary_input.pop();
+        }
         Action::FocusFileTree => {
             if state.show_file_tree {
                 state.focus = Focus::FileTree;
@@ -277,11 +622,64 @@ pub(super) fn apply_action(action: Action, state: &mut AppState) {
         Action::FocusDiffView => {
             state.focus = Focus::DiffView;
         }
+        Action::CycleTreeSort => state.cycle_tree_sort(),
+        Action::OpenFinder => state.open_finder(),
+        Action::FinderNext => state.finder_move(true),
+        Action::FinderPrev => state.finder_move(false),
+        Action::FinderJump => state.finder_jump(),
+        Action::CancelFinder => {
+            state.finder_query.clear();
+            state.mode = AppMode::Normal;
+        }
+        Action::FinderBackspace => state.finder_backspace(),
+        Action::EnterCommand => state.open_command(),
+        Action::SubmitCommand => state.submit_command(),
+        Action::CancelCommand => {
+            state.command_input.clear();
+            state.mode = AppMode::Normal;
+        }
+        Action::CommandBackspace => {
+            state.command_input.pop();
+        }
+        Action::CompleteCommand => state.complete_command(),
+        Action::CommandHistoryPrev => state.command_history_move(true),
+        Action::CommandHistoryNext => state.command_history_move(false),
+        Action::EnterTreeFilter => {
+            state.mode = AppMode::TreeFilter;
+        }
+        Action::SubmitTreeFilter => {
+            state.apply_tree_filter();
+            state.mode = AppMode::Normal;
+        }
+        Action::CancelTreeFilter => {
+            state.clear_tree_filter();
+            state.mode = AppMode::Normal;
+        }
+        Action::TreeFilterBackspace => {
+            state.tree_filter.pop();
+        }
         Action::ToggleFullFile => {
             state.show_full_file = !state.show_full_file;
             state.viewport_offset = 0;
             state.ensure_visible();
         }
+        Action::ToggleWrap => state.toggle_wrap(),
+        Action::ToggleWhitespace => {
+            state.show_whitespace = !state.show_whitespace;
+            state.ensure_visible();
+        }
+        Action::JumpMoved => {
+            state.message = Some(state.jump_moved().unwrap_or_else(|e| e));
+        }
+        Action::AcceptWhitespace => {
+            state.message = Some(match state.accept_whitespace_only() {
+                Ok(count) => format!("accepted {} whitespace-only hunks", count),
+                Err(e) => format!("load failed: {e}"),
+            });
+        }
+        Action::ScrollLeft => state.scroll_horizontal(-HSCROLL_STEP),
+        Action::ScrollRight => state.scroll_horizontal(HSCROLL_STEP),
+        Action::ScrollHome => state.hscroll = 0,
         Action::RequestQuit => {
             state.mode = AppMode::ConfirmQuit;
         }
@@ -291,6 +689,8 @@ pub(super) fn apply_action(action: Action, state: &mut AppState) {
         Action::CancelQuit => {
             state.mode = AppMode::Normal;
         }
+        // Need the terminal; run_loop handles them before apply_action
+        Action::EditInEditor | Action::EditHunk => {}
         Action::None => {}
     }
 }
@@ -299,7 +699,7 @@ pub(super) fn apply_action(action: Action, state: &mut AppState) {
 mod tests {
     use super::*;
     use crate::config::Config;
-    use crate::model::{Diff, DiffLine, FileDiff, Hunk, ReviewStatus};
+    use crate::model::{Diff, DiffLine, FileDiff, Hunk, ReviewStatus, Severity};
     use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
     fn key(code: KeyCode) -> KeyEvent {
@@ -319,7 +719,10 @@ mod tests {
             new_count: 1,
             lines: vec![DiffLine::Context("x".to_string())],
             status,
-            comment: None,
+            comments: Vec::new(),
+            rule: None,
+            edited: false,
+            original_lines: None,
         }
     }
 
@@ -331,22 +734,22 @@ mod tests {
             raw_new_path: format!("b/{}", name),
             hunks,
             is_binary: false,
+            viewed: false,
+            deferred: None,
         }
     }
 
     fn state_normal() -> AppState {
-        let diff = Diff {
-            files: vec![
-                make_file(
-                    "a.rs",
-                    vec![
-                        make_hunk(ReviewStatus::Pending),
-                        make_hunk(ReviewStatus::Pending),
-                    ],
-                ),
-                make_file("b.rs", vec![make_hunk(ReviewStatus::Pending)]),
-            ],
-        };
+        let diff = Diff::new(vec![
+            make_file(
+                "a.rs",
+                vec![
+                    make_hunk(ReviewStatus::Pending),
+                    make_hunk(ReviewStatus::Pending),
+                ],
+            ),
+            make_file("b.rs", vec![make_hunk(ReviewStatus::Pending)]),
+        ]);
         AppState::new(diff, &Config::default())
     }
 
@@ -398,6 +801,7 @@ mod tests {
     fn test_key_u_undo() {
         let state = state_normal();
         assert_eq!(handle_key(&key(KeyCode::Char('u')), &state), Action::Undo);
+        assert_eq!(handle_key(&ctrl('r'), &state), Action::Redo);
     }
 
     #[test]
@@ -414,7 +818,10 @@ mod tests {
         let state = state_normal();
         assert_eq!(
             handle_key(&key(KeyCode::Char('g')), &state),
-            Action::EnterPendingG
+            Action::PendingKeys(KeySeq {
+                count: None,
+                prefix: Some('g'),
+            })
         );
     }
 
@@ -438,12 +845,36 @@ mod tests {
         assert_eq!(handle_key(&ctrl('d'), &state), Action::PageDown);
     }
 
-    // --- PendingG mode tests ---
+    // --- Key sequence tests ---
+
+    /// Feed keys like run_loop does, re-dispatching after CancelKeys
+    fn press(state: &mut AppState, keys: &[KeyEvent]) {
+        for k in keys {
+            let action = handle_key(k, state);
+            apply_action(action, state);
+            if action == Action::CancelKeys {
+                let action = handle_key(k, state);
+                apply_action(action, state);
+            }
+        }
+    }
+
+    fn chars(s: &str) -> Vec<KeyEvent> {
+        s.chars().map(|c| key(KeyCode::Char(c))).collect()
+    }
+
+    fn with_g_prefix() -> AppState {
+        let mut state = state_normal();
+        state.keys = KeySeq {
+            count: None,
+            prefix: Some('g'),
+        };
+        state
+    }
 
     #[test]
     fn test_pending_g_then_g() {
-        let mut state = state_normal();
-        state.mode = AppMode::PendingG;
+        let state = with_g_prefix();
         assert_eq!(
             handle_key(&key(KeyCode::Char('g')), &state),
             Action::FirstHunk
@@ -452,12 +883,165 @@ mod tests {
 
     #[test]
     fn test_pending_g_then_other() {
-        let mut state = state_normal();
-        state.mode = AppMode::PendingG;
+        let mut state = with_g_prefix();
         assert_eq!(
             handle_key(&key(KeyCode::Char('j')), &state),
-            Action::CancelPendingG
+            Action::CancelKeys
         );
+        // The key then runs on its own
+        press(&mut state, &chars("j"));
+        assert!(state.keys.is_empty());
+        assert_eq!(state.hunk_index, 1);
+    }
+
+    #[test]
+    fn test_pending_z_fold_keys() {
+        let mut state = state_normal();
+        press(&mut state, &chars("z"));
+        assert_eq!(state.keys.prefix, Some('z'));
+        for (c, action) in [
+            ('a', Action::Fold(None)),
+            ('c', Action::Fold(Some(true))),
+            ('o', Action::Fold(Some(false))),
+            ('A', Action::FoldFile),
+            ('M', Action::FoldAll),
+            ('R', Action::UnfoldAll),
+            ('j', Action::CancelKeys),
+        ] {
+            assert_eq!(handle_key(&key(KeyCode::Char(c)), &state), action);
+        }
+        press(&mut state, &chars("a"));
+        assert!(state.keys.is_empty());
+        assert!(state.is_folded(state.file_index, state.hunk_index));
+    }
+
+    fn state_four_hunks() -> AppState {
+        let diff = Diff::new(vec![
+            make_file(
+                "a.rs",
+                (0..4).map(|_| make_hunk(ReviewStatus::Pending)).collect(),
+            ),
+            make_file("b.rs", vec![make_hunk(ReviewStatus::Pending)]),
+        ]);
+        AppState::new(diff, &Config::default())
+    }
+
+    #[test]
+    fn test_counts() {
+        let mut state = state_four_hunks();
+        press(&mut state, &chars("2j"));
+        assert_eq!(state.hunk_index, 2);
+        press(&mut state, &chars("9k"));
+        assert_eq!(state.hunk_index, 0);
+
+        // 3a accepts this hunk and the next two as one undo step
+        press(&mut state, &chars("3a"));
+        let statuses: Vec<_> = state.diff.files[0].hunks.iter().map(|h| h.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ReviewStatus::Accepted,
+                ReviewStatus::Accepted,
+                ReviewStatus::Accepted,
+                ReviewStatus::Pending
+            ]
+        );
+        assert_eq!(state.hunk_index, 2);
+        press(&mut state, &chars("u"));
+        assert_eq!(state.message.as_deref(), Some("undid accept (3 hunks)"));
+        assert_eq!(state.diff.files[0].hunks[2].status, ReviewStatus::Pending);
+
+        // A count stops at the last hunk of the file
+        press(&mut state, &chars("9r"));
+        assert_eq!(state.diff.files[0].hunks[3].status, ReviewStatus::Rejected);
+        assert_eq!(state.diff.files[1].hunks[0].status, ReviewStatus::Pending);
+
+        // NG / Ngg go to the Nth hunk of the whole diff
+        press(&mut state, &chars("5G"));
+        assert_eq!((state.file_index, state.hunk_index), (1, 0));
+        press(&mut state, &chars("2gg"));
+        assert_eq!((state.file_index, state.hunk_index), (0, 1));
+
+        // Esc drops a pending count instead of quitting
+        press(&mut state, &chars("4"));
+        press(&mut state, &[key(KeyCode::Esc)]);
+        assert!(state.keys.is_empty());
+        assert_eq!(state.mode, AppMode::Normal);
+        press(&mut state, &chars("j"));
+        assert_eq!(state.hunk_index, 2);
+    }
+
+    #[test]
+    fn test_marks_and_jump_list() {
+        let mut state = state_four_hunks();
+        press(&mut state, &chars("jma"));
+        assert_eq!(state.message.as_deref(), Some("mark a: a.rs hunk 2"));
+
+        press(&mut state, &chars("G"));
+        assert_eq!(state.file_index, 1);
+        press(&mut state, &chars("'a"));
+        assert_eq!((state.file_index, state.hunk_index), (0, 1));
+
+        press(&mut state, &[ctrl('o')]);
+        assert_eq!(state.file_index, 1);
+        press(&mut state, &[ctrl('o')]);
+        assert_eq!((state.file_index, state.hunk_index), (0, 1));
+        press(&mut state, &[ctrl('i')]);
+        assert_eq!(state.file_index, 1);
+
+        // '' flips between the last two jump positions
+        press(&mut state, &chars("''"));
+        assert_eq!((state.file_index, state.hunk_index), (0, 1));
+        press(&mut state, &chars("``"));
+        assert_eq!(state.file_index, 1);
+
+        press(&mut state, &chars("'z"));
+        assert_eq!(state.message.as_deref(), Some("mark z not set"));
+        assert_eq!(state.file_index, 1);
+    }
+
+    #[test]
+    fn test_layout_keys() {
+        let mut state = state_four_hunks();
+        state.update_layout(ratatui::layout::Rect::new(0, 0, 160, 40));
+        press(&mut state, &chars("2]"));
+        assert_eq!(state.layout.tree_width, 68);
+        press(&mut state, &chars("["));
+        assert_eq!(state.layout.tree_width, 64);
+
+        press(&mut state, &chars("b"));
+        assert!(state.layout.panel);
+        state.update_layout(ratatui::layout::Rect::new(0, 0, 160, 40));
+        assert_eq!(state.viewport_height, 28);
+
+        press(&mut state, &chars("\\"));
+        state.update_layout(ratatui::layout::Rect::new(0, 0, 160, 40));
+        assert!(state.layout.areas.vertical);
+        assert_eq!(state.tree_height, 10);
+    }
+
+    #[test]
+    fn test_yank_prefix() {
+        let mut state = state_four_hunks();
+        press(&mut state, &chars("yp"));
+        assert_eq!(state.message.as_deref(), Some("copied a.rs:1 to clipboard"));
+        assert_eq!(
+            state.clipboard.take().as_deref(),
+            Some("\x1b]52;c;YS5yczox\x07")
+        );
+
+        press(&mut state, &chars("yy"));
+        assert_eq!(
+            state.message.as_deref(),
+            Some("copied hunk (4 lines) to clipboard")
+        );
+        assert!(state.clipboard.take().is_some());
+
+        // An unknown key cancels the prefix and runs on its own
+        press(&mut state, &chars("yj"));
+        assert_eq!(state.hunk_index, 1);
+        assert!(state.clipboard.is_none());
+        assert!(state.keys.is_empty());
     }
 
     // --- ConfirmQuit mode test ---
@@ -493,6 +1077,95 @@ mod tests {
         assert!(state.show_file_tree);
     }
 
+    // --- Wrap / horizontal scroll ---
+
+    #[test]
+    fn test_wrap_and_hscroll_keys() {
+        let mut state = state_normal();
+        assert_eq!(
+            handle_key(&key(KeyCode::Char('w')), &state),
+            Action::ToggleWrap
+        );
+        apply_action(Action::ScrollRight, &mut state);
+        apply_action(Action::ScrollRight, &mut state);
+        assert_eq!(state.hscroll, 2 * HSCROLL_STEP as usize);
+        apply_action(Action::ScrollLeft, &mut state);
+        assert_eq!(state.hscroll, HSCROLL_STEP as usize);
+        apply_action(Action::ScrollHome, &mut state);
+        assert_eq!(state.hscroll, 0);
+
+        // No horizontal scrolling while wrapping
+        apply_action(Action::ToggleWrap, &mut state);
+        assert!(state.wrap);
+        apply_action(Action::ScrollRight, &mut state);
+        assert_eq!(state.hscroll, 0);
+    }
+
+    #[test]
+    fn test_whitespace_keys() {
+        let mut state = state_normal();
+        assert_eq!(
+            handle_key(&key(KeyCode::Char('W')), &state),
+            Action::AcceptWhitespace
+        );
+        assert_eq!(
+            handle_key(&key(KeyCode::Char('i')), &state),
+            Action::ToggleWhitespace
+        );
+        apply_action(Action::ToggleWhitespace, &mut state);
+        assert!(state.show_whitespace);
+        apply_action(Action::AcceptWhitespace, &mut state);
+        assert_eq!(
+            state.message.as_deref(),
+            Some("accepted 0 whitespace-only hunks")
+        );
+    }
+
+    #[test]
+    fn test_key_v_toggles_viewed() {
+        let mut state = state_normal();
+        assert_eq!(
+            handle_key(&key(KeyCode::Char('v')), &state),
+            Action::ToggleViewed
+        );
+        apply_action(Action::ToggleViewed, &mut state);
+        assert!(state.diff.files[0].viewed);
+        assert_eq!(
+            state.message.as_deref(),
+            Some("viewed a.rs (1/2 files viewed)")
+        );
+        apply_action(Action::ToggleViewed, &mut state);
+        assert!(!state.diff.files[0].viewed);
+    }
+
+    #[test]
+    fn test_key_shift_e_edits() {
+        let mut state = state_normal();
+        assert_eq!(
+            handle_key(&key(KeyCode::Char('E')), &state),
+            Action::EditInEditor
+        );
+        // The terminal work happens in run_loop; apply_action leaves state alone
+        apply_action(Action::EditInEditor, &mut state);
+        assert_eq!(state.mode, AppMode::Normal);
+        assert!(state.message.is_none());
+    }
+
+    #[test]
+    fn test_key_split_and_edit_hunk() {
+        let mut state = state_normal();
+        assert_eq!(
+            handle_key(&key(KeyCode::Char('S')), &state),
+            Action::SplitHunk
+        );
+        assert_eq!(handle_key(&ctrl('e'), &state), Action::EditHunk);
+        apply_action(Action::SplitHunk, &mut state);
+        assert_eq!(
+            state.message.as_deref(),
+            Some("move the line cursor (J/K) to where the hunk should split")
+        );
+    }
+
     // --- Search mode ---
 
     #[test]
@@ -545,7 +1218,7 @@ mod tests {
         state.search_matches.push(super::super::state::SearchMatch {
             file_index: 0,
             hunk_index: 0,
-            line_index: 0,
+            line_index: Some(0),
         });
         assert_eq!(
             handle_key(&key(KeyCode::Char('n')), &state),
@@ -623,7 +1296,6 @@ mod tests {
 
     // --- Mouse support tests ---
 
-
     #[test]
     fn test_key_h_focus_file_tree() {
         let state = state_normal();
@@ -723,6 +1395,112 @@ mod tests {
         assert_eq!(state.focus, Focus::DiffView);
     }
 
+    #[test]
+    fn test_tree_filter_keys() {
+        let mut state = state_normal();
+        assert_eq!(
+            handle_key(&key(KeyCode::Char('/')), &state),
+            Action::EnterSearch
+        );
+        state.focus = Focus::FileTree;
+        assert_eq!(
+            handle_key(&key(KeyCode::Char('/')), &state),
+            Action::EnterTreeFilter
+        );
+        apply_action(Action::EnterTreeFilter, &mut state);
+        assert_eq!(state.mode, AppMode::TreeFilter);
+        assert_eq!(
+            handle_key(&key(KeyCode::Enter), &state),
+            Action::SubmitTreeFilter
+        );
+        assert_eq!(
+            handle_key(&key(KeyCode::Esc), &state),
+            Action::CancelTreeFilter
+        );
+
+        state.tree_filter = "b.rs".to_string();
+        apply_action(Action::SubmitTreeFilter, &mut state);
+        assert_eq!(state.mode, AppMode::Normal);
+        assert_eq!(state.file_index, 1);
+
+        apply_action(Action::EnterTreeFilter, &mut state);
+        apply_action(Action::CancelTreeFilter, &mut state);
+        assert!(state.tree_filter.is_empty());
+    }
+
+    #[test]
+    fn test_ctrl_p_file_finder() {
+        let mut state = state_normal();
+        assert_eq!(handle_key(&ctrl('p'), &state), Action::OpenFinder);
+        apply_action(Action::OpenFinder, &mut state);
+        assert_eq!(state.mode, AppMode::FileFinder);
+        assert_eq!(handle_key(&ctrl('n'), &state), Action::FinderNext);
+        assert_eq!(handle_key(&ctrl('p'), &state), Action::FinderPrev);
+        assert_eq!(handle_key(&key(KeyCode::Down), &state), Action::FinderNext);
+        assert_eq!(handle_key(&key(KeyCode::Enter), &state), Action::FinderJump);
+        assert_eq!(handle_key(&key(KeyCode::Esc), &state), Action::CancelFinder);
+
+        state.finder_query = "b".to_string();
+        apply_action(Action::FinderJump, &mut state);
+        assert_eq!(state.mode, AppMode::Normal);
+        assert_eq!(state.file_index, 1);
+    }
+
+    #[test]
+    fn test_command_line_keys_and_history() {
+        let mut state = state_normal();
+        assert_eq!(
+            handle_key(&key(KeyCode::Char(':')), &state),
+            Action::EnterCommand
+        );
+        apply_action(Action::EnterCommand, &mut state);
+        assert_eq!(state.mode, AppMode::Command);
+        // Backspace on an empty line leaves command mode, like vim
+        assert_eq!(
+            handle_key(&key(KeyCode::Backspace), &state),
+            Action::CancelCommand
+        );
+
+        state.command_input = "accept b.rs".to_string();
+        apply_action(Action::SubmitCommand, &mut state);
+        assert_eq!(state.mode, AppMode::Normal);
+        assert_eq!(state.diff.files[1].hunks[0].status, ReviewStatus::Accepted);
+        assert_eq!(state.diff.files[0].hunks[0].status, ReviewStatus::Pending);
+        assert_eq!(state.message.as_deref(), Some("accepted 1 hunks"));
+
+        apply_action(Action::EnterCommand, &mut state);
+        state.command_input = "bogus".to_string();
+        apply_action(Action::SubmitCommand, &mut state);
+        assert_eq!(
+            state.message.as_deref(),
+            Some("error: unknown command: bogus")
+        );
+
+        apply_action(Action::EnterCommand, &mut state);
+        apply_action(Action::CommandHistoryPrev, &mut state);
+        assert_eq!(state.command_input, "bogus");
+        apply_action(Action::CommandHistoryPrev, &mut state);
+        assert_eq!(state.command_input, "accept b.rs");
+        apply_action(Action::CommandHistoryNext, &mut state);
+        apply_action(Action::CommandHistoryNext, &mut state);
+        assert_eq!(state.command_input, "");
+
+        state.command_input = "goto b".to_string();
+        apply_action(Action::CompleteCommand, &mut state);
+        assert_eq!(state.command_input, "goto b.rs");
+    }
+
+    #[test]
+    fn test_key_o_cycles_tree_sort() {
+        let mut state = state_normal();
+        assert_eq!(
+            handle_key(&key(KeyCode::Char('o')), &state),
+            Action::CycleTreeSort
+        );
+        apply_action(Action::CycleTreeSort, &mut state);
+        assert_eq!(state.tree_sort.label(), "changes");
+    }
+
     #[test]
     fn test_toggle_file_tree_resets_focus() {
         let mut state = state_normal();
@@ -817,14 +1595,57 @@ mod tests {
         assert_eq!(state.mode, AppMode::CommentEdit);
 
         state.comment_input = "needs fix".to_string();
+        apply_action(Action::CycleSeverity, &mut state);
         apply_action(Action::SubmitComment, &mut state);
         assert_eq!(state.mode, AppMode::Normal);
+        let comment = state.current_hunk().unwrap().comments[0].clone();
+        assert_eq!(comment.text, "needs fix");
+        // Pending hunk starts as Nit, Tab cycles to Question
+        assert_eq!(comment.severity, Severity::Question);
+    }
+
+    #[test]
+    fn test_comment_mode_tab_cycles_severity() {
+        let mut state = state_normal();
+        state.mode = AppMode::CommentEdit;
         assert_eq!(
-            state.current_hunk().unwrap().comment,
-            Some("needs fix".to_string())
+            handle_key(&key(KeyCode::Tab), &state),
+            Action::CycleSeverity
         );
     }
 
+    // --- Review summary tests ---
+
+    #[test]
+    fn test_confirm_quit_m_enters_summary() {
+        let mut state = state_normal();
+        state.mode = AppMode::ConfirmQuit;
+        assert_eq!(
+            handle_key(&key(KeyCode::Char('m')), &state),
+            Action::EnterSummary
+        );
+    }
+
+    #[test]
+    fn test_summary_submit_returns_to_quit() {
+        let mut state = state_normal();
+        state.mode = AppMode::ConfirmQuit;
+        apply_action(Action::EnterSummary, &mut state);
+        assert_eq!(state.mode, AppMode::SummaryEdit);
+
+        state.summary_input = "overall fine".to_string();
+        apply_action(Action::SubmitSummary, &mut state);
+        assert_eq!(state.mode, AppMode::ConfirmQuit);
+        assert_eq!(state.diff.summary, Some("overall fine".to_string()));
+
+        // Re-entering pre-fills the existing summary
+        apply_action(Action::EnterSummary, &mut state);
+        assert_eq!(state.summary_input, "overall fine");
+        apply_action(Action::CancelSummary, &mut state);
+        assert_eq!(state.mode, AppMode::ConfirmQuit);
+        assert_eq!(state.diff.summary, Some("overall fine".to_string()));
+    }
+
     #[test]
     fn test_comment_cancel_action() {
         let mut state = state_normal();
@@ -833,6 +1654,69 @@ mod tests {
         apply_action(Action::CancelComment, &mut state);
         assert_eq!(state.mode, AppMode::Normal);
         assert!(state.comment_input.is_empty());
-        assert!(state.current_hunk().unwrap().comment.is_none());
+        assert!(state.current_hunk().unwrap().comments.is_empty());
+    }
+
+    #[test]
+    fn test_comment_mode_select_and_delete_keys() {
+        let mut state = state_normal();
+        state.mode = AppMode::CommentEdit;
+        assert_eq!(handle_key(&key(KeyCode::Down), &state), Action::NextComment);
+        assert_eq!(handle_key(&key(KeyCode::Up), &state), Action::PrevComment);
+        assert_eq!(handle_key(&ctrl('d'), &state), Action::DeleteComment);
+    }
+
+    #[test]
+    fn test_comment_edit_existing_and_delete() {
+        let mut state = state_normal();
+        apply_action(Action::EnterComment, &mut state);
+        state.comment_input = "first".to_string();
+        apply_action(Action::SubmitComment, &mut state);
+        apply_action(Action::EnterComment, &mut state);
+        state.comment_input = "second".to_string();
+        apply_action(Action::SubmitComment, &mut state);
+        assert_eq!(state.current_hunk().unwrap().comments.len(), 2);
+
+        // Select the first comment and edit it in place
+        apply_action(Action::EnterComment, &mut state);
+        apply_action(Action::NextComment, &mut state);
+        assert_eq!(state.comment_input, "first");
+        state.comment_input = "first, edited".to_string();
+        apply_action(Action::SubmitComment, &mut state);
+        let comments = &state.current_hunk().unwrap().comments;
+        assert_eq!(comments[0].text, "first, edited");
+        assert_eq!(comments.len(), 2);
+
+        // Select the second comment and delete it
+        apply_action(Action::EnterComment, &mut state);
+        apply_action(Action::PrevComment, &mut state);
+        assert_eq!(state.comment_input, "second");
+        apply_action(Action::DeleteComment, &mut state);
+        assert_eq!(state.mode, AppMode::Normal);
+        assert_eq!(state.current_hunk().unwrap().comments.len(), 1);
+    }
+
+    #[test]
+    fn test_line_cursor_keys() {
+        let mut state = state_normal();
+        assert_eq!(
+            handle_key(&key(KeyCode::Char('J')), &state),
+            Action::LineCursorDown
+        );
+        assert_eq!(
+            handle_key(&key(KeyCode::Char('K')), &state),
+            Action::LineCursorUp
+        );
+        assert_eq!(handle_key(&key(KeyCode::Esc), &state), Action::RequestQuit);
+
+        apply_action(Action::LineCursorDown, &mut state);
+        assert_eq!(state.cursor_line(), Some(0));
+        // Esc clears the cursor before it asks to quit
+        assert_eq!(
+            handle_key(&key(KeyCode::Esc), &state),
+            Action::ClearLineCursor
+        );
+        apply_action(Action::ClearLineCursor, &mut state);
+        assert_eq!(state.cursor_line(), None);
     }
 }
@@ -0,0 +1,119 @@
+//! Marks (`ma`, `'a`) and the jump list (`Ctrl-o`, `Ctrl-i`).
+//! Positions are kept by path so they survive re-diffing.
+
+/// A file and hunk in the review
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) struct Position {
+    pub(super) path: String,
+    pub(super) hunk_index: usize,
+}
+
+/// Oldest jumps are dropped beyond this
+const MAX_JUMPS: usize = 100;
+
+/// Positions left by jumps, walked back and forth like browser history
+#[derive(Clone, Debug, Default)]
+pub(super) struct JumpList {
+    entries: Vec<Position>,
+    /// Entry Ctrl-o/Ctrl-i is at; `entries.len()` when not walking the list
+    index: usize,
+}
+
+impl JumpList {
+    /// Remember where a jump started. Entries after the one walked back to are dropped.
+    pub(super) fn push(&mut self, from: Position) {
+        self.entries.truncate(self.index);
+        if self.entries.last() != Some(&from) {
+            self.entries.push(from);
+        }
+        if self.entries.len() > MAX_JUMPS {
+            self.entries.remove(0);
+        }
+        self.index = self.entries.len();
+    }
+
+    /// Older position (Ctrl-o). `current` is kept so Ctrl-i can return to it.
+    pub(super) fn back(&mut self, current: Position) -> Option<Position> {
+        if self.index == self.entries.len() {
+            if self.entries.last() != Some(&current) {
+                self.entries.push(current.clone());
+            }
+            self.index = self.entries.len() - 1;
+        }
+        while self.index > 0 {
+            self.index -= 1;
+            if self.entries[self.index] != current {
+                return Some(self.entries[self.index].clone());
+            }
+        }
+        None
+    }
+
+    /// Newer position (Ctrl-i)
+    pub(super) fn forward(&mut self, current: &Position) -> Option<Position> {
+        while self.index + 1 < self.entries.len() {
+            self.index += 1;
+            if &self.entries[self.index] != current {
+                return Some(self.entries[self.index].clone());
+            }
+        }
+        None
+    }
+
+    /// Where the latest jump away from `current` started (`''`)
+    pub(super) fn latest(&self, current: &Position) -> Option<&Position> {
+        self.entries.iter().rev().find(|pos| *pos != current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pos(path: &str, hunk_index: usize) -> Position {
+        Position {
+            path: path.to_string(),
+            hunk_index,
+        }
+    }
+
+    #[test]
+    fn test_back_and_forward() {
+        let mut jumps = JumpList::default();
+        assert_eq!(jumps.back(pos("a", 0)), None);
+
+        // a:0 -> b:1 -> c:2
+        jumps.push(pos("a", 0));
+        jumps.push(pos("b", 1));
+        assert_eq!(jumps.back(pos("c", 2)), Some(pos("b", 1)));
+        assert_eq!(jumps.back(pos("b", 1)), Some(pos("a", 0)));
+        assert_eq!(jumps.back(pos("a", 0)), None);
+        assert_eq!(jumps.forward(&pos("a", 0)), Some(pos("b", 1)));
+        assert_eq!(jumps.forward(&pos("b", 1)), Some(pos("c", 2)));
+        assert_eq!(jumps.forward(&pos("c", 2)), None);
+    }
+
+    #[test]
+    fn test_push_after_back_drops_newer() {
+        let mut jumps = JumpList::default();
+        jumps.push(pos("a", 0));
+        jumps.push(pos("b", 0));
+        assert_eq!(jumps.back(pos("c", 0)), Some(pos("b", 0)));
+        assert_eq!(jumps.back(pos("b", 0)), Some(pos("a", 0)));
+        // Jumping from a:0 again forgets b and c
+        jumps.push(pos("a", 0));
+        assert_eq!(jumps.forward(&pos("d", 0)), None);
+        assert_eq!(jumps.latest(&pos("d", 0)), Some(&pos("a", 0)));
+        assert_eq!(jumps.latest(&pos("a", 0)), None);
+    }
+
+    #[test]
+    fn test_capped() {
+        let mut jumps = JumpList::default();
+        for i in 0..MAX_JUMPS + 5 {
+            jumps.push(pos("a", i));
+        }
+        assert_eq!(jumps.entries.len(), MAX_JUMPS);
+        assert_eq!(jumps.entries[0], pos("a", 5));
+    }
+}
@@ -0,0 +1,146 @@
+//! Normal-mode key sequences: an optional count, then either a prefix key
+//! (`g`, `z`, `m`, `y`, `'`, `` ` ``) and the key it takes, or a single key.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Counts are capped so a stray run of digits cannot loop for long
+const MAX_COUNT: usize = 9999;
+
+/// Keys that wait for a second key
+const PREFIXES: &[char] = &['g', 'z', 'm', 'y', '\'', '`'];
+
+/// Keys typed so far in an unfinished sequence
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(super) struct KeySeq {
+    pub(super) count: Option<usize>,
+    pub(super) prefix: Option<char>,
+}
+
+impl KeySeq {
+    pub(super) fn is_empty(&self) -> bool {
+        self.count.is_none() && self.prefix.is_none()
+    }
+
+    /// The sequence as typed, for the status bar (`12g`)
+    pub(super) fn display(&self) -> String {
+        let mut s = self.count.map_or(String::new(), |n| n.to_string());
+        s.extend(self.prefix);
+        s
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Step {
+    /// The sequence goes on
+    Pending(KeySeq),
+    /// `key` ends the sequence
+    Complete {
+        count: Option<usize>,
+        prefix: Option<char>,
+        key: KeyEvent,
+    },
+}
+
+/// Feed one key to the sequence typed so far
+pub(super) fn step(seq: KeySeq, key: &KeyEvent) -> Step {
+    let plain = !key
+        .modifiers
+        .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+    if seq.prefix.is_none()
+        && plain
+        && let KeyCode::Char(c) = key.code
+    {
+        // A leading 0 is a key of its own (scroll home), not a count
+        if let Some(digit) = c.to_digit(10)
+            && (digit != 0 || seq.count.is_some())
+        {
+            let count = seq
+                .count
+                .unwrap_or(0)
+                .saturating_mul(10)
+                .saturating_add(digit as usize)
+                .min(MAX_COUNT);
+            return Step::Pending(KeySeq {
+                count: Some(count),
+                prefix: None,
+            });
+        }
+        if PREFIXES.contains(&c) {
+            return Step::Pending(KeySeq {
+                prefix: Some(c),
+                ..seq
+            });
+        }
+    }
+    Step::Complete {
+        count: seq.count,
+        prefix: seq.prefix,
+        key: *key,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(keys: &str) -> Step {
+        let mut seq = KeySeq::default();
+        let mut last = None;
+        for c in keys.chars() {
+            match step(seq, &KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)) {
+                Step::Pending(next) => seq = next,
+                done => last = Some(done),
+            }
+        }
+        last.unwrap_or(Step::Pending(seq))
+    }
+
+    fn complete(count: Option<usize>, prefix: Option<char>, c: char) -> Step {
+        Step::Complete {
+            count,
+            prefix,
+            key: KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE),
+        }
+    }
+
+    #[test]
+    fn test_counts_and_prefixes() {
+        assert_eq!(feed("j"), complete(None, None, 'j'));
+        assert_eq!(feed("12j"), complete(Some(12), None, 'j'));
+        assert_eq!(feed("gg"), complete(None, Some('g'), 'g'));
+        assert_eq!(feed("3gg"), complete(Some(3), Some('g'), 'g'));
+        assert_eq!(feed("za"), complete(None, Some('z'), 'a'));
+        assert_eq!(feed("ma"), complete(None, Some('m'), 'a'));
+        assert_eq!(feed("'a"), complete(None, Some('\''), 'a'));
+        // Digits after a prefix are its argument, not a count
+        assert_eq!(feed("m1"), complete(None, Some('m'), '1'));
+        assert_eq!(feed("0"), complete(None, None, '0'));
+        assert_eq!(
+            feed("10"),
+            Step::Pending(KeySeq {
+                count: Some(10),
+                prefix: None,
+            })
+        );
+        assert_eq!(feed("99999999j"), complete(Some(MAX_COUNT), None, 'j'));
+    }
+
+    #[test]
+    fn test_ctrl_keys_complete() {
+        let seq = KeySeq {
+            count: Some(2),
+            prefix: None,
+        };
+        let ctrl_o = KeyEvent::new(KeyCode::Char('o'), KeyModifiers::CONTROL);
+        assert_eq!(
+            step(seq, &ctrl_o),
+            Step::Complete {
+                count: Some(2),
+                prefix: None,
+                key: ctrl_o,
+            }
+        );
+        assert_eq!(seq.display(), "2");
+        assert!(KeySeq::default().is_empty());
+    }
+}
@@ -0,0 +1,340 @@
+//! Pane layout: where the file tree, diff view and bottom panel go and how
+//! big they are. Render and mouse hit-testing share the rects computed here.
+
+use crate::config::{Defaults, LayoutMode};
+use ratatui::layout::{Position, Rect};
+
+/// `layout = "auto"` stacks the tree above the diff below this terminal width
+const AUTO_VERTICAL_BELOW: u16 = 100;
+/// Smallest sizes, borders included
+const MIN_TREE_WIDTH: u16 = 16;
+const MIN_DIFF_WIDTH: u16 = 20;
+const MIN_HEIGHT: u16 = 3;
+/// Columns per `[`/`]` press; rows move by half as much
+const RESIZE_STEP: u16 = 4;
+/// Limits of the side-by-side split, percent of the diff pane for the old side
+const MIN_SPLIT: u16 = 20;
+const MAX_SPLIT: u16 = 80;
+
+/// Rects of the last frame
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(super) struct Areas {
+    pub(super) file_bar: Rect,
+    /// Everything between the file bar and the status bar
+    pub(super) main: Rect,
+    pub(super) tree: Option<Rect>,
+    pub(super) diff: Rect,
+    pub(super) panel: Option<Rect>,
+    pub(super) status: Rect,
+    /// Tree above the diff instead of beside it
+    pub(super) vertical: bool,
+}
+
+impl Areas {
+    /// The whole terminal
+    pub(super) fn bounds(&self) -> Rect {
+        self.file_bar.union(self.status)
+    }
+}
+
+/// Border that can be dragged to resize
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Divider {
+    /// Right (or bottom, when stacked) border of the file tree
+    Tree,
+    /// Top border of the bottom panel
+    Panel,
+    /// Middle of the side-by-side view
+    Split,
+}
+
+#[derive(Clone, Debug)]
+pub(super) struct PaneLayout {
+    pub(super) mode: LayoutMode,
+    pub(super) tree_width: u16,
+    pub(super) tree_height: u16,
+    pub(super) panel: bool,
+    pub(super) panel_height: u16,
+    /// Width of the old side in the side-by-side view, percent
+    pub(super) split: u16,
+    pub(super) areas: Areas,
+    /// Divider being dragged with the mouse
+    pub(super) dragging: Option<Divider>,
+}
+
+impl PaneLayout {
+    pub(super) fn new(defaults: &Defaults) -> Self {
+        Self {
+            mode: defaults.layout,
+            tree_width: defaults.tree_width.max(MIN_TREE_WIDTH),
+            tree_height: defaults.tree_height.max(MIN_HEIGHT),
+            panel: defaults.panel,
+            panel_height: defaults.panel_height.max(MIN_HEIGHT),
+            split: 50,
+            areas: Areas::default(),
+            dragging: None,
+        }
+    }
+
+    fn is_vertical(&self, width: u16) -> bool {
+        match self.mode {
+            LayoutMode::Auto => width < AUTO_VERTICAL_BELOW,
+            LayoutMode::Horizontal => false,
+            LayoutMode::Vertical => true,
+        }
+    }
+
+    /// Split the terminal area. Sizes are clamped so every pane keeps some
+    /// room, without changing the sizes asked for.
+    pub(super) fn split(&self, area: Rect, show_tree: bool) -> Areas {
+        let file_bar = Rect { height: 1, ..area }.intersection(area);
+        let status = Rect {
+            y: area.bottom().saturating_sub(1),
+            height: 1,
+            ..area
+        }
+        .intersection(area);
+        let main = Rect {
+            y: area.y + file_bar.height,
+            height: area.height.saturating_sub(file_bar.height + status.height),
+            ..area
+        };
+
+        let mut rest = main;
+        let panel = if self.panel {
+            let height = clamp(self.panel_height, MIN_HEIGHT, rest.height / 2);
+            rest.height -= height;
+            Some(Rect {
+                y: rest.bottom(),
+                height,
+                ..rest
+            })
+        } else {
+            None
+        };
+
+        let vertical = self.is_vertical(area.width);
+        let tree = show_tree.then(|| {
+            if vertical {
+                let height = clamp(
+                    self.tree_height,
+                    MIN_HEIGHT,
+                    rest.height.saturating_sub(MIN_HEIGHT),
+                );
+                let tree = Rect { height, ..rest };
+                rest.y += height;
+                rest.height -= height;
+                tree
+            } else {
+                let width = clamp(
+                    self.tree_width,
+                    MIN_TREE_WIDTH,
+                    rest.width.saturating_sub(MIN_DIFF_WIDTH),
+                );
+                let tree = Rect { width, ..rest };
+                rest.x += width;
+                rest.width -= width;
+                tree
+            }
+        });
+
+        Areas {
+            file_bar,
+            main,
+            tree,
+            diff: rest,
+            panel,
+            status,
+            vertical,
+        }
+    }
+
+    /// Grow (positive) or shrink the file tree by `steps` key presses
+    pub(super) fn resize_tree(&mut self, steps: i32) {
+        if self.areas.vertical {
+            let max = self.areas.main.height.saturating_sub(MIN_HEIGHT);
+            self.tree_height = resize(
+                self.tree_height,
+                steps * (RESIZE_STEP / 2) as i32,
+                MIN_HEIGHT,
+                max,
+            );
+        } else {
+            let max = self.areas.main.width.saturating_sub(MIN_DIFF_WIDTH);
+            self.tree_width = resize(
+                self.tree_width,
+                steps * RESIZE_STEP as i32,
+                MIN_TREE_WIDTH,
+                max,
+            );
+        }
+    }
+
+    pub(super) fn resize_panel(&mut self, steps: i32) {
+        let max = self.areas.main.height / 2;
+        self.panel_height = resize(
+            self.panel_height,
+            steps * (RESIZE_STEP / 2) as i32,
+            MIN_HEIGHT,
+            max,
+        );
+    }
+
+    /// Flip between side by side and stacked panes
+    pub(super) fn toggle_orientation(&mut self) -> &'static str {
+        if self.areas.vertical {
+            self.mode = LayoutMode::Horizontal;
+            "layout: tree beside diff"
+        } else {
+            self.mode = LayoutMode::Vertical;
+            "layout: tree above diff"
+        }
+    }
+
+    /// Divider under the mouse
+    pub(super) fn divider_at(&self, pos: Position) -> Option<Divider> {
+        if let Some(tree) = self.areas.tree
+            && tree.contains(pos)
+        {
+            let edge = if self.areas.vertical {
+                pos.y == tree.bottom() - 1
+            } else {
+                pos.x == tree.right() - 1
+            };
+            if edge {
+                return Some(Divider::Tree);
+            }
+        }
+        self.areas
+            .panel
+            .filter(|panel| panel.contains(pos) && pos.y == panel.y)
+            .map(|_| Divider::Panel)
+    }
+
+    /// Move a divider to the mouse position
+    pub(super) fn drag(&mut self, divider: Divider, pos: Position) {
+        let main = self.areas.main;
+        match divider {
+            Divider::Tree => {
+                let Some(tree) = self.areas.tree else {
+                    return;
+                };
+                if self.areas.vertical {
+                    let max = main.height.saturating_sub(MIN_HEIGHT);
+                    let height = pos.y.saturating_sub(tree.y) + 1;
+                    self.tree_height = clamp(height, MIN_HEIGHT, max);
+                } else {
+                    let max = main.width.saturating_sub(MIN_DIFF_WIDTH);
+                    let width = pos.x.saturating_sub(tree.x) + 1;
+                    self.tree_width = clamp(width, MIN_TREE_WIDTH, max);
+                }
+            }
+            Divider::Panel => {
+                let height = main.bottom().saturating_sub(pos.y);
+                self.panel_height = clamp(height, MIN_HEIGHT, main.height / 2);
+            }
+            Divider::Split => {}
+        }
+    }
+
+    /// Move the side-by-side split of `area` (inside the diff border) to the mouse
+    pub(super) fn drag_split(&mut self, area: Rect, pos: Position) {
+        let offset = pos.x.saturating_sub(area.x) as u32;
+        let percent = offset * 100 / (area.width.max(1) as u32);
+        self.split = clamp(percent as u16, MIN_SPLIT, MAX_SPLIT);
+    }
+
+    /// Columns of the old side when `width` is split side by side
+    pub(super) fn split_width(&self, width: u16) -> u16 {
+        (width as u32 * self.split as u32 / 100) as u16
+    }
+}
+
+/// Like `u16::clamp`, but a too-small `max` wins instead of panicking
+fn clamp(value: u16, min: u16, max: u16) -> u16 {
+    value.max(min).min(max)
+}
+
+fn resize(value: u16, delta: i32, min: u16, max: u16) -> u16 {
+    let value = (value.min(max.max(min)) as i32 + delta).clamp(0, u16::MAX as i32) as u16;
+    value.max(min).min(max.max(min))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout() -> PaneLayout {
+        PaneLayout::new(&Defaults::default())
+    }
+
+    #[test]
+    fn test_split_horizontal() {
+        let layout = layout();
+        let areas = layout.split(Rect::new(0, 0, 160, 40), true);
+        assert!(!areas.vertical);
+        assert_eq!(areas.file_bar, Rect::new(0, 0, 160, 1));
+        assert_eq!(areas.tree, Some(Rect::new(0, 1, 60, 38)));
+        assert_eq!(areas.diff, Rect::new(60, 1, 100, 38));
+        assert_eq!(areas.status, Rect::new(0, 39, 160, 1));
+        assert_eq!(areas.panel, None);
+
+        // A narrow terminal leaves the diff its minimum width
+        let areas = PaneLayout {
+            mode: LayoutMode::Horizontal,
+            ..layout.clone()
+        }
+        .split(Rect::new(0, 0, 70, 40), true);
+        assert_eq!(areas.tree.map(|r| r.width), Some(50));
+        assert_eq!(areas.diff.width, 20);
+
+        let areas = layout.split(Rect::new(0, 0, 160, 40), false);
+        assert_eq!(areas.diff, Rect::new(0, 1, 160, 38));
+    }
+
+    #[test]
+    fn test_split_vertical_with_panel() {
+        let layout = PaneLayout {
+            panel: true,
+            ..layout()
+        };
+        // auto stacks the panes in a narrow terminal
+        let areas = layout.split(Rect::new(0, 0, 80, 40), true);
+        assert!(areas.vertical);
+        assert_eq!(areas.tree, Some(Rect::new(0, 1, 80, 12)));
+        assert_eq!(areas.diff, Rect::new(0, 13, 80, 18));
+        assert_eq!(areas.panel, Some(Rect::new(0, 31, 80, 8)));
+    }
+
+    #[test]
+    fn test_resize_and_drag() {
+        let mut layout = layout();
+        layout.areas = layout.split(Rect::new(0, 0, 160, 40), true);
+        layout.resize_tree(-2);
+        assert_eq!(layout.tree_width, 52);
+        layout.resize_tree(-100);
+        assert_eq!(layout.tree_width, MIN_TREE_WIDTH);
+        layout.resize_tree(100);
+        assert_eq!(layout.tree_width, 140);
+
+        layout.tree_width = 60;
+        layout.areas = layout.split(Rect::new(0, 0, 160, 40), true);
+        let border = Position::new(59, 10);
+        assert_eq!(layout.divider_at(border), Some(Divider::Tree));
+        assert_eq!(layout.divider_at(Position::new(58, 10)), None);
+        layout.drag(Divider::Tree, Position::new(39, 10));
+        assert_eq!(layout.tree_width, 40);
+
+        layout.panel = true;
+        layout.areas = layout.split(Rect::new(0, 0, 160, 40), true);
+        let top = layout.areas.panel.unwrap().y;
+        assert_eq!(
+            layout.divider_at(Position::new(100, top)),
+            Some(Divider::Panel)
+        );
+        layout.drag(Divider::Panel, Position::new(100, 30));
+        assert_eq!(layout.panel_height, 9);
+        layout.resize_panel(1);
+        assert_eq!(layout.panel_height, 11);
+    }
+}
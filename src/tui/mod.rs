@@ -1,21 +1,37 @@
 //! TUI module
 
+mod clipboard;
+mod command;
+mod doc;
+mod editor;
+mod fuzzy;
 mod highlight;
+mod history;
 mod input;
+mod jumps;
+mod keys;
+mod layout;
+mod mouse;
 mod render;
+mod search;
 mod state;
+mod text;
+mod tree;
 
 use crate::config::Config;
+use crate::lazy::Loader;
 use crate::model::Diff;
 use anyhow::Result;
 use crossterm::{
-    event::{Event, KeyCode, KeyModifiers, MouseButton, MouseEvent, MouseEventKind},
+    event::{Event, KeyCode, KeyModifiers},
     execute, terminal,
 };
 use ratatui::Terminal;
 use ratatui::backend::CrosstermBackend;
+use ratatui::layout::Rect;
 use std::fs::OpenOptions;
-use std::time::Duration;
+use std::io::Write;
+use std::time::{Duration, Instant};
 
 use state::AppState;
 
@@ -24,22 +40,48 @@ struct CleanupGuard;
 
 impl Drop for CleanupGuard {
     fn drop(&mut self) {
-        let _ = crossterm::terminal::disable_raw_mode();
-        let _ = execute!(std::io::stderr(), terminal::LeaveAlternateScreen);
-        let _ = execute!(std::io::stderr(), crossterm::event::DisableMouseCapture);
+        let _ = leave_terminal(&mut std::io::stderr());
     }
 }
 
-/// Run the TUI and return the reviewed diff
-pub fn run(diff: Diff, config: &Config) -> Result<Diff> {
-    let mut tty_write = OpenOptions::new().write(true).open("/dev/tty")?;
-
+/// Raw mode, alternate screen and mouse capture
+fn enter_terminal(w: &mut impl Write) -> Result<()> {
     crossterm::terminal::enable_raw_mode()?;
     execute!(
-        tty_write,
+        w,
         terminal::EnterAlternateScreen,
         crossterm::event::EnableMouseCapture
     )?;
+    Ok(())
+}
+
+/// Give the terminal back (on exit, on panic, and while `$EDITOR` runs)
+fn leave_terminal(w: &mut impl Write) -> Result<()> {
+    execute!(
+        w,
+        crossterm::event::DisableMouseCapture,
+        terminal::LeaveAlternateScreen
+    )?;
+    crossterm::terminal::disable_raw_mode()?;
+    Ok(())
+}
+
+/// How long to wait for a key before taking more files from the loader
+const LOAD_POLL: Duration = Duration::from_millis(50);
+
+/// Run the TUI and return the reviewed diff.
+/// `rediff` regenerates the diff after the reviewer edits a file (git mode only).
+/// `loader` streams in the rest of the files while the first ones are reviewed
+/// (pipe mode only); files it has not delivered yet are not in the result.
+pub fn run(
+    diff: Diff,
+    config: &Config,
+    rediff: Option<&dyn Fn() -> Result<Diff>>,
+    loader: Option<&mut Loader>,
+) -> Result<Diff> {
+    let mut tty_write = OpenOptions::new().write(true).open("/dev/tty")?;
+
+    enter_terminal(&mut tty_write)?;
 
     let _guard = CleanupGuard;
 
@@ -48,36 +90,85 @@ pub fn run(diff: Diff, config: &Config) -> Result<Diff> {
     terminal.clear()?;
 
     let mut state = AppState::new(diff, config);
+    state.loading = loader.as_ref().is_some_and(|l| !l.is_done());
 
-    let result = run_loop(&mut terminal, &mut state);
+    let result = run_loop(&mut terminal, &mut state, rediff, loader);
 
-    let tty_write = terminal.backend_mut();
-    execute!(
-        tty_write,
-        crossterm::event::DisableMouseCapture,
-        terminal::LeaveAlternateScreen
-    )?;
-    crossterm::terminal::disable_raw_mode()?;
+    leave_terminal(terminal.backend_mut())?;
 
     result?;
 
     Ok(state.diff)
 }
 
+/// What changes between the frames drawn by `bench_frames`
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug)]
+pub enum BenchStep {
+    /// Scroll down one row (the document is reused)
+    Scroll,
+    /// Toggle the current hunk's status (the document is rebuilt)
+    Review,
+}
+
+/// Draw `frames` frames of `diff` on an off-screen terminal, applying `step`
+/// before each. Only for the render benchmark in `benches/`.
+#[doc(hidden)]
+pub fn bench_frames(
+    diff: Diff,
+    config: &Config,
+    size: (u16, u16),
+    step: BenchStep,
+    frames: usize,
+) -> Result<()> {
+    let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(size.0, size.1))?;
+    let mut state = AppState::new(diff, config);
+    for _ in 0..frames {
+        match step {
+            BenchStep::Scroll => state.scroll_down(1),
+            BenchStep::Review => state.toggle_current_status(),
+        }
+        terminal.draw(|f| render::render(f, &mut state))?;
+    }
+    Ok(())
+}
+
 fn run_loop(
     terminal: &mut Terminal<CrosstermBackend<std::fs::File>>,
     state: &mut AppState,
+    rediff: Option<&dyn Fn() -> Result<Diff>>,
+    mut loader: Option<&mut Loader>,
 ) -> Result<()> {
     loop {
-        // Update viewport height from terminal size
+        // A large file body stays in the spool until it is opened
+        if let Err(e) = state.load_current_file() {
+            state.message = Some(format!("load failed: {e}"));
+        }
+
+        // Size the viewports from the pane layout before scrolling into view
         let size = terminal.size()?;
-        state.viewport_height = (size.height as usize).saturating_sub(2); // file bar + status bar
+        state.update_layout(Rect::new(0, 0, size.width, size.height));
         state.ensure_visible();
 
         terminal.draw(|f| render::render(f, state))?;
 
+        // Take the files parsed since the last frame until a key arrives
+        if let Some(loader) = loader.as_deref_mut()
+            && !loader.is_done()
+            && !crossterm::event::poll(LOAD_POLL)?
+        {
+            // A read error, or malformed input in strict mode, ends the review
+            let files = loader.try_files()?;
+            state.append_files(files, loader.is_done());
+            state.add_warnings(loader.take_warnings());
+            continue;
+        }
+
         match crossterm::event::read()? {
             Event::Key(key_event) => {
+                // Command results stay on screen until the next key
+                state.message = None;
+
                 // CommentEdit mode: intercept char input before action dispatch
                 if state.mode == state::AppMode::CommentEdit
                     && let KeyCode::Char(c) = key_event.code
@@ -87,73 +178,108 @@ fn run_loop(
                     continue;
                 }
 
+                // SummaryEdit mode: intercept char input before action dispatch
+                if state.mode == state::AppMode::SummaryEdit
+                    && let KeyCode::Char(c) = key_event.code
+                    && !key_event.modifiers.contains(KeyModifiers::CONTROL)
+                {
+                    state.summary_input.push(c);
+                    continue;
+                }
+
                 // Search mode: intercept char input before action dispatch
                 if state.mode == state::AppMode::Search
                     && let KeyCode::Char(c) = key_event.code
                     && !key_event.modifiers.contains(KeyModifiers::CONTROL)
                 {
-                    state.search_query.push(c);
+                    state.search_input(c);
+                    continue;
+                }
+
+                // TreeFilter mode: intercept char input before action dispatch
+                if state.mode == state::AppMode::TreeFilter
+                    && let KeyCode::Char(c) = key_event.code
+                    && !key_event.modifiers.contains(KeyModifiers::CONTROL)
+                {
+                    state.tree_filter.push(c);
+                    continue;
+                }
+
+                // FileFinder mode: intercept char input before action dispatch
+                if state.mode == state::AppMode::FileFinder
+                    && let KeyCode::Char(c) = key_event.code
+                    && !key_event.modifiers.contains(KeyModifiers::CONTROL)
+                {
+                    state.finder_input(c);
+                    continue;
+                }
+
+                // Command mode: intercept char input before action dispatch
+                if state.mode == state::AppMode::Command
+                    && let KeyCode::Char(c) = key_event.code
+                    && !key_event.modifiers.contains(KeyModifiers::CONTROL)
+                {
+                    state.command_input.push(c);
                     continue;
                 }
 
                 let action = input::handle_key(&key_event, state);
 
-                // Smooth scroll animation for PageUp/PageDown
+                // Smooth scroll animation for PageUp/PageDown: clamp the jump
+                // once, then draw the rows in between from the cached document
                 if action == input::Action::PageUp || action == input::Action::PageDown {
+                    let from = state.viewport_offset;
                     let total = state.viewport_height / 2;
-                    for _ in 0..total {
-                        if action == input::Action::PageUp {
-                            state.scroll_up(1);
-                        } else {
-                            state.scroll_down(1);
-                        }
+                    if action == input::Action::PageUp {
+                        state.scroll_up(total);
+                    } else {
+                        state.scroll_down(total);
+                    }
+                    let to = state.viewport_offset;
+                    let steps: Vec<usize> = if to >= from {
+                        (from + 1..=to).collect()
+                    } else {
+                        (to..from).rev().collect()
+                    };
+                    for offset in steps {
+                        state.viewport_offset = offset;
                         terminal.draw(|f| render::render(f, state))?;
                         std::thread::sleep(Duration::from_millis(8));
                     }
+                } else if action == input::Action::EditInEditor {
+                    editor::edit_current_hunk(terminal, state, rediff)?;
+                } else if action == input::Action::EditHunk {
+                    editor::edit_hunk_text(terminal, state)?;
                 } else {
                     input::apply_action(action, state);
 
-                    // CancelPendingG: re-dispatch the same key in Normal mode
-                    if action == input::Action::CancelPendingG {
+                    // CancelKeys: re-dispatch the same key on its own
+                    if action == input::Action::CancelKeys {
                         let action2 = input::handle_key(&key_event, state);
                         input::apply_action(action2, state);
                     }
                 }
             }
             Event::Mouse(mouse_event) => {
-                handle_mouse(mouse_event, state);
+                mouse::handle_mouse(mouse_event, state, Instant::now());
             }
             _ => {}
         }
 
+        // Yank: send the OSC 52 sequence straight to the terminal
+        if let Some(sequence) = state.clipboard.take() {
+            let backend = terminal.backend_mut();
+            if let Err(e) = backend
+                .write_all(sequence.as_bytes())
+                .and_then(|()| backend.flush())
+            {
+                state.message = Some(format!("clipboard: {}", e));
+            }
+        }
+
         if state.should_quit {
             break;
         }
     }
     Ok(())
 }
-
-fn handle_mouse(mouse_event: MouseEvent, state: &mut AppState) {
-    match mouse_event.kind {
-        MouseEventKind::Down(MouseButton::Left) => {
-            // File tree area: columns 0..60 (with border)
-            // Row 0 = file bar, row 1 = top border, row 2+ = file entries
-            if state.show_file_tree && mouse_event.column < 60 && mouse_event.row >= 2 {
-                let tree_row = mouse_event.row - 2;
-                if let Some(file_idx) = state.row_to_file_index(tree_row) {
-                    state.file_index = file_idx;
-                    state.hunk_index = 0;
-                    state.viewport_offset = 0;
-                    state.ensure_visible();
-                }
-            }
-        }
-        MouseEventKind::ScrollUp => {
-            state.scroll_up(1);
-        }
-        MouseEventKind::ScrollDown => {
-            state.scroll_down(1);
-        }
-        _ => {}
-    }
-}
@@ -0,0 +1,364 @@
+//! Mouse handling. Clicks in the diff pane are resolved through the row map
+//! render leaves behind (`DocMap`), so they hit whatever is on screen.
+
+use super::clipboard::YankKind;
+use super::input::{self, Action};
+use super::layout::Divider;
+use super::state::{AppMode, AppState, Focus};
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::{Position, Rect};
+use std::time::{Duration, Instant};
+
+/// Two clicks on the same cell within this are a double-click
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+/// What one row of the virtual document shows
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum DocRow {
+    /// Hunk header; the `[ ]` status icon starts at column `status_col`
+    Header { hunk: usize, status_col: usize },
+    /// A diff line (every row of a wrapped line)
+    Line { hunk: usize, line: usize },
+    /// Side-by-side row: old (or context) line on the left, new line on the right
+    Pair {
+        hunk: usize,
+        left: Option<usize>,
+        right: Option<usize>,
+    },
+    /// Comment or moved-code annotation under a hunk
+    Note { hunk: usize },
+}
+
+/// Row map of the diff pane from the last frame
+#[derive(Clone, Debug, Default)]
+pub(super) struct DocMap {
+    /// One entry per visible row, top to bottom
+    pub(super) rows: Vec<DocRow>,
+    /// Inside of the diff pane's border
+    pub(super) area: Rect,
+    /// Column where the right side of the side-by-side view starts
+    pub(super) split_col: Option<u16>,
+}
+
+impl DocMap {
+    /// Row under the mouse, with its column inside the pane
+    fn hit(&self, pos: Position) -> Option<(DocRow, usize)> {
+        if !self.area.contains(pos) {
+            return None;
+        }
+        let row = self.rows.get((pos.y - self.area.y) as usize)?;
+        Some((*row, (pos.x - self.area.x) as usize))
+    }
+}
+
+/// Right-click menu, opened at `pos`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) struct ContextMenu {
+    pub(super) pos: Position,
+    pub(super) cursor: usize,
+}
+
+pub(super) const MENU_ITEMS: &[(&str, Action)] = &[
+    ("Accept", Action::Accept),
+    ("Reject", Action::Reject),
+    ("Comment", Action::EnterComment),
+    ("Split hunk here", Action::SplitHunk),
+    ("Fold / unfold", Action::Fold(None)),
+    ("Mark file viewed", Action::ToggleViewed),
+    ("Copy hunk", Action::Yank(YankKind::Patch)),
+    ("Copy path:line", Action::Yank(YankKind::Location)),
+    ("Copy as markdown", Action::Yank(YankKind::Markdown)),
+];
+
+/// Where the menu opened at `pos` is drawn, kept inside `bounds`
+pub(super) fn menu_rect(pos: Position, bounds: Rect) -> Rect {
+    let width = MENU_ITEMS
+        .iter()
+        .map(|(label, _)| label.len())
+        .max()
+        .unwrap_or(0) as u16
+        + 4;
+    let height = MENU_ITEMS.len() as u16 + 2;
+    let x = pos
+        .x
+        .min(bounds.right().saturating_sub(width))
+        .max(bounds.x);
+    let y = pos
+        .y
+        .min(bounds.bottom().saturating_sub(height))
+        .max(bounds.y);
+    Rect::new(x, y, width, height).intersection(bounds)
+}
+
+pub(super) fn handle_mouse(event: MouseEvent, state: &mut AppState, now: Instant) {
+    let pos = Position::new(event.column, event.row);
+    if state.mode == AppMode::Menu {
+        menu_mouse(event.kind, pos, state);
+        return;
+    }
+    match event.kind {
+        MouseEventKind::Down(MouseButton::Left) if state.mode == AppMode::Normal => {
+            left_click(pos, state, now);
+        }
+        MouseEventKind::Down(MouseButton::Right)
+            if state.mode == AppMode::Normal && click_diff(pos, state, false).is_some() =>
+        {
+            state.menu = Some(ContextMenu { pos, cursor: 0 });
+            state.mode = AppMode::Menu;
+        }
+        MouseEventKind::Drag(MouseButton::Left) => match state.layout.dragging {
+            Some(Divider::Split) => {
+                let area = state.doc_map.area;
+                state.layout.drag_split(area, pos);
+            }
+            Some(divider) => state.layout.drag(divider, pos),
+            None => {}
+        },
+        MouseEventKind::Up(MouseButton::Left) => state.layout.dragging = None,
+        MouseEventKind::ScrollUp => {
+            state.scroll_up(1);
+        }
+        MouseEventKind::ScrollDown => {
+            state.scroll_down(1);
+        }
+        MouseEventKind::ScrollLeft => state.scroll_horizontal(-input::HSCROLL_STEP),
+        MouseEventKind::ScrollRight => state.scroll_horizontal(input::HSCROLL_STEP),
+        _ => {}
+    }
+}
+
+fn left_click(pos: Position, state: &mut AppState, now: Instant) {
+    if let Some(divider) = state.layout.divider_at(pos) {
+        state.layout.dragging = Some(divider);
+        return;
+    }
+    if let Some(tree) = state.layout.areas.tree
+        && tree.contains(pos)
+    {
+        // Directory rows toggle collapse, file rows open the file
+        if pos.y > tree.y && pos.y + 1 < tree.bottom() {
+            state.click_tree_row(pos.y - tree.y - 1);
+        }
+        return;
+    }
+    if state.doc_map.split_col == Some(pos.x) && state.doc_map.area.contains(pos) {
+        state.layout.dragging = Some(Divider::Split);
+        return;
+    }
+    let double = state
+        .last_click
+        .is_some_and(|(at, last)| last == pos && now.duration_since(at) <= DOUBLE_CLICK);
+    match click_diff(pos, state, !double) {
+        Some(Clicked::Status) | None => state.last_click = None,
+        Some(Clicked::Row) if double => {
+            state.last_click = None;
+            input::apply_action(Action::EnterComment, state);
+        }
+        Some(Clicked::Row) => state.last_click = Some((now, pos)),
+    }
+}
+
+enum Clicked {
+    Row,
+    /// The status icon of a hunk header was toggled
+    Status,
+}
+
+/// Select the hunk (and line) under the mouse. `toggle` lets a click on the
+/// header's status icon change the review status.
+fn click_diff(pos: Position, state: &mut AppState, toggle: bool) -> Option<Clicked> {
+    let (row, col) = state.doc_map.hit(pos)?;
+    state.focus = Focus::DiffView;
+    match row {
+        DocRow::Header { hunk, status_col } => {
+            state.select_hunk(hunk, None);
+            if toggle && (status_col..status_col + 3).contains(&col) {
+                input::apply_action(Action::Toggle, state);
+                return Some(Clicked::Status);
+            }
+        }
+        DocRow::Line { hunk, line } => state.select_hunk(hunk, Some(line)),
+        DocRow::Pair { hunk, left, right } => {
+            let right_side = state.doc_map.split_col.is_some_and(|c| pos.x >= c);
+            let line = if right_side {
+                right.or(left)
+            } else {
+                left.or(right)
+            };
+            state.select_hunk(hunk, line);
+        }
+        DocRow::Note { hunk } => state.select_hunk(hunk, None),
+    }
+    Some(Clicked::Row)
+}
+
+fn menu_mouse(kind: MouseEventKind, pos: Position, state: &mut AppState) {
+    let Some(menu) = state.menu else {
+        state.mode = AppMode::Normal;
+        return;
+    };
+    let rect = menu_rect(menu.pos, state.layout.areas.bounds());
+    let item = (rect.contains(pos) && pos.y > rect.y && pos.y + 1 < rect.bottom())
+        .then(|| (pos.y - rect.y - 1) as usize)
+        .filter(|&i| i < MENU_ITEMS.len());
+    match kind {
+        MouseEventKind::Moved => {
+            if let Some(i) = item {
+                state.menu = Some(ContextMenu { cursor: i, ..menu });
+            }
+        }
+        MouseEventKind::Down(MouseButton::Left) => match item {
+            Some(i) => {
+                state.menu = Some(ContextMenu { cursor: i, ..menu });
+                input::apply_action(Action::MenuSelect, state);
+            }
+            None if !rect.contains(pos) => input::apply_action(Action::CloseMenu, state),
+            None => {}
+        },
+        MouseEventKind::Down(_) => input::apply_action(Action::CloseMenu, state),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::model::{Diff, DiffLine, FileDiff, Hunk, ReviewStatus};
+    use crossterm::event::KeyModifiers;
+
+    fn state() -> AppState {
+        let hunk = Hunk {
+            header: "@@ -1,2 +1,2 @@".to_string(),
+            old_start: 1,
+            old_count: 2,
+            new_start: 1,
+            new_count: 2,
+            lines: vec![
+                DiffLine::Context("a".to_string()),
+                DiffLine::Removed("b".to_string()),
+                DiffLine::Added("c".to_string()),
+            ],
+            status: ReviewStatus::Pending,
+            comments: Vec::new(),
+            rule: None,
+            edited: false,
+            original_lines: None,
+        };
+        let diff = Diff::new(vec![FileDiff {
+            old_path: "a.rs".to_string(),
+            new_path: "a.rs".to_string(),
+            raw_old_path: "a/a.rs".to_string(),
+            raw_new_path: "b/a.rs".to_string(),
+            hunks: vec![hunk.clone(), hunk],
+            is_binary: false,
+            viewed: false,
+            deferred: None,
+        }]);
+        let mut state = AppState::new(diff, &Config::default());
+        state.update_layout(Rect::new(0, 0, 160, 40));
+        // What render leaves for hunk 0 expanded and hunk 1 collapsed
+        state.doc_map = DocMap {
+            rows: vec![
+                DocRow::Header {
+                    hunk: 0,
+                    status_col: 19,
+                },
+                DocRow::Line { hunk: 0, line: 0 },
+                DocRow::Line { hunk: 0, line: 1 },
+                DocRow::Line { hunk: 0, line: 2 },
+                DocRow::Header {
+                    hunk: 1,
+                    status_col: 19,
+                },
+            ],
+            area: Rect::new(61, 2, 98, 36),
+            split_col: None,
+        };
+        state
+    }
+
+    fn event(kind: MouseEventKind, column: u16, row: u16) -> MouseEvent {
+        MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    fn click(state: &mut AppState, column: u16, row: u16, now: Instant) {
+        let down = MouseEventKind::Down(MouseButton::Left);
+        handle_mouse(event(down, column, row), state, now);
+    }
+
+    #[test]
+    fn test_click_selects_hunk_line_and_status() {
+        let mut state = state();
+        let now = Instant::now();
+        click(&mut state, 70, 5, now);
+        assert_eq!(state.cursor_line(), Some(2));
+
+        click(&mut state, 65, 6, now + Duration::from_secs(1));
+        assert_eq!(state.hunk_index, 1);
+        assert_eq!(state.cursor_line(), None);
+
+        // The status icon of the header toggles the hunk
+        state.hunk_index = 0;
+        click(&mut state, 61 + 20, 2, now + Duration::from_secs(2));
+        assert_eq!(state.diff.files[0].hunks[0].status, ReviewStatus::Accepted);
+        assert_eq!(state.mode, AppMode::Normal);
+    }
+
+    #[test]
+    fn test_double_click_comments() {
+        let mut state = state();
+        let now = Instant::now();
+        click(&mut state, 70, 4, now);
+        click(&mut state, 70, 4, now + Duration::from_millis(100));
+        assert_eq!(state.mode, AppMode::CommentEdit);
+        assert_eq!(state.cursor_line(), Some(1));
+    }
+
+    #[test]
+    fn test_context_menu() {
+        let mut state = state();
+        let right = MouseEventKind::Down(MouseButton::Right);
+        handle_mouse(event(right, 70, 3), &mut state, Instant::now());
+        assert_eq!(state.mode, AppMode::Menu);
+
+        // Second item: Reject
+        let rect = menu_rect(Position::new(70, 3), state.layout.areas.bounds());
+        click(&mut state, rect.x + 2, rect.y + 2, Instant::now());
+        assert_eq!(state.mode, AppMode::Normal);
+        assert_eq!(state.diff.files[0].hunks[0].status, ReviewStatus::Rejected);
+
+        handle_mouse(event(right, 70, 3), &mut state, Instant::now());
+        click(&mut state, 0, 0, Instant::now());
+        assert_eq!(state.mode, AppMode::Normal);
+        assert!(state.menu.is_none());
+    }
+
+    #[test]
+    fn test_menu_rect_stays_on_screen() {
+        let bounds = Rect::new(0, 0, 80, 24);
+        let rect = menu_rect(Position::new(79, 23), bounds);
+        assert_eq!(rect.right(), 80);
+        assert_eq!(rect.bottom(), 24);
+        assert_eq!(rect.height as usize, MENU_ITEMS.len() + 2);
+    }
+
+    #[test]
+    fn test_drag_side_by_side_split() {
+        let mut state = state();
+        state.doc_map.split_col = Some(61 + 49);
+        let now = Instant::now();
+        click(&mut state, 110, 10, now);
+        assert_eq!(state.layout.dragging, Some(Divider::Split));
+        let drag = MouseEventKind::Drag(MouseButton::Left);
+        handle_mouse(event(drag, 61 + 30, 10), &mut state, now);
+        assert_eq!(state.layout.split, 30);
+        handle_mouse(event(drag, 61, 10), &mut state, now);
+        assert_eq!(state.layout.split, 20);
+    }
+}
@@ -1,47 +1,39 @@
 //! Widget rendering
 
+use super::doc::DocKey;
 use super::highlight;
-use super::state::{AppMode, AppState, DiffViewMode, Focus};
-use crate::model::{DiffLine, FileReviewSummary, ReviewStatus};
+use super::mouse::{self, DocMap, DocRow};
+use super::state::{AppMode, AppState, Focus};
+use super::text;
+use super::tree::TreeEntry;
+use crate::model::{Comment, DiffLine, FileReviewSummary, Hunk, ReviewStatus, Severity};
+use crate::moved::MovedLine;
 use ratatui::{
     Frame,
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{Block, BorderType, Borders, Clear, Padding, Paragraph, Wrap},
 };
+use std::borrow::Cow;
 
 /// Focus highlight color (bright green)
 const FOCUS_COLOR: Color = Color::LightGreen;
 
 /// Main render function
 pub(super) fn render(frame: &mut Frame, state: &mut AppState) {
-    let vertical = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Length(1), // file bar
-            Constraint::Min(0),    // main content
-            Constraint::Length(1), // status bar
-        ])
-        .split(frame.area());
-
-    render_file_bar(frame, state, vertical[0]);
-
-    if state.show_file_tree {
-        let horizontal = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([
-                Constraint::Length(60), // file tree
-                Constraint::Min(0),     // diff view
-            ])
-            .split(vertical[1]);
-        render_file_tree(frame, state, horizontal[0]);
-        render_diff_view(frame, state, horizontal[1]);
-    } else {
-        render_diff_view(frame, state, vertical[1]);
-    }
+    state.update_layout(frame.area());
+    let areas = state.layout.areas;
 
-    render_status_bar(frame, state, vertical[2]);
+    render_file_bar(frame, state, areas.file_bar);
+    if let Some(tree) = areas.tree {
+        render_file_tree(frame, state, tree);
+    }
+    render_diff_view(frame, state, areas.diff);
+    if let Some(panel) = areas.panel {
+        render_detail_panel(frame, state, panel);
+    }
+    render_status_bar(frame, state, areas.status);
 
     if state.mode == AppMode::Help {
         render_help_overlay(frame, state);
@@ -50,10 +42,61 @@ pub(super) fn render(frame: &mut Frame, state: &mut AppState) {
     } else if state.mode == AppMode::CommentEdit {
         render_comment_overlay(frame, state);
     } else if state.mode == AppMode::ConfirmQuit {
-        render_quit_overlay(frame);
+        render_quit_overlay(frame, state);
+    } else if state.mode == AppMode::SummaryEdit {
+        render_summary_overlay(frame, state);
+    } else if state.mode == AppMode::FileFinder {
+        render_finder_overlay(frame, state);
+    } else if state.mode == AppMode::Menu {
+        render_context_menu(frame, state);
     }
 }
 
+/// Color for a comment severity
+fn severity_color(severity: Severity) -> Color {
+    match severity {
+        Severity::Blocking => Color::Red,
+        Severity::Nit => Color::Yellow,
+        Severity::Question => Color::Magenta,
+        Severity::Praise => Color::Green,
+    }
+}
+
+/// Comment line shown below a hunk header or an anchored diff line.
+/// `show_anchor` prefixes line-anchored comments with their line number
+/// (used when the hunk is collapsed and the line itself is not visible).
+fn comment_line(comment: &Comment, show_anchor: bool) -> Line<'_> {
+    let color = severity_color(comment.severity);
+    let indent = if comment.anchor.is_some() && !show_anchor {
+        "      # "
+    } else {
+        "    # "
+    };
+    let mut spans = vec![Span::raw(indent)];
+    if show_anchor && let Some(anchor) = comment.anchor {
+        let label = match (anchor.old_line, anchor.new_line) {
+            (_, Some(n)) => format!("L{}: ", n),
+            (Some(n), None) => format!("L-{}: ", n),
+            (None, None) => format!("#{}: ", anchor.line_index),
+        };
+        spans.push(Span::styled(label, Style::default().fg(Color::DarkGray)));
+    }
+    spans.extend([
+        Span::styled(
+            format!("[{}] ", comment.severity.as_str()),
+            Style::default().fg(color),
+        ),
+        Span::styled(comment.text.as_str(), Style::default().fg(Color::Yellow)),
+    ]);
+    for tag in &comment.tags {
+        spans.push(Span::styled(
+            format!(" #{}", tag),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    Line::from(spans)
+}
+
 /// File bar
 fn render_file_bar(frame: &mut Frame, state: &AppState, area: Rect) {
     let spans = if let Some(f) = state.current_file() {
@@ -61,7 +104,7 @@ fn render_file_bar(frame: &mut Frame, state: &AppState, area: Rect) {
         let file_total = state.diff.files.len();
         let added = f.lines_added();
         let removed = f.lines_removed();
-        vec![
+        let mut spans = vec![
             Span::styled(
                 format!(" {}  ", f.new_path),
                 Style::default().bg(Color::Blue).fg(Color::White),
@@ -79,7 +122,14 @@ fn render_file_bar(frame: &mut Frame, state: &AppState, area: Rect) {
                 format!("  [file {}/{}]", file_num, file_total),
                 Style::default().bg(Color::Blue).fg(Color::White),
             ),
-        ]
+        ];
+        if f.viewed {
+            spans.push(Span::styled(
+                "  [viewed]",
+                Style::default().bg(Color::Blue).fg(Color::Yellow),
+            ));
+        }
+        spans
     } else {
         vec![Span::styled(
             " (no file)",
@@ -93,33 +143,50 @@ fn render_file_bar(frame: &mut Frame, state: &AppState, area: Rect) {
 
 /// File tree sidebar
 fn render_file_tree(frame: &mut Frame, state: &AppState, area: Rect) {
-    let mut lines: Vec<Line> = Vec::new();
+    let rows = state.tree_rows();
+    let selected = state.tree_selected_row(&rows);
+    let scroll = state.tree_scroll(&rows);
+    let height = (area.height as usize).saturating_sub(2);
 
-    for (i, file) in state.diff.files.iter().enumerate() {
-        let is_current = i == state.file_index;
-        let marker = if is_current { ">" } else { " " };
-
-        // Truncate path to fit: area.width - marker(1) - stats(~10) - icon(2) - borders(2)
-        let max_path_len = (area.width as usize).saturating_sub(15);
-        let path = &file.new_path;
-        let display_path = if path.len() > max_path_len {
-            let truncated = &path[path.len() - max_path_len + 3..];
-            format!("...{}", truncated)
+    let mut lines: Vec<Line> = Vec::new();
+    for (i, row) in rows.iter().enumerate().skip(scroll).take(height) {
+        let is_selected = Some(i) == selected;
+        let marker = if is_selected { ">" } else { " " };
+        let indent = "  ".repeat(row.depth);
+
+        let all_viewed = row.stats.files > 0 && row.stats.viewed == row.stats.files;
+        let (icon, name, name_color) = match &row.entry {
+            TreeEntry::Dir { collapsed, .. } => (
+                if *collapsed { "▸ " } else { "▾ " },
+                format!("{}/", row.name),
+                Color::Blue,
+            ),
+            TreeEntry::File(_) => ("", row.name.clone(), Color::White),
+        };
+        // Viewed files (and directories with nothing left to view) are dimmed
+        let name_color = if all_viewed {
+            Color::DarkGray
         } else {
-            path.to_string()
+            name_color
         };
 
-        let added = file.lines_added();
-        let removed = file.lines_removed();
+        let added = format!(" +{}", row.stats.added);
+        let removed = format!(" -{}", row.stats.removed);
 
-        let review_icon = match file.review_summary() {
+        // Truncate name to fit: borders(2) + marker(2) + indent + icon + stats + review icon(2)
+        let reserved =
+            6 + indent.chars().count() + icon.chars().count() + added.len() + removed.len();
+        let max_name_len = (area.width as usize).saturating_sub(reserved).max(4);
+        let display_name = text::truncate_start_to_width(&name, max_name_len);
+
+        let review_icon = match row.stats.summary() {
             FileReviewSummary::AllAccepted => Span::styled(" ✓", Style::default().fg(Color::Green)),
             FileReviewSummary::HasRejected => Span::styled(" ✗", Style::default().fg(Color::Red)),
             FileReviewSummary::Partial => Span::styled(" ~", Style::default().fg(Color::Yellow)),
             FileReviewSummary::AllPending | FileReviewSummary::Empty => Span::raw("  "),
         };
 
-        let bg = if is_current {
+        let bg = if is_selected {
             Style::default().bg(Color::DarkGray)
         } else {
             Style::default()
@@ -127,9 +194,10 @@ fn render_file_tree(frame: &mut Frame, state: &AppState, area: Rect) {
 
         lines.push(Line::from(vec![
             Span::styled(format!("{} ", marker), bg.fg(Color::Yellow)),
-            Span::styled(display_path, bg.fg(Color::White)),
-            Span::styled(format!(" +{}", added), bg.fg(Color::Green)),
-            Span::styled(format!(" -{}", removed), bg.fg(Color::Red)),
+            Span::styled(format!("{}{}", indent, icon), bg.fg(Color::DarkGray)),
+            Span::styled(display_name, bg.fg(name_color)),
+            Span::styled(added, bg.fg(Color::Green)),
+            Span::styled(removed, bg.fg(Color::Red)),
             review_icon,
         ]));
     }
@@ -139,10 +207,23 @@ fn render_file_tree(frame: &mut Frame, state: &AppState, area: Rect) {
     } else {
         Style::default().fg(Color::DarkGray)
     };
+    let mut title = format!(" Files [{}] ", state.tree_sort.label());
+    if state.mode == AppMode::TreeFilter {
+        title.push_str(&format!("/{}\u{2588} ", state.tree_filter));
+    } else if !state.tree_filter.is_empty() {
+        title.push_str(&format!("/{} ", state.tree_filter));
+    }
+    let progress = state.diff.progress();
     let block = Block::default()
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
-        .title(" Files ")
+        .title(title)
+        .title_bottom(format!(
+            " {} {}/{} viewed ",
+            text::progress_bar(progress.viewed_files, progress.files, 10),
+            progress.viewed_files,
+            progress.files
+        ))
         .style(border_style);
 
     let paragraph = Paragraph::new(lines).block(block);
@@ -155,10 +236,52 @@ fn is_search_match(state: &AppState, hunk_index: usize, line_index: usize) -> bo
         return false;
     }
     let fi = state.file_index;
-    state
-        .search_matches
-        .iter()
-        .any(|m| m.file_index == fi && m.hunk_index == hunk_index && m.line_index == line_index)
+    state.search_matches.iter().any(|m| {
+        m.file_index == fi && m.hunk_index == hunk_index && m.line_index == Some(line_index)
+    })
+}
+
+/// Hunk header row: marker, header, status icon and the rule that decided it
+fn hunk_header_line<'a>(
+    hunk: &'a Hunk,
+    marker: Span<'a>,
+    header_style: Style,
+    status_icon: Span<'a>,
+    whitespace_only: bool,
+    folded: bool,
+) -> Line<'a> {
+    let mut spans = vec![
+        marker,
+        Span::styled(&hunk.header, header_style),
+        Span::raw("  "),
+        status_icon,
+    ];
+    if folded {
+        spans.push(Span::styled(
+            format!(
+                " \u{25b8} folded ({} lines, {} comments)",
+                hunk.lines.len(),
+                hunk.comments.len()
+            ),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    if whitespace_only {
+        spans.push(Span::styled(
+            " whitespace only",
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    if hunk.edited {
+        spans.push(Span::styled(" edited", Style::default().fg(Color::Yellow)));
+    }
+    if let Some(rule) = &hunk.rule {
+        spans.push(Span::styled(
+            format!(" rule: {}", rule),
+            Style::default().fg(Color::Magenta),
+        ));
+    }
+    Line::from(spans)
 }
 
 /// Check if a line is the *current* search match (for stronger highlight)
@@ -168,17 +291,59 @@ fn is_current_search_match(state: &AppState, hunk_index: usize, line_index: usiz
     {
         return m.file_index == state.file_index
             && m.hunk_index == hunk_index
-            && m.line_index == line_index;
+            && m.line_index == Some(line_index);
     }
     false
 }
 
-/// Build virtual document lines for the current file
-fn build_virtual_doc<'a>(state: &'a AppState) -> Vec<Line<'a>> {
+/// Colour of a moved line that was not edited in the move; adjacent blocks
+/// alternate shades (like git's zebra mode). Edited lines keep the diff colour.
+fn moved_color(moved: &MovedLine, is_added: bool) -> Option<Color> {
+    if moved.edited {
+        return None;
+    }
+    Some(match (is_added, moved.block % 2 == 0) {
+        (true, true) => Color::Cyan,
+        (true, false) => Color::LightBlue,
+        (false, true) => Color::Magenta,
+        (false, false) => Color::LightMagenta,
+    })
+}
+
+/// "moved from path:line" row under the first line of a moved block
+fn moved_annotation(
+    state: &AppState,
+    moved: &MovedLine,
+    is_added: bool,
+    indent: usize,
+) -> Line<'static> {
+    let direction = if is_added { "moved from" } else { "moved to" };
+    Line::from(vec![
+        Span::raw(" ".repeat(indent)),
+        Span::styled(
+            format!(
+                "\u{21b3} {} {} (M to jump)",
+                direction,
+                state.line_location(moved.counterpart)
+            ),
+            Style::default()
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::ITALIC),
+        ),
+    ])
+}
+
+/// Lines of the unified view, with what each row shows for mouse hit-testing.
+/// The line cursor and search matches are drawn later by `decorate_window`.
+fn build_virtual_doc<'a>(
+    state: &'a AppState,
+    memo: &mut highlight::Memo,
+) -> (Vec<Line<'a>>, Vec<DocRow>) {
     let mut lines = Vec::new();
+    let mut rows = Vec::new();
     let file = match state.current_file() {
         Some(f) => f,
-        None => return lines,
+        None => return (lines, rows),
     };
 
     // Get file extension for highlighting
@@ -204,134 +369,168 @@ fn build_virtual_doc<'a>(state: &'a AppState) -> Vec<Line<'a>> {
             Style::default().fg(Color::Cyan)
         };
 
-        lines.push(Line::from(vec![
+        let whitespace_only = hunk.is_whitespace_only(state.ignore_whitespace);
+        let folded = state.is_folded(state.file_index, hi);
+        lines.push(hunk_header_line(
+            hunk,
             marker,
-            Span::styled(&hunk.header, header_style),
-            Span::raw("  "),
+            header_style,
             status_icon,
-        ]));
-
-        // Show comment below header if present
-        if let Some(comment) = &hunk.comment {
-            lines.push(Line::from(vec![
-                Span::raw("    # "),
-                Span::styled(comment, Style::default().fg(Color::Yellow)),
-            ]));
+            whitespace_only,
+            folded,
+        ));
+        rows.push(header_row(hi, hunk));
+        if folded {
+            continue;
         }
 
         // Expand current hunk (or all hunks when show_full_file)
-        if is_current || state.show_full_file {
+        let expanded = state.is_expanded(hi, hunk);
+
+        // Hunk-level comments below the header; when collapsed, line comments too
+        if expanded {
+            lines.extend(hunk.hunk_comments().map(|c| comment_line(c, true)));
+        } else {
+            lines.extend(hunk.comments.iter().map(|c| comment_line(c, true)));
+        }
+        rows.resize(lines.len(), DocRow::Note { hunk: hi });
+
+        if expanded {
             let mut old_line = hunk.old_start;
             let mut new_line = hunk.new_start;
 
-            // Calculate gutter width
-            let max_line = (hunk.old_start + hunk.old_count).max(hunk.new_start + hunk.new_count);
-            let gutter_width = max_line.to_string().len();
+            let gutter_width = text::gutter_width(hunk);
 
             for (li, diff_line) in hunk.lines.iter().enumerate() {
-                let search_bg = if is_current_search_match(state, hi, li) {
-                    Some(Color::Yellow)
-                } else if is_search_match(state, hi, li) {
-                    Some(Color::Rgb(50, 50, 0))
-                } else {
-                    None
-                };
-
-                let line = match diff_line {
+                let (gutter, fg, marker, s) = match diff_line {
                     DiffLine::Context(s) => {
                         let old_str = format!("{:>w$}", old_line, w = gutter_width);
                         let new_str = format!("{:>w$}", new_line, w = gutter_width);
                         old_line += 1;
                         new_line += 1;
-                        let mut gutter_style = Style::default().fg(Color::DarkGray);
-                        let mut text_style = Style::default().fg(Color::DarkGray);
-                        if let Some(bg) = search_bg {
-                            gutter_style = gutter_style.bg(bg);
-                            text_style = text_style.bg(bg);
-                        }
-                        let mut line_spans = vec![
-                            Span::styled(format!("  {} {} ", old_str, new_str), gutter_style),
-                            Span::styled("| ", text_style),
-                        ];
-                        if state.show_highlight {
-                            line_spans.extend(highlight::highlight_line(s, ext, text_style));
-                        } else {
-                            line_spans.push(Span::styled(s.as_str(), text_style));
-                        }
-                        Line::from(line_spans)
+                        (
+                            format!("{} {} ", old_str, new_str),
+                            Color::DarkGray,
+                            "| ",
+                            s,
+                        )
                     }
                     DiffLine::Added(s) => {
                         let pad = " ".repeat(gutter_width);
                         let new_str = format!("{:>w$}", new_line, w = gutter_width);
                         new_line += 1;
-                        let mut gutter_style = Style::default().fg(Color::Green);
-                        let mut text_style = Style::default().fg(Color::Green);
-                        if let Some(bg) = search_bg {
-                            gutter_style = gutter_style.bg(bg);
-                            text_style = text_style.bg(bg);
-                        } else if is_current {
-                            gutter_style = gutter_style.bg(Color::Rgb(0, 40, 0));
-                            text_style = text_style.bg(Color::Rgb(0, 40, 0));
-                        }
-                        let mut line_spans = vec![
-                            Span::styled(format!("  {} {} ", pad, new_str), gutter_style),
-                            Span::styled("|+", text_style),
-                        ];
-                        if state.show_highlight {
-                            line_spans.extend(highlight::highlight_line(s, ext, text_style));
-                        } else {
-                            line_spans.push(Span::styled(s.as_str(), text_style));
-                        }
-                        Line::from(line_spans)
+                        (format!("{} {} ", pad, new_str), Color::Green, "|+", s)
                     }
                     DiffLine::Removed(s) => {
                         let old_str = format!("{:>w$}", old_line, w = gutter_width);
                         let pad = " ".repeat(gutter_width);
                         old_line += 1;
-                        let mut gutter_style = Style::default().fg(Color::Red);
-                        let mut text_style = Style::default().fg(Color::Red);
-                        if let Some(bg) = search_bg {
-                            gutter_style = gutter_style.bg(bg);
-                            text_style = text_style.bg(bg);
-                        } else if is_current {
-                            gutter_style = gutter_style.bg(Color::Rgb(60, 0, 0));
-                            text_style = text_style.bg(Color::Rgb(60, 0, 0));
+                        (format!("{} {} ", old_str, pad), Color::Red, "|-", s)
+                    }
+                    DiffLine::NoNewline => {
+                        lines.push(Line::from(Span::styled(
+                            "\\ No newline at end of file",
+                            Style::default().fg(Color::Yellow),
+                        )));
+                        rows.push(DocRow::Line { hunk: hi, line: li });
+                        for comment in hunk.line_comments(li) {
+                            lines.push(comment_line(comment, false));
                         }
+                        rows.resize(lines.len(), DocRow::Note { hunk: hi });
+                        continue;
+                    }
+                };
+                let moved = state.moved_line(hi, li).copied();
+                let is_added = matches!(diff_line, DiffLine::Added(_));
+                let fg = moved.map_or(fg, |m| moved_color(&m, is_added).unwrap_or(fg));
+                let mut gutter_style = Style::default().fg(fg);
+                let mut text_style = Style::default().fg(fg);
+                let current_bg = match diff_line {
+                    DiffLine::Added(_) => Some(Color::Rgb(0, 40, 0)),
+                    DiffLine::Removed(_) => Some(Color::Rgb(60, 0, 0)),
+                    _ => None,
+                };
+                if let Some(bg) = current_bg.filter(|_| is_current) {
+                    gutter_style = gutter_style.bg(bg);
+                    text_style = text_style.bg(bg);
+                }
+
+                let text = if state.show_whitespace {
+                    Cow::Borrowed(s.as_str())
+                } else {
+                    text::expand_tabs(s, state.tab_width)
+                };
+                let mut content = if state.show_highlight {
+                    memo.highlight(&text, ext, text_style)
+                } else {
+                    vec![Span::styled(text.as_ref(), text_style)]
+                };
+                if state.show_whitespace {
+                    content = text::visualize_whitespace(&content, state.tab_width);
+                }
+                let content_width = state.content_width(hunk);
+                if state.wraps() {
+                    // Continuation rows keep the gutter column blank
+                    let blank = " ".repeat(2 + gutter.len());
+                    for (row, spans) in text::wrap_spans(&content, content_width)
+                        .into_iter()
+                        .enumerate()
+                    {
+                        let lead = if row == 0 {
+                            format!("  {}", gutter)
+                        } else {
+                            blank.clone()
+                        };
                         let mut line_spans = vec![
-                            Span::styled(format!("  {} {} ", old_str, pad), gutter_style),
-                            Span::styled("|-", text_style),
+                            Span::styled(lead, gutter_style),
+                            Span::styled(marker, text_style),
                         ];
-                        if state.show_highlight {
-                            line_spans.extend(highlight::highlight_line(s, ext, text_style));
-                        } else {
-                            line_spans.push(Span::styled(s.as_str(), text_style));
-                        }
-                        Line::from(line_spans)
+                        line_spans.extend(spans);
+                        lines.push(Line::from(line_spans));
                     }
-                    DiffLine::NoNewline => Line::from(Span::styled(
-                        "\\ No newline at end of file",
-                        Style::default().fg(Color::Yellow),
-                    )),
-                };
-                lines.push(line);
+                } else {
+                    let mut line_spans = vec![
+                        Span::styled(format!("  {}", gutter), gutter_style),
+                        Span::styled(marker, text_style),
+                    ];
+                    line_spans.extend(text::slice_spans(&content, state.hscroll, content_width));
+                    lines.push(Line::from(line_spans));
+                }
+                rows.resize(lines.len(), DocRow::Line { hunk: hi, line: li });
+                if let Some(m) = moved.filter(|m| m.block_start) {
+                    lines.push(moved_annotation(state, &m, is_added, 2 + gutter.len()));
+                }
+                for comment in hunk.line_comments(li) {
+                    lines.push(comment_line(comment, false));
+                }
+                rows.resize(lines.len(), DocRow::Note { hunk: hi });
             }
         }
     }
 
-    lines
+    (lines, rows)
 }
 
-/// Helper enum for side-by-side line pairing
+/// Row map entry of a hunk header; the status icon follows the marker, the
+/// header and two spaces (see `hunk_header_line`)
+fn header_row(hi: usize, hunk: &Hunk) -> DocRow {
+    DocRow::Header {
+        hunk: hi,
+        status_col: 2 + text::display_width(&hunk.header) + 2,
+    }
+}
+
+/// Helper enum for side-by-side line pairing (each side keeps its line index)
 enum SideBySideLine<'a> {
-    Context(&'a str),
-    Changed(Option<&'a str>, Option<&'a str>),
+    Context(usize, &'a str),
+    Changed(Option<(usize, &'a str)>, Option<(usize, &'a str)>),
 }
 
 /// Flush buffered removed/added lines into side-by-side pairs
 fn flush_sbs_pairs<'a>(
     groups: &mut Vec<SideBySideLine<'a>>,
-    removed: &mut Vec<&'a str>,
-    added: &mut Vec<&'a str>,
+    removed: &mut Vec<(usize, &'a str)>,
+    added: &mut Vec<(usize, &'a str)>,
 ) {
     let max = removed.len().max(added.len());
     for i in 0..max {
@@ -344,27 +543,33 @@ fn flush_sbs_pairs<'a>(
     added.clear();
 }
 
-/// Truncate string to max width with ellipsis
-#[allow(dead_code)]
-fn truncate_str(s: &str, max_width: usize) -> String {
-    if s.len() <= max_width {
-        s.to_string()
-    } else if max_width > 3 {
-        format!("{}...", &s[..max_width - 3])
+/// One side-by-side column: `prefix` then the (tab-expanded, horizontally
+/// scrolled) content, clipped and padded to exactly `width` columns
+fn sbs_cell(state: &AppState, prefix: &str, content: &str, width: usize) -> String {
+    let content = if state.show_whitespace {
+        text::visualize_whitespace(&[Span::raw(content)], state.tab_width)
     } else {
-        s[..max_width].to_string()
-    }
+        vec![Span::raw(text::expand_tabs(content, state.tab_width))]
+    };
+    let room = width.saturating_sub(text::display_width(prefix));
+    let visible: String = text::slice_spans(&content, state.hscroll, room)
+        .iter()
+        .map(|s| s.content.as_ref())
+        .collect();
+    text::pad_to_width(&format!("{}{}", prefix, visible), width)
 }
 
-/// Render side-by-side diff view
-fn render_side_by_side(frame: &mut Frame, state: &AppState, area: Rect) {
+/// Lines of the side-by-side view, the old side `left_width` columns wide,
+/// with the row map. The line cursor is drawn later by `decorate_window`.
+fn build_side_by_side(state: &AppState, left_width: usize) -> (Vec<Line<'_>>, Vec<DocRow>) {
+    let mut rows = Vec::new();
+    let mut all_lines: Vec<Line> = Vec::new();
     let file = match state.current_file() {
         Some(f) => f,
-        None => return,
+        None => return (all_lines, rows),
     };
 
-    let half_width = area.width / 2;
-    let mut all_lines: Vec<Line> = Vec::new();
+    let right_width = state.viewport_width.saturating_sub(left_width);
 
     for (hi, hunk) in file.hunks.iter().enumerate() {
         let is_current = hi == state.hunk_index;
@@ -385,126 +590,151 @@ fn render_side_by_side(frame: &mut Frame, state: &AppState, area: Rect) {
         } else {
             Style::default().fg(Color::Cyan)
         };
-        all_lines.push(Line::from(vec![
+        let whitespace_only = hunk.is_whitespace_only(state.ignore_whitespace);
+        let folded = state.is_folded(state.file_index, hi);
+        all_lines.push(hunk_header_line(
+            hunk,
             marker,
-            Span::styled(&hunk.header, header_style),
-            Span::raw("  "),
+            header_style,
             status_icon,
-        ]));
-
-        // Show comment below header if present
-        if let Some(comment) = &hunk.comment {
-            all_lines.push(Line::from(vec![
-                Span::raw("    # "),
-                Span::styled(comment, Style::default().fg(Color::Yellow)),
-            ]));
+            whitespace_only,
+            folded,
+        ));
+        rows.push(header_row(hi, hunk));
+        if folded {
+            continue;
         }
 
         // Expand current hunk in side-by-side (or all when show_full_file)
-        if is_current || state.show_full_file {
+        let expanded = state.is_expanded(hi, hunk);
+
+        // Hunk-level comments below the header; when collapsed, line comments too
+        if expanded {
+            all_lines.extend(hunk.hunk_comments().map(|c| comment_line(c, true)));
+        } else {
+            all_lines.extend(hunk.comments.iter().map(|c| comment_line(c, true)));
+        }
+        rows.resize(all_lines.len(), DocRow::Note { hunk: hi });
+
+        if expanded {
             let mut old_line_num = hunk.old_start;
             let mut new_line_num = hunk.new_start;
 
             // Pair lines
-            let mut removed_buf: Vec<&str> = Vec::new();
-            let mut added_buf: Vec<&str> = Vec::new();
+            let mut removed_buf: Vec<(usize, &str)> = Vec::new();
+            let mut added_buf: Vec<(usize, &str)> = Vec::new();
             let mut line_groups: Vec<SideBySideLine> = Vec::new();
+            let mut no_newline = Vec::new();
 
             // Process hunk lines into groups
-            for diff_line in &hunk.lines {
+            for (li, diff_line) in hunk.lines.iter().enumerate() {
                 match diff_line {
                     DiffLine::Context(s) => {
                         // Flush pending
                         flush_sbs_pairs(&mut line_groups, &mut removed_buf, &mut added_buf);
-                        line_groups.push(SideBySideLine::Context(s.as_str()));
+                        line_groups.push(SideBySideLine::Context(li, s.as_str()));
                     }
                     DiffLine::Removed(s) => {
-                        removed_buf.push(s.as_str());
+                        removed_buf.push((li, s.as_str()));
                     }
                     DiffLine::Added(s) => {
-                        added_buf.push(s.as_str());
+                        added_buf.push((li, s.as_str()));
                     }
-                    DiffLine::NoNewline => {}
+                    DiffLine::NoNewline => no_newline.push(li),
                 }
             }
             flush_sbs_pairs(&mut line_groups, &mut removed_buf, &mut added_buf);
 
-            // Render each paired line
+            // Render each paired line, followed by comments anchored to either side
             for sbs_line in &line_groups {
+                let (left_li, right_li) = match sbs_line {
+                    SideBySideLine::Context(li, _) => (Some(*li), None),
+                    SideBySideLine::Changed(l, r) => (l.map(|(li, _)| li), r.map(|(li, _)| li)),
+                };
                 match sbs_line {
-                    SideBySideLine::Context(s) => {
-                        let left = format!("{:>4} │ {}", old_line_num, s);
-                        let right = format!("{:>4} │ {}", new_line_num, s);
+                    SideBySideLine::Context(_, s) => {
+                        let left = format!(" {:>4} │ ", old_line_num);
+                        let right = format!(" {:>4} │ ", new_line_num);
                         old_line_num += 1;
                         new_line_num += 1;
 
-                        let left_truncated = &left;
-                        let right_truncated = &right;
-
                         all_lines.push(Line::from(vec![
                             Span::styled(
-                                format!("{:<w$}", left_truncated, w = half_width as usize),
+                                sbs_cell(state, &left, s, left_width),
                                 Style::default().fg(Color::DarkGray),
                             ),
                             Span::styled(
-                                format!("{:<w$}", right_truncated, w = half_width as usize),
+                                sbs_cell(state, &right, s, right_width),
                                 Style::default().fg(Color::DarkGray),
                             ),
                         ]));
                     }
                     SideBySideLine::Changed(left_opt, right_opt) => {
-                        let left_str = if let Some(s) = left_opt {
-                            let num = format!("{:>4} │-{}", old_line_num, s);
+                        let left_str = if let Some((_, s)) = left_opt {
+                            let prefix = format!(" {:>4} │-", old_line_num);
                             old_line_num += 1;
-                            num
+                            sbs_cell(state, &prefix, s, left_width)
                         } else {
-                            "     │".to_string()
+                            sbs_cell(state, "      │", "", left_width)
                         };
-                        let right_str = if let Some(s) = right_opt {
-                            let num = format!("{:>4} │+{}", new_line_num, s);
+                        let right_str = if let Some((_, s)) = right_opt {
+                            let prefix = format!(" {:>4} │+", new_line_num);
                             new_line_num += 1;
-                            num
+                            sbs_cell(state, &prefix, s, right_width)
                         } else {
-                            "     │".to_string()
+                            sbs_cell(state, "      │", "", right_width)
                         };
 
-                        let left_truncated = &left_str;
-                        let right_truncated = &right_str;
-
+                        let side_fg = |li: Option<usize>, is_added: bool, fg: Color| {
+                            li.and_then(|li| state.moved_line(hi, li))
+                                .and_then(|m| moved_color(m, is_added))
+                                .unwrap_or(fg)
+                        };
+                        let left_fg = side_fg(left_li, false, Color::Red);
+                        let right_fg = side_fg(right_li, true, Color::Green);
                         let left_style = if is_current {
-                            Style::default().fg(Color::Red).bg(Color::Rgb(60, 0, 0))
+                            Style::default().fg(left_fg).bg(Color::Rgb(60, 0, 0))
                         } else {
-                            Style::default().fg(Color::Red)
+                            Style::default().fg(left_fg)
                         };
                         let right_style = if is_current {
-                            Style::default().fg(Color::Green).bg(Color::Rgb(0, 40, 0))
+                            Style::default().fg(right_fg).bg(Color::Rgb(0, 40, 0))
                         } else {
-                            Style::default().fg(Color::Green)
+                            Style::default().fg(right_fg)
                         };
 
                         all_lines.push(Line::from(vec![
-                            Span::styled(
-                                format!("{:<w$}", left_truncated, w = half_width as usize),
-                                left_style,
-                            ),
-                            Span::styled(
-                                format!("{:<w$}", right_truncated, w = half_width as usize),
-                                right_style,
-                            ),
+                            Span::styled(left_str, left_style),
+                            Span::styled(right_str, right_style),
                         ]));
                     }
                 }
+                rows.push(DocRow::Pair {
+                    hunk: hi,
+                    left: left_li,
+                    right: right_li,
+                });
+                for (li, is_added) in [(left_li, false), (right_li, true)] {
+                    if let Some(m) = li
+                        .and_then(|li| state.moved_line(hi, li))
+                        .filter(|m| m.block_start)
+                    {
+                        all_lines.push(moved_annotation(state, m, is_added, 6));
+                    }
+                }
+                for li in [left_li, right_li].into_iter().flatten() {
+                    all_lines.extend(hunk.line_comments(li).map(|c| comment_line(c, false)));
+                }
+                rows.resize(all_lines.len(), DocRow::Note { hunk: hi });
+            }
+            for li in no_newline {
+                all_lines.extend(hunk.line_comments(li).map(|c| comment_line(c, false)));
             }
+            rows.resize(all_lines.len(), DocRow::Note { hunk: hi });
         }
     }
 
-    // Apply viewport
-    let start = state.viewport_offset.min(all_lines.len());
-    let end = (start + area.height as usize).min(all_lines.len());
-    let visible: Vec<Line> = all_lines[start..end].to_vec();
-
-    let paragraph = Paragraph::new(visible).wrap(Wrap { trim: false });
-    frame.render_widget(paragraph, area);
+    (all_lines, rows)
 }
 
 /// Diff view with viewport scrolling
@@ -522,29 +752,141 @@ fn render_diff_view(frame: &mut Frame, state: &mut AppState, area: Rect) {
 
     let inner = diff_block.inner(area);
     state.viewport_height = inner.height as usize;
+    state.viewport_width = inner.width as usize;
     frame.render_widget(diff_block, area);
 
-    // Fall back to unified if terminal too narrow
-    let use_side_by_side = state.diff_view_mode == DiffViewMode::SideBySide && inner.width >= 100;
+    // Rebuild the document only when what it shows changed (the key falls
+    // back to unified when the pane is too narrow for side by side)
+    let key = DocKey::new(state);
+    let mut doc = std::mem::take(&mut state.doc);
+    if !doc.is_fresh(&key) {
+        let (lines, rows) = match key.split {
+            Some(left_width) => build_side_by_side(state, left_width),
+            None => build_virtual_doc(state, &mut doc.memo),
+        };
+        doc.store(key.clone(), lines, rows);
+    }
+
+    let window = doc.window(state.viewport_offset, inner.height as usize);
+    let mut visible = doc.lines[window.clone()].to_vec();
+    decorate_window(state, &mut visible, &doc.rows, window.start);
+    frame.render_widget(Paragraph::new(visible), inner);
 
-    if use_side_by_side {
-        render_side_by_side(frame, state, inner);
-    } else {
-        let all_lines = build_virtual_doc(state);
+    state.doc_map = DocMap {
+        rows: doc.rows[window].to_vec(),
+        area: inner,
+        // The old side gets `layout.split` percent; the mouse can drag it
+        split_col: key.split.map(|left| inner.x + left as u16),
+    };
+    state.doc = doc;
+}
 
-        // Slice to viewport
-        let start = state.viewport_offset.min(all_lines.len());
-        let end = (start + inner.height as usize).min(all_lines.len());
-        let visible: Vec<Line> = all_lines[start..end].to_vec();
+/// Draw the line cursor and search matches on the visible `lines`, which
+/// start at row `start` of the document
+fn decorate_window(state: &AppState, lines: &mut [Line<'_>], rows: &[DocRow], start: usize) {
+    let Some(file) = state.current_file() else {
+        return;
+    };
+    let cursor = state.cursor_line();
+    for (i, line) in lines.iter_mut().enumerate() {
+        let row = start + i;
+        match rows[row] {
+            DocRow::Line { hunk, line: li } => {
+                if !matches!(
+                    file.hunks.get(hunk).and_then(|h| h.lines.get(li)),
+                    Some(DiffLine::Context(_) | DiffLine::Added(_) | DiffLine::Removed(_))
+                ) {
+                    continue;
+                }
+                // Only the first row of a wrapped line carries the cursor
+                let first = row == 0 || rows[row - 1] != rows[row];
+                if first && hunk == state.hunk_index && cursor == Some(li) {
+                    set_cursor_mark(line, 0);
+                }
+                let search_bg = if is_current_search_match(state, hunk, li) {
+                    Some(Color::Yellow)
+                } else if is_search_match(state, hunk, li) {
+                    Some(Color::Rgb(50, 50, 0))
+                } else {
+                    None
+                };
+                if let Some(bg) = search_bg {
+                    for span in &mut line.spans {
+                        span.style = span.style.bg(bg);
+                    }
+                }
+            }
+            DocRow::Pair { hunk, left, right } if hunk == state.hunk_index => {
+                for (span, li) in [(0, left), (1, right)] {
+                    if li.is_some() && li == cursor {
+                        set_cursor_mark(line, span);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
 
-        let paragraph = Paragraph::new(visible).wrap(Wrap { trim: false });
-        frame.render_widget(paragraph, inner);
+/// Put the cursor mark over the blank first cell of span `index`
+fn set_cursor_mark(line: &mut Line<'_>, index: usize) {
+    if let Some(span) = line.spans.get_mut(index)
+        && let Some(rest) = span.content.strip_prefix(' ')
+    {
+        span.content = format!("\u{25b8}{}", rest).into();
     }
 }
 
+/// Bottom panel: where the current hunk is, its review state and all its comments
+fn render_detail_panel(frame: &mut Frame, state: &AppState, area: Rect) {
+    let mut lines = Vec::new();
+    if let (Some(file), Some(hunk)) = (state.current_file(), state.current_hunk()) {
+        let status_icon = match hunk.status {
+            ReviewStatus::Pending => Span::styled("[ ]", Style::default().fg(Color::DarkGray)),
+            ReviewStatus::Accepted => Span::styled("[✓]", Style::default().fg(Color::Green)),
+            ReviewStatus::Rejected => Span::styled("[✗]", Style::default().fg(Color::Red)),
+        };
+        let mut header = vec![
+            status_icon,
+            Span::styled(
+                format!(" {}", file.path()),
+                Style::default().fg(Color::Cyan),
+            ),
+            Span::raw(format!(
+                "  hunk {}/{}  ",
+                state.hunk_index + 1,
+                file.hunks.len()
+            )),
+            Span::styled(hunk.header.as_str(), Style::default().fg(Color::DarkGray)),
+        ];
+        if let Some(rule) = &hunk.rule {
+            header.push(Span::styled(
+                format!("  rule: {}", rule),
+                Style::default().fg(Color::Magenta),
+            ));
+        }
+        lines.push(Line::from(header));
+        if hunk.comments.is_empty() {
+            lines.push(Line::styled(
+                "    no comments (c: add)",
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+        lines.extend(hunk.comments.iter().map(|c| comment_line(c, true)));
+    }
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title(" Details ")
+        .border_style(Style::default().fg(Color::DarkGray));
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
 /// Status bar
 fn render_status_bar(frame: &mut Frame, state: &AppState, area: Rect) {
     let text = match state.mode {
+        AppMode::Menu => " menu: j/k move  Enter run  Esc close".to_string(),
         AppMode::ConfirmQuit => {
             let total = state.total_hunks();
             let current = state.flat_hunk_index() + 1;
@@ -564,9 +906,38 @@ fn render_status_bar(frame: &mut Frame, state: &AppState, area: Rect) {
             )
         }
         AppMode::Search => {
+            let count = if let Some(err) = &state.search_error {
+                format!("[{}]", err)
+            } else if state.search_query.is_empty() {
+                String::new()
+            } else {
+                let idx = state.search_index.map_or(0, |i| i + 1);
+                format!(
+                    "[{}/{} in {}]",
+                    idx,
+                    state.search_matches.len(),
+                    state.search_scope.label()
+                )
+            };
             format!(
-                " /{}\u{2588}                              (Enter: search, Esc: cancel)",
-                state.search_query
+                " /{}\u{2588}  {}      (Enter: keep, Esc: cancel, scopes: +: -: f: c:)",
+                state.search_query, count
+            )
+        }
+        AppMode::Command => {
+            let hint = state
+                .message
+                .as_deref()
+                .map_or(String::new(), |m| format!("    {}", m));
+            format!(" :{}\u{2588}{}", state.command_input, hint)
+        }
+        _ if state.message.is_some() => {
+            format!(" {}", state.message.as_deref().unwrap_or_default())
+        }
+        AppMode::TreeFilter => {
+            format!(
+                " filter files: {}\u{2588}                    (Enter: keep, Esc: clear)",
+                state.tree_filter
             )
         }
         AppMode::CommentEdit => {
@@ -588,14 +959,19 @@ fn render_status_bar(frame: &mut Frame, state: &AppState, area: Rect) {
                 rejected,
             )
         }
-        AppMode::PendingG => {
+        AppMode::Normal if state.keys.prefix == Some('y') => {
+            " yank: y hunk patch  p path:line  f path  m markdown with comments      (Esc: cancel)"
+                .to_string()
+        }
+        AppMode::Normal if !state.keys.is_empty() => {
+            let prefix = state.keys.display();
             let total = state.total_hunks();
             let current = state.flat_hunk_index() + 1;
             let reviewed = state.reviewed_hunks();
             let accepted = state.accepted_hunks();
             let rejected = reviewed - accepted;
             format!(
-                " file {}/{} | hunk {}/{} | reviewed: {}/{} [accepted: {}  rejected: {}] | g-",
+                " file {}/{} | hunk {}/{} | reviewed: {}/{} [accepted: {}  rejected: {}] | {}-",
                 state.file_index + 1,
                 state.diff.files.len(),
                 current,
@@ -604,6 +980,7 @@ fn render_status_bar(frame: &mut Frame, state: &AppState, area: Rect) {
                 total,
                 accepted,
                 rejected,
+                prefix,
             )
         }
         _ => {
@@ -614,20 +991,43 @@ fn render_status_bar(frame: &mut Frame, state: &AppState, area: Rect) {
             let rejected = reviewed - accepted;
             let search_hint = if state.has_active_search() {
                 let idx = state.search_index.map_or(0, |i| i + 1);
-                format!(" | [{}/{}] n/N:match", idx, state.search_matches.len())
+                format!(
+                    " | [{}/{} {}] n/N:match",
+                    idx,
+                    state.search_matches.len(),
+                    state.search_scope.label()
+                )
             } else {
                 String::new()
             };
+            let progress = state.diff.progress();
+            // More files are still being read from the pipe
+            let loading = if state.loading {
+                " loading\u{2026}"
+            } else {
+                ""
+            };
+            let warnings = match state.diff.warnings.len() {
+                0 => String::new(),
+                n => format!(" | \u{26a0} {} parse warnings", n),
+            };
             format!(
-                " file {}/{} | hunk {}/{} | reviewed: {}/{} [accepted: {}  rejected: {}]{} | move:\u{2190}\u{2191}\u{2193}\u{2192}/hjkl | accept:a reject:r comment:c toggle:space | ?:help q:quit",
+                " file {}/{}{}{} | hunk {}/{} | reviewed: {}/{} [accepted: {}  rejected: {}] {} lines {}/{} | viewed {}/{}{} | move:\u{2190}\u{2191}\u{2193}\u{2192}/hjkl | accept:a reject:r comment:c toggle:space | ?:help q:quit",
                 state.file_index + 1,
                 state.diff.files.len(),
+                loading,
+                warnings,
                 current,
                 total,
                 reviewed,
                 total,
                 accepted,
                 rejected,
+                text::progress_bar(progress.reviewed_lines, progress.lines, 10),
+                progress.reviewed_lines,
+                progress.lines,
+                progress.viewed_files,
+                progress.files,
                 search_hint,
             )
         }
@@ -745,6 +1145,34 @@ fn render_stats_overlay(frame: &mut Frame, state: &AppState) {
         ),
     ]));
 
+    let mut comment_spans = vec![Span::styled(
+        " Comments: ",
+        Style::default().fg(Color::White),
+    )];
+    for severity in [
+        Severity::Blocking,
+        Severity::Nit,
+        Severity::Question,
+        Severity::Praise,
+    ] {
+        comment_spans.push(Span::styled(
+            format!(
+                "{} {}  ",
+                state.diff.comment_count(severity),
+                severity.as_str()
+            ),
+            Style::default().fg(severity_color(severity)),
+        ));
+    }
+    lines.push(Line::from(comment_spans));
+
+    if let Some(summary) = &state.diff.summary {
+        lines.push(Line::from(vec![
+            Span::styled(" Summary: ", Style::default().fg(Color::White)),
+            Span::styled(summary.as_str(), Style::default().fg(Color::Yellow)),
+        ]));
+    }
+
     lines.push(Line::from(""));
     lines.push(Line::from(Span::styled(
         " j/k:navigate Enter:go s/Esc:close",
@@ -774,9 +1202,43 @@ fn render_comment_overlay(frame: &mut Frame, state: &AppState) {
         .map(|h| h.header.as_str())
         .unwrap_or("");
 
+    let target = match state
+        .cursor_line()
+        .and_then(|li| state.current_hunk().and_then(|h| h.anchor_at(li)))
+    {
+        Some(a) => match (a.old_line, a.new_line) {
+            (_, Some(n)) => format!("line {}", n),
+            (Some(n), None) => format!("old line {}", n),
+            (None, None) => "hunk".to_string(),
+        },
+        None => "hunk".to_string(),
+    };
+    let existing = state.comments_at_cursor();
+    let editing = match state.comment_edit_index {
+        Some(ci) => format!(
+            "editing {}/{}",
+            existing.iter().position(|&i| i == ci).map_or(0, |p| p + 1),
+            existing.len()
+        ),
+        None if existing.is_empty() => "new".to_string(),
+        None => format!("new ({} existing, Up/Down to select)", existing.len()),
+    };
+
+    let severity = state.comment_severity;
     let lines = vec![
         Line::from(Span::styled(hunk_info, Style::default().fg(Color::Cyan))),
-        Line::from(""),
+        Line::from(Span::styled(
+            format!("on {} - {}", target, editing),
+            Style::default().fg(Color::DarkGray),
+        )),
+        Line::from(vec![
+            Span::styled("severity: ", Style::default().fg(Color::DarkGray)),
+            Span::styled(
+                severity.as_str(),
+                Style::default().fg(severity_color(severity)),
+            ),
+            Span::styled("  (#word adds a tag)", Style::default().fg(Color::DarkGray)),
+        ]),
         Line::from(vec![
             Span::styled("> ", Style::default().fg(Color::Yellow)),
             Span::raw(state.comment_input.as_str()),
@@ -788,7 +1250,9 @@ fn render_comment_overlay(frame: &mut Frame, state: &AppState) {
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
         .title(" Comment ")
-        .title_bottom(Line::from(" Enter: save  Esc: cancel ").right_aligned())
+        .title_bottom(
+            Line::from(" Tab: severity  ^D: delete  Enter: save  Esc: cancel ").right_aligned(),
+        )
         .style(Style::default().fg(FOCUS_COLOR));
 
     let paragraph = Paragraph::new(lines)
@@ -805,23 +1269,35 @@ fn centered_fixed_rect(width: u16, height: u16, r: Rect) -> Rect {
 }
 
 /// Quit confirmation overlay
-fn render_quit_overlay(frame: &mut Frame) {
-    let area = centered_fixed_rect(44, 7, frame.area());
+fn render_quit_overlay(frame: &mut Frame, state: &AppState) {
+    let area = centered_fixed_rect(44, 8, frame.area());
     frame.render_widget(Clear, area);
 
+    let summary_line = match &state.diff.summary {
+        Some(summary) => Line::from(vec![
+            Span::styled("Summary: ", Style::default().fg(Color::DarkGray)),
+            Span::raw(summary.as_str()),
+        ]),
+        None => Line::from(Span::styled(
+            "m: add a review summary",
+            Style::default().fg(Color::DarkGray),
+        )),
+    };
+
     let lines = vec![
         Line::from(""),
         Line::from(Span::styled(
             "Unsaved review will be lost.",
             Style::default().fg(Color::Yellow),
         )),
+        summary_line,
     ];
 
     let block = Block::default()
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
         .title(" Quit? ")
-        .title_bottom(Line::from(" Enter: yes  Esc: no ").right_aligned())
+        .title_bottom(Line::from(" Enter: yes  Esc: no  m: summary ").right_aligned())
         .padding(Padding::new(2, 2, 0, 0))
         .style(Style::default().fg(FOCUS_COLOR));
 
@@ -831,9 +1307,176 @@ fn render_quit_overlay(frame: &mut Frame) {
     frame.render_widget(paragraph, area);
 }
 
+/// Review summary edit overlay
+fn render_summary_overlay(frame: &mut Frame, state: &AppState) {
+    let area = centered_rect(50, 20, frame.area());
+    frame.render_widget(Clear, area);
+
+    let lines = vec![
+        Line::from(Span::styled(
+            "Message for the whole review",
+            Style::default().fg(Color::Cyan),
+        )),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("> ", Style::default().fg(Color::Yellow)),
+            Span::raw(state.summary_input.as_str()),
+            Span::styled("\u{2588}", Style::default().fg(Color::White)),
+        ]),
+    ];
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title(" Review Summary ")
+        .title_bottom(Line::from(" Enter: save  Esc: cancel ").right_aligned())
+        .style(Style::default().fg(FOCUS_COLOR));
+
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false });
+    frame.render_widget(paragraph, area);
+}
+
+/// File finder overlay: query and ranked matches on the left, preview on the right
+fn render_finder_overlay(frame: &mut Frame, state: &AppState) {
+    let area = centered_rect(80, 70, frame.area());
+    frame.render_widget(Clear, area);
+
+    let panes = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
+        .split(area);
+
+    let results = state.finder_results();
+    let list_height = (panes[0].height as usize).saturating_sub(4); // borders + query + blank
+    let scroll = state
+        .finder_cursor
+        .saturating_sub(list_height.saturating_sub(1));
+
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("> ", Style::default().fg(Color::Yellow)),
+            Span::raw(state.finder_query.as_str()),
+            Span::styled("\u{2588}", Style::default().fg(Color::White)),
+        ]),
+        Line::from(""),
+    ];
+    for (i, (fi, m)) in results.iter().enumerate().skip(scroll).take(list_height) {
+        let file = &state.diff.files[*fi];
+        let is_cursor = i == state.finder_cursor;
+        let base = if is_cursor {
+            Style::default().bg(Color::DarkGray)
+        } else {
+            Style::default()
+        };
+        let mut spans = vec![Span::styled(
+            if is_cursor { "> " } else { "  " },
+            base.fg(Color::Yellow),
+        )];
+        // Matched characters are highlighted
+        for (ci, c) in file.path().chars().enumerate() {
+            let style = if m.positions.contains(&ci) {
+                base.fg(Color::Cyan).add_modifier(Modifier::BOLD)
+            } else {
+                base.fg(Color::White)
+            };
+            spans.push(Span::styled(c.to_string(), style));
+        }
+        spans.push(Span::styled(
+            format!("  +{} -{}", file.lines_added(), file.lines_removed()),
+            base.fg(Color::DarkGray),
+        ));
+        lines.push(Line::from(spans));
+    }
+    if results.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "  no matching files",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+
+    let list_block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title(format!(
+            " Go to File ({}/{}) ",
+            results.len(),
+            state.diff.files.len()
+        ))
+        .title_bottom(Line::from(" Enter: open  Esc: cancel ").right_aligned())
+        .style(Style::default().fg(FOCUS_COLOR));
+    frame.render_widget(Paragraph::new(lines).block(list_block), panes[0]);
+
+    // Live preview of the selected file's hunks
+    let mut preview: Vec<Line> = Vec::new();
+    let mut title = " Preview ".to_string();
+    if let Some(file) = state.finder_selected().map(|fi| &state.diff.files[fi]) {
+        title = format!(" {} ", file.path());
+        for hunk in &file.hunks {
+            preview.push(Line::from(Span::styled(
+                hunk.header.as_str(),
+                Style::default().fg(Color::Cyan),
+            )));
+            for line in &hunk.lines {
+                preview.push(match line {
+                    DiffLine::Added(s) => Line::from(Span::styled(
+                        format!("+{}", s),
+                        Style::default().fg(Color::Green),
+                    )),
+                    DiffLine::Removed(s) => Line::from(Span::styled(
+                        format!("-{}", s),
+                        Style::default().fg(Color::Red),
+                    )),
+                    DiffLine::Context(s) => Line::from(format!(" {}", s)),
+                    DiffLine::NoNewline => Line::from(Span::styled(
+                        "\\ No newline at end of file",
+                        Style::default().fg(Color::DarkGray),
+                    )),
+                });
+            }
+        }
+    }
+    let preview_block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title(title)
+        .style(Style::default().fg(Color::DarkGray));
+    frame.render_widget(Paragraph::new(preview).block(preview_block), panes[1]);
+}
+
+/// Right-click menu at the mouse position
+fn render_context_menu(frame: &mut Frame, state: &AppState) {
+    let Some(menu) = state.menu else {
+        return;
+    };
+    let area = mouse::menu_rect(menu.pos, frame.area());
+    frame.render_widget(Clear, area);
+    let lines: Vec<Line> = mouse::MENU_ITEMS
+        .iter()
+        .enumerate()
+        .map(|(i, (label, _))| {
+            let style = if i == menu.cursor {
+                Style::default().fg(Color::Black).bg(Color::Cyan)
+            } else {
+                Style::default()
+            };
+            Line::styled(
+                format!(" {:<w$} ", label, w = area.width as usize - 4),
+                style,
+            )
+        })
+        .collect();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::Cyan));
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
 /// Help overlay
 fn render_help_overlay(frame: &mut Frame, _state: &AppState) {
-    let area = centered_fixed_rect(56, 41, frame.area());
+    let area = centered_fixed_rect(56, 65, frame.area());
     frame.render_widget(Clear, area);
 
     let help_text = vec![
@@ -853,6 +1496,10 @@ fn render_help_overlay(frame: &mut Frame, _state: &AppState) {
             Span::styled(format!("{:>10} ", "N"), Style::default().fg(Color::Cyan)),
             Span::raw("Previous file"),
         ]),
+        Line::from(vec![
+            Span::styled(format!("{:>10} ", "^P"), Style::default().fg(Color::Cyan)),
+            Span::raw("Go to file (fuzzy finder)"),
+        ]),
         Line::from(vec![
             Span::styled(format!("{:>10} ", "gg"), Style::default().fg(Color::Cyan)),
             Span::raw("First hunk"),
@@ -865,6 +1512,27 @@ fn render_help_overlay(frame: &mut Frame, _state: &AppState) {
             Span::styled(format!("{:>10} ", "Tab"), Style::default().fg(Color::Cyan)),
             Span::raw("Next pending hunk"),
         ]),
+        Line::from(vec![
+            Span::styled(
+                format!("{:>10} ", "5j 3a"),
+                Style::default().fg(Color::Cyan),
+            ),
+            Span::raw("Repeat with a count"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                format!("{:>10} ", "ma 'a"),
+                Style::default().fg(Color::Cyan),
+            ),
+            Span::raw("Set / jump to mark"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                format!("{:>10} ", "^O ^I"),
+                Style::default().fg(Color::Cyan),
+            ),
+            Span::raw("Jump list back / forward"),
+        ]),
         Line::from(""),
         Line::from(vec![
             Span::styled(format!("{:>10} ", "a"), Style::default().fg(Color::Cyan)),
@@ -875,16 +1543,27 @@ fn render_help_overlay(frame: &mut Frame, _state: &AppState) {
             Span::raw("Reject current hunk"),
         ]),
         Line::from(vec![
-            Span::styled(format!("{:>10} ", "Space"), Style::default().fg(Color::Cyan)),
+            Span::styled(
+                format!("{:>10} ", "Space"),
+                Style::default().fg(Color::Cyan),
+            ),
             Span::raw("Toggle (Pending→Accepted→Rejected)"),
         ]),
         Line::from(vec![
             Span::styled(format!("{:>10} ", "u"), Style::default().fg(Color::Cyan)),
             Span::raw("Undo last action"),
         ]),
+        Line::from(vec![
+            Span::styled(format!("{:>10} ", "^R"), Style::default().fg(Color::Cyan)),
+            Span::raw("Redo"),
+        ]),
         Line::from(vec![
             Span::styled(format!("{:>10} ", "c"), Style::default().fg(Color::Cyan)),
-            Span::raw("Add/edit comment on hunk"),
+            Span::raw("Add/edit comment (Tab: severity, Up/Down: select)"),
+        ]),
+        Line::from(vec![
+            Span::styled(format!("{:>10} ", "J/K"), Style::default().fg(Color::Cyan)),
+            Span::raw("Move line cursor (comments anchor to it)"),
         ]),
         Line::from(""),
         Line::from(vec![
@@ -895,13 +1574,68 @@ fn render_help_overlay(frame: &mut Frame, _state: &AppState) {
             Span::styled(format!("{:>10} ", "R"), Style::default().fg(Color::Cyan)),
             Span::raw("Reject all hunks"),
         ]),
+        Line::from(vec![
+            Span::styled(format!("{:>10} ", "v"), Style::default().fg(Color::Cyan)),
+            Span::raw("Mark file viewed / unviewed"),
+        ]),
+        Line::from(vec![
+            Span::styled(format!("{:>10} ", "E"), Style::default().fg(Color::Cyan)),
+            Span::raw("Edit in $EDITOR, then re-diff"),
+        ]),
+        Line::from(vec![
+            Span::styled(format!("{:>10} ", "S"), Style::default().fg(Color::Cyan)),
+            Span::raw("Split hunk at the line cursor"),
+        ]),
+        Line::from(vec![
+            Span::styled(format!("{:>10} ", "^E"), Style::default().fg(Color::Cyan)),
+            Span::raw("Edit hunk text (like git add -p e)"),
+        ]),
+        Line::from(vec![
+            Span::styled(format!("{:>10} ", "W"), Style::default().fg(Color::Cyan)),
+            Span::raw("Accept whitespace-only hunks"),
+        ]),
+        Line::from(vec![
+            Span::styled(format!("{:>10} ", "M"), Style::default().fg(Color::Cyan)),
+            Span::raw("Jump to other side of moved code"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                format!("{:>10} ", "y[ypfm]"),
+                Style::default().fg(Color::Cyan),
+            ),
+            Span::raw("Copy hunk / path:line / path / markdown"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                format!("{:>10} ", "za zo zc"),
+                Style::default().fg(Color::Cyan),
+            ),
+            Span::raw("Toggle/open/close fold of hunk"),
+        ]),
+        Line::from(vec![
+            Span::styled(format!("{:>10} ", "zA"), Style::default().fg(Color::Cyan)),
+            Span::raw("Fold/unfold the whole file"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                format!("{:>10} ", "zM zR"),
+                Style::default().fg(Color::Cyan),
+            ),
+            Span::raw("Fold/unfold all hunks"),
+        ]),
         Line::from(""),
         Line::from(vec![
-            Span::styled(format!("{:>10} ", "h/Left"), Style::default().fg(Color::Cyan)),
+            Span::styled(
+                format!("{:>10} ", "h/Left"),
+                Style::default().fg(Color::Cyan),
+            ),
             Span::raw("Focus file tree"),
         ]),
         Line::from(vec![
-            Span::styled(format!("{:>10} ", "l/Right"), Style::default().fg(Color::Cyan)),
+            Span::styled(
+                format!("{:>10} ", "l/Right"),
+                Style::default().fg(Color::Cyan),
+            ),
             Span::raw("Focus diff view"),
         ]),
         Line::from(""),
@@ -917,10 +1651,51 @@ fn render_help_overlay(frame: &mut Frame, _state: &AppState) {
             Span::styled(format!("{:>10} ", "f"), Style::default().fg(Color::Cyan)),
             Span::raw("Toggle file tree"),
         ]),
+        Line::from(vec![
+            Span::styled(format!("{:>10} ", "[ ]"), Style::default().fg(Color::Cyan)),
+            Span::raw("Narrow / widen the file tree"),
+        ]),
+        Line::from(vec![
+            Span::styled(format!("{:>10} ", "\\"), Style::default().fg(Color::Cyan)),
+            Span::raw("Tree beside / above the diff"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                format!("{:>10} ", "b { }"),
+                Style::default().fg(Color::Cyan),
+            ),
+            Span::raw("Toggle / resize details panel"),
+        ]),
+        Line::from(vec![
+            Span::styled(format!("{:>10} ", "o"), Style::default().fg(Color::Cyan)),
+            Span::raw("Cycle tree sort (path/changes/pending)"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                format!("{:>10} ", "/ (tree)"),
+                Style::default().fg(Color::Cyan),
+            ),
+            Span::raw("Filter files; Enter on dir: fold"),
+        ]),
         Line::from(vec![
             Span::styled(format!("{:>10} ", "H"), Style::default().fg(Color::Cyan)),
             Span::raw("Toggle syntax highlighting"),
         ]),
+        Line::from(vec![
+            Span::styled(format!("{:>10} ", "w"), Style::default().fg(Color::Cyan)),
+            Span::raw("Toggle line wrap"),
+        ]),
+        Line::from(vec![
+            Span::styled(format!("{:>10} ", "i"), Style::default().fg(Color::Cyan)),
+            Span::raw("Show whitespace (tabs, trailing, CR)"),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                format!("{:>10} ", "< > 0"),
+                Style::default().fg(Color::Cyan),
+            ),
+            Span::raw("Scroll left/right/home (no wrap)"),
+        ]),
         Line::from(vec![
             Span::styled(format!("{:>10} ", "s"), Style::default().fg(Color::Cyan)),
             Span::raw("Diff summary"),
@@ -935,20 +1710,33 @@ fn render_help_overlay(frame: &mut Frame, _state: &AppState) {
         ]),
         Line::from(""),
         Line::from(vec![
-            Span::styled(format!("{:>10} ", "PgUp/^U"), Style::default().fg(Color::Cyan)),
+            Span::styled(
+                format!("{:>10} ", "PgUp/^U"),
+                Style::default().fg(Color::Cyan),
+            ),
             Span::raw("Scroll up half page"),
         ]),
         Line::from(vec![
-            Span::styled(format!("{:>10} ", "PgDn/^D"), Style::default().fg(Color::Cyan)),
+            Span::styled(
+                format!("{:>10} ", "PgDn/^D"),
+                Style::default().fg(Color::Cyan),
+            ),
             Span::raw("Scroll down half page"),
         ]),
         Line::from(""),
+        Line::from(vec![
+            Span::styled(format!("{:>10} ", ":"), Style::default().fg(Color::Cyan)),
+            Span::raw("Command line (:accept GLOB, :reject /RE/, ...)"),
+        ]),
         Line::from(vec![
             Span::styled(format!("{:>10} ", "?"), Style::default().fg(Color::Cyan)),
             Span::raw("Toggle this help"),
         ]),
         Line::from(vec![
-            Span::styled(format!("{:>10} ", "q/Esc"), Style::default().fg(Color::Cyan)),
+            Span::styled(
+                format!("{:>10} ", "q/Esc"),
+                Style::default().fg(Color::Cyan),
+            ),
             Span::raw("Quit"),
         ]),
     ];
@@ -993,7 +1781,7 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
 mod tests {
     use super::*;
     use crate::config::Config;
-    use crate::model::{Diff, DiffLine, FileDiff, Hunk};
+    use crate::model::{Comment, Diff, DiffLine, FileDiff, Severity};
 
     fn make_hunk_with_lines(
         old_start: u32,
@@ -1013,7 +1801,10 @@ mod tests {
             new_count,
             lines,
             status: ReviewStatus::Pending,
-            comment: None,
+            comments: Vec::new(),
+            rule: None,
+            edited: false,
+            original_lines: None,
         }
     }
 
@@ -1025,8 +1816,10 @@ mod tests {
             raw_new_path: "b/test.rs".to_string(),
             hunks,
             is_binary: false,
+            viewed: false,
+            deferred: None,
         };
-        AppState::new(Diff { files: vec![file] }, &Config::default())
+        AppState::new(Diff::new(vec![file]), &Config::default())
     }
 
     #[test]
@@ -1042,7 +1835,7 @@ mod tests {
                 DiffLine::Context("line3".to_string()),
             ],
         )]);
-        let lines = build_virtual_doc(&state);
+        let lines = build_virtual_doc(&state, &mut highlight::Memo::default()).0;
         // line 0 = header, lines 1-3 = context lines
         assert_eq!(lines.len(), 4);
         // Check that line numbers appear in the spans
@@ -1054,6 +1847,124 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_wrap_and_hscroll_layout() {
+        let mut state = make_state_for_render(vec![make_hunk_with_lines(
+            1,
+            1,
+            1,
+            1,
+            vec![DiffLine::Added("가나다라마\tend".to_string())],
+        )]);
+        // Prefix is 8 columns ("  " + "  1 " + "|+"), leaving 6 for content
+        state.viewport_width = 14;
+        let text =
+            |line: &Line| -> String { line.spans.iter().map(|s| s.content.as_ref()).collect() };
+
+        let lines = build_virtual_doc(&state, &mut highlight::Memo::default()).0;
+        assert_eq!(lines.len(), 2);
+        assert_eq!(text(&lines[1]), "    1 |+가나다");
+
+        state.hscroll = 1;
+        let lines = build_virtual_doc(&state, &mut highlight::Memo::default()).0;
+        // The cut wide char on the left edge becomes a space
+        assert_eq!(text(&lines[1]), "    1 |+ 나다 ");
+
+        state.hscroll = 0;
+        state.wrap = true;
+        let rows: Vec<String> = build_virtual_doc(&state, &mut highlight::Memo::default()).0[1..]
+            .iter()
+            .map(text)
+            .collect();
+        assert_eq!(
+            rows,
+            vec!["    1 |+가나다", "      |+라마  ", "      |+end"]
+        );
+    }
+
+    #[test]
+    fn test_row_map_matches_lines() {
+        let mut hunk = make_hunk_with_lines(
+            1,
+            2,
+            1,
+            1,
+            vec![
+                DiffLine::Removed("old".to_string()),
+                DiffLine::Added("new line that wraps".to_string()),
+            ],
+        );
+        hunk.comments.push(Comment {
+            text: "on the hunk".to_string(),
+            severity: Severity::Nit,
+            tags: Vec::new(),
+            anchor: None,
+        });
+        hunk.comments.push(Comment {
+            text: "on the old line".to_string(),
+            severity: Severity::Nit,
+            tags: Vec::new(),
+            anchor: Some(crate::model::LineAnchor {
+                line_index: 0,
+                old_line: Some(1),
+                new_line: None,
+            }),
+        });
+        let mut state = make_state_for_render(vec![hunk]);
+        state.viewport_width = 16;
+        state.wrap = true;
+        let (lines, rows) = build_virtual_doc(&state, &mut highlight::Memo::default());
+        assert_eq!(lines.len(), rows.len());
+        assert_eq!(
+            rows,
+            vec![
+                DocRow::Header {
+                    hunk: 0,
+                    status_col: 19
+                },
+                DocRow::Note { hunk: 0 },
+                DocRow::Line { hunk: 0, line: 0 },
+                DocRow::Note { hunk: 0 },
+                DocRow::Line { hunk: 0, line: 1 },
+                DocRow::Line { hunk: 0, line: 1 },
+                DocRow::Line { hunk: 0, line: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_moved_lines_coloured_and_annotated() {
+        let diff = crate::parse::parse_diff(
+            "diff --git a/a.rs b/a.rs
+--- a/a.rs
++++ b/a.rs
+@@ -1,4 +1,0 @@
+-fn moved_helper() {
+-    let value = compute_something();
+-    value + 1
+-}
+diff --git a/b.rs b/b.rs
+--- a/b.rs
++++ b/b.rs
+@@ -1,0 +1,4 @@
++fn moved_helper() {
++    let value = compute_something();
++    value + 2
++}
+",
+        )
+        .unwrap();
+        let mut state = AppState::new(diff, &Config::default());
+        state.file_index = 1;
+        let lines = build_virtual_doc(&state, &mut highlight::Memo::default()).0;
+        assert_eq!(lines.len(), state.virtual_doc_height());
+        let text: String = lines[2].spans.iter().map(|s| s.content.as_ref()).collect();
+        assert!(text.contains("moved from a.rs:1"), "{}", text);
+        // Unchanged moved lines are cyan, the edited one stays green
+        assert_eq!(lines[1].spans[1].style.fg, Some(Color::Cyan));
+        assert_eq!(lines[4].spans[1].style.fg, Some(Color::Green));
+    }
+
     #[test]
     fn test_line_numbers_added() {
         let state = make_state_for_render(vec![make_hunk_with_lines(
@@ -1067,7 +1978,7 @@ mod tests {
                 DiffLine::Context("ctx2".to_string()),
             ],
         )]);
-        let lines = build_virtual_doc(&state);
+        let lines = build_virtual_doc(&state, &mut highlight::Memo::default()).0;
         // header + 3 lines = 4
         assert_eq!(lines.len(), 4);
         // Added line (index 2) should have '+' marker
@@ -1092,7 +2003,7 @@ mod tests {
                 DiffLine::Context("ctx2".to_string()),
             ],
         )]);
-        let lines = build_virtual_doc(&state);
+        let lines = build_virtual_doc(&state, &mut highlight::Memo::default()).0;
         assert_eq!(lines.len(), 4);
         // Removed line (index 2) should have '-' marker
         let removed_text: String = lines[2].spans.iter().map(|s| s.content.as_ref()).collect();
@@ -1103,24 +2014,126 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_line_comments_inline() {
+        let mut hunk = make_hunk_with_lines(
+            1,
+            2,
+            1,
+            3,
+            vec![
+                DiffLine::Context("ctx".to_string()),
+                DiffLine::Added("new".to_string()),
+                DiffLine::Context("ctx2".to_string()),
+            ],
+        );
+        let anchor = hunk.anchor_at(1);
+        hunk.comments
+            .push(Comment::from_input("on added", Severity::Nit).with_anchor(anchor));
+        hunk.comments
+            .push(Comment::from_input("whole hunk", Severity::Question));
+        let mut state = make_state_for_render(vec![hunk]);
+        let text =
+            |line: &Line| -> String { line.spans.iter().map(|s| s.content.as_ref()).collect() };
+
+        // Expanded: hunk comment under the header, line comment under its line
+        let lines = build_virtual_doc(&state, &mut highlight::Memo::default()).0;
+        assert_eq!(lines.len(), state.virtual_doc_height());
+        assert!(text(&lines[1]).contains("whole hunk"));
+        assert!(text(&lines[3]).contains("+new"));
+        assert!(text(&lines[4]).contains("on added"));
+
+        // Collapsed: every comment under the header, line comments labelled
+        state.diff.files[0].hunks.push(make_hunk_with_lines(
+            10,
+            1,
+            11,
+            1,
+            vec![DiffLine::Context("x".to_string())],
+        ));
+        state.hunk_index = 1;
+        let lines = build_virtual_doc(&state, &mut highlight::Memo::default()).0;
+        assert_eq!(lines.len(), state.virtual_doc_height());
+        assert!(text(&lines[1]).contains("L2: "));
+        assert!(text(&lines[1]).contains("on added"));
+        assert!(text(&lines[2]).contains("whole hunk"));
+    }
+
+    #[test]
+    fn test_window_draws_cursor_and_search() {
+        let mut state = make_state_for_render(vec![make_hunk_with_lines(
+            1,
+            2,
+            1,
+            2,
+            vec![
+                DiffLine::Context("ctx".to_string()),
+                DiffLine::Removed("old".to_string()),
+                DiffLine::Added("new".to_string()),
+            ],
+        )]);
+        state.select_hunk(0, Some(1));
+        state.search_query = "new".to_string();
+        state.execute_search();
+        let (mut lines, rows) = build_virtual_doc(&state, &mut highlight::Memo::default());
+        assert!(lines[2].spans[0].content.starts_with("  "));
+
+        // Only the window is decorated: row 2 (line 1) onwards
+        decorate_window(&state, &mut lines[2..], &rows, 2);
+        assert!(lines[2].spans[0].content.starts_with("\u{25b8} "));
+        assert!(
+            lines[3]
+                .spans
+                .iter()
+                .all(|s| s.style.bg == Some(Color::Yellow))
+        );
+        assert_eq!(lines[1].spans[0].style.bg, None);
+    }
+
+    #[test]
+    fn test_doc_rebuilt_only_on_change() {
+        let lines = (0..100).map(|i| DiffLine::Added(format!("line {}", i)));
+        let mut state =
+            make_state_for_render(vec![make_hunk_with_lines(1, 0, 1, 100, lines.collect())]);
+        let mut terminal =
+            ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 24)).unwrap();
+        terminal.draw(|f| render(f, &mut state)).unwrap();
+        assert_eq!(state.doc.lines.len(), 101);
+
+        // Scrolling and moving the cursor reuse the document
+        state.scroll_down(10);
+        state.select_hunk(0, Some(15));
+        terminal.draw(|f| render(f, &mut state)).unwrap();
+        assert!(state.doc.is_fresh(&DocKey::new(&state)));
+        assert_eq!(state.doc_map.rows[0], DocRow::Line { hunk: 0, line: 9 });
+
+        // Reviewing changes what the header shows
+        state.set_current_status(ReviewStatus::Accepted);
+        assert!(!state.doc.is_fresh(&DocKey::new(&state)));
+        terminal.draw(|f| render(f, &mut state)).unwrap();
+        let header: String = state.doc.lines[0]
+            .spans
+            .iter()
+            .map(|s| s.content.as_ref())
+            .collect();
+        assert!(header.contains("[✓]"), "{}", header);
+    }
+
     #[test]
     fn test_side_by_side_pair_lines() {
         // Test the line pairing logic
-        let mut removed_buf: Vec<&str> = vec!["old1", "old2"];
-        let mut added_buf: Vec<&str> = vec!["new1", "new2", "new3"];
+        let mut removed_buf = vec![(0, "old1"), (1, "old2")];
+        let mut added_buf = vec![(2, "new1"), (3, "new2"), (4, "new3")];
         let mut groups: Vec<SideBySideLine> = Vec::new();
 
         flush_sbs_pairs(&mut groups, &mut removed_buf, &mut added_buf);
 
         assert_eq!(groups.len(), 3);
+        assert!(matches!(
+            groups[2],
+            SideBySideLine::Changed(None, Some((4, "new3")))
+        ));
         assert!(removed_buf.is_empty());
         assert!(added_buf.is_empty());
     }
-
-    #[test]
-    fn test_truncate_str() {
-        assert_eq!(truncate_str("hello", 10), "hello");
-        assert_eq!(truncate_str("hello world!", 8), "hello...");
-        assert_eq!(truncate_str("hi", 2), "hi");
-    }
 }
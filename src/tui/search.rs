@@ -0,0 +1,215 @@
+//! Search query parsing: regex, smart-case and scope prefixes
+
+use super::state::SearchMatch;
+use crate::model::{DiffLine, FileDiff};
+use regex::{Regex, RegexBuilder};
+
+/// What a search looks at, selected by a prefix on the query
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum SearchScope {
+    /// Every diff line (no prefix)
+    All,
+    /// `+:` added lines only
+    Added,
+    /// `-:` removed lines only
+    Removed,
+    /// `f:` file paths
+    Paths,
+    /// `c:` comment text and tags
+    Comments,
+}
+
+impl SearchScope {
+    /// Whether matching looks at diff lines (which deferred files don't hold)
+    pub(super) fn needs_lines(self) -> bool {
+        matches!(
+            self,
+            SearchScope::All | SearchScope::Added | SearchScope::Removed
+        )
+    }
+
+    pub(super) fn label(self) -> &'static str {
+        match self {
+            SearchScope::All => "lines",
+            SearchScope::Added => "added",
+            SearchScope::Removed => "removed",
+            SearchScope::Paths => "paths",
+            SearchScope::Comments => "comments",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(super) struct SearchQuery {
+    pub(super) scope: SearchScope,
+    regex: Regex,
+}
+
+/// Parse `[scope:]pattern`. The pattern is a regex; it is case-insensitive
+/// unless it contains an uppercase letter (smart-case).
+pub(super) fn parse_query(input: &str) -> Result<SearchQuery, String> {
+    let (scope, pattern) = [
+        ("+:", SearchScope::Added),
+        ("-:", SearchScope::Removed),
+        ("f:", SearchScope::Paths),
+        ("c:", SearchScope::Comments),
+    ]
+    .iter()
+    .find_map(|&(prefix, scope)| input.strip_prefix(prefix).map(|p| (scope, p)))
+    .unwrap_or((SearchScope::All, input));
+
+    let regex = RegexBuilder::new(pattern)
+        .case_insensitive(!has_uppercase_literal(pattern))
+        .build()
+        .map_err(|e| match e {
+            regex::Error::Syntax(_) => "invalid regex".to_string(),
+            other => other.to_string(),
+        })?;
+    Ok(SearchQuery { scope, regex })
+}
+
+/// Whether `pattern` contains an uppercase letter outside escape sequences,
+/// so `\S+`, `\W`, `\p{Lu}` or `\x{1F600}` don't turn smart-case off.
+fn has_uppercase_literal(pattern: &str) -> bool {
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            if c.is_uppercase() {
+                return true;
+            }
+            continue;
+        }
+        if let Some(kind @ ('p' | 'P' | 'x' | 'u' | 'U')) = chars.next() {
+            if chars.next_if_eq(&'{').is_some() {
+                chars.by_ref().find(|&c| c == '}');
+            } else if matches!(kind, 'p' | 'P') {
+                chars.next();
+            } else {
+                while chars.next_if(char::is_ascii_hexdigit).is_some() {}
+            }
+        }
+    }
+    false
+}
+
+impl SearchQuery {
+    pub(super) fn is_match(&self, text: &str) -> bool {
+        self.regex.is_match(text)
+    }
+
+    /// Find matches in `files`, visiting files in `order` (tree order, filtered).
+    /// Bodies left in the spool are skipped unless `deferred` is set; then each
+    /// is parsed into a temporary copy that is dropped once searched.
+    pub(super) fn find(
+        &self,
+        files: &[FileDiff],
+        order: &[usize],
+        deferred: bool,
+    ) -> anyhow::Result<Vec<SearchMatch>> {
+        let mut matches = Vec::new();
+        for &fi in order {
+            let Some(mut file) = files.get(fi) else {
+                continue;
+            };
+            let loaded;
+            if file.deferred.is_some() && self.scope.needs_lines() {
+                if !deferred {
+                    continue;
+                }
+                let mut copy = file.clone();
+                copy.load()?; // warnings were reported when the file was first parsed
+                loaded = copy;
+                file = &loaded;
+            }
+            if self.scope == SearchScope::Paths {
+                if self.is_match(file.path()) {
+                    matches.push(SearchMatch {
+                        file_index: fi,
+                        hunk_index: 0,
+                        line_index: None,
+                    });
+                }
+                continue;
+            }
+            for (hi, hunk) in file.hunks.iter().enumerate() {
+                if self.scope == SearchScope::Comments {
+                    for comment in &hunk.comments {
+                        let tags = comment.tags.iter().map(|t| format!("#{}", t));
+                        let text = std::iter::once(comment.text.clone())
+                            .chain(tags)
+                            .collect::<Vec<_>>()
+                            .join(" ");
+                        if self.is_match(&text) {
+                            matches.push(SearchMatch {
+                                file_index: fi,
+                                hunk_index: hi,
+                                line_index: comment.line_index(),
+                            });
+                        }
+                    }
+                    continue;
+                }
+                for (li, line) in hunk.lines.iter().enumerate() {
+                    let text = match (line, self.scope) {
+                        (DiffLine::Added(s), SearchScope::All | SearchScope::Added)
+                        | (DiffLine::Removed(s), SearchScope::All | SearchScope::Removed)
+                        | (DiffLine::Context(s), SearchScope::All) => s,
+                        _ => continue,
+                    };
+                    if self.is_match(text) {
+                        matches.push(SearchMatch {
+                            file_index: fi,
+                            hunk_index: hi,
+                            line_index: Some(li),
+                        });
+                    }
+                }
+            }
+        }
+        Ok(matches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_smart_case() {
+        let q = parse_query("hello").unwrap();
+        assert!(q.is_match("Hello World"));
+        let q = parse_query("Hello").unwrap();
+        assert!(q.is_match("Hello World"));
+        assert!(!q.is_match("hello world"));
+
+        for pattern in [
+            r"foo\S+", r"\Wbar", r"\D\d", r"\p{Lu}x", r"\pLx", r"\x{1F}x", r"\x4Ax",
+        ] {
+            assert!(!has_uppercase_literal(pattern), "{pattern}");
+        }
+        let q = parse_query(r"foo\S+").unwrap();
+        assert!(q.is_match("FOObar"));
+        assert!(parse_query(r"Foo\s").unwrap().is_match("Foo "));
+        assert!(!parse_query(r"Foo\s").unwrap().is_match("foo "));
+        assert!(has_uppercase_literal(r"\p{L}X"));
+        assert!(has_uppercase_literal(r"\\A"));
+    }
+
+    #[test]
+    fn test_regex_and_scopes() {
+        let q = parse_query(r"+:unwrap\(\)").unwrap();
+        assert_eq!(q.scope, SearchScope::Added);
+        assert!(q.is_match("x.unwrap()"));
+        assert!(!q.is_match("unwrap_or"));
+
+        assert_eq!(parse_query("-:foo").unwrap().scope, SearchScope::Removed);
+        assert_eq!(parse_query("f:\\.lock$").unwrap().scope, SearchScope::Paths);
+        assert_eq!(parse_query("c:todo").unwrap().scope, SearchScope::Comments);
+        assert_eq!(parse_query("a+:b").unwrap().scope, SearchScope::All);
+    }
+
+    #[test]
+    fn test_invalid_regex() {
+        assert_eq!(parse_query("foo(").unwrap_err(), "invalid regex");
+    }
+}
@@ -1,18 +1,44 @@
 //! App state
 
 use crate::config::{Config, ViewMode};
-use crate::model::{Diff, DiffLine, FileDiff, Hunk, ReviewStatus};
-use std::collections::HashMap;
+use crate::hunk_edit;
+use crate::model::{
+    CarryOver, Comment, Diff, DiffLine, FileDiff, Hunk, ReviewStatus, Severity, WhitespaceIgnore,
+};
+use crate::moved::{self, LinePos, MovedLine, MovedLines};
+use crate::parse::ParseError;
+use ratatui::layout::{self, Rect};
+use ratatui::text::Span;
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
+
+use super::clipboard::{self, YankKind};
+use super::command;
+use super::doc::DocCache;
+use super::fuzzy::{self, FuzzyMatch};
+use super::history::{Change, History, Replayed};
+use super::jumps::{JumpList, Position};
+use super::keys::KeySeq;
+use super::layout::PaneLayout;
+use super::mouse::{ContextMenu, DocMap};
+use super::search::{self, SearchScope};
+use super::text;
+use super::tree::{self, TreeEntry, TreeRow, TreeSort};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub(super) enum AppMode {
     Normal,
     Help,
     ConfirmQuit,
-    PendingG,
     Search,
     Stats,
     CommentEdit,
+    SummaryEdit,
+    TreeFilter,
+    FileFinder,
+    Command,
+    /// Right-click context menu
+    Menu,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -31,15 +57,16 @@ pub(super) enum Focus {
 pub(super) struct SearchMatch {
     pub(super) file_index: usize,
     pub(super) hunk_index: usize,
-    pub(super) line_index: usize,
+    /// Matched diff line; None for path matches and hunk-level comments
+    pub(super) line_index: Option<usize>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub(super) struct UndoEntry {
+/// Line cursor inside a hunk, used to anchor comments to a specific line
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) struct LineCursor {
     pub(super) file_index: usize,
     pub(super) hunk_index: usize,
-    pub(super) old_status: ReviewStatus,
-    pub(super) old_comment: Option<String>,
+    pub(super) line_index: usize,
 }
 
 pub(super) struct AppState {
@@ -48,37 +75,145 @@ pub(super) struct AppState {
     pub(super) hunk_index: usize,
     pub(super) mode: AppMode,
     pub(super) should_quit: bool,
-    pub(super) undo_stack: Vec<UndoEntry>,
+    pub(super) history: History,
     pub(super) viewport_offset: usize,
     pub(super) viewport_height: usize,
+    /// Inner width of the diff pane (set by render, used for wrapping)
+    pub(super) viewport_width: usize,
+    /// Soft-wrap long lines instead of clipping them
+    pub(super) wrap: bool,
+    /// First visible content column when not wrapping
+    pub(super) hscroll: usize,
+    pub(super) tab_width: usize,
+    /// Moved blocks detected across the whole diff
+    pub(super) moved: MovedLines,
+    /// Colour moved lines and annotate where they came from
+    pub(super) color_moved: bool,
+    /// Explicit per-hunk fold state, keyed by (file, hunk); overrides fold_reviewed
+    pub(super) folds: HashMap<(usize, usize), bool>,
+    /// Files with every hunk folded to its header
+    pub(super) folded_files: HashSet<usize>,
+    /// Fold reviewed (accepted/rejected) hunks unless unfolded explicitly
+    pub(super) fold_reviewed: bool,
+    /// Tab skips pending hunks in files marked viewed
+    pub(super) skip_viewed: bool,
+    /// Render tabs, trailing spaces and CRs visibly
+    pub(super) show_whitespace: bool,
+    /// Hunks that are whitespace-only under these options stay collapsed
+    pub(super) ignore_whitespace: WhitespaceIgnore,
     pub(super) show_file_tree: bool,
+    pub(super) layout: PaneLayout,
     pub(super) search_query: String,
     pub(super) search_matches: Vec<SearchMatch>,
     pub(super) search_index: Option<usize>,
+    /// Why the current query matches nothing (e.g. invalid regex)
+    pub(super) search_error: Option<String>,
+    pub(super) search_scope: SearchScope,
+    /// Position when search mode was entered (file, hunk, viewport), restored on Esc
+    pub(super) search_origin: Option<(usize, usize, usize)>,
+    /// Also search file bodies left in the spool. Incremental search skips
+    /// them; submitting the query turns this on.
+    pub(super) search_deferred: bool,
     pub(super) stats_cursor: usize,
     pub(super) show_highlight: bool,
     pub(super) diff_view_mode: DiffViewMode,
     pub(super) comment_input: String,
+    pub(super) comment_severity: Severity,
+    pub(super) comment_edit_index: Option<usize>,
+    pub(super) line_cursor: Option<LineCursor>,
+    pub(super) summary_input: String,
     pub(super) focus: Focus,
     pub(super) show_full_file: bool,
     pub(super) file_hunk_positions: HashMap<usize, usize>,
+    /// Collapsed directory paths in the file tree
+    pub(super) tree_collapsed: HashSet<String>,
+    pub(super) tree_sort: TreeSort,
+    /// Directory row selected in the file tree (None = the current file's row)
+    pub(super) tree_selected_dir: Option<String>,
+    /// Case-insensitive path substring filter for the file tree
+    pub(super) tree_filter: String,
+    /// Inner height of the file tree pane (set by render, used for scrolling)
+    pub(super) tree_height: usize,
+    pub(super) finder_query: String,
+    /// Selected row in the file finder results
+    pub(super) finder_cursor: usize,
+    pub(super) command_input: String,
+    pub(super) command_history: Vec<String>,
+    /// Position while browsing history with Up/Down (None = editing a new line)
+    pub(super) command_history_pos: Option<usize>,
+    /// One-shot message shown in the status bar (command results and errors)
+    pub(super) message: Option<String>,
+    /// Unfinished Normal-mode key sequence (count and prefix)
+    pub(super) keys: KeySeq,
+    pub(super) marks: HashMap<char, Position>,
+    pub(super) jumps: JumpList,
+    /// OSC 52 sequence from the last yank, written to the terminal by run_loop
+    pub(super) clipboard: Option<String>,
+    /// Rows of the diff pane from the last frame, for mouse hit-testing
+    pub(super) doc_map: DocMap,
+    /// Rendered diff pane, rebuilt only when what it shows changes
+    pub(super) doc: DocCache,
+    /// Time and cell of the last left click, to detect double-clicks
+    pub(super) last_click: Option<(Instant, layout::Position)>,
+    pub(super) menu: Option<ContextMenu>,
+    /// Files are still arriving from a streamed pipe input
+    pub(super) loading: bool,
+}
+
+/// Status bar message for the first of `warnings`
+fn warning_message(warnings: &[ParseError]) -> Option<String> {
+    let first = warnings.first()?;
+    Some(match warnings.len() {
+        1 => format!("warning: {first}"),
+        n => format!("warning: {first} (+{} more)", n - 1),
+    })
+}
+
+/// Verb for a status change in undo messages
+pub(super) fn status_label(status: ReviewStatus) -> &'static str {
+    match status {
+        ReviewStatus::Accepted => "accept",
+        ReviewStatus::Rejected => "reject",
+        ReviewStatus::Pending => "reset",
+    }
 }
 
 impl AppState {
     pub(super) fn new(diff: Diff, config: &Config) -> Self {
         Self {
+            message: warning_message(&diff.warnings),
+            moved: moved::detect_moves(&diff),
             diff,
             file_index: 0,
             hunk_index: 0,
             mode: AppMode::Normal,
             should_quit: false,
-            undo_stack: Vec::new(),
+            history: History::new(config.defaults.undo_limit),
             viewport_offset: 0,
             viewport_height: 24,
+            viewport_width: 80,
+            wrap: config.defaults.wrap,
+            hscroll: 0,
+            tab_width: config.defaults.tab_width.max(1),
+            show_whitespace: config.defaults.show_whitespace,
+            folds: HashMap::new(),
+            folded_files: HashSet::new(),
+            fold_reviewed: config.defaults.fold_reviewed,
+            skip_viewed: config.defaults.skip_viewed,
+            color_moved: config.defaults.color_moved,
+            ignore_whitespace: WhitespaceIgnore {
+                all_space: config.defaults.ignore_whitespace,
+                blank_lines: config.defaults.ignore_blank_lines,
+            },
             show_file_tree: config.defaults.file_tree,
+            layout: PaneLayout::new(&config.defaults),
             search_query: String::new(),
             search_matches: Vec::new(),
             search_index: None,
+            search_error: None,
+            search_scope: SearchScope::All,
+            search_origin: None,
+            search_deferred: false,
             stats_cursor: 0,
             show_highlight: config.defaults.highlight,
             diff_view_mode: match config.defaults.view {
@@ -86,12 +221,89 @@ impl AppState {
                 ViewMode::SideBySide => DiffViewMode::SideBySide,
             },
             comment_input: String::new(),
+            comment_severity: Severity::default(),
+            comment_edit_index: None,
+            line_cursor: None,
+            summary_input: String::new(),
             focus: Focus::DiffView,
             show_full_file: false,
             file_hunk_positions: HashMap::new(),
+            tree_collapsed: HashSet::new(),
+            tree_sort: TreeSort::default(),
+            tree_selected_dir: None,
+            tree_filter: String::new(),
+            tree_height: 24,
+            finder_query: String::new(),
+            finder_cursor: 0,
+            command_input: String::new(),
+            command_history: Vec::new(),
+            command_history_pos: None,
+            keys: KeySeq::default(),
+            marks: HashMap::new(),
+            jumps: JumpList::default(),
+            clipboard: None,
+            doc_map: DocMap::default(),
+            doc: DocCache::default(),
+            last_click: None,
+            menu: None,
+            loading: false,
+        }
+    }
+
+    /// Lay the panes out in `area` (the whole terminal) and size the
+    /// viewports from the diff and tree rects
+    pub(super) fn update_layout(&mut self, area: Rect) {
+        let areas = self.layout.split(area, self.show_file_tree);
+        self.viewport_height = areas.diff.height.saturating_sub(2) as usize;
+        self.viewport_width = areas.diff.width.saturating_sub(2) as usize;
+        if let Some(tree) = areas.tree {
+            self.tree_height = tree.height.saturating_sub(2) as usize;
+        }
+        self.layout.areas = areas;
+    }
+
+    /// Add files parsed in the background. Moved blocks are detected again
+    /// once the last one has arrived.
+    pub(super) fn append_files(&mut self, files: Vec<FileDiff>, done: bool) {
+        let changed = !files.is_empty() || self.loading == done;
+        let start = self.diff.files.len();
+        self.diff.files.extend(files);
+        if !self.search_query.is_empty() && self.diff.files.len() > start {
+            self.search_appended(start);
+        }
+        self.loading = !done;
+        if done {
+            self.moved = moved::detect_moves(&self.diff);
+        }
+        if changed {
+            self.history.touch();
         }
     }
 
+    /// Keep parse warnings from the background loader and show the first
+    pub(super) fn add_warnings(&mut self, mut warnings: Vec<ParseError>) {
+        // Reparsing a deferred body repeats the warnings of its first parse
+        warnings.retain(|w| !self.diff.warnings.contains(w));
+        if let Some(message) = warning_message(&warnings) {
+            self.message = Some(message);
+        }
+        self.diff.warnings.extend(warnings);
+    }
+
+    /// Parse the current file's body if it was left in the spool
+    pub(super) fn load_current_file(&mut self) -> anyhow::Result<()> {
+        let Some(file) = self.diff.files.get_mut(self.file_index) else {
+            return Ok(());
+        };
+        if file.deferred.is_none() {
+            return Ok(());
+        }
+        let warnings = file.load()?;
+        self.history.touch();
+        self.add_warnings(warnings);
+        Ok(())
+    }
+
     pub(super) fn current_file(&self) -> Option<&FileDiff> {
         self.diff.files.get(self.file_index)
     }
@@ -108,6 +320,20 @@ impl AppState {
             .and_then(|f| f.hunks.get_mut(self.hunk_index))
     }
 
+    /// Select hunk `hi` of the current file, with the line cursor on `line`
+    pub(super) fn select_hunk(&mut self, hi: usize, line: Option<usize>) {
+        if self.current_file().is_none_or(|f| hi >= f.hunks.len()) {
+            return;
+        }
+        self.hunk_index = hi;
+        self.line_cursor = line.map(|line_index| LineCursor {
+            file_index: self.file_index,
+            hunk_index: hi,
+            line_index,
+        });
+        self.ensure_visible();
+    }
+
     pub(super) fn next_hunk(&mut self) {
         if let Some(file) = self.current_file() {
             if self.hunk_index + 1 < file.hunks.len() {
@@ -125,93 +351,325 @@ impl AppState {
     }
 
     pub(super) fn next_file(&mut self) {
-        if self.file_index + 1 < self.diff.files.len() {
-            self.file_hunk_positions.insert(self.file_index, self.hunk_index);
-            self.file_index += 1;
-            self.hunk_index = self.file_hunk_positions.get(&self.file_index).copied().unwrap_or(0);
-            self.viewport_offset = 0;
+        let order = self.file_order();
+        let pos = order.iter().position(|&fi| fi == self.file_index);
+        let next = match pos {
+            Some(p) => order.get(p + 1).copied(),
+            None => order.iter().copied().find(|&fi| fi > self.file_index),
+        };
+        if let Some(fi) = next {
+            self.select_file(fi);
         }
         self.ensure_visible();
     }
 
     pub(super) fn prev_file(&mut self) {
-        if self.file_index > 0 {
-            self.file_hunk_positions.insert(self.file_index, self.hunk_index);
-            self.file_index -= 1;
-            self.hunk_index = self.file_hunk_positions.get(&self.file_index).copied().unwrap_or(0);
-            self.viewport_offset = 0;
+        let order = self.file_order();
+        let pos = order.iter().position(|&fi| fi == self.file_index);
+        let prev = match pos {
+            Some(p) => p.checked_sub(1).map(|p| order[p]),
+            None => order.iter().rev().copied().find(|&fi| fi < self.file_index),
+        };
+        if let Some(fi) = prev {
+            self.select_file(fi);
         }
         self.ensure_visible();
     }
 
-    fn push_undo(&mut self, file_index: usize, hunk_index: usize, old_status: ReviewStatus) {
-        let old_comment = self
-            .diff
-            .files
-            .get(file_index)
-            .and_then(|f| f.hunks.get(hunk_index))
-            .and_then(|h| h.comment.clone());
-        self.undo_stack.push(UndoEntry {
-            file_index,
-            hunk_index,
-            old_status,
-            old_comment,
-        });
+    /// Switch to a file, remembering the hunk position of the one we leave.
+    /// Collapsed ancestors are expanded so the file stays visible in the tree.
+    pub(super) fn select_file(&mut self, fi: usize) {
+        self.tree_selected_dir = None;
+        if let Some(file) = self.diff.files.get(fi) {
+            let path = file.path();
+            self.tree_collapsed
+                .retain(|dir| !path.starts_with(&format!("{}/", dir)));
+        }
+        if fi == self.file_index || fi >= self.diff.files.len() {
+            return;
+        }
+        self.file_hunk_positions
+            .insert(self.file_index, self.hunk_index);
+        self.file_index = fi;
+        self.hunk_index = self.file_hunk_positions.get(&fi).copied().unwrap_or(0);
+        self.viewport_offset = 0;
+        self.ensure_visible();
+    }
+
+    /// Record one hunk's review state before changing it
+    fn record_review(&mut self, label: &str, file_index: usize, hunk_index: usize) {
+        let change = Change::review(&self.diff, file_index, hunk_index);
+        self.history.record(label, change.into_iter().collect());
     }
 
     pub(super) fn set_current_status(&mut self, status: ReviewStatus) {
-        if let Some(hunk) = self.current_hunk() {
-            let old_status = hunk.status;
-            let fi = self.file_index;
-            let hi = self.hunk_index;
-            self.push_undo(fi, hi, old_status);
-        }
+        self.record_review(status_label(status), self.file_index, self.hunk_index);
         if let Some(hunk) = self.current_hunk_mut() {
             hunk.status = status;
+            hunk.rule = None; // reviewer overrode the rule decision
         }
     }
 
     pub(super) fn toggle_current_status(&mut self) {
-        if let Some(hunk) = self.current_hunk() {
-            let old_status = hunk.status;
-            let fi = self.file_index;
-            let hi = self.hunk_index;
-            self.push_undo(fi, hi, old_status);
-        }
+        self.record_review("toggle", self.file_index, self.hunk_index);
         if let Some(hunk) = self.current_hunk_mut() {
             hunk.status = match hunk.status {
                 ReviewStatus::Pending => ReviewStatus::Accepted,
                 ReviewStatus::Accepted => ReviewStatus::Rejected,
                 ReviewStatus::Rejected => ReviewStatus::Pending,
             };
+            hunk.rule = None;
         }
     }
 
     pub(super) fn set_all_status(&mut self, status: ReviewStatus) {
+        let label = format!("{} all", status_label(status));
+        self.set_status_where(&label, status, |_, _| true);
+    }
+
+    /// Set the status of every hunk matching `pred` as one undo step;
+    /// returns how many were set
+    pub(super) fn set_status_where(
+        &mut self,
+        label: &str,
+        status: ReviewStatus,
+        pred: impl Fn(&FileDiff, &Hunk) -> bool,
+    ) -> usize {
+        let mut changes = Vec::new();
         for fi in 0..self.diff.files.len() {
             for hi in 0..self.diff.files[fi].hunks.len() {
-                let old_status = self.diff.files[fi].hunks[hi].status;
-                self.push_undo(fi, hi, old_status);
-                self.diff.files[fi].hunks[hi].status = status;
+                let file = &self.diff.files[fi];
+                if !pred(file, &file.hunks[hi]) {
+                    continue;
+                }
+                let hunk = &mut self.diff.files[fi].hunks[hi];
+                changes.push(Change::Review {
+                    file_index: fi,
+                    hunk_index: hi,
+                    status: std::mem::replace(&mut hunk.status, status),
+                    comments: hunk.comments.clone(),
+                    rule: hunk.rule.take(),
+                });
             }
         }
+        let count = changes.len();
+        self.history.record(label, changes);
+        count
+    }
+
+    /// Accept every whitespace-only hunk (under the active ignore options,
+    /// or ignoring both whitespace and blank lines when none is set)
+    pub(super) fn accept_whitespace_only(&mut self) -> anyhow::Result<usize> {
+        // A deferred hunk has no lines and would look whitespace-only
+        self.load_all()?;
+        let ignore = if self.ignore_whitespace.is_active() {
+            self.ignore_whitespace
+        } else {
+            WhitespaceIgnore::ALL
+        };
+        Ok(self.set_status_where(
+            "accept whitespace-only",
+            ReviewStatus::Accepted,
+            |_, hunk| hunk.status != ReviewStatus::Accepted && hunk.is_whitespace_only(ignore),
+        ))
+    }
+
+    /// Parse every deferred file body (whole-diff checks need the lines)
+    fn load_all(&mut self) -> anyhow::Result<()> {
+        if self.diff.files.iter().any(|f| f.deferred.is_some()) {
+            let warnings = self.diff.load_all()?;
+            self.history.touch();
+            self.add_warnings(warnings);
+        }
+        Ok(())
     }
 
-    pub(super) fn undo(&mut self) {
-        if let Some(entry) = self.undo_stack.pop() {
-            let old_fi = self.file_index;
-            if let Some(file) = self.diff.files.get_mut(entry.file_index)
-                && let Some(hunk) = file.hunks.get_mut(entry.hunk_index)
-            {
-                hunk.status = entry.old_status;
-                hunk.comment = entry.old_comment;
-            }
-            self.file_index = entry.file_index;
-            self.hunk_index = entry.hunk_index;
-            if entry.file_index != old_fi {
+    /// Undo the last action (all hunks of a bulk action at once).
+    /// Returns the status message.
+    pub(super) fn undo(&mut self) -> String {
+        match self.history.undo(&mut self.diff) {
+            Some(undone) => self.after_replay("undid", undone),
+            None => "nothing to undo".to_string(),
+        }
+    }
+
+    pub(super) fn redo(&mut self) -> String {
+        match self.history.redo(&mut self.diff) {
+            Some(redone) => self.after_replay("redid", redone),
+            None => "nothing to redo".to_string(),
+        }
+    }
+
+    /// Move to what an undo/redo changed and drop view state it invalidated
+    fn after_replay(&mut self, verb: &str, replayed: Replayed) -> String {
+        if !replayed.reshaped.is_empty() {
+            self.folds
+                .retain(|(f, _), _| !replayed.reshaped.contains(f));
+            self.line_cursor = None;
+            self.clear_search();
+            self.moved = moved::detect_moves(&self.diff);
+        }
+        if let Some((fi, hi)) = replayed.location
+            && let Some(file) = self.diff.files.get(fi)
+        {
+            if fi != self.file_index {
                 self.viewport_offset = 0;
             }
-            self.ensure_visible();
+            let hi = hi.unwrap_or(if fi == self.file_index {
+                self.hunk_index
+            } else {
+                0
+            });
+            self.file_index = fi;
+            self.hunk_index = hi.min(file.hunks.len().saturating_sub(1));
+        }
+        self.ensure_visible();
+        if replayed.changes > 1 {
+            format!("{} {} ({} hunks)", verb, replayed.label, replayed.changes)
+        } else {
+            format!("{} {}", verb, replayed.label)
+        }
+    }
+
+    /// Run `f` as one undo step (counted actions like `3a`)
+    pub(super) fn as_one_step(&mut self, f: impl FnOnce(&mut Self)) {
+        let mark = self.history.mark();
+        f(self);
+        self.history.merge_since(mark);
+    }
+
+    /// `Ng` / `NG`: the Nth hunk of the whole diff, counting like the status bar
+    pub(super) fn goto_flat_hunk(&mut self, n: usize) {
+        let mut rest = n.max(1) - 1;
+        let last = self.diff.files.len().saturating_sub(1);
+        for (fi, file) in self.diff.files.iter().enumerate() {
+            if rest < file.hunks.len() || fi == last {
+                if fi != self.file_index {
+                    self.viewport_offset = 0;
+                }
+                self.file_index = fi;
+                self.hunk_index = rest.min(file.hunks.len().saturating_sub(1));
+                break;
+            }
+            rest -= file.hunks.len();
+        }
+        self.ensure_visible();
+    }
+
+    // --- Marks and jumps ---
+
+    pub(super) fn position(&self) -> Option<Position> {
+        self.current_file().map(|f| Position {
+            path: f.path().to_string(),
+            hunk_index: self.hunk_index,
+        })
+    }
+
+    /// Remember the current position before a jump (gg, G, search, marks, :goto)
+    pub(super) fn push_jump(&mut self) {
+        self.push_jump_from(self.file_index, self.hunk_index);
+    }
+
+    pub(super) fn push_jump_from(&mut self, file_index: usize, hunk_index: usize) {
+        if let Some(file) = self.diff.files.get(file_index) {
+            self.jumps.push(Position {
+                path: file.path().to_string(),
+                hunk_index,
+            });
+        }
+    }
+
+    /// Move to a remembered position; false if its file left the diff
+    fn goto_position(&mut self, pos: &Position) -> bool {
+        let Some(fi) = self.diff.files.iter().position(|f| f.path() == pos.path) else {
+            return false;
+        };
+        self.select_file(fi);
+        self.hunk_index = pos
+            .hunk_index
+            .min(self.diff.files[fi].hunks.len().saturating_sub(1));
+        self.clear_line_cursor();
+        self.ensure_visible();
+        true
+    }
+
+    pub(super) fn set_mark(&mut self, mark: char) -> String {
+        let Some(pos) = self.position() else {
+            return "no file to mark".to_string();
+        };
+        let msg = format!("mark {}: {} hunk {}", mark, pos.path, pos.hunk_index + 1);
+        self.marks.insert(mark, pos);
+        msg
+    }
+
+    /// Queue the current hunk, location or path for the clipboard
+    pub(super) fn yank(&mut self, kind: YankKind) -> Result<String, String> {
+        let file = self.current_file().ok_or("nothing to yank")?;
+        let text = match self.current_hunk() {
+            Some(hunk) => clipboard::yank_text(kind, file, hunk, self.cursor_line()),
+            None if kind == YankKind::Path => file.path().to_string(),
+            None => return Err("no hunk to yank".to_string()),
+        };
+        let sequence = clipboard::osc52(&text).map_err(|e| format!("{}: {}", kind.label(), e))?;
+        self.clipboard = Some(sequence);
+        let lines = text.lines().count();
+        Ok(if lines > 1 {
+            format!("copied {} ({} lines) to clipboard", kind.label(), lines)
+        } else {
+            format!("copied {} to clipboard", text)
+        })
+    }
+
+    pub(super) fn jump_to_mark(&mut self, mark: char) -> Result<(), String> {
+        let pos = self
+            .marks
+            .get(&mark)
+            .cloned()
+            .ok_or_else(|| format!("mark {} not set", mark))?;
+        self.push_jump();
+        if self.goto_position(&pos) {
+            Ok(())
+        } else {
+            Err(format!(
+                "mark {}: {} is no longer in the diff",
+                mark, pos.path
+            ))
+        }
+    }
+
+    /// Ctrl-o / Ctrl-i; positions whose file left the diff are skipped
+    pub(super) fn jump_back(&mut self, back: bool) -> Result<(), String> {
+        let current = self.position().ok_or("no jumps")?;
+        loop {
+            let pos = if back {
+                self.jumps.back(current.clone())
+            } else {
+                self.jumps.forward(&current)
+            };
+            let pos = pos.ok_or(if back {
+                "at the oldest jump"
+            } else {
+                "at the newest jump"
+            })?;
+            if self.goto_position(&pos) {
+                return Ok(());
+            }
+        }
+    }
+
+    /// `''`: back to where the latest jump started, which is a jump itself
+    pub(super) fn jump_previous(&mut self) -> Result<(), String> {
+        let current = self.position().ok_or("no previous jump")?;
+        let pos = self
+            .jumps
+            .latest(&current)
+            .cloned()
+            .ok_or("no previous jump")?;
+        self.push_jump();
+        if self.goto_position(&pos) {
+            Ok(())
+        } else {
+            Err(format!("{} is no longer in the diff", pos.path))
         }
     }
 
@@ -231,7 +689,35 @@ impl AppState {
         }
     }
 
-    /// Move to the next Pending hunk (wrap-around). Returns false if none found.
+    /// Mark the current file viewed, or clear the mark. Returns the status message.
+    /// Viewed files fold to their hunk headers like on GitHub.
+    pub(super) fn toggle_viewed(&mut self) -> String {
+        let fi = self.file_index;
+        let Some(change) = Change::viewed(&self.diff, fi) else {
+            return String::new();
+        };
+        self.history.record("viewed mark", vec![change]);
+        let file = &mut self.diff.files[fi];
+        file.viewed = !file.viewed;
+        let viewed = file.viewed;
+        let path = file.path().to_string();
+        // Drop explicit folds so the file follows its viewed mark
+        self.folds.retain(|&(f, _), _| f != fi);
+        self.folded_files.remove(&fi);
+        self.clear_line_cursor();
+        self.ensure_visible();
+        let progress = self.diff.progress();
+        format!(
+            "{} {} ({}/{} files viewed)",
+            if viewed { "viewed" } else { "unviewed" },
+            path,
+            progress.viewed_files,
+            progress.files
+        )
+    }
+
+    /// Move to the next Pending hunk (wrap-around), skipping files marked viewed
+    /// when skip_viewed is on. Returns false if none found.
     pub(super) fn next_pending(&mut self) -> bool {
         let total = self.total_hunks();
         if total == 0 {
@@ -242,7 +728,9 @@ impl AppState {
         for offset in 1..=total {
             let flat = (start + offset) % total;
             let (fi, hi) = self.flat_to_indices(flat);
-            if self.diff.files[fi].hunks[hi].status == ReviewStatus::Pending {
+            let file = &self.diff.files[fi];
+            if file.hunks[hi].status == ReviewStatus::Pending && !(self.skip_viewed && file.viewed)
+            {
                 self.file_index = fi;
                 self.hunk_index = hi;
                 if fi != old_fi {
@@ -307,15 +795,7 @@ impl AppState {
             if hi == self.hunk_index {
                 return offset;
             }
-            // Non-current hunks are collapsed (1 line header only),
-            // unless show_full_file is on (all hunks expanded)
-            offset += 1;
-            if hunk.comment.is_some() {
-                offset += 1;
-            }
-            if self.show_full_file {
-                offset += hunk.lines.len();
-            }
+            offset += self.hunk_height(hi, hunk);
         }
         offset
     }
@@ -327,27 +807,29 @@ impl AppState {
         };
         let mut height = 0;
         for (hi, hunk) in file.hunks.iter().enumerate() {
-            height += 1; // header
-            if hunk.comment.is_some() {
-                height += 1; // comment line
-            }
-            if hi == self.hunk_index || self.show_full_file {
-                height += hunk.lines.len();
-            }
+            height += self.hunk_height(hi, hunk);
         }
         height
     }
 
     pub(super) fn ensure_visible(&mut self) {
         let offset = self.current_hunk_line_offset();
-        let current_hunk_height = self.current_hunk().map_or(1, |h| {
-            let mut h_height = 1; // header
-            if h.comment.is_some() {
-                h_height += 1; // comment
+        let current_hunk_height = self
+            .current_hunk()
+            .map_or(1, |h| self.hunk_height(self.hunk_index, h));
+
+        // Keep the line cursor on screen when it is set
+        if let Some(li) = self.cursor_line()
+            && let Some(hunk) = self.current_hunk()
+        {
+            let row = offset + self.hunk_row_of_line(self.hunk_index, hunk, li);
+            if row < self.viewport_offset {
+                self.viewport_offset = row;
+            } else if row >= self.viewport_offset + self.viewport_height {
+                self.viewport_offset = row + 1 - self.viewport_height;
             }
-            h_height += h.lines.len(); // diff lines
-            h_height
-        });
+            return;
+        }
 
         let bottom = offset + current_hunk_height;
         if current_hunk_height <= self.viewport_height {
@@ -371,6 +853,155 @@ impl AppState {
         }
     }
 
+    /// Whether hunk `hi` of the current file shows its lines: the current
+    /// hunk (or every hunk with show_full_file), unless it is folded or
+    /// whitespace-only under the active ignore options
+    pub(super) fn is_expanded(&self, hi: usize, hunk: &Hunk) -> bool {
+        (hi == self.hunk_index || self.show_full_file)
+            && !self.is_folded(self.file_index, hi)
+            && !hunk.is_whitespace_only(self.ignore_whitespace)
+    }
+
+    /// Rows taken by hunk `hi` of the current file: the header, then its
+    /// comments (unless folded), then its lines when expanded
+    fn hunk_height(&self, hi: usize, hunk: &Hunk) -> usize {
+        if self.is_folded(self.file_index, hi) {
+            return 1;
+        }
+        let lines = if self.is_expanded(hi, hunk) {
+            self.lines_height(hi, hunk)
+        } else {
+            0
+        };
+        1 + hunk.comments.len() + lines
+    }
+
+    // --- Folds ---
+
+    /// Folded to its header: the whole file is folded, or the hunk was folded
+    /// explicitly, or (without an explicit choice) its file is marked viewed,
+    /// or it is reviewed and fold_reviewed is on
+    pub(super) fn is_folded(&self, fi: usize, hi: usize) -> bool {
+        if self.folded_files.contains(&fi) {
+            return true;
+        }
+        if let Some(&folded) = self.folds.get(&(fi, hi)) {
+            return folded;
+        }
+        let Some(file) = self.diff.files.get(fi) else {
+            return false;
+        };
+        file.viewed
+            || (self.fold_reviewed
+                && file
+                    .hunks
+                    .get(hi)
+                    .is_some_and(|h| h.status != ReviewStatus::Pending))
+    }
+
+    /// Fold (`Some(true)`), unfold (`Some(false)`) or toggle (`None`) the current hunk
+    pub(super) fn fold_current(&mut self, folded: Option<bool>) {
+        let (fi, hi) = (self.file_index, self.hunk_index);
+        let folded = folded.unwrap_or(!self.is_folded(fi, hi));
+        if !folded {
+            self.folded_files.remove(&fi);
+        }
+        self.folds.insert((fi, hi), folded);
+        self.ensure_visible();
+    }
+
+    /// Fold or unfold every hunk of the current file at once
+    pub(super) fn toggle_file_fold(&mut self) {
+        if !self.folded_files.remove(&self.file_index) {
+            self.folded_files.insert(self.file_index);
+        }
+        self.ensure_visible();
+    }
+
+    /// Fold (zM) or unfold (zR) every hunk in every file
+    pub(super) fn fold_all(&mut self, folded: bool) {
+        self.folded_files.clear();
+        for (fi, file) in self.diff.files.iter().enumerate() {
+            for hi in 0..file.hunks.len() {
+                self.folds.insert((fi, hi), folded);
+            }
+        }
+        self.viewport_offset = 0;
+        self.ensure_visible();
+    }
+
+    /// Open the folds hiding hunk `hi` of file `fi` (after a jump into it)
+    fn unfold(&mut self, fi: usize, hi: usize) {
+        if self.is_folded(fi, hi) {
+            self.folded_files.remove(&fi);
+            self.folds.insert((fi, hi), false);
+        }
+    }
+
+    /// Whether diff lines are soft-wrapped (unified layout only)
+    pub(super) fn wraps(&self) -> bool {
+        let side_by_side =
+            self.diff_view_mode == DiffViewMode::SideBySide && self.viewport_width >= 100;
+        self.wrap && !side_by_side
+    }
+
+    /// Columns left for line content after the unified gutter
+    pub(super) fn content_width(&self, hunk: &Hunk) -> usize {
+        self.viewport_width
+            .saturating_sub(text::unified_prefix_width(hunk))
+            .max(1)
+    }
+
+    /// Moved-code info for line `li` of hunk `hi` in the current file
+    pub(super) fn moved_line(&self, hi: usize, li: usize) -> Option<&MovedLine> {
+        if !self.color_moved {
+            return None;
+        }
+        self.moved.get(self.file_index, hi, li)
+    }
+
+    /// Screen rows taken by diff line `li` of hunk `hi`: more than 1 when
+    /// wrapping, plus the "moved from" annotation under the first line of a block
+    fn line_rows(&self, hi: usize, hunk: &Hunk, li: usize) -> usize {
+        let annotation = self.moved_line(hi, li).is_some_and(|m| m.block_start) as usize;
+        annotation
+            + match hunk.lines.get(li) {
+                Some(DiffLine::Context(s) | DiffLine::Added(s) | DiffLine::Removed(s))
+                    if self.wraps() =>
+                {
+                    let width = self.content_width(hunk);
+                    if self.show_whitespace {
+                        let spans =
+                            text::visualize_whitespace(&[Span::raw(s.as_str())], self.tab_width);
+                        text::wrap_spans(&spans, width).len()
+                    } else {
+                        text::wrapped_rows(&text::expand_tabs(s, self.tab_width), width)
+                    }
+                }
+                _ => 1,
+            }
+    }
+
+    /// Screen rows taken by all diff lines of an expanded hunk
+    fn lines_height(&self, hi: usize, hunk: &Hunk) -> usize {
+        (0..hunk.lines.len())
+            .map(|li| self.line_rows(hi, hunk, li))
+            .sum()
+    }
+
+    pub(super) fn toggle_wrap(&mut self) {
+        self.wrap = !self.wrap;
+        self.hscroll = 0;
+        self.ensure_visible();
+    }
+
+    /// Scroll the diff horizontally by `delta` columns (no-op while wrapping)
+    pub(super) fn scroll_horizontal(&mut self, delta: isize) {
+        if !self.wraps() {
+            self.hscroll = self.hscroll.saturating_add_signed(delta);
+        }
+    }
+
     pub(super) fn scroll_up(&mut self, n: usize) {
         self.viewport_offset = self.viewport_offset.saturating_sub(n);
     }
@@ -388,42 +1019,158 @@ impl AppState {
         !self.search_query.is_empty() && !self.search_matches.is_empty()
     }
 
+    /// Run the query over the files passing the tree filter, in tree order
     pub(super) fn execute_search(&mut self) {
         self.search_matches.clear();
+        self.search_error = None;
+        self.search_index = None;
         if self.search_query.is_empty() {
-            self.search_index = None;
             return;
         }
-        let q = self.search_query.to_lowercase();
-        for (fi, file) in self.diff.files.iter().enumerate() {
-            for (hi, hunk) in file.hunks.iter().enumerate() {
-                for (li, line) in hunk.lines.iter().enumerate() {
-                    let text = match line {
-                        DiffLine::Context(s) | DiffLine::Added(s) | DiffLine::Removed(s) => s,
-                        DiffLine::NoNewline => continue,
-                    };
-                    if text.to_lowercase().contains(&q) {
-                        self.search_matches.push(SearchMatch {
-                            file_index: fi,
-                            hunk_index: hi,
-                            line_index: li,
-                        });
-                    }
+        match search::parse_query(&self.search_query) {
+            Ok(query) => {
+                self.search_scope = query.scope;
+                match query.find(&self.diff.files, &self.file_order(), self.search_deferred) {
+                    Ok(matches) => self.search_matches = matches,
+                    Err(e) => self.search_error = Some(format!("load failed: {e}")),
+                }
+                if !self.search_matches.is_empty() {
+                    self.search_index = Some(0);
                 }
             }
+            Err(err) => self.search_error = Some(err),
         }
+    }
+
+    /// Add the matches in files appended from `start` on, keeping the
+    /// selected match
+    fn search_appended(&mut self, start: usize) {
+        let Ok(query) = search::parse_query(&self.search_query) else {
+            return;
+        };
+        let order: Vec<usize> = self
+            .file_order()
+            .into_iter()
+            .filter(|&fi| fi >= start)
+            .collect();
+        let found = match query.find(&self.diff.files, &order, self.search_deferred) {
+            Ok(found) => found,
+            Err(e) => {
+                self.search_error = Some(format!("load failed: {e}"));
+                return;
+            }
+        };
+        if found.is_empty() {
+            return;
+        }
+        let selected = self
+            .search_index
+            .and_then(|i| self.search_matches.get(i))
+            .cloned();
+        self.search_matches.extend(found);
+        // Matches of one file stay in hunk/line order (the sort is stable)
+        let rank = self.file_ranks();
+        self.search_matches.sort_by_key(|m| rank[m.file_index]);
+        let idx = selected
+            .and_then(|m| self.search_matches.iter().position(|n| *n == m))
+            .unwrap_or(0);
+        self.search_index = Some(idx);
+    }
+
+    /// Enter in search mode: search the bodies left in the spool too, keeping
+    /// the match incremental search reached when it is still there
+    pub(super) fn submit_search(&mut self) {
+        // Files still arriving from the loader are searched in full too
+        self.search_deferred = true;
+        if self.search_query.is_empty() || !self.diff.files.iter().any(|f| f.deferred.is_some()) {
+            return;
+        }
+        let selected = self
+            .search_index
+            .and_then(|i| self.search_matches.get(i))
+            .cloned();
+        self.execute_search();
+        match selected.and_then(|m| self.search_matches.iter().position(|n| *n == m)) {
+            Some(idx) => self.search_index = Some(idx),
+            None => self.select_match_from_origin(),
+        }
+    }
+
+    /// Position of each file in `file_order()` (`usize::MAX` when filtered out)
+    fn file_ranks(&self) -> Vec<usize> {
+        let mut rank = vec![usize::MAX; self.diff.files.len()];
+        for (r, fi) in self.file_order().into_iter().enumerate() {
+            rank[fi] = r;
+        }
+        rank
+    }
+
+    pub(super) fn begin_search(&mut self) {
+        self.clear_search();
+        self.search_origin = Some((self.file_index, self.hunk_index, self.viewport_offset));
+        self.mode = AppMode::Search;
+    }
+
+    /// Incremental search: re-run the query and jump to the first match at or
+    /// after the position search started from (back to it when nothing matches)
+    pub(super) fn update_search(&mut self) {
+        self.execute_search();
+        self.select_match_from_origin();
+    }
+
+    /// Jump to the first match at or after the search origin
+    fn select_match_from_origin(&mut self) {
+        let Some((ofi, ohi, offset)) = self.search_origin else {
+            return;
+        };
         if self.search_matches.is_empty() {
-            self.search_index = None;
-        } else {
-            self.search_index = Some(0);
+            self.jump_to(ofi, ohi, offset);
+            return;
+        }
+        let rank = self.file_ranks();
+        let origin = (rank.get(ofi).copied().unwrap_or(usize::MAX), ohi);
+        let idx = self
+            .search_matches
+            .iter()
+            .position(|m| (rank[m.file_index], m.hunk_index) >= origin)
+            .unwrap_or(0);
+        self.search_index = Some(idx);
+        self.goto_match(idx);
+    }
+
+    pub(super) fn search_input(&mut self, c: char) {
+        self.search_query.push(c);
+        self.update_search();
+    }
+
+    pub(super) fn search_backspace(&mut self) {
+        self.search_query.pop();
+        self.update_search();
+    }
+
+    /// Esc in search mode: drop the query and return to where search started
+    pub(super) fn cancel_search(&mut self) {
+        if let Some((fi, hi, offset)) = self.search_origin.take() {
+            self.jump_to(fi, hi, offset);
         }
+        self.clear_search();
+    }
+
+    fn jump_to(&mut self, fi: usize, hi: usize, offset: usize) {
+        self.file_index = fi;
+        self.hunk_index = hi;
+        self.viewport_offset = offset;
+        self.ensure_visible();
     }
 
     pub(super) fn goto_match(&mut self, idx: usize) {
-        if let Some(m) = self.search_matches.get(idx) {
+        if let Some(m) = self.search_matches.get(idx).cloned() {
             let old_fi = self.file_index;
             self.file_index = m.file_index;
             self.hunk_index = m.hunk_index;
+            if m.line_index.is_some() {
+                self.unfold(m.file_index, m.hunk_index);
+            }
             if m.file_index != old_fi {
                 self.viewport_offset = 0;
             }
@@ -456,25 +1203,216 @@ impl AppState {
     }
 
     pub(super) fn clear_search(&mut self) {
+        self.search_deferred = false;
         self.search_query.clear();
         self.search_matches.clear();
         self.search_index = None;
+        self.search_error = None;
     }
 
-    pub(super) fn set_current_comment(&mut self, comment: String) {
-        if let Some(hunk) = self.current_hunk() {
-            let old_status = hunk.status;
-            let fi = self.file_index;
-            let hi = self.hunk_index;
-            self.push_undo(fi, hi, old_status);
-        }
+    // --- Moved code ---
+
+    /// `path:line` of a diff line (old line number for removed lines)
+    pub(super) fn line_location(&self, pos: LinePos) -> String {
+        let Some(file) = self.diff.files.get(pos.file) else {
+            return String::new();
+        };
+        let line = file
+            .hunks
+            .get(pos.hunk)
+            .and_then(|h| h.line_numbers().get(pos.line).copied())
+            .and_then(|(old, new)| new.or(old))
+            .unwrap_or(0);
+        format!("{}:{}", file.path(), line)
+    }
+
+    /// Jump to the other side of a moved block: from the cursor line if it
+    /// was moved, otherwise from the first moved line of the current hunk
+    pub(super) fn jump_moved(&mut self) -> Result<String, String> {
+        let hunk_len = self.current_hunk().map_or(0, |h| h.lines.len());
+        let source = self
+            .cursor_line()
+            .into_iter()
+            .chain(0..hunk_len)
+            .find_map(|li| self.moved_line(self.hunk_index, li).map(|m| (li, *m)));
+        let Some((li, moved)) = source else {
+            return Err("no moved lines in this hunk".to_string());
+        };
+        let here = LinePos {
+            file: self.file_index,
+            hunk: self.hunk_index,
+            line: li,
+        };
+        let target = moved.counterpart;
+        let is_added = self
+            .current_hunk()
+            .and_then(|h| h.lines.get(li))
+            .is_some_and(|l| matches!(l, DiffLine::Added(_)));
+
+        self.select_file(target.file);
+        self.hunk_index = target.hunk;
+        self.unfold(target.file, target.hunk);
+        self.line_cursor = Some(LineCursor {
+            file_index: target.file,
+            hunk_index: target.hunk,
+            line_index: target.line,
+        });
+        self.ensure_visible();
+        let direction = if is_added { "moved to" } else { "moved from" };
+        Ok(format!("{} {}", direction, self.line_location(here)))
+    }
+
+    // --- Line cursor ---
+
+    /// Line index of the cursor, if it belongs to the current hunk
+    pub(super) fn cursor_line(&self) -> Option<usize> {
+        self.line_cursor
+            .filter(|c| c.file_index == self.file_index && c.hunk_index == self.hunk_index)
+            .filter(|_| {
+                self.current_hunk()
+                    .is_some_and(|h| self.is_expanded(self.hunk_index, h))
+            })
+            .map(|c| c.line_index)
+    }
+
+    /// Move the line cursor within the current hunk. The first move from
+    /// "no cursor" lands on the first (down) or last (up) line.
+    pub(super) fn move_line_cursor(&mut self, down: bool) {
+        let len = match self.current_hunk() {
+            Some(h) if !h.lines.is_empty() && self.is_expanded(self.hunk_index, h) => h.lines.len(),
+            _ => return,
+        };
+        let line_index = match self.cursor_line() {
+            Some(li) if down => (li + 1).min(len - 1),
+            Some(li) => li.saturating_sub(1),
+            None if down => 0,
+            None => len - 1,
+        };
+        self.line_cursor = Some(LineCursor {
+            file_index: self.file_index,
+            hunk_index: self.hunk_index,
+            line_index,
+        });
+        self.ensure_visible();
+    }
+
+    pub(super) fn clear_line_cursor(&mut self) {
+        self.line_cursor = None;
+    }
+
+    /// Row of line `li` relative to the hunk header in the expanded layout
+    /// (hunk-level comments sit under the header, anchored ones under their line)
+    fn hunk_row_of_line(&self, hi: usize, hunk: &Hunk, li: usize) -> usize {
+        let above = hunk
+            .comments
+            .iter()
+            .filter(|c| c.line_index().is_none_or(|a| a < li))
+            .count();
+        let rows: usize = (0..li).map(|i| self.line_rows(hi, hunk, i)).sum();
+        1 + above + rows
+    }
+
+    // --- Comments ---
+
+    /// Indices of comments attached to the cursor line (or the hunk itself)
+    pub(super) fn comments_at_cursor(&self) -> Vec<usize> {
+        let anchor = self.cursor_line();
+        self.current_hunk().map_or_else(Vec::new, |h| {
+            h.comments
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| c.line_index() == anchor)
+                .map(|(i, _)| i)
+                .collect()
+        })
+    }
+
+    /// Save the comment being edited: append a new one, or replace the
+    /// selected one. Empty input deletes the selected comment.
+    pub(super) fn set_current_comment(&mut self, input: String, severity: Severity) {
+        self.record_review("comment", self.file_index, self.hunk_index);
+        let edit_index = self.comment_edit_index.take();
+        let anchor = self
+            .cursor_line()
+            .and_then(|li| self.current_hunk().and_then(|h| h.anchor_at(li)));
         if let Some(hunk) = self.current_hunk_mut() {
-            hunk.comment = if comment.is_empty() {
-                None
-            } else {
-                Some(comment)
-            };
+            let comment = Comment::from_input(&input, severity).with_anchor(anchor);
+            let empty = comment.text.is_empty() && comment.tags.is_empty();
+            match edit_index {
+                Some(i) if i < hunk.comments.len() => {
+                    if empty {
+                        hunk.comments.remove(i);
+                    } else {
+                        hunk.comments[i] = comment;
+                    }
+                }
+                _ if !empty => hunk.comments.push(comment),
+                _ => {}
+            }
+        }
+    }
+
+    /// Delete the comment selected in the editor
+    pub(super) fn delete_edited_comment(&mut self) {
+        if self.comment_edit_index.is_some() {
+            let severity = self.comment_severity;
+            self.set_current_comment(String::new(), severity);
+        }
+    }
+
+    /// Start editing a new comment at the line cursor (or on the hunk)
+    pub(super) fn begin_comment_edit(&mut self) {
+        self.comment_edit_index = None;
+        self.comment_input.clear();
+        self.comment_severity = match self.current_hunk() {
+            Some(h) if h.status == ReviewStatus::Rejected => Severity::Blocking,
+            _ => Severity::Nit,
+        };
+    }
+
+    /// Cycle the editor between "new comment" and existing comments at the
+    /// cursor, loading the selected comment's text and severity
+    pub(super) fn cycle_comment_edit(&mut self, forward: bool) {
+        let targets = self.comments_at_cursor();
+        if targets.is_empty() {
+            return;
+        }
+        // Slot 0 is "new comment", slots 1.. are existing comments
+        let slots = targets.len() + 1;
+        let current = self
+            .comment_edit_index
+            .and_then(|i| targets.iter().position(|&t| t == i))
+            .map_or(0, |p| p + 1);
+        let next = if forward {
+            (current + 1) % slots
+        } else {
+            (current + slots - 1) % slots
+        };
+        if next == 0 {
+            self.begin_comment_edit();
+            return;
+        }
+        let ci = targets[next - 1];
+        if let Some((input, severity)) = self
+            .current_hunk()
+            .and_then(|h| h.comments.get(ci))
+            .map(|c| (c.to_input(), c.severity))
+        {
+            self.comment_input = input;
+            self.comment_severity = severity;
         }
+        self.comment_edit_index = Some(ci);
+    }
+
+    pub(super) fn set_summary(&mut self, summary: String) {
+        self.history
+            .record("summary", vec![Change::summary(&self.diff)]);
+        let summary = summary.trim();
+        self.diff.summary = if summary.is_empty() {
+            None
+        } else {
+            Some(summary.to_string())
+        };
     }
 
     pub(super) fn flat_hunk_index(&self) -> usize {
@@ -514,14 +1452,402 @@ impl AppState {
         }
     }
 
+    // --- File tree ---
+
+    /// Whether a file passes the file tree filter
+    pub(super) fn file_visible(&self, fi: usize) -> bool {
+        if self.tree_filter.is_empty() {
+            return true;
+        }
+        self.diff.files.get(fi).is_some_and(|f| {
+            f.path()
+                .to_lowercase()
+                .contains(&self.tree_filter.to_lowercase())
+        })
+    }
+
+    /// Visible file tree rows
+    pub(super) fn tree_rows(&self) -> Vec<TreeRow> {
+        tree::build_tree_rows(
+            &self.diff.files,
+            |fi| self.file_visible(fi),
+            &self.tree_collapsed,
+            self.tree_sort,
+        )
+    }
+
+    /// Filtered files in tree order, ignoring collapsed directories
+    fn file_order(&self) -> Vec<usize> {
+        tree::build_tree_rows(
+            &self.diff.files,
+            |fi| self.file_visible(fi),
+            &HashSet::new(),
+            self.tree_sort,
+        )
+        .into_iter()
+        .filter_map(|row| match row.entry {
+            TreeEntry::File(fi) => Some(fi),
+            TreeEntry::Dir { .. } => None,
+        })
+        .collect()
+    }
+
+    /// Index of the selected row among `rows`
+    pub(super) fn tree_selected_row(&self, rows: &[TreeRow]) -> Option<usize> {
+        rows.iter()
+            .position(|row| match (&row.entry, &self.tree_selected_dir) {
+                (TreeEntry::Dir { path, .. }, Some(dir)) => path == dir,
+                (TreeEntry::File(fi), None) => *fi == self.file_index,
+                _ => false,
+            })
+    }
+
+    /// First visible tree row, keeping the selected row on screen
+    pub(super) fn tree_scroll(&self, rows: &[TreeRow]) -> usize {
+        let height = self.tree_height.max(1);
+        let selected = self.tree_selected_row(rows).unwrap_or(0);
+        let max_scroll = rows.len().saturating_sub(height);
+        selected.saturating_sub(height - 1).min(max_scroll)
+    }
+
+    /// Move the tree selection one row down/up; landing on a file opens it
+    pub(super) fn tree_move(&mut self, down: bool) {
+        let rows = self.tree_rows();
+        if rows.is_empty() {
+            return;
+        }
+        let target = match self.tree_selected_row(&rows) {
+            Some(r) if down => (r + 1).min(rows.len() - 1),
+            Some(r) => r.saturating_sub(1),
+            None => 0,
+        };
+        self.select_tree_entry(&rows[target].entry);
+    }
+
+    fn select_tree_entry(&mut self, entry: &TreeEntry) {
+        match entry {
+            TreeEntry::Dir { path, .. } => self.tree_selected_dir = Some(path.clone()),
+            TreeEntry::File(fi) => self.select_file(*fi),
+        }
+    }
+
+    /// Enter/Space in the file tree: toggle a directory, or focus the diff for a file
+    pub(super) fn tree_activate(&mut self) {
+        match self.tree_selected_dir.clone() {
+            Some(dir) => self.toggle_dir(&dir),
+            None => self.focus = Focus::DiffView,
+        }
+    }
+
+    pub(super) fn toggle_dir(&mut self, dir: &str) {
+        if !self.tree_collapsed.remove(dir) {
+            self.tree_collapsed.insert(dir.to_string());
+        }
+    }
+
+    pub(super) fn cycle_tree_sort(&mut self) {
+        self.tree_sort = self.tree_sort.next();
+    }
+
+    /// Re-apply the tree filter; jump to the first match if the current file is hidden
+    pub(super) fn apply_tree_filter(&mut self) {
+        self.tree_selected_dir = None;
+        if !self.file_visible(self.file_index)
+            && let Some(&fi) = self.file_order().first()
+        {
+            self.select_file(fi);
+        }
+    }
+
+    pub(super) fn clear_tree_filter(&mut self) {
+        self.tree_filter.clear();
+        self.tree_selected_dir = None;
+    }
+
+    // --- File finder ---
+
+    pub(super) fn open_finder(&mut self) {
+        self.finder_query.clear();
+        self.finder_cursor = 0;
+        self.mode = AppMode::FileFinder;
+    }
+
+    /// Files matching the finder query, best match first
+    pub(super) fn finder_results(&self) -> Vec<(usize, FuzzyMatch)> {
+        fuzzy::rank(
+            &self.finder_query,
+            self.diff
+                .files
+                .iter()
+                .enumerate()
+                .map(|(i, f)| (i, f.path())),
+        )
+    }
+
+    /// File under the finder cursor (shown in the preview)
+    pub(super) fn finder_selected(&self) -> Option<usize> {
+        self.finder_results()
+            .get(self.finder_cursor)
+            .map(|(fi, _)| *fi)
+    }
+
+    pub(super) fn finder_move(&mut self, down: bool) {
+        let len = self.finder_results().len();
+        if len == 0 {
+            return;
+        }
+        self.finder_cursor = if down {
+            (self.finder_cursor + 1) % len
+        } else {
+            (self.finder_cursor + len - 1) % len
+        };
+    }
+
+    /// Query edits reset the cursor to the best match
+    pub(super) fn finder_input(&mut self, c: char) {
+        self.finder_query.push(c);
+        self.finder_cursor = 0;
+    }
+
+    pub(super) fn finder_backspace(&mut self) {
+        self.finder_query.pop();
+        self.finder_cursor = 0;
+    }
+
+    /// Jump to the selected file, keeping its remembered hunk position
+    pub(super) fn finder_jump(&mut self) {
+        if let Some(fi) = self.finder_selected() {
+            self.select_file(fi);
+        }
+        self.finder_query.clear();
+        self.mode = AppMode::Normal;
+    }
+
+    // --- Command line ---
+
+    pub(super) fn open_command(&mut self) {
+        self.command_input.clear();
+        self.command_history_pos = None;
+        self.mode = AppMode::Command;
+    }
+
+    /// Run the typed command and record it in the history
+    pub(super) fn submit_command(&mut self) {
+        let line = std::mem::take(&mut self.command_input);
+        self.mode = AppMode::Normal;
+        if line.trim().is_empty() {
+            return;
+        }
+        if self.command_history.last() != Some(&line) {
+            self.command_history.push(line.clone());
+        }
+        self.command_history_pos = None;
+        self.message = match command::run(&line, self) {
+            Ok(msg) if msg.is_empty() => None,
+            Ok(msg) => Some(msg),
+            Err(err) => Some(format!("error: {}", err)),
+        };
+    }
+
+    pub(super) fn command_history_move(&mut self, older: bool) {
+        let len = self.command_history.len();
+        let pos = match (self.command_history_pos, older) {
+            (None, true) if len > 0 => Some(len - 1),
+            (Some(p), true) => Some(p.saturating_sub(1)),
+            (Some(p), false) if p + 1 < len => Some(p + 1),
+            _ => None,
+        };
+        self.command_history_pos = pos;
+        self.command_input = pos
+            .map(|p| self.command_history[p].clone())
+            .unwrap_or_default();
+    }
+
+    pub(super) fn complete_command(&mut self) {
+        let paths: Vec<&str> = self.diff.files.iter().map(|f| f.path()).collect();
+        let (line, candidates) = command::complete(&self.command_input, &paths);
+        self.message = (candidates.len() > 1).then(|| candidates.join("  "));
+        self.command_input = line;
+    }
+
+    /// `:goto` — jump to a file (exact path, path suffix, then fuzzy match) and
+    /// to the hunk containing new-side `line`, placing the line cursor on it
+    pub(super) fn goto(&mut self, path: Option<&str>, line: Option<u32>) -> Result<String, String> {
+        if let Some(path) = path {
+            let suffix = format!("/{}", path);
+            let fi = self
+                .diff
+                .files
+                .iter()
+                .position(|f| f.path() == path)
+                .or_else(|| {
+                    self.diff
+                        .files
+                        .iter()
+                        .position(|f| f.path().ends_with(&suffix))
+                })
+                .or_else(|| {
+                    fuzzy::rank(
+                        path,
+                        self.diff
+                            .files
+                            .iter()
+                            .enumerate()
+                            .map(|(i, f)| (i, f.path())),
+                    )
+                    .first()
+                    .map(|(fi, _)| *fi)
+                })
+                .ok_or_else(|| format!("no file matches {}", path))?;
+            self.select_file(fi);
+        }
+        let Some(line) = line else {
+            return Ok(String::new());
+        };
+        let file = self.current_file().ok_or("no file")?;
+        // The hunk containing the line, else the last hunk starting before it
+        let hi = file
+            .hunks
+            .iter()
+            .position(|h| line >= h.new_start && line < h.new_start + h.new_count.max(1))
+            .or_else(|| file.hunks.iter().rposition(|h| h.new_start <= line))
+            .unwrap_or(0);
+        let li = file
+            .hunks
+            .get(hi)
+            .and_then(|h| h.line_numbers().iter().position(|&(_, n)| n == Some(line)));
+        self.hunk_index = hi;
+        if li.is_some() {
+            self.unfold(self.file_index, hi);
+        }
+        self.line_cursor = li.map(|line_index| LineCursor {
+            file_index: self.file_index,
+            hunk_index: hi,
+            line_index,
+        });
+        self.ensure_visible();
+        Ok(String::new())
+    }
+
+    // --- Editor ---
+
+    /// File and new-side line to open in `$EDITOR`: the line under the line
+    /// cursor (or the nearest new-side line above it), else the first added
+    /// line of the current hunk, else the hunk start. None for deleted files.
+    pub(super) fn editor_target(&self) -> Option<(String, u32)> {
+        let file = self.current_file()?;
+        if file.new_path == "/dev/null" || file.is_binary {
+            return None;
+        }
+        let Some(hunk) = self.current_hunk() else {
+            return Some((file.new_path.clone(), 1));
+        };
+        let numbers = hunk.line_numbers();
+        let line = self
+            .cursor_line()
+            .and_then(|li| numbers[..=li].iter().rev().find_map(|&(_, new)| new))
+            .or_else(|| {
+                hunk.lines
+                    .iter()
+                    .zip(&numbers)
+                    .find(|(l, _)| matches!(l, DiffLine::Added(_)))
+                    .and_then(|(_, &(_, new))| new)
+            })
+            .unwrap_or(hunk.new_start.max(1));
+        Some((file.new_path.clone(), line))
+    }
+
+    /// Swap in a diff generated again after editing. Review state carries over
+    /// to unchanged hunks, and the view returns to the edited file and line.
+    /// Undo history and folds refer to old hunk positions, so they are dropped.
+    pub(super) fn replace_diff(&mut self, mut diff: Diff) -> CarryOver {
+        let target = self.editor_target();
+        let report = diff.carry_review_from(&self.diff);
+        self.moved = moved::detect_moves(&diff);
+        self.diff = diff;
+        self.file_index = 0;
+        self.hunk_index = 0;
+        self.viewport_offset = 0;
+        self.history.clear();
+        self.folds.clear();
+        self.folded_files.clear();
+        self.file_hunk_positions.clear();
+        self.line_cursor = None;
+        self.stats_cursor = 0;
+        self.clear_search();
+        if let Some((path, line)) = target
+            && self.diff.files.iter().any(|f| f.path() == path)
+        {
+            let _ = self.goto(Some(&path), Some(line));
+        }
+        self.ensure_visible();
+        report
+    }
+
+    /// Replace the current hunk with `pieces` (a split or an edited hunk).
+    /// Folds of later hunks shift along; search matches are dropped.
+    pub(super) fn replace_current_hunk(&mut self, label: &str, pieces: Vec<Hunk>) {
+        let (fi, hi) = (self.file_index, self.hunk_index);
+        let added = pieces.len().saturating_sub(1);
+        if self.current_hunk().is_none() {
+            return;
+        }
+        self.history
+            .record(label, Change::hunks(&self.diff, fi).into_iter().collect());
+        self.diff.files[fi].hunks.splice(hi..=hi, pieces);
+        if added > 0 {
+            self.folds = self
+                .folds
+                .drain()
+                .map(|((f, h), folded)| {
+                    let h = if f == fi && h > hi { h + added } else { h };
+                    ((f, h), folded)
+                })
+                .collect();
+        }
+        self.clear_search();
+        self.line_cursor = None;
+        self.moved = moved::detect_moves(&self.diff);
+        self.ensure_visible();
+    }
+
+    /// Split the current hunk before the line cursor (`git add -p` `s`, at any line)
+    pub(super) fn split_current_hunk(&mut self) -> Result<String, String> {
+        let li = self
+            .cursor_line()
+            .ok_or("move the line cursor (J/K) to where the hunk should split")?;
+        let hunk = self.current_hunk().ok_or("no hunk")?;
+        if hunk.original_lines.is_some() {
+            return Err("an edited hunk can't be split; split it before editing".to_string());
+        }
+        let (first, second) =
+            hunk_edit::split_at(hunk, li).ok_or("nothing to split: both parts need a change")?;
+        self.replace_current_hunk("split", vec![first, second]);
+        Ok("split hunk in two".to_string())
+    }
+
+    /// Map a row inside the file tree pane to its entry (for mouse clicks)
+    pub(super) fn tree_entry_at(&self, row: u16) -> Option<TreeEntry> {
+        let rows = self.tree_rows();
+        let index = self.tree_scroll(&rows) + row as usize;
+        rows.into_iter().nth(index).map(|r| r.entry)
+    }
+
     /// Map row to file index for mouse clicks in file tree
     pub(super) fn row_to_file_index(&self, row: u16) -> Option<usize> {
-        // File tree starts at row 0 (after borders)
-        let file_idx = row as usize;
-        if file_idx < self.diff.files.len() {
-            Some(file_idx)
-        } else {
-            None
+        match self.tree_entry_at(row)? {
+            TreeEntry::File(fi) => Some(fi),
+            TreeEntry::Dir { .. } => None,
+        }
+    }
+
+    /// Handle a click on a file tree row: toggle a directory or open a file
+    pub(super) fn click_tree_row(&mut self, row: u16) {
+        if let Some(fi) = self.row_to_file_index(row) {
+            self.select_file(fi);
+        } else if let Some(TreeEntry::Dir { path, .. }) = self.tree_entry_at(row) {
+            self.toggle_dir(&path);
+            self.tree_selected_dir = Some(path);
         }
     }
 }
@@ -540,23 +1866,192 @@ mod tests {
             new_count: 1,
             lines: vec![DiffLine::Context("x".to_string())],
             status,
-            comment: None,
+            comments: Vec::new(),
+            rule: None,
+            edited: false,
+            original_lines: None,
+        }
+    }
+
+    fn make_file(name: &str, hunks: Vec<Hunk>) -> FileDiff {
+        FileDiff {
+            old_path: name.to_string(),
+            new_path: name.to_string(),
+            raw_old_path: format!("a/{}", name),
+            raw_new_path: format!("b/{}", name),
+            hunks,
+            is_binary: false,
+            viewed: false,
+            deferred: None,
         }
     }
 
-    fn make_file(name: &str, hunks: Vec<Hunk>) -> FileDiff {
-        FileDiff {
-            old_path: name.to_string(),
-            new_path: name.to_string(),
-            raw_old_path: format!("a/{}", name),
-            raw_new_path: format!("b/{}", name),
-            hunks,
-            is_binary: false,
+    fn make_state(files: Vec<FileDiff>) -> AppState {
+        AppState::new(Diff::new(files), &Config::default())
+    }
+
+    #[test]
+    fn test_append_files_while_loading() {
+        let mut state = make_state(vec![make_file(
+            "a.rs",
+            vec![make_hunk(ReviewStatus::Pending)],
+        )]);
+        state.loading = true;
+        let revision = state.history.revision;
+
+        state.append_files(
+            vec![make_file("b.rs", vec![make_hunk(ReviewStatus::Pending)])],
+            false,
+        );
+        assert_eq!(state.diff.files.len(), 2);
+        assert!(state.loading);
+        assert!(state.history.revision > revision);
+
+        let revision = state.history.revision;
+        state.append_files(Vec::new(), false);
+        assert_eq!(state.history.revision, revision);
+
+        state.append_files(Vec::new(), true);
+        assert!(!state.loading);
+        assert!(state.history.revision > revision);
+        assert!(state.load_current_file().is_ok());
+    }
+
+    /// Files from the background loader: `small.rs`, then `big.lock` whose
+    /// body is larger than DEFER_BYTES and stays in the spool
+    fn load_deferred_files() -> Vec<FileDiff> {
+        use std::fmt::Write as _;
+        let mut input = String::from(
+            "diff --git a/small.rs b/small.rs\n--- a/small.rs\n+++ b/small.rs\n@@ -1 +1 @@\n-a\n+b\n",
+        );
+        input.push_str("diff --git a/big.lock b/big.lock\n--- a/big.lock\n+++ b/big.lock\n");
+        for h in 0..200 {
+            writeln!(input, "@@ -{0},40 +{0},40 @@", h * 100 + 1).unwrap();
+            for i in 0..40 {
+                writeln!(input, "-name = \"old-{}-{}\"", h, i).unwrap();
+                writeln!(input, "+name = \"new-{}-{}\"", h, i).unwrap();
+            }
+        }
+        let mut loader = crate::lazy::Loader::spawn(
+            std::io::Cursor::new(input),
+            Vec::new(),
+            crate::parse::ParseOptions::default(),
+        )
+        .unwrap();
+        let mut files = Vec::new();
+        while let Some(file) = loader.next_file().unwrap() {
+            files.push(file);
+        }
+        assert!(files[1].deferred.is_some());
+        files
+    }
+
+    #[test]
+    fn test_search_deferred_files_on_submit() {
+        let mut state = make_state(load_deferred_files());
+        state.begin_search();
+        for c in "new-7-".chars() {
+            state.search_input(c);
+        }
+        // Typing doesn't parse spooled bodies
+        assert!(state.search_matches.is_empty());
+        assert!(state.diff.files[1].deferred.is_some());
+
+        state.submit_search();
+        assert_eq!(state.search_matches.len(), 40);
+        assert!(state.search_matches.iter().all(|m| m.hunk_index == 7));
+        assert_eq!(state.search_index, Some(0));
+        // The body was searched in a copy, not kept
+        assert!(state.diff.files[1].deferred.is_some());
+    }
+
+    #[test]
+    fn test_search_covers_appended_files() {
+        let mut files = load_deferred_files();
+        let big = files.pop().unwrap();
+        let mut state = make_state(files);
+        state.loading = true;
+        state.begin_search();
+        for c in "+:name|b".chars() {
+            state.search_input(c);
         }
+        state.submit_search();
+        assert_eq!(state.search_matches.len(), 1);
+        state.next_match();
+
+        state.append_files(vec![big], true);
+        assert_eq!(state.search_matches.len(), 1 + 200 * 40);
+        // The file sorts before small.rs; the selected match stays selected
+        assert_eq!(state.search_matches[0].file_index, 1);
+        let selected = &state.search_matches[state.search_index.unwrap()];
+        assert_eq!(selected.file_index, 0);
     }
 
-    fn make_state(files: Vec<FileDiff>) -> AppState {
-        AppState::new(Diff { files }, &Config::default())
+    #[test]
+    fn test_resume_restores_review_into_state() {
+        use clap::Parser;
+        let deleted = |path: &str| FileDiff {
+            new_path: "/dev/null".to_string(),
+            raw_new_path: "/dev/null".to_string(),
+            ..make_file(path, vec![make_hunk(ReviewStatus::Pending)])
+        };
+        let files = || {
+            vec![
+                make_file("a.rs", vec![make_hunk(ReviewStatus::Pending)]),
+                deleted("x.rs"),
+                deleted("y.rs"),
+            ]
+        };
+
+        // A review saved with `:write` (same format as `--json`)
+        let mut saved = Diff::new(files());
+        saved.files[0].viewed = true;
+        saved.files[1].hunks[0].status = ReviewStatus::Rejected;
+        saved.files[2].hunks[0].status = ReviewStatus::Accepted;
+        let path = std::env::temp_dir().join(format!("diffy-resume-{}.json", std::process::id()));
+        let mut json = Vec::new();
+        crate::output::write_json(&saved, &mut json).unwrap();
+        std::fs::write(&path, json).unwrap();
+
+        let cli =
+            crate::cli::Cli::try_parse_from(["diffy", "--resume", path.to_str().unwrap()]).unwrap();
+        assert!(!cli.is_scripted());
+        let mut diff = Diff::new(files());
+        cli.apply_saved_decisions(&mut diff).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut state = AppState::new(diff, &Config::default());
+        assert!(state.diff.files[0].viewed);
+        assert_eq!(state.diff.progress().viewed_files, 1);
+        assert_eq!(state.diff.files[1].hunks[0].status, ReviewStatus::Rejected);
+        assert_eq!(state.diff.files[2].hunks[0].status, ReviewStatus::Accepted);
+        // Tab skips the viewed file, and the rest is decided
+        assert!(!state.next_pending());
+    }
+
+    #[test]
+    fn test_parse_warnings_shown() {
+        let warning = |line| ParseError {
+            line,
+            kind: crate::parse::ParseErrorKind::UnexpectedLine("??".to_string()),
+        };
+        let mut diff = Diff::new(vec![make_file(
+            "a.rs",
+            vec![make_hunk(ReviewStatus::Pending)],
+        )]);
+        diff.warnings = vec![warning(3), warning(9)];
+        let mut state = AppState::new(diff, &Config::default());
+        assert_eq!(
+            state.message.as_deref(),
+            Some("warning: line 3: unexpected line `??` in hunk (+1 more)")
+        );
+
+        state.message = None;
+        state.add_warnings(Vec::new());
+        assert!(state.message.is_none());
+        state.add_warnings(vec![warning(20)]);
+        assert_eq!(state.diff.warnings.len(), 3);
+        assert!(state.message.unwrap().starts_with("warning: line 20"));
     }
 
     // --- Undo tests ---
@@ -573,6 +2068,21 @@ mod tests {
         assert_eq!(state.current_hunk().unwrap().status, ReviewStatus::Pending);
     }
 
+    #[test]
+    fn test_override_clears_rule_mark() {
+        let mut hunk = make_hunk(ReviewStatus::Accepted);
+        hunk.rule = Some("lockfiles".to_string());
+        let mut state = make_state(vec![make_file("Cargo.lock", vec![hunk])]);
+
+        state.set_current_status(ReviewStatus::Rejected);
+        assert_eq!(state.current_hunk().unwrap().rule, None);
+
+        state.undo();
+        let hunk = state.current_hunk().unwrap();
+        assert_eq!(hunk.status, ReviewStatus::Accepted);
+        assert_eq!(hunk.rule.as_deref(), Some("lockfiles"));
+    }
+
     #[test]
     fn test_undo_toggle() {
         let mut state = make_state(vec![make_file(
@@ -609,22 +2119,65 @@ mod tests {
             ),
             make_file("b.rs", vec![make_hunk(ReviewStatus::Pending)]),
         ]);
+        state.next_file();
         state.set_all_status(ReviewStatus::Accepted);
-        // 3 undo entries (one per hunk)
-        assert_eq!(state.undo_stack.len(), 3);
-        // Undo last (b.rs hunk 0)
-        state.undo();
-        assert_eq!(state.diff.files[1].hunks[0].status, ReviewStatus::Pending);
-        assert_eq!(state.file_index, 1);
-        assert_eq!(state.hunk_index, 0);
-        // Undo second (a.rs hunk 1)
+        // One undo step for the whole bulk action
+        assert_eq!(state.history.undo.len(), 1);
+        assert_eq!(state.undo(), "undid accept all (3 hunks)");
+        assert!(
+            state
+                .diff
+                .files
+                .iter()
+                .flat_map(|f| &f.hunks)
+                .all(|h| h.status == ReviewStatus::Pending)
+        );
+        // Navigates to the first hunk it touched
+        assert_eq!((state.file_index, state.hunk_index), (0, 0));
+        assert_eq!(state.undo(), "nothing to undo");
+
+        assert_eq!(state.redo(), "redid accept all (3 hunks)");
+        assert_eq!(state.diff.files[1].hunks[0].status, ReviewStatus::Accepted);
+        assert_eq!(state.redo(), "nothing to redo");
+    }
+
+    #[test]
+    fn test_undo_viewed_and_summary() {
+        let mut state = make_state(vec![make_file(
+            "a.rs",
+            vec![make_hunk(ReviewStatus::Pending)],
+        )]);
+        state.set_summary("looks good".to_string());
+        state.toggle_viewed();
+        assert_eq!(state.undo(), "undid viewed mark");
+        assert!(!state.diff.files[0].viewed);
+        assert_eq!(state.undo(), "undid summary");
+        assert_eq!(state.diff.summary, None);
+
+        // A new action drops what could be redone
+        state.set_current_status(ReviewStatus::Rejected);
+        assert_eq!(state.redo(), "nothing to redo");
+    }
+
+    #[test]
+    fn test_undo_limit() {
+        let mut config = Config::default();
+        config.defaults.undo_limit = 2;
+        let mut state = AppState::new(
+            Diff::new(vec![make_file(
+                "a.rs",
+                vec![make_hunk(ReviewStatus::Pending)],
+            )]),
+            &config,
+        );
+        for _ in 0..3 {
+            state.toggle_current_status();
+        }
+        assert_eq!(state.history.undo.len(), 2);
         state.undo();
-        assert_eq!(state.diff.files[0].hunks[1].status, ReviewStatus::Pending);
-        assert_eq!(state.file_index, 0);
-        assert_eq!(state.hunk_index, 1);
-        // Undo first (a.rs hunk 0)
         state.undo();
-        assert_eq!(state.diff.files[0].hunks[0].status, ReviewStatus::Pending);
+        // The first toggle fell off the history
+        assert_eq!(state.current_hunk().unwrap().status, ReviewStatus::Accepted);
     }
 
     #[test]
@@ -704,6 +2257,37 @@ mod tests {
         assert_eq!(state.hunk_index, 1);
     }
 
+    #[test]
+    fn test_viewed_files_fold_and_are_skipped() {
+        let mut state = make_state(vec![
+            make_file("a.rs", vec![make_hunk(ReviewStatus::Pending)]),
+            make_file("b.rs", vec![make_hunk(ReviewStatus::Pending)]),
+            make_file("c.rs", vec![make_hunk(ReviewStatus::Pending)]),
+        ]);
+        state.file_index = 1;
+        state.toggle_viewed();
+        assert!(state.diff.files[1].viewed);
+        assert!(state.is_folded(1, 0));
+
+        // Tab skips b.rs unless skip_viewed is off, and stops when only viewed files are left
+        state.file_index = 0;
+        assert!(state.next_pending());
+        assert_eq!(state.file_index, 2);
+        state.skip_viewed = false;
+        state.file_index = 0;
+        assert!(state.next_pending());
+        assert_eq!(state.file_index, 1);
+        state.skip_viewed = true;
+        state.diff.files[0].hunks[0].status = ReviewStatus::Accepted;
+        state.diff.files[2].hunks[0].status = ReviewStatus::Accepted;
+        assert!(!state.next_pending());
+
+        // Unmarking drops the fold again
+        state.file_index = 1;
+        state.toggle_viewed();
+        assert!(!state.is_folded(1, 0));
+    }
+
     #[test]
     fn test_next_pending_none() {
         let mut state = make_state(vec![make_file(
@@ -746,7 +2330,10 @@ mod tests {
                 .map(|i| DiffLine::Context(format!("line{}", i)))
                 .collect(),
             status,
-            comment: None,
+            comments: Vec::new(),
+            rule: None,
+            edited: false,
+            original_lines: None,
         }
     }
 
@@ -766,6 +2353,126 @@ mod tests {
         assert_eq!(state.virtual_doc_height(), 8);
     }
 
+    #[test]
+    fn test_virtual_doc_height_with_wrap() {
+        let mut hunk = make_hunk_with_lines(0, ReviewStatus::Pending);
+        hunk.lines = vec![
+            DiffLine::Added("0123456789abcdefghij".to_string()),
+            DiffLine::Added("가나다라마바".to_string()),
+            DiffLine::Context("\tx".to_string()),
+        ];
+        let mut state = make_state(vec![make_file("a.rs", vec![hunk])]);
+        // Gutter prefix is 8 columns → 10 columns of content
+        state.viewport_width = 18;
+        assert_eq!(state.virtual_doc_height(), 4);
+
+        state.wrap = true;
+        // header + 2 + 2 (wide chars) + 1
+        assert_eq!(state.virtual_doc_height(), 6);
+
+        // Line cursor rows account for the wrapped lines above
+        state.viewport_height = 3;
+        state.line_cursor = Some(LineCursor {
+            file_index: 0,
+            hunk_index: 0,
+            line_index: 2,
+        });
+        state.ensure_visible();
+        assert_eq!(state.viewport_offset, 3);
+
+        // Side-by-side layout never wraps
+        state.diff_view_mode = DiffViewMode::SideBySide;
+        state.viewport_width = 120;
+        assert_eq!(state.virtual_doc_height(), 4);
+    }
+
+    #[test]
+    fn test_whitespace_only_hunks_collapse_and_bulk_accept() {
+        let mut reindent = make_hunk_with_lines(0, ReviewStatus::Pending);
+        reindent.lines = vec![
+            DiffLine::Removed("\tx();".to_string()),
+            DiffLine::Added("    x();".to_string()),
+        ];
+        let mut blank = make_hunk_with_lines(1, ReviewStatus::Pending);
+        blank.lines.push(DiffLine::Added(String::new()));
+        let mut real = make_hunk_with_lines(0, ReviewStatus::Pending);
+        real.lines = vec![DiffLine::Added("y();".to_string())];
+        let mut state = make_state(vec![make_file("a.rs", vec![reindent, blank, real])]);
+
+        // Without -w the current hunk expands as usual
+        assert_eq!(state.virtual_doc_height(), 1 + 2 + 1 + 1);
+        state.ignore_whitespace.all_space = true;
+        // Whitespace-only current hunk stays collapsed, line cursor cannot enter it
+        assert!(!state.is_expanded(0, &state.diff.files[0].hunks[0]));
+        assert_eq!(state.virtual_doc_height(), 3);
+        state.move_line_cursor(true);
+        assert_eq!(state.cursor_line(), None);
+
+        // With -w only the re-indent is accepted; with no option both are
+        assert_eq!(state.accept_whitespace_only().unwrap(), 1);
+        state.ignore_whitespace.all_space = false;
+        assert_eq!(state.accept_whitespace_only().unwrap(), 1);
+        let statuses: Vec<ReviewStatus> =
+            state.diff.files[0].hunks.iter().map(|h| h.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ReviewStatus::Accepted,
+                ReviewStatus::Accepted,
+                ReviewStatus::Pending
+            ]
+        );
+        state.undo();
+        assert_eq!(state.diff.files[0].hunks[1].status, ReviewStatus::Pending);
+    }
+
+    #[test]
+    fn test_moved_block_annotation_and_jump() {
+        let diff = crate::parse::parse_diff(
+            "diff --git a/a.rs b/a.rs
+--- a/a.rs
++++ b/a.rs
+@@ -1,5 +1,1 @@
+ keep();
+-fn moved_helper() {
+-    let value = compute_something();
+-    value + 1
+-}
+diff --git a/b.rs b/b.rs
+--- a/b.rs
++++ b/b.rs
+@@ -10,1 +10,5 @@
+ other();
++fn moved_helper() {
++    let value = compute_something();
++    value + 1
++}
+",
+        )
+        .unwrap();
+        let mut state = AppState::new(diff, &Config::default());
+        assert_eq!(state.moved.blocks(), 1);
+
+        // header + 5 lines + "moved to" annotation under the first removed line
+        assert_eq!(state.virtual_doc_height(), 7);
+        state.color_moved = false;
+        assert_eq!(state.virtual_doc_height(), 6);
+        state.color_moved = true;
+
+        state.move_line_cursor(true);
+        assert!(state.jump_moved().is_ok());
+        assert_eq!(state.file_index, 1);
+        assert_eq!(state.cursor_line(), Some(1));
+        // Jumping back from the destination reports where it was moved to
+        assert_eq!(state.jump_moved(), Ok("moved to b.rs:11".to_string()));
+        assert_eq!(state.file_index, 0);
+        assert_eq!(state.cursor_line(), Some(1));
+        assert_eq!(
+            state.line_location(state.moved.get(0, 0, 1).unwrap().counterpart),
+            "b.rs:11"
+        );
+    }
+
     #[test]
     fn test_ensure_visible_scrolls_down() {
         let mut state = make_state(vec![make_file(
@@ -938,7 +2645,10 @@ mod tests {
                 DiffLine::Removed("baz qux".to_string()),
             ],
             status: ReviewStatus::Pending,
-            comment: None,
+            comments: Vec::new(),
+            rule: None,
+            edited: false,
+            original_lines: None,
         };
         let hunk1 = Hunk {
             header: "@@ -10,2 +10,2 @@".to_string(),
@@ -951,7 +2661,10 @@ mod tests {
                 DiffLine::Added("world peace".to_string()),
             ],
             status: ReviewStatus::Pending,
-            comment: None,
+            comments: Vec::new(),
+            rule: None,
+            edited: false,
+            original_lines: None,
         };
         let file0 = make_file("a.rs", vec![hunk0, hunk1]);
         let file1 = make_file(
@@ -964,7 +2677,10 @@ mod tests {
                 new_count: 1,
                 lines: vec![DiffLine::Context("hello b".to_string())],
                 status: ReviewStatus::Pending,
-                comment: None,
+                comments: Vec::new(),
+                rule: None,
+                edited: false,
+                original_lines: None,
             }],
         );
         make_state(vec![file0, file1])
@@ -981,13 +2697,76 @@ mod tests {
     }
 
     #[test]
-    fn test_search_case_insensitive() {
+    fn test_search_smart_case() {
         let mut state = make_search_state();
         state.search_query = "HELLO".to_string();
         state.execute_search();
+        assert!(state.search_matches.is_empty());
+        state.search_query = "hELLO".to_string();
+        state.execute_search();
+        assert!(state.search_matches.is_empty());
+        state.search_query = "hello".to_string();
+        state.execute_search();
         assert_eq!(state.search_matches.len(), 3);
     }
 
+    #[test]
+    fn test_search_regex_scope_and_filter() {
+        let mut state = make_search_state();
+        state.search_query = "^(hello|world) ".to_string();
+        state.execute_search();
+        assert_eq!(state.search_matches.len(), 4);
+
+        state.search_query = "+:world".to_string();
+        state.execute_search();
+        assert_eq!(state.search_matches.len(), 1);
+        assert_eq!(state.search_matches[0].line_index, Some(1));
+        assert_eq!(state.search_scope, SearchScope::Added);
+
+        state.search_query = "f:^b".to_string();
+        state.execute_search();
+        assert_eq!(state.search_matches.len(), 1);
+        assert_eq!(state.search_matches[0].file_index, 1);
+
+        // Files hidden by the tree filter are not searched
+        state.tree_filter = "b.rs".to_string();
+        state.search_query = "hello".to_string();
+        state.execute_search();
+        assert_eq!(state.search_matches.len(), 1);
+
+        state.search_query = "(".to_string();
+        state.execute_search();
+        assert_eq!(state.search_error.as_deref(), Some("invalid regex"));
+        assert!(!state.has_active_search());
+    }
+
+    #[test]
+    fn test_incremental_search_and_cancel() {
+        let mut state = make_search_state();
+        state.hunk_index = 1;
+        state.begin_search();
+        for c in "hel".chars() {
+            state.search_input(c);
+        }
+        // First match at/after the origin hunk (f0, h1)
+        assert_eq!(state.search_index, Some(1));
+        assert_eq!((state.file_index, state.hunk_index), (0, 1));
+        state.search_input('l');
+        state.search_input('o');
+        state.search_input(' ');
+        state.search_input('b');
+        assert_eq!((state.file_index, state.hunk_index), (1, 0));
+
+        state.search_input('z'); // no match: back to the origin
+        assert_eq!((state.file_index, state.hunk_index), (0, 1));
+        state.search_backspace();
+        assert_eq!(state.file_index, 1);
+
+        state.cancel_search();
+        assert_eq!((state.file_index, state.hunk_index), (0, 1));
+        assert!(state.search_query.is_empty());
+    }
+
     #[test]
     fn test_search_no_match() {
         let mut state = make_search_state();
@@ -1108,7 +2887,6 @@ mod tests {
 
     // --- Mouse support tests ---
 
-
     #[test]
     fn test_mouse_file_tree_coordinate_mapping() {
         let state = make_state(vec![
@@ -1127,6 +2905,235 @@ mod tests {
         assert_eq!(state.row_to_file_index(3), None);
     }
 
+    fn make_tree_state() -> AppState {
+        make_state(vec![
+            make_file("src/b.rs", vec![make_hunk(ReviewStatus::Pending)]),
+            make_file("README.md", vec![make_hunk(ReviewStatus::Pending)]),
+            make_file("src/a.rs", vec![make_hunk(ReviewStatus::Pending)]),
+        ])
+    }
+
+    #[test]
+    fn test_mouse_file_tree_directory_rows() {
+        let mut state = make_tree_state();
+        // Rows: src/, a.rs, b.rs, README.md
+        assert_eq!(state.row_to_file_index(0), None);
+        assert_eq!(state.row_to_file_index(1), Some(2));
+        assert_eq!(state.row_to_file_index(3), Some(1));
+
+        // Clicking the directory collapses it
+        state.click_tree_row(0);
+        assert!(state.tree_collapsed.contains("src"));
+        assert_eq!(state.row_to_file_index(1), Some(1));
+        assert_eq!(state.row_to_file_index(2), None);
+    }
+
+    #[test]
+    fn test_tree_move_follows_tree_order() {
+        let mut state = make_tree_state();
+        state.file_index = 2; // src/a.rs
+        state.tree_move(true);
+        assert_eq!(state.file_index, 0); // src/b.rs
+        state.tree_move(true);
+        assert_eq!(state.file_index, 1); // README.md
+        state.tree_move(false);
+        state.tree_move(false);
+        state.tree_move(false);
+        assert_eq!(state.tree_selected_dir.as_deref(), Some("src"));
+
+        // Enter on the directory collapses it; selecting a file inside expands it again
+        state.tree_activate();
+        assert!(state.tree_collapsed.contains("src"));
+        state.select_file(0);
+        assert!(state.tree_collapsed.is_empty());
+        assert_eq!(state.tree_selected_dir, None);
+    }
+
+    #[test]
+    fn test_next_file_skips_filtered_files() {
+        let mut state = make_tree_state();
+        state.tree_filter = "SRC".to_string();
+        state.file_index = 1; // README.md, hidden by the filter
+        state.apply_tree_filter();
+        assert_eq!(state.file_index, 2); // first match in tree order: src/a.rs
+        state.next_file();
+        assert_eq!(state.file_index, 0);
+        state.next_file();
+        assert_eq!(state.file_index, 0); // README.md is filtered out
+
+        state.clear_tree_filter();
+        state.next_file();
+        assert_eq!(state.file_index, 1);
+    }
+
+    #[test]
+    fn test_tree_scroll_keeps_selection_visible() {
+        let files = (0..10)
+            .map(|i| {
+                make_file(
+                    &format!("f{}.rs", i),
+                    vec![make_hunk(ReviewStatus::Pending)],
+                )
+            })
+            .collect();
+        let mut state = make_state(files);
+        state.tree_height = 4;
+        state.file_index = 6;
+        let rows = state.tree_rows();
+        assert_eq!(state.tree_scroll(&rows), 3);
+        assert_eq!(state.row_to_file_index(0), Some(3));
+    }
+
+    #[test]
+    fn test_finder_jump_preserves_hunk_position() {
+        let mut state = make_tree_state();
+        state.file_index = 0;
+        state.hunk_index = 0;
+        state.file_hunk_positions.insert(2, 0);
+        state.open_finder();
+        state.finder_input('x');
+        assert_eq!(state.finder_selected(), None);
+        state.finder_backspace();
+        for c in "rs".chars() {
+            state.finder_input(c);
+        }
+        // Equal scores: shorter path first, then diff order
+        assert_eq!(state.finder_selected(), Some(0));
+        state.finder_move(true);
+        assert_eq!(state.finder_selected(), Some(2));
+
+        state.finder_jump();
+        assert_eq!(state.mode, AppMode::Normal);
+        assert_eq!(state.file_index, 2);
+        assert_eq!(state.file_hunk_positions.get(&0), Some(&0));
+    }
+
+    #[test]
+    fn test_editor_target_and_replace_diff() {
+        let edited_hunk = |status| {
+            let mut hunk = make_hunk(status);
+            hunk.new_start = 10;
+            hunk.lines = vec![
+                DiffLine::Context("c".to_string()),
+                DiffLine::Removed("old".to_string()),
+                DiffLine::Added("new".to_string()),
+            ];
+            hunk
+        };
+        let mut state = make_state(vec![
+            make_file("a.rs", vec![make_hunk(ReviewStatus::Pending)]),
+            make_file("b.rs", vec![edited_hunk(ReviewStatus::Pending)]),
+        ]);
+        state.select_file(1);
+        // First added line, or the new-side line at/above the line cursor
+        assert_eq!(state.editor_target(), Some(("b.rs".to_string(), 11)));
+        state.move_line_cursor(true);
+        state.move_line_cursor(true);
+        assert_eq!(state.editor_target(), Some(("b.rs".to_string(), 10)));
+        state.set_current_status(ReviewStatus::Accepted);
+
+        // The accepted hunk survives re-diffing; the reviewer's fix is marked edited
+        let mut fix = make_hunk(ReviewStatus::Pending);
+        fix.lines = vec![DiffLine::Added("fix".to_string())];
+        let report = state.replace_diff(Diff::new(vec![
+            make_file("c.rs", vec![make_hunk(ReviewStatus::Pending)]),
+            make_file("a.rs", vec![make_hunk(ReviewStatus::Pending)]),
+            make_file("b.rs", vec![edited_hunk(ReviewStatus::Pending), fix]),
+        ]));
+        assert_eq!((report.kept, report.edited, report.dropped), (2, 2, 0));
+        assert_eq!(state.current_file().unwrap().path(), "b.rs");
+        let hunks = &state.diff.files[2].hunks;
+        assert_eq!(hunks[0].status, ReviewStatus::Accepted);
+        assert!(hunks[1].edited);
+        assert!(state.history.undo.is_empty());
+
+        let mut deleted = make_file("gone.rs", vec![make_hunk(ReviewStatus::Pending)]);
+        deleted.new_path = "/dev/null".to_string();
+        state.replace_diff(Diff::new(vec![deleted]));
+        assert_eq!(state.editor_target(), None);
+    }
+
+    #[test]
+    fn test_split_current_hunk_shifts_later_hunks() {
+        let mut two_changes = make_hunk(ReviewStatus::Pending);
+        two_changes.lines = vec![
+            DiffLine::Removed("a".to_string()),
+            DiffLine::Added("b".to_string()),
+            DiffLine::Removed("c".to_string()),
+            DiffLine::Added("d".to_string()),
+        ];
+        let mut state = make_state(vec![make_file(
+            "a.rs",
+            vec![two_changes, make_hunk(ReviewStatus::Pending)],
+        )]);
+        state.hunk_index = 1;
+        state.set_current_status(ReviewStatus::Accepted);
+        state.folds.insert((0, 1), false);
+        state.hunk_index = 0;
+
+        // The split point is the line cursor
+        assert!(state.split_current_hunk().is_err());
+        state.move_line_cursor(true);
+        assert!(state.split_current_hunk().is_err());
+        state.move_line_cursor(true);
+        state.move_line_cursor(true);
+        assert_eq!(
+            state.split_current_hunk().as_deref(),
+            Ok("split hunk in two")
+        );
+
+        let hunks = &state.diff.files[0].hunks;
+        assert_eq!(hunks.len(), 3);
+        assert_eq!(hunks[0].lines.len(), 2);
+        assert_eq!(hunks[1].old_start, 2);
+        assert_eq!(hunks[2].status, ReviewStatus::Accepted);
+        assert_eq!(state.hunk_index, 0);
+        assert!(state.line_cursor.is_none());
+        assert_eq!(state.folds.get(&(0, 2)), Some(&false));
+
+        // Undoing the split restores the hunk list; the accept is next in line
+        assert_eq!(state.undo(), "undid split");
+        assert_eq!(state.diff.files[0].hunks.len(), 2);
+        assert!(state.folds.is_empty());
+        assert_eq!(state.undo(), "undid accept");
+        assert_eq!(state.diff.files[0].hunks[1].status, ReviewStatus::Pending);
+        assert_eq!(state.redo(), "redid accept");
+        assert_eq!(state.redo(), "redid split");
+        assert_eq!(state.diff.files[0].hunks.len(), 3);
+    }
+
+    #[test]
+    fn test_command_bulk_regex_and_goto() {
+        let mut hunk = make_hunk_with_lines(3, ReviewStatus::Pending);
+        hunk.new_start = 10;
+        hunk.new_count = 3;
+        hunk.lines[1] = DiffLine::Added("let x = y.unwrap();".to_string());
+        let mut state = make_state(vec![
+            make_file("src/a.rs", vec![make_hunk(ReviewStatus::Pending), hunk]),
+            make_file("src/b.rs", vec![make_hunk(ReviewStatus::Pending)]),
+        ]);
+
+        assert_eq!(
+            command::run("reject /unwrap\\(\\)/", &mut state),
+            Ok("rejected 1 hunks".to_string())
+        );
+        assert_eq!(state.diff.files[0].hunks[1].status, ReviewStatus::Rejected);
+        assert_eq!(state.diff.files[0].hunks[0].status, ReviewStatus::Pending);
+
+        state.select_file(1);
+        assert_eq!(command::run("goto a.rs:11", &mut state), Ok(String::new()));
+        assert_eq!(state.file_index, 0);
+        assert_eq!(state.hunk_index, 1);
+        assert_eq!(state.cursor_line(), Some(1));
+
+        command::run("comment blocking: no unwrap #errors", &mut state).unwrap();
+        let comment = &state.diff.files[0].hunks[1].comments[0];
+        assert_eq!(comment.severity, Severity::Blocking);
+        assert_eq!(comment.line_index(), Some(1));
+
+        assert!(command::run("goto nothing.py", &mut state).is_err());
+    }
+
     #[test]
     fn test_scroll_via_mouse() {
         let mut state = make_state(vec![make_file(
@@ -1158,39 +3165,187 @@ mod tests {
             "a.rs",
             vec![make_hunk(ReviewStatus::Pending)],
         )]);
-        state.set_current_comment("old comment".to_string());
-        assert_eq!(
-            state.current_hunk().unwrap().comment,
-            Some("old comment".to_string())
-        );
-
-        state.set_current_comment("new comment".to_string());
-        assert_eq!(
-            state.current_hunk().unwrap().comment,
-            Some("new comment".to_string())
-        );
+        state.set_current_comment("old comment".to_string(), Severity::Nit);
+        state.comment_edit_index = Some(0);
+        state.set_current_comment("new comment".to_string(), Severity::Blocking);
+        let comments = &state.current_hunk().unwrap().comments;
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0].text, "new comment");
 
         state.undo();
-        assert_eq!(
-            state.current_hunk().unwrap().comment,
-            Some("old comment".to_string())
-        );
+        let restored = &state.current_hunk().unwrap().comments[0];
+        assert_eq!(restored.text, "old comment");
+        assert_eq!(restored.severity, Severity::Nit);
 
         state.undo();
-        assert_eq!(state.current_hunk().unwrap().comment, None);
+        assert!(state.current_hunk().unwrap().comments.is_empty());
+    }
+
+    #[test]
+    fn test_comment_add_multiple_and_delete() {
+        let mut state = make_state(vec![make_file(
+            "a.rs",
+            vec![make_hunk(ReviewStatus::Pending)],
+        )]);
+        state.set_current_comment("first".to_string(), Severity::Nit);
+        state.set_current_comment("second".to_string(), Severity::Question);
+        // Empty input for a new comment is a no-op
+        state.set_current_comment("".to_string(), Severity::Nit);
+        assert_eq!(state.current_hunk().unwrap().comments.len(), 2);
+
+        state.comment_edit_index = Some(0);
+        state.delete_edited_comment();
+        let comments = &state.current_hunk().unwrap().comments;
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0].text, "second");
+        assert_eq!(state.comment_edit_index, None);
+    }
+
+    #[test]
+    fn test_comment_anchored_to_line_cursor() {
+        let mut state = make_state(vec![make_file(
+            "a.rs",
+            vec![make_hunk_with_lines(3, ReviewStatus::Pending)],
+        )]);
+        state.move_line_cursor(true);
+        state.move_line_cursor(true);
+        assert_eq!(state.cursor_line(), Some(1));
+        state.set_current_comment("on line".to_string(), Severity::Nit);
+
+        let comment = &state.current_hunk().unwrap().comments[0];
+        assert_eq!(comment.line_index(), Some(1));
+        assert_eq!(comment.anchor.unwrap().new_line, Some(2));
+
+        // The cursor only applies to the hunk it was placed in
+        state.diff.files[0]
+            .hunks
+            .push(make_hunk_with_lines(2, ReviewStatus::Pending));
+        state.next_hunk();
+        assert_eq!(state.cursor_line(), None);
+    }
+
+    #[test]
+    fn test_move_line_cursor_clamps() {
+        let mut state = make_state(vec![make_file(
+            "a.rs",
+            vec![make_hunk_with_lines(3, ReviewStatus::Pending)],
+        )]);
+        state.move_line_cursor(false);
+        assert_eq!(state.cursor_line(), Some(2));
+        state.move_line_cursor(true);
+        assert_eq!(state.cursor_line(), Some(2));
+        state.move_line_cursor(false);
+        state.move_line_cursor(false);
+        state.move_line_cursor(false);
+        assert_eq!(state.cursor_line(), Some(0));
+        state.clear_line_cursor();
+        assert_eq!(state.cursor_line(), None);
+    }
+
+    #[test]
+    fn test_begin_comment_edit_default_severity() {
+        let mut state = make_state(vec![make_file(
+            "a.rs",
+            vec![
+                make_hunk(ReviewStatus::Rejected),
+                make_hunk(ReviewStatus::Accepted),
+            ],
+        )]);
+        state.begin_comment_edit();
+        assert_eq!(state.comment_severity, Severity::Blocking);
+
+        state.hunk_index = 1;
+        state.begin_comment_edit();
+        assert_eq!(state.comment_severity, Severity::Nit);
+    }
+
+    #[test]
+    fn test_cycle_comment_edit() {
+        let mut state = make_state(vec![make_file(
+            "a.rs",
+            vec![make_hunk_with_lines(3, ReviewStatus::Pending)],
+        )]);
+        state.set_current_comment("why? #api".to_string(), Severity::Question);
+        state.move_line_cursor(true);
+        state.set_current_comment("line note".to_string(), Severity::Nit);
+        state.clear_line_cursor();
+
+        // Only the hunk-level comment is selectable without a cursor
+        state.begin_comment_edit();
+        state.cycle_comment_edit(true);
+        assert_eq!(state.comment_edit_index, Some(0));
+        assert_eq!(state.comment_severity, Severity::Question);
+        assert_eq!(state.comment_input, "why? #api");
+
+        state.cycle_comment_edit(true);
+        assert_eq!(state.comment_edit_index, None);
+        assert!(state.comment_input.is_empty());
+
+        state.cycle_comment_edit(false);
+        assert_eq!(state.comment_edit_index, Some(0));
     }
 
     #[test]
-    fn test_comment_empty_clears() {
+    fn test_set_summary() {
         let mut state = make_state(vec![make_file(
             "a.rs",
             vec![make_hunk(ReviewStatus::Pending)],
         )]);
-        state.set_current_comment("comment".to_string());
-        assert!(state.current_hunk().unwrap().comment.is_some());
+        state.set_summary("  ship it  ".to_string());
+        assert_eq!(state.diff.summary, Some("ship it".to_string()));
+        state.set_summary("   ".to_string());
+        assert_eq!(state.diff.summary, None);
+    }
 
-        state.set_current_comment("".to_string());
-        assert!(state.current_hunk().unwrap().comment.is_none());
+    #[test]
+    fn test_folds_and_height() {
+        let mut state = make_state(vec![make_file(
+            "a.rs",
+            vec![
+                make_hunk_with_lines(5, ReviewStatus::Pending),
+                make_hunk_with_lines(3, ReviewStatus::Pending),
+                make_hunk_with_lines(2, ReviewStatus::Pending),
+            ],
+        )]);
+        state.diff.files[0].hunks[0]
+            .comments
+            .push(Comment::from_input("note", Severity::Nit));
+        assert_eq!(state.virtual_doc_height(), (1 + 1 + 5) + 1 + 1);
+
+        // za: a folded hunk is just its header, comments included
+        state.fold_current(None);
+        assert!(state.is_folded(0, 0));
+        assert_eq!(state.virtual_doc_height(), 3);
+        assert_eq!(state.cursor_line(), None);
+        state.fold_current(None);
+        assert_eq!(state.virtual_doc_height(), 9);
+
+        // Reviewed hunks fold automatically unless disabled or opened explicitly
+        state.diff.files[0].hunks[1].status = ReviewStatus::Accepted;
+        assert!(state.is_folded(0, 1));
+        state.fold_reviewed = false;
+        assert!(!state.is_folded(0, 1));
+        state.fold_reviewed = true;
+        state.hunk_index = 1;
+        state.fold_current(Some(false));
+        assert!(!state.is_folded(0, 1));
+        assert_eq!(state.virtual_doc_height(), 2 + (1 + 3) + 1);
+
+        // zM / zR override per-hunk choices
+        state.fold_all(true);
+        assert_eq!(state.virtual_doc_height(), 3);
+        state.fold_all(false);
+        assert!(!state.is_folded(0, 1));
+        assert_eq!(state.virtual_doc_height(), 2 + (1 + 3) + 1);
+
+        // zA folds the whole file; search and goto open the target hunk
+        state.toggle_file_fold();
+        assert!(state.is_folded(0, 1));
+        assert_eq!(state.virtual_doc_height(), 3);
+        state.toggle_file_fold();
+        state.fold_current(Some(true));
+        state.goto(None, Some(1)).unwrap();
+        assert!(!state.is_folded(0, state.hunk_index));
     }
 
     #[test]
@@ -1208,7 +3363,16 @@ mod tests {
         assert_eq!(state.virtual_doc_height(), 7);
 
         // Add comment to hunk1: hunk0 (1+5) + hunk1 (1+1) = 8
-        state.diff.files[0].hunks[1].comment = Some("test".to_string());
+        state.diff.files[0].hunks[1]
+            .comments
+            .push(Comment::from_input("test", Severity::Nit));
         assert_eq!(state.virtual_doc_height(), 8);
+
+        // Line comments add a row too: hunk0 (1+1+5) + hunk1 (1+1) = 9
+        let anchor = state.diff.files[0].hunks[0].anchor_at(2);
+        state.diff.files[0].hunks[0]
+            .comments
+            .push(Comment::from_input("line", Severity::Nit).with_anchor(anchor));
+        assert_eq!(state.virtual_doc_height(), 9);
     }
 }
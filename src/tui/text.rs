@@ -0,0 +1,339 @@
+//! Display-width aware text layout: tabs, truncation, padding, wrapping and
+//! horizontal slicing of styled spans
+
+use crate::model::Hunk;
+use ratatui::style::{Color, Style};
+use ratatui::text::Span;
+use std::borrow::Cow;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Terminal columns taken by a char (control chars count as 0)
+pub(super) fn char_width(c: char) -> usize {
+    c.width().unwrap_or(0)
+}
+
+/// Terminal columns taken by a string (CJK and emoji are 2 columns wide)
+pub(super) fn display_width(s: &str) -> usize {
+    s.width()
+}
+
+/// Replace tabs with spaces up to the next multiple of `tab_width`
+pub(super) fn expand_tabs(s: &str, tab_width: usize) -> Cow<'_, str> {
+    if !s.contains('\t') {
+        return Cow::Borrowed(s);
+    }
+    let tab_width = tab_width.max(1);
+    let mut out = String::with_capacity(s.len() + tab_width);
+    let mut col = 0;
+    for c in s.chars() {
+        if c == '\t' {
+            let n = tab_width - col % tab_width;
+            out.extend(std::iter::repeat_n(' ', n));
+            col += n;
+        } else {
+            out.push(c);
+            col += char_width(c);
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// Style used for the visible whitespace markers
+fn whitespace_marker_style(style: Style) -> Style {
+    style.fg(Color::DarkGray)
+}
+
+/// Expand tabs and make whitespace visible: tabs become `→` plus padding,
+/// trailing spaces `·` and carriage returns `␍` (markers are dimmed)
+pub(super) fn visualize_whitespace(spans: &[Span<'_>], tab_width: usize) -> Vec<Span<'static>> {
+    let tab_width = tab_width.max(1);
+    let chars: Vec<(char, Style)> = spans
+        .iter()
+        .flat_map(|s| s.content.chars().map(move |c| (c, s.style)))
+        .collect();
+    // Everything after the last visible char is trailing whitespace
+    let trailing_from = chars
+        .iter()
+        .rposition(|(c, _)| !c.is_whitespace())
+        .map_or(0, |i| i + 1);
+
+    let mut out: Vec<Span<'static>> = Vec::new();
+    let mut push = |text: &str, style: Style| match out.last_mut() {
+        Some(last) if last.style == style => last.content.to_mut().push_str(text),
+        _ => out.push(Span::styled(text.to_string(), style)),
+    };
+    let mut col = 0;
+    for (i, &(c, style)) in chars.iter().enumerate() {
+        match c {
+            '\t' => {
+                let n = tab_width - col % tab_width;
+                let marker = format!("→{}", " ".repeat(n - 1));
+                push(&marker, whitespace_marker_style(style));
+                col += n;
+            }
+            '\r' => {
+                push("␍", whitespace_marker_style(style));
+                col += 1;
+            }
+            ' ' if i >= trailing_from => {
+                push("·", whitespace_marker_style(style));
+                col += 1;
+            }
+            _ => {
+                push(c.encode_utf8(&mut [0; 4]), style);
+                col += char_width(c);
+            }
+        }
+    }
+    out
+}
+
+/// Truncate to at most `max` columns, ending with "..." when cut
+pub(super) fn truncate_to_width(s: &str, max: usize) -> String {
+    if display_width(s) <= max {
+        return s.to_string();
+    }
+    let (budget, ellipsis) = if max > 3 { (max - 3, "...") } else { (max, "") };
+    let mut out = String::new();
+    let mut width = 0;
+    for c in s.chars() {
+        let w = char_width(c);
+        if width + w > budget {
+            break;
+        }
+        out.push(c);
+        width += w;
+    }
+    out.push_str(ellipsis);
+    out
+}
+
+/// Keep the last columns of `s` (for paths), starting with "..." when cut
+pub(super) fn truncate_start_to_width(s: &str, max: usize) -> String {
+    if display_width(s) <= max {
+        return s.to_string();
+    }
+    let budget = max.saturating_sub(3);
+    let mut tail = Vec::new();
+    let mut width = 0;
+    for c in s.chars().rev() {
+        let w = char_width(c);
+        if width + w > budget {
+            break;
+        }
+        tail.push(c);
+        width += w;
+    }
+    let mut out = "...".to_string();
+    out.extend(tail.into_iter().rev());
+    out
+}
+
+/// Truncate or pad with spaces to exactly `width` columns
+pub(super) fn pad_to_width(s: &str, width: usize) -> String {
+    let mut out = truncate_to_width(s, width);
+    let w = display_width(&out);
+    out.extend(std::iter::repeat_n(' ', width.saturating_sub(w)));
+    out
+}
+
+/// `width`-cell bar filled in proportion to `done / total` (`███░░░`)
+pub(super) fn progress_bar(done: usize, total: usize, width: usize) -> String {
+    let filled = (done.min(total) * width)
+        .checked_div(total)
+        .unwrap_or(width);
+    let mut bar = "\u{2588}".repeat(filled);
+    bar.push_str(&"\u{2591}".repeat(width - filled));
+    bar
+}
+
+/// Columns `[skip, skip + width)` of the spans (horizontal scroll / clipping).
+/// A wide char cut by either edge is replaced by spaces so columns stay aligned.
+pub(super) fn slice_spans(spans: &[Span<'_>], skip: usize, width: usize) -> Vec<Span<'static>> {
+    let end = skip + width;
+    let mut out = Vec::new();
+    let mut col = 0;
+    for span in spans {
+        let mut text = String::new();
+        for c in span.content.chars() {
+            let w = char_width(c);
+            let (start, stop) = (col, col + w);
+            col = stop;
+            if stop <= skip || start >= end {
+                continue;
+            }
+            if start < skip || stop > end {
+                // Partially visible wide char
+                let visible = stop.min(end) - start.max(skip);
+                text.extend(std::iter::repeat_n(' ', visible));
+            } else {
+                text.push(c);
+            }
+        }
+        if !text.is_empty() {
+            out.push(Span::styled(text, span.style));
+        }
+        if col >= end {
+            break;
+        }
+    }
+    out
+}
+
+/// Row break positions (char offsets) when wrapping chars of the given
+/// widths at `width` columns. A wide char that does not fit moves to the next row.
+fn wrap_breaks(widths: impl Iterator<Item = usize>, width: usize) -> Vec<usize> {
+    let width = width.max(2);
+    let mut breaks = Vec::new();
+    let mut col = 0;
+    for (i, w) in widths.enumerate() {
+        if col + w > width && col > 0 {
+            breaks.push(i);
+            col = 0;
+        }
+        col += w;
+    }
+    breaks
+}
+
+/// Number of rows `s` takes when wrapped at `width` columns (at least 1)
+pub(super) fn wrapped_rows(s: &str, width: usize) -> usize {
+    1 + wrap_breaks(s.chars().map(char_width), width).len()
+}
+
+/// Split spans into rows of at most `width` columns, keeping styles
+pub(super) fn wrap_spans(spans: &[Span<'_>], width: usize) -> Vec<Vec<Span<'static>>> {
+    let chars: Vec<(char, ratatui::style::Style)> = spans
+        .iter()
+        .flat_map(|s| s.content.chars().map(move |c| (c, s.style)))
+        .collect();
+    let breaks = wrap_breaks(chars.iter().map(|(c, _)| char_width(*c)), width);
+
+    let mut rows = Vec::new();
+    let mut start = 0;
+    for end in breaks.into_iter().chain(std::iter::once(chars.len())) {
+        let mut row: Vec<Span<'static>> = Vec::new();
+        for &(c, style) in &chars[start..end] {
+            match row.last_mut() {
+                Some(last) if last.style == style => last.content.to_mut().push(c),
+                _ => row.push(Span::styled(c.to_string(), style)),
+            }
+        }
+        rows.push(row);
+        start = end;
+    }
+    rows
+}
+
+/// Width of the line-number gutter in the unified view for a hunk
+pub(super) fn gutter_width(hunk: &Hunk) -> usize {
+    let max_line = (hunk.old_start + hunk.old_count).max(hunk.new_start + hunk.new_count);
+    max_line.to_string().len()
+}
+
+/// Columns before the line content in the unified view:
+/// cursor mark (2) + old/new numbers with spaces + "| " marker (2)
+pub(super) fn unified_prefix_width(hunk: &Hunk) -> usize {
+    2 + 2 * (gutter_width(hunk) + 1) + 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(spans: &[Span]) -> String {
+        spans.iter().map(|s| s.content.as_ref()).collect()
+    }
+
+    #[test]
+    fn test_korean_display_width() {
+        assert_eq!(display_width("한글"), 4);
+        assert_eq!(display_width("let 변수 = 1;"), 13);
+        assert_eq!(display_width("abc"), 3);
+    }
+
+    #[test]
+    fn test_expand_tabs() {
+        assert_eq!(expand_tabs("\tx", 4), "    x");
+        assert_eq!(expand_tabs("ab\tx", 4), "ab  x");
+        assert_eq!(expand_tabs("한\tx", 4), "한  x");
+        assert!(matches!(expand_tabs("no tabs", 4), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_visualize_whitespace() {
+        let red = Style::default().fg(Color::Red);
+        let spans = vec![Span::styled("\t한 x  \r", red)];
+        let out = visualize_whitespace(&spans, 4);
+        // Inner space stays a space; trailing spaces and CR get markers
+        assert_eq!(text(&out), "→   한 x··␍");
+        assert_eq!(out[0].style.fg, Some(Color::DarkGray));
+        assert_eq!(out[1].content, "한 x");
+        assert_eq!(out[1].style, red);
+        // The tab marker keeps alignment with expand_tabs
+        let plain = visualize_whitespace(&[Span::raw("ab\tc")], 4);
+        assert_eq!(
+            display_width(&text(&plain)),
+            display_width(&expand_tabs("ab\tc", 4))
+        );
+    }
+
+    #[test]
+    fn test_truncate_and_pad_korean() {
+        assert_eq!(truncate_to_width("안녕하세요", 7), "안녕...");
+        assert_eq!(truncate_to_width("안녕하세요", 10), "안녕하세요");
+        assert_eq!(display_width(&truncate_to_width("안녕하세요 world", 9)), 9);
+        assert_eq!(pad_to_width("한글", 6), "한글  ");
+        assert_eq!(display_width(&pad_to_width("한글 주석입니다", 7)), 7);
+        assert_eq!(
+            truncate_start_to_width("src/모듈/파일.rs", 10),
+            "...파일.rs"
+        );
+    }
+
+    #[test]
+    fn test_slice_spans_wide_chars() {
+        let spans = vec![
+            Span::styled("가나", Style::default().fg(Color::Red)),
+            Span::raw("다ab"),
+        ];
+        assert_eq!(text(&slice_spans(&spans, 0, 4)), "가나");
+        // Cutting a wide char on either edge leaves spaces
+        assert_eq!(text(&slice_spans(&spans, 1, 4)), " 나 ");
+        assert_eq!(text(&slice_spans(&spans, 4, 10)), "다ab");
+        assert_eq!(slice_spans(&spans, 0, 4)[0].style.fg, Some(Color::Red));
+    }
+
+    #[test]
+    fn test_wrap_korean() {
+        let spans = vec![Span::raw("가나다라마")];
+        let rows = wrap_spans(&spans, 5);
+        let rows: Vec<String> = rows.iter().map(|r| text(r)).collect();
+        assert_eq!(rows, vec!["가나", "다라", "마"]);
+        assert_eq!(wrapped_rows("가나다라마", 5), 3);
+        assert_eq!(wrapped_rows("", 5), 1);
+        assert_eq!(wrapped_rows("abcdefghij", 5), 2);
+    }
+
+    #[test]
+    fn test_progress_bar() {
+        assert_eq!(progress_bar(0, 10, 5), "░░░░░");
+        assert_eq!(progress_bar(1, 10, 5), "░░░░░");
+        assert_eq!(progress_bar(5, 10, 5), "██░░░");
+        assert_eq!(progress_bar(9, 10, 5), "████░");
+        assert_eq!(progress_bar(10, 10, 5), "█████");
+        assert_eq!(progress_bar(0, 0, 3), "███");
+    }
+
+    #[test]
+    fn test_wrap_keeps_styles() {
+        let red = Style::default().fg(Color::Red);
+        let spans = vec![Span::styled("abc", red), Span::raw("def")];
+        let rows = wrap_spans(&spans, 4);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0][0].content, "abc");
+        assert_eq!(rows[0][0].style, red);
+        assert_eq!(rows[0][1].content, "d");
+        assert_eq!(text(&rows[1]), "ef");
+    }
+}
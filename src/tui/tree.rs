@@ -0,0 +1,366 @@
+//! Hierarchical file tree model (directory rows, aggregate stats, sorting)
+
+use crate::model::{FileDiff, FileReviewSummary, ReviewStatus};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashSet};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(super) enum TreeSort {
+    #[default]
+    Path,
+    LinesChanged,
+    PendingFirst,
+}
+
+impl TreeSort {
+    pub(super) fn next(self) -> Self {
+        match self {
+            TreeSort::Path => TreeSort::LinesChanged,
+            TreeSort::LinesChanged => TreeSort::PendingFirst,
+            TreeSort::PendingFirst => TreeSort::Path,
+        }
+    }
+
+    pub(super) fn label(self) -> &'static str {
+        match self {
+            TreeSort::Path => "path",
+            TreeSort::LinesChanged => "changes",
+            TreeSort::PendingFirst => "pending",
+        }
+    }
+}
+
+/// Aggregate change and review counts for a file or directory
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(super) struct TreeStats {
+    pub(super) added: usize,
+    pub(super) removed: usize,
+    pub(super) hunks: usize,
+    pub(super) pending: usize,
+    pub(super) accepted: usize,
+    pub(super) rejected: usize,
+    pub(super) files: usize,
+    /// Files marked viewed
+    pub(super) viewed: usize,
+}
+
+impl TreeStats {
+    fn of_file(file: &FileDiff) -> Self {
+        let count = |status| file.hunks.iter().filter(|h| h.status == status).count();
+        Self {
+            added: file.lines_added(),
+            removed: file.lines_removed(),
+            hunks: file.hunks.len(),
+            pending: count(ReviewStatus::Pending),
+            accepted: count(ReviewStatus::Accepted),
+            rejected: count(ReviewStatus::Rejected),
+            files: 1,
+            viewed: usize::from(file.viewed),
+        }
+    }
+
+    fn add(&mut self, other: &TreeStats) {
+        self.added += other.added;
+        self.removed += other.removed;
+        self.hunks += other.hunks;
+        self.pending += other.pending;
+        self.accepted += other.accepted;
+        self.rejected += other.rejected;
+        self.files += other.files;
+        self.viewed += other.viewed;
+    }
+
+    pub(super) fn summary(&self) -> FileReviewSummary {
+        if self.hunks == 0 {
+            FileReviewSummary::Empty
+        } else if self.accepted == self.hunks {
+            FileReviewSummary::AllAccepted
+        } else if self.rejected > 0 {
+            FileReviewSummary::HasRejected
+        } else if self.pending == self.hunks {
+            FileReviewSummary::AllPending
+        } else {
+            FileReviewSummary::Partial
+        }
+    }
+
+    fn sort_key(&self, sort: TreeSort) -> (bool, Reverse<usize>) {
+        match sort {
+            TreeSort::Path => (false, Reverse(0)),
+            TreeSort::LinesChanged => (false, Reverse(self.added + self.removed)),
+            TreeSort::PendingFirst => (self.pending == 0, Reverse(0)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) enum TreeEntry {
+    /// Directory row; `path` is the full directory path used as collapse key
+    Dir { path: String, collapsed: bool },
+    /// File row; index into `diff.files`
+    File(usize),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) struct TreeRow {
+    pub(super) depth: usize,
+    pub(super) name: String,
+    pub(super) entry: TreeEntry,
+    pub(super) stats: TreeStats,
+}
+
+#[derive(Default)]
+struct Node {
+    dirs: BTreeMap<String, Node>,
+    files: Vec<(String, usize, TreeStats)>,
+    stats: TreeStats,
+}
+
+/// Build the visible rows of the file tree.
+/// Files rejected by `visible` are omitted (and directories left empty by that),
+/// children of collapsed directories are skipped, and single-child directory
+/// chains are merged into one row (`src/tui`).
+pub(super) fn build_tree_rows(
+    files: &[FileDiff],
+    visible: impl Fn(usize) -> bool,
+    collapsed: &HashSet<String>,
+    sort: TreeSort,
+) -> Vec<TreeRow> {
+    let mut root = Node::default();
+    for (fi, file) in files.iter().enumerate() {
+        if !visible(fi) {
+            continue;
+        }
+        let stats = TreeStats::of_file(file);
+        let mut parts: Vec<&str> = file.path().split('/').collect();
+        let name = parts.pop().unwrap_or_default();
+        let mut node = &mut root;
+        node.stats.add(&stats);
+        for part in parts {
+            node = node.dirs.entry(part.to_string()).or_default();
+            node.stats.add(&stats);
+        }
+        node.files.push((name.to_string(), fi, stats));
+    }
+
+    let mut rows = Vec::new();
+    emit_rows(&root, "", 0, collapsed, sort, &mut rows);
+    rows
+}
+
+fn emit_rows(
+    node: &Node,
+    prefix: &str,
+    depth: usize,
+    collapsed: &HashSet<String>,
+    sort: TreeSort,
+    rows: &mut Vec<TreeRow>,
+) {
+    // Directories first, then files; BTreeMap order gives the path tiebreak
+    let mut dirs: Vec<(&String, &Node)> = node.dirs.iter().collect();
+    dirs.sort_by_key(|(_, n)| n.stats.sort_key(sort));
+    for (name, mut child) in dirs {
+        let mut name = name.clone();
+        while child.files.is_empty() && child.dirs.len() == 1 {
+            let (next_name, next) = child.dirs.iter().next().unwrap();
+            name = format!("{}/{}", name, next_name);
+            child = next;
+        }
+        let path = format!("{}{}", prefix, name);
+        let is_collapsed = collapsed.contains(&path);
+        rows.push(TreeRow {
+            depth,
+            name,
+            entry: TreeEntry::Dir {
+                path: path.clone(),
+                collapsed: is_collapsed,
+            },
+            stats: child.stats,
+        });
+        if !is_collapsed {
+            emit_rows(
+                child,
+                &format!("{}/", path),
+                depth + 1,
+                collapsed,
+                sort,
+                rows,
+            );
+        }
+    }
+
+    let mut files: Vec<&(String, usize, TreeStats)> = node.files.iter().collect();
+    files.sort_by(|a, b| {
+        a.2.sort_key(sort)
+            .cmp(&b.2.sort_key(sort))
+            .then_with(|| a.0.cmp(&b.0))
+    });
+    for (name, fi, stats) in files {
+        rows.push(TreeRow {
+            depth,
+            name: name.clone(),
+            entry: TreeEntry::File(*fi),
+            stats: *stats,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{DiffLine, Hunk};
+
+    fn make_file(path: &str, added: usize, status: ReviewStatus) -> FileDiff {
+        FileDiff {
+            old_path: path.to_string(),
+            new_path: path.to_string(),
+            raw_old_path: format!("a/{}", path),
+            raw_new_path: format!("b/{}", path),
+            hunks: vec![Hunk {
+                header: "@@ -1,1 +1,1 @@".to_string(),
+                old_start: 1,
+                old_count: 1,
+                new_start: 1,
+                new_count: 1,
+                lines: (0..added)
+                    .map(|i| DiffLine::Added(format!("line{}", i)))
+                    .collect(),
+                status,
+                comments: Vec::new(),
+                rule: None,
+                edited: false,
+                original_lines: None,
+            }],
+            is_binary: false,
+            viewed: false,
+            deferred: None,
+        }
+    }
+
+    fn names(rows: &[TreeRow]) -> Vec<String> {
+        rows.iter()
+            .map(|r| format!("{}{}", "  ".repeat(r.depth), r.name))
+            .collect()
+    }
+
+    fn sample() -> Vec<FileDiff> {
+        vec![
+            make_file("src/tui/render.rs", 1, ReviewStatus::Accepted),
+            make_file("src/tui/state.rs", 5, ReviewStatus::Pending),
+            make_file("src/main.rs", 2, ReviewStatus::Rejected),
+            make_file("README.md", 9, ReviewStatus::Pending),
+            make_file("docs/guide/intro.md", 1, ReviewStatus::Accepted),
+        ]
+    }
+
+    #[test]
+    fn test_tree_rows_by_path() {
+        let rows = build_tree_rows(&sample(), |_| true, &HashSet::new(), TreeSort::Path);
+        assert_eq!(
+            names(&rows),
+            vec![
+                "docs/guide",
+                "  intro.md",
+                "src",
+                "  tui",
+                "    render.rs",
+                "    state.rs",
+                "  main.rs",
+                "README.md",
+            ]
+        );
+        assert_eq!(rows[4].entry, TreeEntry::File(0));
+    }
+
+    #[test]
+    fn test_directory_stats_aggregate() {
+        let rows = build_tree_rows(&sample(), |_| true, &HashSet::new(), TreeSort::Path);
+        let src = &rows[2].stats;
+        assert_eq!(src.added, 8);
+        assert_eq!(src.hunks, 3);
+        assert_eq!(src.summary(), FileReviewSummary::HasRejected);
+        let tui = &rows[3].stats;
+        assert_eq!(tui.summary(), FileReviewSummary::Partial);
+        assert_eq!(rows[0].stats.summary(), FileReviewSummary::AllAccepted);
+    }
+
+    #[test]
+    fn test_directory_viewed_counts() {
+        let mut files = sample();
+        files[0].viewed = true;
+        files[1].viewed = true;
+        let rows = build_tree_rows(&files, |_| true, &HashSet::new(), TreeSort::Path);
+        let (src, tui) = (&rows[2].stats, &rows[3].stats);
+        assert_eq!((src.viewed, src.files), (2, 3));
+        assert_eq!((tui.viewed, tui.files), (2, 2));
+    }
+
+    #[test]
+    fn test_collapsed_directory_hides_children() {
+        let collapsed: HashSet<String> = ["src/tui".to_string()].into_iter().collect();
+        let rows = build_tree_rows(&sample(), |_| true, &collapsed, TreeSort::Path);
+        assert_eq!(
+            names(&rows),
+            vec![
+                "docs/guide",
+                "  intro.md",
+                "src",
+                "  tui",
+                "  main.rs",
+                "README.md"
+            ]
+        );
+        assert_eq!(
+            rows[3].entry,
+            TreeEntry::Dir {
+                path: "src/tui".to_string(),
+                collapsed: true
+            }
+        );
+    }
+
+    #[test]
+    fn test_sort_by_lines_changed_and_pending() {
+        let rows = build_tree_rows(&sample(), |_| true, &HashSet::new(), TreeSort::LinesChanged);
+        assert_eq!(
+            names(&rows),
+            vec![
+                "src",
+                "  tui",
+                "    state.rs",
+                "    render.rs",
+                "  main.rs",
+                "docs/guide",
+                "  intro.md",
+                "README.md",
+            ]
+        );
+
+        let rows = build_tree_rows(&sample(), |_| true, &HashSet::new(), TreeSort::PendingFirst);
+        assert_eq!(
+            names(&rows),
+            vec![
+                "src",
+                "  tui",
+                "    state.rs",
+                "    render.rs",
+                "  main.rs",
+                "docs/guide",
+                "  intro.md",
+                "README.md",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_filter_drops_empty_directories() {
+        let files = sample();
+        let rows = build_tree_rows(
+            &files,
+            |fi| files[fi].new_path.ends_with(".md"),
+            &HashSet::new(),
+            TreeSort::Path,
+        );
+        assert_eq!(names(&rows), vec!["docs/guide", "  intro.md", "README.md"]);
+    }
+}
@@ -1,9 +1,11 @@
 //! 통합 테스트: parse → 상태 조작 → output 파이프라인
 
+use diffy_tui::config::HookConfig;
 use diffy_tui::hook::write_feedback;
-use diffy_tui::model::ReviewStatus;
+use diffy_tui::model::{Comment, ReviewStatus, Severity};
 use diffy_tui::output::{write_diff, write_json};
-use diffy_tui::parse::parse_diff;
+use diffy_tui::parse::{ParseError, ParseOptions, parse_diff, parse_diff_with};
+use diffy_tui::script::apply_decisions;
 use indoc::indoc;
 use serde_json::Value;
 
@@ -16,7 +18,7 @@ const SAMPLE_DIFF: &str = indoc! {"
     +    println!(\"hello\");
          // existing
      }
-    @@ -10,3 +11,2 @@
+    @@ -10,4 +11,3 @@
      fn helper() {
     -    old_code();
          new_code();
@@ -41,7 +43,7 @@ fn test_parse_accept_all_roundtrip() {
     let text = String::from_utf8(output).unwrap();
     // Output should contain both hunks
     assert!(text.contains("@@ -1,3 +1,4 @@"));
-    assert!(text.contains("@@ -10,3 +11,2 @@"));
+    assert!(text.contains("@@ -10,4 +11,3 @@"));
     assert!(text.contains("+    println!(\"hello\");"));
     assert!(text.contains("-    old_code();"));
 }
@@ -77,7 +79,7 @@ fn test_parse_partial_accept() {
     assert!(text.contains("@@ -1,3 +1,4 @@"));
     assert!(text.contains("+    println!(\"hello\");"));
     // Second hunk should NOT be in output
-    assert!(!text.contains("@@ -10,3 +11,2 @@"));
+    assert!(!text.contains("@@ -10,4 +11,3 @@"));
     assert!(!text.contains("-    old_code();"));
 }
 
@@ -111,7 +113,7 @@ fn test_hook_feedback_integration() {
     diff.files[0].hunks[1].status = ReviewStatus::Rejected;
 
     let mut output = Vec::new();
-    let all_accepted = write_feedback(&diff, &mut output).unwrap();
+    let all_accepted = write_feedback(&diff, &mut output, &HookConfig::default()).unwrap();
     assert!(!all_accepted);
 
     let text = String::from_utf8(output).unwrap();
@@ -143,11 +145,372 @@ fn test_json_comment_in_output() {
     "};
     let mut diff = parse_diff(input).unwrap();
     diff.files[0].hunks[0].status = ReviewStatus::Rejected;
-    diff.files[0].hunks[0].comment = Some("needs improvement".to_string());
+    diff.files[0].hunks[0]
+        .comments
+        .push(Comment::from_input("needs improvement", Severity::Blocking));
 
     let mut buf = Vec::new();
     write_json(&diff, &mut buf).unwrap();
     let json: Value = serde_json::from_slice(&buf).unwrap();
 
+    // 예전 형식의 `comment` 필드도 계속 나온다
     assert_eq!(json["files"][0]["hunks"][0]["comment"], "needs improvement");
+    let comment = &json["files"][0]["hunks"][0]["comments"][0];
+    assert_eq!(comment["text"], "needs improvement");
+    assert_eq!(comment["severity"], "blocking");
+}
+
+#[test]
+fn test_decisions_replay_reproduces_output() {
+    let mut reviewed = parse_diff(SAMPLE_DIFF).unwrap();
+    reviewed.files[0].hunks[0].status = ReviewStatus::Accepted;
+    reviewed.files[0].hunks[1].status = ReviewStatus::Rejected;
+    let mut expected = Vec::new();
+    write_diff(&reviewed, &mut expected).unwrap();
+
+    let mut decisions = Vec::new();
+    write_json(&reviewed, &mut decisions).unwrap();
+
+    let mut replayed = parse_diff(SAMPLE_DIFF).unwrap();
+    let report = apply_decisions(&mut replayed, decisions.as_slice()).unwrap();
+    assert_eq!(report.applied, 2);
+
+    let mut actual = Vec::new();
+    write_diff(&replayed, &mut actual).unwrap();
+    assert_eq!(
+        String::from_utf8(actual).unwrap(),
+        String::from_utf8(expected).unwrap()
+    );
+}
+
+#[test]
+fn test_scripted_mode_without_tty() {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_diffy"))
+        .args(["--accept", "src/**", "--json"])
+        .env(
+            "XDG_CONFIG_HOME",
+            std::env::temp_dir().join("diffy-no-config"),
+        )
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(SAMPLE_DIFF.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["summary"]["accepted"], 2);
+    assert_eq!(json["summary"]["pending"], 0);
+}
+
+const STRICT: ParseOptions = ParseOptions {
+    strict: true,
+    strip: None,
+};
+
+/// `tests/fixtures/malformed`의 각 파일은 첫 줄에 strict 모드에서 기대하는 에러를 적는다
+/// (`# strict: line N Kind`). diff 앞의 줄은 파서가 건너뛰므로 입력에는 영향이 없다.
+#[test]
+fn test_malformed_corpus() {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/malformed");
+    let mut cases = 0;
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        let input = std::fs::read_to_string(&path).unwrap();
+        let expect = input.lines().next().unwrap();
+        let expect: Vec<&str> = expect
+            .trim_start_matches("# strict: line ")
+            .split(' ')
+            .collect();
+        let (line, kind): (usize, &str) = (expect[0].parse().unwrap(), expect[1]);
+
+        // 관대한 모드: 에러 없이 경고로 남긴다
+        let diff = parse_diff(&input).unwrap_or_else(|e| panic!("{name}: {e}"));
+        let first = diff
+            .warnings
+            .first()
+            .unwrap_or_else(|| panic!("{name}: no warning"));
+        assert_eq!(first.line, line, "{name}: {first}");
+
+        // strict 모드: 같은 위치에서 ParseError
+        let err = parse_diff_with(&input, STRICT).unwrap_err();
+        let err = err
+            .downcast_ref::<ParseError>()
+            .unwrap_or_else(|| panic!("{name}: {err}"));
+        assert_eq!(err, first, "{name}");
+        assert!(format!("{:?}", err.kind).starts_with(kind), "{name}: {err}");
+        cases += 1;
+    }
+    assert!(cases >= 10);
+}
+
+/// 유효한 diff를 자르고, 줄을 지우고, 문자를 바꾼 입력에서도 파서가 패닉하지 않고
+/// strict 모드의 에러는 항상 입력 안의 줄을 가리키는 ParseError다
+#[test]
+fn test_mutated_diffs_never_panic() {
+    let check = |input: &str| {
+        let diff = parse_diff(input).unwrap();
+        let lines = input.lines().count();
+        assert!(diff.warnings.iter().all(|w| w.line >= 1 && w.line <= lines));
+        match parse_diff_with(input, STRICT) {
+            Ok(strict) => assert!(diff.warnings.is_empty() && strict.warnings.is_empty()),
+            Err(e) => assert_eq!(e.downcast_ref::<ParseError>(), diff.warnings.first()),
+        }
+    };
+
+    assert!(parse_diff_with(SAMPLE_DIFF, STRICT).is_ok());
+    for (end, _) in SAMPLE_DIFF.char_indices() {
+        check(&SAMPLE_DIFF[..end]);
+    }
+    let lines: Vec<&str> = SAMPLE_DIFF.lines().collect();
+    for skip in 0..lines.len() {
+        let mut input: Vec<&str> = lines.clone();
+        input.remove(skip);
+        check(&input.join("\n"));
+        input.insert(skip, lines[skip]);
+        input.insert(skip, lines[skip]);
+        check(&input.join("\n"));
+    }
+
+    // 결정적인 xorshift로 문자 하나씩 바꾸기
+    let tricky = ['@', '-', '+', ' ', '\\', '\n', ',', '0', 'x'];
+    let bytes: Vec<char> = SAMPLE_DIFF.chars().collect();
+    let mut seed: u64 = 0x9e37_79b9_7f4a_7c15;
+    for _ in 0..2000 {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        let mut mutated = bytes.clone();
+        mutated[seed as usize % bytes.len()] = tricky[(seed >> 32) as usize % tricky.len()];
+        check(&mutated.iter().collect::<String>());
+    }
+}
+
+/// `diff -ruN` 출력도 파이프 모드로 리뷰하고, 출력은 원래 헤더 그대로 `patch -p1`에 넘길 수 있다
+#[test]
+fn test_scripted_mode_with_gnu_diff() {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let input = "diff -ruN old/src/a.c new/src/a.c\n\
+                 --- old/src/a.c\t2024-01-01 10:00:00.000000000 +0100\n\
+                 +++ new/src/a.c\t2024-01-02 10:00:00.000000000 +0100\n\
+                 @@ -1 +1 @@\n\
+                 -int x;\n\
+                 +long x;\n\
+                 diff -ruN old/vendor/b.c new/vendor/b.c\n\
+                 --- old/vendor/b.c\t2024-01-01 10:00:00.000000000 +0100\n\
+                 +++ new/vendor/b.c\t2024-01-02 10:00:00.000000000 +0100\n\
+                 @@ -1 +1 @@\n\
+                 -a\n\
+                 +b\n";
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_diffy"))
+        .args(["--accept", "src/**", "--strict"])
+        .env(
+            "XDG_CONFIG_HOME",
+            std::env::temp_dir().join("diffy-no-config"),
+        )
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(
+        stdout,
+        input
+            .lines()
+            .skip(1)
+            .take(5)
+            .map(|l| format!("{l}\n"))
+            .collect::<String>()
+    );
+}
+
+/// 변경이 하나 있는 임시 git 저장소 (`src/lib.rs`의 한 줄을 고침)
+fn temp_repo(name: &str) -> std::path::PathBuf {
+    use std::process::Command;
+
+    let dir = std::env::temp_dir().join(format!("diffy-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    let git = |args: &[&str]| {
+        let status = Command::new("git")
+            .args([
+                "-c",
+                "user.name=diffy",
+                "-c",
+                "user.email=diffy@example.com",
+            ])
+            .args(args)
+            .current_dir(&dir)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?}", args);
+    };
+    git(&["init", "-q"]);
+    std::fs::write(dir.join("src/lib.rs"), "fn a() {}\n").unwrap();
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "init"]);
+    std::fs::write(dir.join("src/lib.rs"), "fn b() {}\n").unwrap();
+    dir
+}
+
+/// CI나 훅처럼 stdin을 /dev/null로 두고 `repo`에서 diffy를 실행한다
+fn run_in_repo(repo: &std::path::Path, args: &[&str]) -> std::process::Output {
+    use std::process::{Command, Stdio};
+
+    Command::new(env!("CARGO_BIN_EXE_diffy"))
+        .args(args)
+        .current_dir(repo)
+        .env(
+            "XDG_CONFIG_HOME",
+            std::env::temp_dir().join("diffy-no-config"),
+        )
+        .stdin(Stdio::null())
+        .output()
+        .unwrap()
+}
+
+/// 파이프로 받은 diff도 `--hook-mode`면 stderr 피드백과 exit code로 답한다
+#[test]
+fn test_scripted_hook_mode_with_piped_diff() {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_diffy"))
+        .args(["--hook-mode", "--reject", "src/**"])
+        .env(
+            "XDG_CONFIG_HOME",
+            std::env::temp_dir().join("diffy-no-config"),
+        )
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(SAMPLE_DIFF.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("[diffy review result]"), "{stderr}");
+}
+
+#[test]
+fn test_scripted_mode_with_empty_stdin_runs_git_diff() {
+    let repo = temp_repo("scripted-null-stdin");
+    let output = run_in_repo(&repo, &["--accept", "*", "--json"]);
+    assert!(output.status.success());
+
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["summary"]["accepted"], 1);
+    assert_eq!(json["files"][0]["path"], "src/lib.rs");
+    std::fs::remove_dir_all(repo).unwrap();
+}
+
+#[test]
+fn test_scripted_hook_mode_with_empty_stdin() {
+    let repo = temp_repo("scripted-hook");
+    let output = run_in_repo(&repo, &["--hook-mode", "--accept", "*"]);
+    assert_eq!(output.status.code(), Some(0));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("all 1 hunks accepted"), "{stderr}");
+
+    let output = run_in_repo(&repo, &["--hook-mode", "--reject", "src/**"]);
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("src/lib.rs"), "{stderr}");
+    std::fs::remove_dir_all(repo).unwrap();
+}
+
+/// `--resume`으로 불러온 결정은 삭제된 파일마다 따로 복원된다
+/// (`--reject`와 함께 주면 TUI 없이 결과를 확인할 수 있다)
+#[test]
+fn test_resume_keeps_deleted_file_decisions() {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let input = "diff --git a/x b/x\ndeleted file mode 100644\n--- a/x\n+++ /dev/null\n@@ -1 +0,0 @@\n-x\n\
+                 diff --git a/y b/y\ndeleted file mode 100644\n--- a/y\n+++ /dev/null\n@@ -1 +0,0 @@\n-y\n\
+                 diff --git a/z b/z\n--- a/z\n+++ b/z\n@@ -1 +1 @@\n-a\n+b\n";
+    let mut reviewed = parse_diff(input).unwrap();
+    reviewed.files[0].hunks[0].status = ReviewStatus::Rejected;
+    reviewed.files[1].hunks[0].status = ReviewStatus::Accepted;
+    let saved = std::env::temp_dir().join(format!("diffy-resume-{}.json", std::process::id()));
+    let mut json = Vec::new();
+    write_json(&reviewed, &mut json).unwrap();
+    std::fs::write(&saved, json).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_diffy"))
+        .args(["--json", "--reject", "z", "--resume"])
+        .arg(&saved)
+        .env(
+            "XDG_CONFIG_HOME",
+            std::env::temp_dir().join("diffy-no-config"),
+        )
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    std::fs::remove_file(&saved).unwrap();
+    assert!(output.status.success());
+
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["files"][0]["path"], "x");
+    assert_eq!(json["files"][0]["hunks"][0]["status"], "rejected");
+    assert_eq!(json["files"][1]["path"], "y");
+    assert_eq!(json["files"][1]["hunks"][0]["status"], "accepted");
+    assert_eq!(json["files"][2]["hunks"][0]["status"], "rejected");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(!stderr.contains("did not match"), "{stderr}");
+}
+
+/// 라인이 모자란 헌크는 관대한 모드에서 실제 라인 수로 헤더를 고쳐 출력한다
+#[test]
+fn test_truncated_hunk_output_has_matching_header() {
+    let input = "diff --git a/x b/x\n--- a/x\n+++ b/x\n@@ -1,2 +1,2 @@\n-a\n+b\n";
+    let mut diff = parse_diff(input).unwrap();
+    assert_eq!(diff.warnings.len(), 1);
+    diff.files[0].hunks[0].status = ReviewStatus::Accepted;
+
+    let mut output = Vec::new();
+    write_diff(&diff, &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("@@ -1,1 +1,1 @@\n-a\n+b\n"), "{output}");
 }